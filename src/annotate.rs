@@ -0,0 +1,149 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Metadata attached to the paths of a document, such as the file and line
+//! a value was read from, kept apart from the document itself.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::path::{parse_concrete, render, Segment};
+use crate::{Error, Json};
+
+/// Parse the path of an annotation, the empty path being the document itself.
+fn segments(path: &str) -> Result<Vec<Segment>, Error> {
+    if path.is_empty() {
+        Ok(Vec::new())
+    } else {
+        Ok(parse_concrete(path)?)
+    }
+}
+
+impl Json {
+    /// Attach metadata to the given path, replacing the one already
+    /// attached to it if any. Annotations are not part of the document:
+    /// they are neither serialized nor compared, but they are kept by
+    /// `Clone` and `Json::merge`, which adds the annotations of the
+    /// merged document. The path doesn't have to exist in the document.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut config = inline!("server" => inline!("port" => 8080));
+    ///     config.annotate("server.port", "defaults.json:3")?;
+    ///
+    ///     let mut overrides = inline!("server" => inline!("host" => "0.0.0.0"));
+    ///     overrides.annotate("server.host", "local.json:2")?;
+    ///     config.merge(overrides);
+    ///
+    ///     assert_eq!(
+    ///         Some("local.json:2".to_string()),
+    ///         config.annotation("server.host")?
+    ///     );
+    ///     assert_eq!(2, config.annotations().len());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if the path cannot be
+    /// parsed or holds wildcards, or an `Err(json_ez::Error::Json)` if the
+    /// metadata cannot be represented as JSON
+    pub fn annotate<V: Serialize>(&mut self, path: &str, metadata: V) -> Result<(), Error> {
+        let segments = segments(path)?;
+        self.annotations
+            .insert(segments, serde_json::to_value(metadata)?);
+        Ok(())
+    }
+
+    /// Get the metadata attached to the given path, if any.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if the path cannot be
+    /// parsed or holds wildcards, or an `Err(json_ez::Error::Json)` if the
+    /// metadata cannot be deserialized as a `T`
+    pub fn annotation<T: DeserializeOwned>(&self, path: &str) -> Result<Option<T>, Error> {
+        match self.annotations.get(&segments(path)?) {
+            Some(metadata) => Ok(Some(T::deserialize(metadata)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// All the annotated paths along with their metadata, sorted by path.
+    pub fn annotations(&self) -> Vec<(String, &Value)> {
+        self.annotations
+            .iter()
+            .map(|(segments, metadata)| (render(segments), metadata))
+            .collect()
+    }
+
+    /// Detach the metadata attached to the given path, returning it.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if the path cannot be
+    /// parsed or holds wildcards
+    pub fn remove_annotation(&mut self, path: &str) -> Result<Option<Value>, Error> {
+        Ok(self.annotations.remove(&segments(path)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+    use serde_json::json;
+    use std::error::Error;
+
+    #[test]
+    fn annotations() -> Result<(), Box<dyn Error>> {
+        let mut json = inline!("servers" => vec![inline!("port" => 80)]);
+        json.annotate("", json!({ "file": "base.json" }))?;
+        json.annotate("servers[0].port", json!({ "file": "base.json", "line": 2 }))?;
+        assert!(json.annotate("servers[*]", "any").is_err());
+
+        let copy = json.clone();
+        assert_eq!(json, inline!("servers" => vec![inline!("port" => 80)]));
+        assert_eq!(
+            Some(2),
+            copy.annotation::<serde_json::Value>("servers[0].port")?
+                .and_then(|m| m["line"].as_u64())
+        );
+
+        let mut other = Json::new();
+        other.annotate("servers[0].port", "override.json")?;
+        json.merge(other);
+        let paths: Vec<String> = json.annotations().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(vec!["", "servers[0].port"], paths);
+        assert_eq!(
+            Some("override.json".to_string()),
+            json.annotation("servers[0].port")?
+        );
+
+        assert!(json.remove_annotation("")?.is_some());
+        assert_eq!(None, json.annotation::<String>("")?);
+        Ok(())
+    }
+}
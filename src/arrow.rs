@@ -0,0 +1,393 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Conversions between arrays of JSON objects and Arrow `RecordBatch`es.
+//!
+//! Column types are inferred from the values found in every object:
+//! integers and floats widen to `Float64`, objects become `Struct`s, arrays
+//! become `List`s and columns mixing incompatible types fall back to `Utf8`
+//! where non-string values are stored as JSON text.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+use arrow_array::{
+    cast::AsArray,
+    types::{
+        Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+        UInt32Type, UInt64Type, UInt8Type,
+    },
+    Array, ArrayRef, BooleanArray, Float64Array, Int64Array, ListArray, NullArray, RecordBatch,
+    RecordBatchOptions, StringArray, StructArray,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::{ArrowError, DataType, Field, Fields, Schema};
+use serde_json::{Map, Number, Value};
+
+use crate::{Json, NotFound, TypeMismatch};
+
+static NULL: Value = Value::Null;
+
+impl Json {
+    /// Convert the array of objects associated to the given key into an Arrow
+    /// `RecordBatch`, one row per object, inferring the schema from the data.
+    /// # Errors
+    /// Return an `Err(json_ez::NotFound)` if the given key doesn't exist,
+    /// an `Err(json_ez::TypeMismatch)` if it isn't an array of objects
+    /// or an `Err(arrow_schema::ArrowError)` if the batch cannot be built.
+    pub fn array_to_arrow(&self, k: &str) -> Result<RecordBatch, Box<dyn Error>> {
+        let items = match self.json_data.get(k) {
+            Some(Value::Array(items)) => items,
+            Some(other) => return Err(Box::new(TypeMismatch::new("array", other))),
+            None => return Err(Box::new(NotFound::new(k.into(), self)?)),
+        };
+        let mut rows = Vec::with_capacity(items.len());
+        for item in items {
+            match item {
+                Value::Object(row) => rows.push(row),
+                other => return Err(Box::new(TypeMismatch::new("object", other))),
+            }
+        }
+
+        let fields = infer_fields(rows.iter().copied());
+        let columns = fields
+            .iter()
+            .map(|field| {
+                let values: Vec<&Value> = rows
+                    .iter()
+                    .map(|row| row.get(field.name()).unwrap_or(&NULL))
+                    .collect();
+                build_array(field.data_type(), &values)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+        let batch =
+            RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), columns, &options)?;
+        Ok(batch)
+    }
+
+    /// Convert every row of an Arrow `RecordBatch` into a `Json` instance.
+    /// Data types without JSON counterpart (dates, decimals, binaries...)
+    /// are rendered as strings.
+    /// # Errors
+    /// Return an `Err(arrow_schema::ArrowError)` if a column cannot be read.
+    pub fn from_arrow(batch: &RecordBatch) -> Result<Vec<Json>, Box<dyn Error>> {
+        let schema = batch.schema();
+        let mut rows: Vec<Json> = (0..batch.num_rows()).map(|_| Json::new()).collect();
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            for (i, row) in rows.iter_mut().enumerate() {
+                row.json_data
+                    .insert(field.name().clone(), to_value(column.as_ref(), i)?);
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Lattice of the types observed while scanning JSON values.
+#[derive(Clone, PartialEq)]
+enum Inferred {
+    Null,
+    Bool,
+    Int,
+    Float,
+    Text,
+    List(Box<Inferred>),
+    Struct(BTreeMap<String, Inferred>),
+}
+
+impl Inferred {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => Inferred::Null,
+            Value::Bool(_) => Inferred::Bool,
+            Value::Number(n) if n.is_i64() => Inferred::Int,
+            Value::Number(_) => Inferred::Float,
+            Value::String(_) => Inferred::Text,
+            Value::Array(items) => Inferred::List(Box::new(
+                items
+                    .iter()
+                    .map(Inferred::of)
+                    .fold(Inferred::Null, Inferred::merge),
+            )),
+            Value::Object(map) => Inferred::Struct(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), Inferred::of(v)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn merge(self, other: Inferred) -> Self {
+        match (self, other) {
+            (Inferred::Null, other) | (other, Inferred::Null) => other,
+            (Inferred::Int, Inferred::Float) | (Inferred::Float, Inferred::Int) => Inferred::Float,
+            (Inferred::List(a), Inferred::List(b)) => Inferred::List(Box::new(a.merge(*b))),
+            (Inferred::Struct(mut a), Inferred::Struct(b)) => {
+                for (k, t) in b {
+                    let merged = match a.remove(&k) {
+                        Some(existing) => existing.merge(t),
+                        None => t,
+                    };
+                    a.insert(k, merged);
+                }
+                Inferred::Struct(a)
+            }
+            (a, b) if a == b => a,
+            _ => Inferred::Text,
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Inferred::Null => DataType::Null,
+            Inferred::Bool => DataType::Boolean,
+            Inferred::Int => DataType::Int64,
+            Inferred::Float => DataType::Float64,
+            Inferred::Text => DataType::Utf8,
+            Inferred::List(item) => {
+                DataType::List(Arc::new(Field::new_list_field(item.data_type(), true)))
+            }
+            Inferred::Struct(fields) => DataType::Struct(to_fields(fields)),
+        }
+    }
+}
+
+fn to_fields(fields: &BTreeMap<String, Inferred>) -> Fields {
+    fields
+        .iter()
+        .map(|(name, t)| Field::new(name, t.data_type(), true))
+        .collect()
+}
+
+fn infer_fields<'a, I: Iterator<Item = &'a Map<String, Value>>>(rows: I) -> Fields {
+    let mut fields = BTreeMap::new();
+    for row in rows {
+        for (k, v) in row {
+            let merged = match fields.remove(k) {
+                Some(existing) => Inferred::merge(existing, Inferred::of(v)),
+                None => Inferred::of(v),
+            };
+            fields.insert(k.clone(), merged);
+        }
+    }
+    to_fields(&fields)
+}
+
+fn validity(values: &[&Value]) -> Option<NullBuffer> {
+    let buffer = NullBuffer::from_iter(values.iter().map(|v| !v.is_null()));
+    if buffer.null_count() > 0 {
+        Some(buffer)
+    } else {
+        None
+    }
+}
+
+fn build_array(data_type: &DataType, values: &[&Value]) -> Result<ArrayRef, ArrowError> {
+    let array: ArrayRef = match data_type {
+        DataType::Null => Arc::new(NullArray::new(values.len())),
+        DataType::Boolean => Arc::new(BooleanArray::from_iter(values.iter().map(|v| v.as_bool()))),
+        DataType::Int64 => Arc::new(Int64Array::from_iter(values.iter().map(|v| v.as_i64()))),
+        DataType::Float64 => Arc::new(Float64Array::from_iter(values.iter().map(|v| v.as_f64()))),
+        DataType::Utf8 => Arc::new(StringArray::from_iter(values.iter().map(|v| match v {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }))),
+        DataType::List(field) => {
+            let lists: Vec<&[Value]> = values
+                .iter()
+                .map(|v| v.as_array().map_or(&[][..], |a| a.as_slice()))
+                .collect();
+            let items: Vec<&Value> = lists.iter().flat_map(|l| l.iter()).collect();
+            Arc::new(ListArray::try_new(
+                field.clone(),
+                OffsetBuffer::from_lengths(lists.iter().map(|l| l.len())),
+                build_array(field.data_type(), &items)?,
+                validity(values),
+            )?)
+        }
+        DataType::Struct(fields) => {
+            let children = fields
+                .iter()
+                .map(|field| {
+                    let items: Vec<&Value> = values
+                        .iter()
+                        .map(|v| v.get(field.name()).unwrap_or(&NULL))
+                        .collect();
+                    build_array(field.data_type(), &items)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Arc::new(StructArray::try_new_with_length(
+                fields.clone(),
+                children,
+                validity(values),
+                values.len(),
+            )?)
+        }
+        other => {
+            return Err(ArrowError::NotYetImplemented(format!(
+                "Cannot build {} column from JSON values",
+                other
+            )))
+        }
+    };
+    Ok(array)
+}
+
+fn to_value(array: &dyn Array, i: usize) -> Result<Value, ArrowError> {
+    if array.is_null(i) {
+        return Ok(Value::Null);
+    }
+    macro_rules! integer {
+        ($t: ty) => {
+            Value::from(array.as_primitive::<$t>().value(i))
+        };
+    }
+    macro_rules! float {
+        ($t: ty) => {
+            Number::from_f64(array.as_primitive::<$t>().value(i).into())
+                .map_or(Value::Null, Value::Number)
+        };
+    }
+    let value = match array.data_type() {
+        DataType::Null => Value::Null,
+        DataType::Boolean => Value::Bool(array.as_boolean().value(i)),
+        DataType::Int8 => integer!(Int8Type),
+        DataType::Int16 => integer!(Int16Type),
+        DataType::Int32 => integer!(Int32Type),
+        DataType::Int64 => integer!(Int64Type),
+        DataType::UInt8 => integer!(UInt8Type),
+        DataType::UInt16 => integer!(UInt16Type),
+        DataType::UInt32 => integer!(UInt32Type),
+        DataType::UInt64 => integer!(UInt64Type),
+        DataType::Float32 => float!(Float32Type),
+        DataType::Float64 => float!(Float64Type),
+        DataType::Utf8 => Value::String(array.as_string::<i32>().value(i).into()),
+        DataType::LargeUtf8 => Value::String(array.as_string::<i64>().value(i).into()),
+        DataType::Utf8View => Value::String(array.as_string_view().value(i).into()),
+        DataType::List(_) => to_array(array.as_list::<i32>().value(i).as_ref())?,
+        DataType::LargeList(_) => to_array(array.as_list::<i64>().value(i).as_ref())?,
+        DataType::Struct(fields) => {
+            let array = array.as_struct();
+            let mut map = Map::new();
+            for (field, column) in fields.iter().zip(array.columns()) {
+                map.insert(field.name().clone(), to_value(column.as_ref(), i)?);
+            }
+            Value::Object(map)
+        }
+        _ => Value::String(
+            ArrayFormatter::try_new(array, &FormatOptions::default())?
+                .value(i)
+                .to_string(),
+        ),
+    };
+    Ok(value)
+}
+
+fn to_array(array: &dyn Array) -> Result<Value, ArrowError> {
+    (0..array.len())
+        .map(|i| to_value(array, i))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Value::Array)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    fn records() -> Json {
+        inline!("records" => vec![
+            inline!(
+                "title" => "The Hitchhiker's Guide to the Galaxy",
+                "release_date" => 1979,
+                "rating" => 5,
+                "tags" => vec!["novel", "sci-fi"]
+            ),
+            inline!(
+                "title" => "Mostly Harmless",
+                "release_date" => 1992,
+                "rating" => 3.5,
+                "movie" => inline!("release_date" => 2005)
+            )
+        ])
+    }
+
+    #[test]
+    fn array_to_arrow_infers_schema() -> Result<(), Box<dyn Error>> {
+        let batch = records().array_to_arrow("records")?;
+        let schema = batch.schema();
+        assert_eq!(2, batch.num_rows());
+        assert_eq!(
+            &DataType::Utf8,
+            schema.field_with_name("title")?.data_type()
+        );
+        assert_eq!(
+            &DataType::Int64,
+            schema.field_with_name("release_date")?.data_type()
+        );
+        assert_eq!(
+            &DataType::Float64,
+            schema.field_with_name("rating")?.data_type()
+        );
+        assert!(matches!(
+            schema.field_with_name("tags")?.data_type(),
+            DataType::List(_)
+        ));
+        assert!(matches!(
+            schema.field_with_name("movie")?.data_type(),
+            DataType::Struct(_)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn arrow_round_trip() -> Result<(), Box<dyn Error>> {
+        let rows = Json::from_arrow(&records().array_to_arrow("records")?)?;
+        assert_eq!(2, rows.len());
+        assert_eq!(1979, rows[0].get::<u16>("release_date")?);
+        assert_eq!(5.0, rows[0].get::<f64>("rating")?);
+        assert_eq!(vec!["novel", "sci-fi"], rows[0].get::<Vec<String>>("tags")?);
+        assert!(rows[0].get::<Option<Json>>("movie")?.is_none());
+        assert_eq!(None, rows[1].get::<Option<Vec<String>>>("tags")?);
+        assert_eq!(
+            2005,
+            rows[1].get::<Json>("movie")?.get::<u16>("release_date")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn array_to_arrow_errors() {
+        let json = inline!("records" => vec![1, 2]);
+        assert!(json.array_to_arrow("missing").is_err());
+        assert_eq!(
+            "TypeMismatch: Expected object but found number",
+            format!("{}", json.array_to_arrow("records").unwrap_err())
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Typed accessors borrowing the values of a document rather than
+//! deserializing copies of them, for hot paths.
+
+use serde_json::{Map, Value};
+
+use crate::{Error, Json, TypeMismatch};
+
+impl Json {
+    /// The value of the given key, borrowed with the given accessor.
+    fn borrowed<'a, T>(
+        &'a self,
+        k: &str,
+        expected: &str,
+        as_type: impl Fn(&'a Value) -> Option<T>,
+    ) -> Result<T, Error> {
+        let value = self.member(k)?;
+        as_type(value).ok_or_else(|| {
+            let found = match value {
+                // Numbers which don't fit are reported with their value
+                Value::Number(n) => format!("number {}", n),
+                other => crate::type_name(other).into(),
+            };
+            TypeMismatch {
+                expected: expected.into(),
+                found,
+            }
+            .into()
+        })
+    }
+
+    /// Borrow the string stored under the given key, without copying it
+    /// like `Json::get::<String>` does.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// fn main() -> Result<(), json_ez::Error> {
+    ///     let response = inline!("status" => "ok", "count" => 3, "ratio" => 0.5, "done" => true);
+    ///
+    ///     assert_eq!("ok", response.get_str("status")?);
+    ///     assert_eq!(3, response.get_i64("count")?);
+    ///     assert_eq!(0.5, response.get_f64("ratio")?);
+    ///     assert!(response.get_bool("done")?);
+    ///     assert_eq!(
+    ///         "TypeMismatch: Expected i64 but found number 0.5",
+    ///         response.get_i64("ratio").unwrap_err().to_string()
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or
+    /// an `Err(json_ez::Error::TypeMismatch)` if its value is not a string
+    pub fn get_str(&self, k: &str) -> Result<&str, Error> {
+        self.borrowed(k, "string", Value::as_str)
+    }
+
+    /// Get the integer stored under the given key as an `i64`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::TypeMismatch)` if its value is not an integer
+    /// which fits in an `i64`
+    pub fn get_i64(&self, k: &str) -> Result<i64, Error> {
+        self.borrowed(k, "i64", Value::as_i64)
+    }
+
+    /// Get the integer stored under the given key as a `u64`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::TypeMismatch)` if its value is not a non-negative
+    /// integer which fits in a `u64`
+    pub fn get_u64(&self, k: &str) -> Result<u64, Error> {
+        self.borrowed(k, "u64", Value::as_u64)
+    }
+
+    /// Get the number stored under the given key as a `f64`, integers
+    /// included.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or
+    /// an `Err(json_ez::Error::TypeMismatch)` if its value is not a number
+    pub fn get_f64(&self, k: &str) -> Result<f64, Error> {
+        self.borrowed(k, "number", Value::as_f64)
+    }
+
+    /// Get the boolean stored under the given key.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or
+    /// an `Err(json_ez::Error::TypeMismatch)` if its value is not a boolean
+    pub fn get_bool(&self, k: &str) -> Result<bool, Error> {
+        self.borrowed(k, "boolean", Value::as_bool)
+    }
+
+    /// Borrow the items of the array stored under the given key.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or
+    /// an `Err(json_ez::Error::TypeMismatch)` if its value is not an array
+    pub fn get_array(&self, k: &str) -> Result<&[Value], Error> {
+        self.borrowed(k, "array", |v| v.as_array().map(Vec::as_slice))
+    }
+
+    /// Borrow the members of the object stored under the given key.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or
+    /// an `Err(json_ez::Error::TypeMismatch)` if its value is not an object
+    pub fn get_object(&self, k: &str) -> Result<&Map<String, Value>, Error> {
+        self.borrowed(k, "object", Value::as_object)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{deserialise, Error};
+
+    #[test]
+    fn borrowed() -> Result<(), Box<dyn std::error::Error>> {
+        let json = deserialise!(
+            r#"{ "big": 18446744073709551615, "neg": -1, "tags": ["a"], "meta": {}, "name": "x" }"#
+        )?;
+        assert_eq!(u64::MAX, json.get_u64("big")?);
+        assert_eq!(
+            "TypeMismatch: Expected i64 but found number 18446744073709551615",
+            json.get_i64("big").unwrap_err().to_string()
+        );
+        assert_eq!(-1, json.get_i64("neg")?);
+        assert!(json.get_u64("neg").is_err());
+        assert_eq!(1, json.get_array("tags")?.len());
+        assert!(json.get_object("meta")?.is_empty());
+        assert_eq!(
+            "TypeMismatch: Expected boolean but found string",
+            json.get_bool("name").unwrap_err().to_string()
+        );
+        assert!(matches!(json.get_str("title"), Err(Error::NotFound(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! `bytes` support, converting `json_ez::Json` to and from `bytes::Bytes`
+//! buffers as found in network code.
+
+use std::convert::TryFrom;
+
+use bytes::Bytes;
+use serde_json::Error as SerdeError;
+
+use crate::{Error, Json};
+
+impl Json {
+    /// Serialize this `Json` instance as a `Bytes` buffer, see `Json::to_vec`.
+    ///
+    /// # Example
+    /// ```
+    /// use bytes::Bytes;
+    /// use json_ez::{inline, Json};
+    /// use std::convert::TryFrom;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let json = inline!("title" => "Mostly Harmless");
+    ///     let body: Bytes = json.to_bytes()?;
+    ///
+    ///     assert_eq!(json, Json::try_from(body)?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(serde_json::error::Error)` if the document
+    /// cannot be represented as JSON
+    pub fn to_bytes(&self) -> Result<Bytes, SerdeError> {
+        self.to_vec().map(Bytes::from)
+    }
+
+    /// Serialize this `Json` instance as `Bytes` chunks produced on demand,
+    /// see `Json::to_string_stream`.
+    ///
+    /// # Panics
+    /// Panic if `chunk_size` is zero
+    pub fn to_bytes_stream(&self, chunk_size: usize) -> impl Iterator<Item = Bytes> + '_ {
+        self.to_string_stream(chunk_size).map(Bytes::from)
+    }
+}
+
+impl TryFrom<Bytes> for Json {
+    type Error = Error;
+
+    fn try_from(bytes: Bytes) -> Result<Self, Error> {
+        Json::from_slice(&bytes)
+    }
+}
+
+impl TryFrom<&Bytes> for Json {
+    type Error = Error;
+
+    fn try_from(bytes: &Bytes) -> Result<Self, Error> {
+        Json::from_slice(bytes)
+    }
+}
@@ -0,0 +1,473 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Assembling nested documents from flattened sources, such as
+//! records emitted as `(path, value)` pairs or query strings.
+
+use std::env;
+
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+use crate::path::{self, Segment};
+use crate::ser::{self, NonFinite};
+use crate::{Error, InvalidPath, Json, TypeMismatch};
+
+/// Handling of the keys repeated in query strings, such as `tag=a&tag=b`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RepeatedKeys {
+    /// Keep the first value
+    First,
+    /// Keep the last value
+    #[default]
+    Last,
+    /// Collect all values in an array
+    Array,
+}
+
+/// Infer the type of a textual value: booleans and numbers
+/// written as in JSON are converted, anything else is kept as a string.
+fn infer(s: &str) -> Value {
+    match s {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => match serde_json::from_str::<Number>(s) {
+            Ok(n) => Value::Number(n),
+            Err(_) => Value::String(s.into()),
+        },
+    }
+}
+
+/// Assemble a document from `PREFIX_KEY__NESTED` variables.
+fn from_vars<I>(prefix: &str, vars: I) -> Result<Json, Error>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut vars: Vec<_> = vars
+        .into_iter()
+        .filter_map(|(k, v)| k.strip_prefix(prefix).map(|k| (k.to_lowercase(), v)))
+        .collect();
+    vars.sort();
+    let mut json = Json::new();
+    for (name, value) in vars {
+        if name.split("__").any(str::is_empty) {
+            continue;
+        }
+        let segments: Vec<Segment> = name.split("__").map(|k| Segment::Key(k.into())).collect();
+        json.insert_at(&segments, infer(&value))?;
+    }
+    Ok(json)
+}
+
+/// A step of a bracketed query key, such as `movie[cast][][name]`.
+enum QueryStep {
+    Key(String),
+    Index(usize),
+    Append,
+}
+
+/// Split a query key in steps, taking it literally if its brackets are unbalanced.
+fn query_steps(key: &str) -> Vec<QueryStep> {
+    let literal = || vec![QueryStep::Key(key.into())];
+    let (name, mut rest) = match key.find('[') {
+        Some(0) | None => return literal(),
+        Some(i) => key.split_at(i),
+    };
+    let mut steps = vec![QueryStep::Key(name.into())];
+    while !rest.is_empty() {
+        let inner = match (rest.strip_prefix('['), rest.find(']')) {
+            (Some(_), Some(end)) => &rest[1..end],
+            _ => return literal(),
+        };
+        steps.push(if inner.is_empty() {
+            QueryStep::Append
+        } else if let Ok(i) = inner.parse() {
+            QueryStep::Index(i)
+        } else {
+            QueryStep::Key(inner.into())
+        });
+        rest = &rest[inner.len() + 2..];
+    }
+    steps
+}
+
+/// Set the value of a query key, split in steps by `query_steps`.
+fn insert_query(
+    slot: &mut Value,
+    key: &str,
+    steps: &[QueryStep],
+    value: &str,
+    repeated: RepeatedKeys,
+) -> Result<(), Error> {
+    let (step, rest) = match steps.split_first() {
+        Some(split) => split,
+        None => {
+            match (slot.take(), repeated) {
+                (Value::Null, _) | (_, RepeatedKeys::Last) => *slot = value.into(),
+                (first, RepeatedKeys::First) => *slot = first,
+                (Value::Array(mut items), RepeatedKeys::Array) => {
+                    items.push(value.into());
+                    *slot = Value::Array(items);
+                }
+                (other, RepeatedKeys::Array) => *slot = Value::Array(vec![other, value.into()]),
+            }
+            return Ok(());
+        }
+    };
+    match step {
+        QueryStep::Key(name) => {
+            if slot.is_null() {
+                *slot = Value::Object(Map::new());
+            }
+            match slot {
+                Value::Object(map) => {
+                    let slot = map.entry(name.clone()).or_insert(Value::Null);
+                    insert_query(slot, key, rest, value, repeated)
+                }
+                other => Err(TypeMismatch::new("object", other).into()),
+            }
+        }
+        _ => {
+            if slot.is_null() {
+                *slot = Value::Array(Vec::new());
+            }
+            match slot {
+                Value::Array(items) => {
+                    let i = match step {
+                        QueryStep::Index(i) => *i,
+                        _ => items.len(),
+                    };
+                    if i > items.len().saturating_add(path::MAX_PADDING) {
+                        let reason = format!(
+                            "index more than {} past the end of the array",
+                            path::MAX_PADDING
+                        );
+                        return Err(InvalidPath::new(key, &reason).into());
+                    }
+                    if items.len() <= i {
+                        items.resize(i + 1, Value::Null);
+                    }
+                    insert_query(&mut items[i], key, rest, value, repeated)
+                }
+                other => Err(TypeMismatch::new("array", other).into()),
+            }
+        }
+    }
+}
+
+impl Json {
+    /// Assemble a nested document from `(path, value)` pairs, where paths
+    /// locate values with dot separated keys and bracketed array indices.
+    /// Missing objects and arrays are created, arrays being padded with
+    /// `null`s, and later pairs override earlier ones.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::Json;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let json = Json::from_kv_stream(vec![
+    ///         ("movie.title", "The Hitchhiker's Guide to the Galaxy"),
+    ///         ("movie.cast[0].name", "Martin Freeman"),
+    ///     ])?;
+    ///
+    ///     assert_eq!(
+    ///         "Martin Freeman",
+    ///         json.get::<serde_json::Value>("movie")?["cast"][0]["name"]
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if a path cannot be parsed,
+    /// holds wildcards or an index more than 1024 past the end of an array, an
+    /// `Err(json_ez::Error::TypeMismatch)` if it goes through
+    /// a value which is not an object or an array, or an
+    /// `Err(json_ez::Error::Json)` if a value cannot be represented as JSON
+    pub fn from_kv_stream<I, P, V>(pairs: I) -> Result<Json, Error>
+    where
+        I: IntoIterator<Item = (P, V)>,
+        P: AsRef<str>,
+        V: Serialize,
+    {
+        let mut json = Json::new();
+        for (p, v) in pairs {
+            let segments = path::parse_concrete(p.as_ref())?;
+            json.insert_at(&segments, ser::to_value(&v, NonFinite::Null, None)?)?;
+        }
+        Ok(json)
+    }
+
+    /// Assemble a document from decoded query string pairs, with string
+    /// values. Bracketed keys are nested: `movie[title]` sets the `title`
+    /// key of the `movie` object, `tags[]` appends to the `tags` array and
+    /// `cast[0]` sets its first element. Keys repeated without brackets
+    /// are handled according to the given policy.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{Json, RepeatedKeys};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let pairs = vec![("movie[title]", "H2G2"), ("tag", "sci-fi"), ("tag", "comedy")];
+    ///     let json = Json::from_query_pairs(pairs, RepeatedKeys::Array)?;
+    ///
+    ///     assert_eq!(vec!["sci-fi", "comedy"], json.get::<Vec<String>>("tag")?);
+    ///     assert_eq!("H2G2", json.get::<serde_json::Value>("movie")?["title"]);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::TypeMismatch)` if keys conflict,
+    /// such as `movie=H2G2` and `movie[title]=H2G2`, or an
+    /// `Err(json_ez::Error::InvalidPath)` if a key holds an index more
+    /// than 1024 past the end of an array, such as `ids[4000000000]`
+    pub fn from_query_pairs<I, K, V>(pairs: I, repeated: RepeatedKeys) -> Result<Json, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut root = Value::Object(Map::new());
+        for (k, v) in pairs {
+            let k = k.as_ref();
+            insert_query(&mut root, k, &query_steps(k), v.as_ref(), repeated)?;
+        }
+        match root {
+            Value::Object(map) => Ok(Json::from_data(map.into_iter().collect())),
+            _ => unreachable!("query keys always start with a name"),
+        }
+    }
+
+    /// Assemble a document from the environment variables starting with
+    /// the given prefix, such as `APP_SERVER__PORT=8080` for the `APP_`
+    /// prefix. Double underscores separate nested keys, which are
+    /// lowercased, and values written as JSON booleans or numbers are
+    /// converted. Variables which are not valid unicode are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::Json;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     std::env::set_var("H2G2_SERVER__PORT", "8080");
+    ///     std::env::set_var("H2G2_DEBUG", "true");
+    ///     let json = Json::from_env("H2G2_")?;
+    ///
+    ///     assert!(json.get::<bool>("debug")?);
+    ///     assert_eq!(8080, json.get::<serde_json::Value>("server")?["port"]);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::TypeMismatch)` if variables
+    /// conflict, such as `APP_SERVER=on` and `APP_SERVER__PORT=8080`
+    pub fn from_env(prefix: &str) -> Result<Json, Error> {
+        let vars = env::vars_os()
+            .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)));
+        from_vars(prefix, vars)
+    }
+
+    /// Assemble a document from `path=value` arguments, such as the ones
+    /// given to a `--set` command line option, values written as JSON
+    /// booleans or numbers being converted. Paths locate values like in
+    /// `Json::from_kv_stream`.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut config = inline!("server" => inline!("host" => "localhost", "port" => 80));
+    ///     config.merge(Json::from_kv_args(&["server.port=8080", "debug=true"])?);
+    ///
+    ///     assert!(config.get::<bool>("debug")?);
+    ///     assert_eq!(
+    ///         inline!("host" => "localhost", "port" => 8080),
+    ///         config.get::<Json>("server")?
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if an argument has no `=`
+    /// separator or an invalid path, like `Json::from_kv_stream`, or an
+    /// `Err(json_ez::Error::TypeMismatch)` if arguments conflict, such as
+    /// `server=on` and `server.port=8080`
+    pub fn from_kv_args<I, S>(args: I) -> Result<Json, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut json = Json::new();
+        for arg in args {
+            let arg = arg.as_ref();
+            let (path, value) = match arg.find('=') {
+                Some(i) => (&arg[..i], &arg[i + 1..]),
+                None => return Err(InvalidPath::new(arg, "missing '=' separator").into()),
+            };
+            json.insert_at(&path::parse_concrete(path)?, infer(value))?;
+        }
+        Ok(json)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ErrorCode;
+    use serde_json::{json, Value};
+    use std::error::Error;
+
+    #[test]
+    fn kv_stream() -> Result<(), Box<dyn Error>> {
+        let json = Json::from_kv_stream(vec![
+            ("a.b[0].c", json!(1)),
+            ("a.b[2]", json!("x")),
+            ("a.b[0].d", json!(null)),
+            ("e", json!([true])),
+            ("a\\.b", json!(2)),
+        ])?;
+        assert_eq!(
+            json!({ "b": [{ "c": 1, "d": null }, null, "x"] }),
+            json.get::<Value>("a")?
+        );
+        assert!(json.get::<Vec<bool>>("e")?[0]);
+        assert_eq!(2, json.get::<u8>("a.b")?);
+        Ok(())
+    }
+
+    #[test]
+    fn query_pairs() -> Result<(), Box<dyn Error>> {
+        let pairs = vec![
+            ("q", "towel"),
+            ("q", "babel fish"),
+            ("page[size]", "10"),
+            ("cast[][name]", "Martin"),
+            ("cast[][name]", "Mos"),
+            ("ids[1]", "b"),
+            ("broken[key", "x"),
+            ("[empty]", "y"),
+        ];
+        let last = Json::from_query_pairs(pairs.clone(), RepeatedKeys::Last)?;
+        assert_eq!("babel fish", &last.get::<String>("q")?);
+        assert_eq!(json!({ "size": "10" }), last.get::<Value>("page")?);
+        assert_eq!(
+            json!([{ "name": "Martin" }, { "name": "Mos" }]),
+            last.get::<Value>("cast")?
+        );
+        assert_eq!(json!([null, "b"]), last.get::<Value>("ids")?);
+        assert_eq!("x", &last.get::<String>("broken[key")?);
+        assert_eq!("y", &last.get::<String>("[empty]")?);
+        let first = Json::from_query_pairs(pairs.clone(), RepeatedKeys::First)?;
+        assert_eq!("towel", &first.get::<String>("q")?);
+        let array = Json::from_query_pairs(pairs, RepeatedKeys::Array)?;
+        assert_eq!(vec!["towel", "babel fish"], array.get::<Vec<String>>("q")?);
+
+        let conflict = Json::from_query_pairs(vec![("a", "1"), ("a[b]", "2")], RepeatedKeys::Last);
+        assert!(conflict.is_err());
+        for key in ["ids[18446744073709551615]", "ids[4000000000]"] {
+            let err = Json::from_query_pairs(vec![(key, "1")], RepeatedKeys::Last).unwrap_err();
+            assert_eq!(ErrorCode::InvalidPath, err.code());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn env_vars() -> Result<(), Box<dyn Error>> {
+        let vars = vec![
+            ("APP_SERVER__PORT", "8080"),
+            ("APP_SERVER__HOST", "localhost"),
+            ("APP_LOG_LEVEL", "debug"),
+            ("APP_RATIO", "0.5"),
+            ("APP_ZIP", "00501"),
+            ("APP_DEBUG", "false"),
+            ("APP_", "ignored"),
+            ("APP_A____B", "ignored"),
+            ("OTHER", "ignored"),
+        ];
+        let vars = vars
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()));
+        let json = from_vars("APP_", vars)?;
+        assert_eq!(
+            json!({
+                "server": { "port": 8080, "host": "localhost" },
+                "log_level": "debug",
+                "ratio": 0.5,
+                "zip": "00501",
+                "debug": false
+            }),
+            serde_json::to_value(&json)?
+        );
+
+        let conflict = vec![
+            ("APP_A__B".to_string(), "1".to_string()),
+            ("APP_A".into(), "2".into()),
+        ];
+        assert!(from_vars("APP_", conflict).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn kv_args() -> Result<(), Box<dyn Error>> {
+        let json = Json::from_kv_args(vec![
+            "server.port=8080",
+            "server.tags[0]=a=b",
+            "name=",
+            "debug=true",
+            "debug=false",
+        ])?;
+        assert_eq!(
+            json!({ "port": 8080, "tags": ["a=b"] }),
+            json.get::<Value>("server")?
+        );
+        assert_eq!("", &json.get::<String>("name")?);
+        assert!(!json.get::<bool>("debug")?);
+        assert_eq!(
+            "InvalidPath: Cannot parse path debug, missing '=' separator",
+            Json::from_kv_args(["debug"]).unwrap_err().to_string()
+        );
+        assert!(Json::from_kv_args(["=1"]).is_err());
+        assert!(Json::from_kv_args(["a[18446744073709551615]=1"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn kv_stream_errors() {
+        let err = Json::from_kv_stream(vec![("a", 1), ("a.b", 2)]).unwrap_err();
+        assert_eq!(
+            "TypeMismatch: Expected object but found number",
+            err.to_string()
+        );
+        assert!(Json::from_kv_stream(vec![("[0]", 1)]).is_err());
+        assert!(Json::from_kv_stream(vec![("a.*", 1)]).is_err());
+    }
+}
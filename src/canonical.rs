@@ -0,0 +1,199 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Canonical serialization following the JSON Canonicalization Scheme
+//! (RFC 8785), giving the same bytes for equal documents so that they can
+//! be hashed or signed.
+
+use serde_json::{Number, Value};
+
+use crate::Json;
+
+impl Json {
+    /// Serialize this `Json` instance following the JSON Canonicalization
+    /// Scheme (RFC 8785): no whitespace, keys sorted by their UTF-16 code
+    /// units and numbers written like JavaScript does.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let json = inline!("title" => "Mostly Harmless", "rating" => 4.0, "pages" => 1e21);
+    ///
+    /// assert_eq!(
+    ///     r#"{"pages":1e+21,"rating":4,"title":"Mostly Harmless"}"#,
+    ///     json.to_canonical_string()
+    /// );
+    /// ```
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        write_object(&mut out, self.json_data.iter());
+        out
+    }
+}
+
+fn write_object<'a, I: Iterator<Item = (&'a String, &'a Value)>>(out: &mut String, members: I) {
+    let mut members: Vec<(Vec<u16>, &String, &Value)> = members
+        .map(|(k, v)| (k.encode_utf16().collect(), k, v))
+        .collect();
+    members.sort_by(|a, b| a.0.cmp(&b.0));
+    out.push('{');
+    for (i, (_, k, v)) in members.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_member(out, k, v);
+    }
+    out.push('}');
+}
+
+/// Write a member of an object, `"key":value`.
+pub(crate) fn write_member(out: &mut String, k: &str, v: &Value) {
+    write_string(out, k);
+    out.push(':');
+    write_value(out, v);
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&number(n)),
+        Value::String(s) => write_string(out, s),
+        Value::Array(values) => {
+            out.push('[');
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, v);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => write_object(out, map.iter()),
+    }
+}
+
+/// Strings are escaped like `JSON.stringify` does, which is what
+/// `serde_json` does as well.
+fn write_string(out: &mut String, s: &str) {
+    out.push_str(&serde_json::to_string(s).expect("strings are always serializable"));
+}
+
+/// Numbers are IEEE 754 doubles for RFC 8785, integers beyond 2^53 are
+/// written as their nearest double.
+fn number(n: &Number) -> String {
+    es_number(n.as_f64().unwrap_or(f64::NAN))
+}
+
+/// Format a float like ECMAScript `Number.prototype.toString`.
+fn es_number(x: f64) -> String {
+    if x == 0.0 {
+        return "0".into();
+    }
+    if !x.is_finite() {
+        return "null".into();
+    }
+    // Shortest round-tripping digits and exponent, such as 1.5e22
+    let scientific = format!("{:e}", x.abs());
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("scientific notation has an exponent");
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().expect("exponent is an integer") + 1;
+
+    let mut s = String::new();
+    if x < 0.0 {
+        s.push('-');
+    }
+    if k <= n && n <= 21 {
+        s.push_str(&digits);
+        s.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        s.push_str(&digits[..n as usize]);
+        s.push('.');
+        s.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        s.push_str("0.");
+        s.push_str(&"0".repeat(-n as usize));
+        s.push_str(&digits);
+    } else {
+        s.push_str(&digits[..1]);
+        if k > 1 {
+            s.push('.');
+            s.push_str(&digits[1..]);
+        }
+        s.push('e');
+        s.push(if n - 1 < 0 { '-' } else { '+' });
+        s.push_str(&(n - 1).abs().to_string());
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deserialise;
+    use std::error::Error;
+
+    #[test]
+    fn es_numbers() {
+        let cases = [
+            (1.0, "1"),
+            (-0.0, "0"),
+            (0.5, "0.5"),
+            (-12.75, "-12.75"),
+            (1e20, "100000000000000000000"),
+            (1e21, "1e+21"),
+            (1.5e22, "1.5e+22"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-1.25e-9, "-1.25e-9"),
+            (333333333.3333333, "333333333.3333333"),
+            (4.5e-324, "5e-324"),
+            (f64::MAX, "1.7976931348623157e+308"),
+        ];
+        for (x, expected) in &cases {
+            assert_eq!(*expected, es_number(*x), "{}", x);
+        }
+    }
+
+    #[test]
+    fn canonical_documents() -> Result<(), Box<dyn Error>> {
+        let json = deserialise!(
+            // U+FF21 sorts after the U+1F600 surrogate pair in UTF-16
+            r#"{ "b": [1.0, "é", { "z": null, "a": true }], "a\n": 10, "\uff21": 1, "😀": 2 }"#
+        )?;
+        assert_eq!(
+            "{\"a\\n\":10,\"b\":[1,\"é\",{\"a\":true,\"z\":null}],\"\u{1f600}\":2,\"\u{ff21}\":1}",
+            json.to_canonical_string()
+        );
+
+        let json = deserialise!(r#"{ "id": 9007199254740993, "max": 18446744073709551615 }"#)?;
+        assert_eq!(
+            r#"{"id":9007199254740992,"max":18446744073709552000}"#,
+            json.to_canonical_string()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Extraction of a single field across the objects of an array.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::path::pointer_child;
+use crate::{type_name, CoercionError, Error, Json, NotFound, TypeMismatch};
+
+impl Json {
+    /// Extract the given field of every object of the array stored under
+    /// `k`, in a single pass. All the elements are checked, so the error
+    /// reports every faulty one instead of the first.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, Json};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let json = deserialise!(r#"{
+    ///         "measurements": [{ "value": 1.5 }, { "value": 2 }, { "value": "n/a" }, {}]
+    ///     }"#)?;
+    ///
+    ///     let err = json.column::<f64>("measurements", "value").unwrap_err();
+    ///     assert_eq!(
+    ///         "CoercionError: Cannot convert /measurements/2/value: \
+    ///          invalid type: string \"n/a\", expected f64, /measurements/3/value: missing",
+    ///         err.to_string()
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist, an
+    /// `Err(json_ez::Error::TypeMismatch)` if its value is not an array or an
+    /// `Err(json_ez::Error::Coercion)` listing the locations of the elements
+    /// which are not objects and of the fields missing or not holding a `T`
+    pub fn column<T: DeserializeOwned>(&self, k: &str, field: &str) -> Result<Vec<T>, Error> {
+        let items = match self.json_data.get(self.key(k).as_ref()) {
+            Some(Value::Array(items)) => items,
+            Some(other) => return Err(TypeMismatch::new("array", other).into()),
+            None => return Err(NotFound::new(k.into(), self)?.into()),
+        };
+        let array = pointer_child("", k);
+        let mut column = Vec::with_capacity(items.len());
+        let mut failures = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            let element = pointer_child(&array, &i.to_string());
+            let value = match item {
+                Value::Object(members) => members.get(self.key(field).as_ref()),
+                other => {
+                    failures.push(format!(
+                        "{}: expected object but found {}",
+                        element,
+                        type_name(other)
+                    ));
+                    continue;
+                }
+            };
+            let location = pointer_child(&element, field);
+            match value.map(T::deserialize) {
+                Some(Ok(value)) => column.push(value),
+                Some(Err(err)) => failures.push(format!("{}: {}", location, err)),
+                None => failures.push(format!("{}: missing", location)),
+            }
+        }
+        if failures.is_empty() {
+            Ok(column)
+        } else {
+            Err(CoercionError::new(failures).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, ErrorCode};
+    use std::error::Error;
+
+    #[test]
+    fn columns() -> Result<(), Box<dyn Error>> {
+        let mut json = Json::new();
+        json.add(
+            "points",
+            vec![inline!("x" => 1, "y" => 2), inline!("x" => 3, "y" => 4)],
+        );
+        json.add("mixed", serde_json::json!([{ "x": 1 }, 2, { "x": "3" }]));
+        json.add("title", "Points");
+
+        assert_eq!(vec![2, 4], json.column::<u8>("points", "y")?);
+        json.add("empty", Vec::<Json>::new());
+        assert!(json.column::<u8>("empty", "x")?.is_empty());
+
+        let err = json.column::<u8>("mixed", "x").unwrap_err();
+        assert_eq!(ErrorCode::CoercionFailed, err.code());
+        assert_eq!(
+            "CoercionError: Cannot convert /mixed/1: expected object but found number, \
+             /mixed/2/x: invalid type: string \"3\", expected u8",
+            err.to_string()
+        );
+        assert_eq!(
+            ErrorCode::TypeMismatch,
+            json.column::<u8>("title", "x").unwrap_err().code()
+        );
+        assert_eq!(
+            ErrorCode::NotFound,
+            json.column::<u8>("dots", "x").unwrap_err().code()
+        );
+        Ok(())
+    }
+}
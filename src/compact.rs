@@ -0,0 +1,118 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Compact storage, releasing the spare capacity of the strings, keys
+//! and arrays of documents kept in memory for long.
+
+use serde_json::{Map, Value};
+
+use crate::Json;
+
+impl Json {
+    /// Enable or disable compact storage. Once enabled, the document is
+    /// shrunk with `Json::shrink_to_fit` and so are the values added
+    /// afterwards, trading a few reallocations on insertion for documents
+    /// holding no spare capacity.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let mut json = inline!("title" => "Mostly Harmless");
+    /// json.set_compact(true);
+    ///
+    /// json.add("author", "Douglas Adams");
+    /// assert_eq!("Douglas Adams", json.get::<String>("author").unwrap());
+    /// ```
+    pub fn set_compact(&mut self, enabled: bool) {
+        self.compact = enabled;
+        if enabled {
+            self.shrink_to_fit();
+        }
+    }
+
+    /// Release the spare capacity of the keys and the nested strings,
+    /// arrays and objects of the document. Documents built with `String`s
+    /// grown while parsing or formatting commonly allocate a lot more
+    /// memory than they hold, which adds up when keeping many of them.
+    pub fn shrink_to_fit(&mut self) {
+        self.json_data = self
+            .json_data
+            .drain()
+            .map(|(k, v)| (shrink_key(k), shrink_value(v)))
+            .collect();
+    }
+}
+
+/// The given key without spare capacity.
+pub(crate) fn shrink_key(key: String) -> String {
+    // Going through a boxed str reallocates the key to its exact length
+    key.into_boxed_str().into()
+}
+
+/// The given value with no spare capacity, nested values included.
+pub(crate) fn shrink_value(value: Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(shrink_key(s)),
+        Value::Array(items) => {
+            let items: Vec<Value> = items.into_iter().map(shrink_value).collect();
+            Value::Array(items.into_boxed_slice().into())
+        }
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (shrink_key(k), shrink_value(v)))
+                .collect::<Map<_, _>>(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shrink() {
+        let mut key = String::with_capacity(64);
+        key.push_str("title");
+        let mut title = String::with_capacity(64);
+        title.push_str("Mostly Harmless");
+        let mut tags = Vec::with_capacity(16);
+        tags.push(Value::from("novel"));
+
+        let mut json = Json::new();
+        json.add(&key, title);
+        json.add("tags", tags);
+        json.set_compact(true);
+        json.add("author", String::with_capacity(64) + "Douglas Adams");
+
+        for (k, v) in &json.json_data {
+            assert_eq!(k.len(), k.capacity());
+            match v {
+                Value::String(s) => assert_eq!(s.len(), s.capacity()),
+                Value::Array(items) => assert_eq!(items.len(), items.capacity()),
+                _ => unreachable!(),
+            }
+        }
+        assert_eq!("Mostly Harmless", json.get::<String>("title").unwrap());
+    }
+}
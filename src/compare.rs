@@ -0,0 +1,561 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Relaxed comparisons between documents.
+
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+use crate::path::{matches, parse, InvalidPath, Segment, Step};
+use crate::{Error, Json};
+
+/// Placeholder values matching any value of a given kind when
+/// comparing documents with `Json::eq_ignoring`.
+///
+/// Matchers are serialized as reserved strings (`"<any uuid>"`...),
+/// so they can be added to a document like any other value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Matcher {
+    /// Match any value, including `null`
+    Any,
+    /// Match any string
+    AnyString,
+    /// Match any number
+    AnyNumber,
+    /// Match any boolean
+    AnyBoolean,
+    /// Match any string holding a UUID (`8-4-4-4-12` hexadecimal digits)
+    AnyUuid,
+}
+
+impl Matcher {
+    fn as_str(self) -> &'static str {
+        match self {
+            Matcher::Any => "<any>",
+            Matcher::AnyString => "<any string>",
+            Matcher::AnyNumber => "<any number>",
+            Matcher::AnyBoolean => "<any boolean>",
+            Matcher::AnyUuid => "<any uuid>",
+        }
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        let matcher = match value.as_str()? {
+            "<any>" => Matcher::Any,
+            "<any string>" => Matcher::AnyString,
+            "<any number>" => Matcher::AnyNumber,
+            "<any boolean>" => Matcher::AnyBoolean,
+            "<any uuid>" => Matcher::AnyUuid,
+            _ => return None,
+        };
+        Some(matcher)
+    }
+
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            Matcher::Any => true,
+            Matcher::AnyString => value.is_string(),
+            Matcher::AnyNumber => value.is_number(),
+            Matcher::AnyBoolean => value.is_boolean(),
+            Matcher::AnyUuid => value.as_str().is_some_and(is_uuid),
+        }
+    }
+}
+
+impl Serialize for Matcher {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip(&[8, 4, 4, 4, 12])
+            .all(|(g, len)| g.len() == *len && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// A configurable comparison between documents.
+///
+/// # Example
+/// ```
+/// use json_ez::{inline, Comparison};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let response = inline!(
+///         "tags" => vec!["sci-fi", "novel"],
+///         "novels" => vec![
+///             inline!("id" => 2, "title" => "The Restaurant at the End of the Universe"),
+///             inline!("id" => 1, "title" => "The Hitchhiker's Guide to the Galaxy")
+///         ],
+///         "total" => 2
+///     );
+///     let expected = inline!(
+///         "tags" => vec!["novel", "sci-fi"],
+///         "novels" => vec![
+///             inline!("id" => 1, "title" => "The Hitchhiker's Guide to the Galaxy"),
+///             inline!("id" => 2, "title" => "The Restaurant at the End of the Universe")
+///         ]
+///     );
+///
+///     let comparison = Comparison::new()
+///         .unordered_arrays()
+///         .key_arrays_by("novels", "id")?;
+///
+///     assert!(!comparison.equal(&response, &expected));
+///     assert!(comparison.contains(&response, &expected));
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Comparison {
+    epsilon: f64,
+    ignored: Vec<Vec<Segment>>,
+    matchers: bool,
+    unordered: bool,
+    keys: Vec<(Vec<Segment>, String)>,
+}
+
+impl Comparison {
+    /// Create a new `Comparison`, strict until configured otherwise.
+    pub fn new() -> Self {
+        Comparison::default()
+    }
+
+    /// Consider numbers as equal when they differ by at most `epsilon`.
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Skip the values located at the given path pattern,
+    /// whether they are present on both sides or not.
+    /// # Errors
+    /// Return an `Err(json_ez::InvalidPath)` if the pattern cannot be parsed
+    pub fn ignore(mut self, pattern: &str) -> Result<Self, InvalidPath> {
+        self.ignored.push(parse(pattern)?);
+        Ok(self)
+    }
+
+    /// Honor `Matcher` placeholders found on either side.
+    pub fn with_matchers(mut self) -> Self {
+        self.matchers = true;
+        self
+    }
+
+    /// Compare every array as a multiset, ignoring the order of its elements.
+    pub fn unordered_arrays(mut self) -> Self {
+        self.unordered = true;
+        self
+    }
+
+    /// Compare the arrays located at the given path pattern as multisets,
+    /// pairing objects holding the same value for `field` before comparing them.
+    /// # Errors
+    /// Return an `Err(json_ez::InvalidPath)` if the pattern cannot be parsed
+    pub fn key_arrays_by(mut self, pattern: &str, field: &str) -> Result<Self, InvalidPath> {
+        self.keys.push((parse(pattern)?, field.into()));
+        Ok(self)
+    }
+
+    /// Whether both documents are equal according to this comparison.
+    pub fn equal(&self, left: &Json, right: &Json) -> bool {
+        self.documents(left, right, false)
+    }
+
+    /// Whether every value of `part` is found in `whole` according to this
+    /// comparison: objects of `whole` may hold additional keys and, when
+    /// compared as multisets, arrays of `whole` may hold additional elements.
+    pub fn contains(&self, whole: &Json, part: &Json) -> bool {
+        self.documents(whole, part, true)
+    }
+
+    fn ignores(&self, location: &[Step]) -> bool {
+        self.ignored.iter().any(|p| matches(p, location))
+    }
+
+    fn key_for(&self, location: &[Step]) -> Option<&str> {
+        self.keys
+            .iter()
+            .find(|(p, _)| matches(p, location))
+            .map(|(_, field)| field.as_str())
+    }
+
+    fn values<'a>(
+        &self,
+        left: &'a Value,
+        right: &'a Value,
+        location: &mut Vec<Step<'a>>,
+        subset: bool,
+    ) -> bool {
+        if self.matchers {
+            if let Some(matcher) = Matcher::from_value(left) {
+                return matcher.accepts(right);
+            }
+            if let Some(matcher) = Matcher::from_value(right) {
+                return matcher.accepts(left);
+            }
+        }
+        match (left, right) {
+            (Value::Number(l), Value::Number(r)) if l != r => match (l.as_f64(), r.as_f64()) {
+                (Some(l), Some(r)) => (l - r).abs() <= self.epsilon,
+                _ => false,
+            },
+            (Value::Array(l), Value::Array(r)) => self.arrays(l, r, location, subset),
+            (Value::Object(l), Value::Object(r)) => self.entries(
+                l.iter().map(|(k, v)| (k.as_str(), v)),
+                r.iter().map(|(k, v)| (k.as_str(), v)),
+                |k| l.get(k),
+                |k| r.get(k),
+                location,
+                subset,
+            ),
+            (l, r) => l == r,
+        }
+    }
+
+    fn element<'a>(
+        &self,
+        left: &'a Value,
+        right: &'a Value,
+        i: usize,
+        location: &mut Vec<Step<'a>>,
+        subset: bool,
+    ) -> bool {
+        location.push(Step::Index(i));
+        let equal = self.ignores(location) || self.values(left, right, location, subset);
+        location.pop();
+        equal
+    }
+
+    fn arrays<'a>(
+        &self,
+        left: &'a [Value],
+        right: &'a [Value],
+        location: &mut Vec<Step<'a>>,
+        subset: bool,
+    ) -> bool {
+        let key = self.key_for(location);
+        if !self.unordered && key.is_none() {
+            return left.len() == right.len()
+                && left
+                    .iter()
+                    .zip(right)
+                    .enumerate()
+                    .all(|(i, (l, r))| self.element(l, r, i, location, subset));
+        }
+
+        if left.len() < right.len() || (!subset && left.len() != right.len()) {
+            return false;
+        }
+        // Pair every element of the right side with a distinct one of the
+        // left side, among the ones it matches
+        let mut candidates = Vec::with_capacity(right.len());
+        for r in right {
+            let expected_key = key.and_then(|k| r.get(k).map(|v| (k, v)));
+            let matching: Vec<usize> = (0..left.len())
+                .filter(|&i| {
+                    expected_key.is_none_or(|(k, v)| left[i].get(k) == Some(v))
+                        && self.element(&left[i], r, i, location, subset)
+                })
+                .collect();
+            if matching.is_empty() {
+                return false;
+            }
+            candidates.push(matching);
+        }
+        let mut owners = vec![None; left.len()];
+        (0..right.len()).all(|r| augment(r, &candidates, &mut owners))
+    }
+
+    fn entries<'a, L, R, FL, FR>(
+        &self,
+        left: L,
+        right: R,
+        in_left: FL,
+        in_right: FR,
+        location: &mut Vec<Step<'a>>,
+        subset: bool,
+    ) -> bool
+    where
+        L: Iterator<Item = (&'a str, &'a Value)>,
+        R: Iterator<Item = (&'a str, &'a Value)>,
+        FL: Fn(&str) -> Option<&'a Value>,
+        FR: Fn(&str) -> Option<&'a Value>,
+    {
+        // Keys only present on the left side must be ignored, unless looking for a subset
+        for (k, l) in left {
+            location.push(Step::Key(k));
+            let equal = self.ignores(location)
+                || match in_right(k) {
+                    Some(r) => self.values(l, r, location, subset),
+                    None => subset,
+                };
+            location.pop();
+            if !equal {
+                return false;
+            }
+        }
+        // Keys only present on the right side must be ignored
+        for (k, _) in right.filter(|(k, _)| in_left(k).is_none()) {
+            location.push(Step::Key(k));
+            let ignored = self.ignores(location);
+            location.pop();
+            if !ignored {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn documents(&self, left: &Json, right: &Json, subset: bool) -> bool {
+        self.entries(
+            left.json_data.iter().map(|(k, v)| (k.as_str(), v)),
+            right.json_data.iter().map(|(k, v)| (k.as_str(), v)),
+            |k| left.json_data.get(k),
+            |k| right.json_data.get(k),
+            &mut Vec::new(),
+            subset,
+        )
+    }
+}
+
+/// Pair the element `start` of the right side of a bipartite matching with
+/// one of its candidates on the left side, owned by the given elements of the
+/// right side, looking for an augmenting path if they are all taken already.
+/// Return whether a pair was found, `owners` being updated accordingly.
+fn augment(start: usize, candidates: &[Vec<usize>], owners: &mut [Option<usize>]) -> bool {
+    if let Some(&l) = candidates[start].iter().find(|&&l| owners[l].is_none()) {
+        owners[l] = Some(start);
+        return true;
+    }
+    let mut visited = vec![false; owners.len()];
+    // Elements of the right side being moved, with their next candidate to
+    // try, and the elements of the left side they would be moved to
+    let mut stack = vec![(start, 0)];
+    let mut path: Vec<usize> = Vec::new();
+    while let Some(frame) = stack.last_mut() {
+        let (r, next) = *frame;
+        frame.1 += 1;
+        let l = match candidates[r].get(next) {
+            Some(&l) => l,
+            None => {
+                stack.pop();
+                path.pop();
+                continue;
+            }
+        };
+        if visited[l] {
+            continue;
+        }
+        visited[l] = true;
+        path.push(l);
+        match owners[l] {
+            Some(owner) => stack.push((owner, 0)),
+            None => {
+                for (&(r, _), &l) in stack.iter().zip(&path) {
+                    owners[l] = Some(r);
+                }
+                return true;
+            }
+        }
+    }
+    false
+}
+
+impl Json {
+    /// Compare two `Json` instances, considering numbers as equal when
+    /// they differ by at most `epsilon`. Any other value must be strictly equal.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let computed = inline!("ratio" => 0.1 + 0.2, "count" => 3);
+    /// let expected = inline!("ratio" => 0.3, "count" => 3);
+    ///
+    /// assert!(computed != expected);
+    /// assert!(computed.approx_eq(&expected, 1e-9));
+    /// ```
+    pub fn approx_eq(&self, other: &Json, epsilon: f64) -> bool {
+        Comparison::new().epsilon(epsilon).equal(self, other)
+    }
+
+    /// Compare two `Json` instances, skipping the values located at the
+    /// given path patterns (`meta.request_id`, `*.updated_at`, `**.etag`...),
+    /// whether they are present on both sides or not.
+    ///
+    /// Values equal to a `Matcher` placeholder on either side only need
+    /// to be of the kind described by the matcher.
+    /// See `json_ez::Comparison` for more comparison options.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Matcher};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let response = inline!(
+    ///         "id" => "936da01f-9abd-4d9d-80c7-02af85c822a8",
+    ///         "meta" => inline!("request_id" => 42),
+    ///         "user" => inline!("name" => "Arthur", "updated_at" => "2005-04-28")
+    ///     );
+    ///     let expected = inline!(
+    ///         "id" => Matcher::AnyUuid,
+    ///         "meta" => inline!(),
+    ///         "user" => inline!("name" => "Arthur")
+    ///     );
+    ///
+    ///     assert!(response.eq_ignoring(&expected, &["*.updated_at", "meta.request_id"])?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if a pattern cannot be parsed
+    pub fn eq_ignoring(&self, other: &Json, ignored: &[&str]) -> Result<bool, Error> {
+        let comparison = ignored
+            .iter()
+            .try_fold(Comparison::new().with_matchers(), |c, p| c.ignore(p))?;
+        Ok(comparison.equal(self, other))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+    use std::error::Error;
+
+    #[test]
+    fn approx_eq() {
+        let left = inline!("metrics" => inline!("cpu" => 0.1 + 0.2, "samples" => vec![1.0, 2.0]));
+        assert!(left.approx_eq(
+            &inline!("metrics" => inline!("cpu" => 0.3, "samples" => vec![1, 2])),
+            1e-9
+        ));
+        assert!(!left.approx_eq(
+            &inline!("metrics" => inline!("cpu" => 0.31, "samples" => vec![1, 2])),
+            1e-9
+        ));
+        assert!(!left.approx_eq(&inline!("metrics" => inline!("cpu" => 0.3)), 1e-9));
+        assert!(!inline!("a" => "1").approx_eq(&inline!("a" => 1), 1.0));
+    }
+
+    #[test]
+    fn eq_ignoring_paths() -> Result<(), Box<dyn Error>> {
+        let left = inline!(
+            "items" => vec![
+                inline!("name" => "towel", "updated_at" => 1),
+                inline!("name" => "guide", "updated_at" => 2)
+            ],
+            "etag" => "abc"
+        );
+        let right = inline!(
+            "items" => vec![inline!("name" => "towel"), inline!("name" => "guide", "updated_at" => 3)]
+        );
+        assert!(left.eq_ignoring(&right, &["items[*].updated_at", "etag"])?);
+        assert!(left.eq_ignoring(&right, &["**.updated_at", "etag"])?);
+        assert!(!left.eq_ignoring(&right, &["items[1].updated_at", "etag"])?);
+        assert!(!left.eq_ignoring(&right, &["items[*].updated_at"])?);
+        assert!(left.eq_ignoring(&right, &["a..b"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn eq_ignoring_matchers() -> Result<(), Box<dyn Error>> {
+        let actual = inline!(
+            "id" => "936da01f-9abd-4d9d-80c7-02af85c822a8",
+            "name" => "Arthur",
+            "age" => 42,
+            "tags" => vec!["towel"]
+        );
+        let expected = inline!(
+            "id" => Matcher::AnyUuid,
+            "name" => Matcher::AnyString,
+            "age" => Matcher::AnyNumber,
+            "tags" => Matcher::Any
+        );
+        assert!(actual.eq_ignoring(&expected, &[])?);
+        assert!(expected.eq_ignoring(&actual, &[])?);
+        assert!(
+            !inline!("id" => "not-a-uuid").eq_ignoring(&inline!("id" => Matcher::AnyUuid), &[])?
+        );
+        assert!(!inline!("age" => "42").eq_ignoring(&inline!("age" => Matcher::AnyNumber), &[])?);
+        Ok(())
+    }
+
+    #[test]
+    fn unordered_arrays() {
+        let left = inline!("tags" => vec!["a", "b", "a"], "nested" => vec![vec![1, 2], vec![3]]);
+        let right = inline!("tags" => vec!["a", "a", "b"], "nested" => vec![vec![3], vec![2, 1]]);
+        assert!(left != right);
+        assert!(Comparison::new().unordered_arrays().equal(&left, &right));
+        assert!(!Comparison::new().unordered_arrays().equal(
+            &left,
+            &inline!("tags" => vec!["a", "b", "b"], "nested" => vec![vec![3], vec![2, 1]])
+        ));
+    }
+
+    #[test]
+    fn unordered_arrays_pairing() {
+        // Pairing each element with the first one it matches is not enough
+        let whole = inline!("xs" => vec![inline!("a" => 1, "b" => 2), inline!("a" => 1)]);
+        let part = inline!("xs" => vec![inline!("a" => 1), inline!("a" => 1, "b" => 2)]);
+        assert!(Comparison::new().unordered_arrays().contains(&whole, &part));
+        let left = inline!("xs" => vec![1.0, 1.1]);
+        let right = inline!("xs" => vec![1.05, 1.0]);
+        let approx = Comparison::new().epsilon(0.06).unordered_arrays();
+        assert!(approx.equal(&left, &right));
+        assert!(!approx.equal(&left, &inline!("xs" => vec![1.0, 1.0])));
+        assert!(approx.contains(&left, &inline!("xs" => vec![1.05])));
+
+        let many: Vec<u32> = (0..2000).map(|i| i % 3).collect();
+        let mut shuffled = many.clone();
+        shuffled.reverse();
+        let left = inline!("xs" => many);
+        assert!(Comparison::new()
+            .unordered_arrays()
+            .equal(&left, &inline!("xs" => shuffled)));
+    }
+
+    #[test]
+    fn keyed_arrays() -> Result<(), Box<dyn Error>> {
+        let left = inline!("items" => vec![
+            inline!("id" => 1, "qty" => 2, "price" => 9.99),
+            inline!("id" => 2, "qty" => 2)
+        ]);
+        let right = inline!("items" => vec![inline!("id" => 2, "qty" => 2), inline!("id" => 1, "qty" => 2)]);
+        let keyed = Comparison::new().key_arrays_by("items", "id")?;
+        assert!(!keyed.equal(&left, &right));
+        assert!(keyed.contains(&left, &right));
+        assert!(!keyed.contains(&right, &left));
+        assert!(keyed.contains(&left, &inline!("items" => vec![inline!("id" => 2)])));
+        assert!(!keyed.contains(&left, &inline!("items" => vec![inline!("id" => 3)])));
+        // Ordered arrays must have the same length, even when looking for a subset
+        assert!(!Comparison::new().contains(&left, &inline!("items" => vec![inline!("id" => 1)])));
+        Ok(())
+    }
+}
@@ -0,0 +1,486 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Compatibility checks between two versions of a JSON Schema, in the
+//! fashion of schema registries: a new version is backward compatible if
+//! every document valid under the old one is still valid, and forward
+//! compatible if every document valid under the new one was valid under
+//! the old one.
+//!
+//! Checks are conservative: a change is reported whenever the keywords of
+//! the narrower version cannot be shown to accept every document accepted
+//! by the wider one. `$ref` is not resolved.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use serde_json::{Map, Value};
+
+use crate::error::{self, ErrorCode};
+use crate::path::pointer_child;
+use crate::validate;
+use crate::{Json, Schema};
+
+/// Bounds compared between versions: a lower bound must not be raised
+/// and an upper bound must not be lowered.
+const LOWER_BOUNDS: &[&str] = &[
+    "minimum",
+    "exclusiveMinimum",
+    "minLength",
+    "minItems",
+    "minProperties",
+];
+const UPPER_BOUNDS: &[&str] = &[
+    "maximum",
+    "exclusiveMaximum",
+    "maxLength",
+    "maxItems",
+    "maxProperties",
+];
+/// Keywords which must be kept unchanged, and not be added.
+const EXACT: &[&str] = &["pattern", "format", "multipleOf", "uniqueItems"];
+
+impl Schema {
+    /// Whether every document valid under `old` is valid under `new`,
+    /// so that consumers can upgrade to `new` before producers do.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, Schema};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let old = Schema::new(deserialise!(r#"{
+    ///         "properties": { "id": { "type": "integer" } },
+    ///         "required": ["id"]
+    ///     }"#)?);
+    ///     let new = Schema::new(deserialise!(r#"{
+    ///         "properties": { "id": { "type": "number" }, "email": { "type": "string" } },
+    ///         "required": ["id", "email"]
+    ///     }"#)?);
+    ///
+    ///     assert!(!Schema::is_backward_compatible(&old, &new));
+    ///     let err = Schema::check_backward_compatibility(&old, &new).unwrap_err();
+    ///     assert_eq!(
+    ///         [
+    ///             "#: property email became required",
+    ///             "#/properties/email: type restricted to string"
+    ///         ],
+    ///         err.changes()
+    ///     );
+    ///     assert!(!Schema::is_forward_compatible(&old, &new));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn is_backward_compatible(old: &Schema, new: &Schema) -> bool {
+        Schema::check_backward_compatibility(old, new).is_ok()
+    }
+
+    /// Whether every document valid under `new` is valid under `old`,
+    /// so that producers can upgrade to `new` before consumers do.
+    pub fn is_forward_compatible(old: &Schema, new: &Schema) -> bool {
+        Schema::check_forward_compatibility(old, new).is_ok()
+    }
+
+    /// Check that every document valid under `old` is valid under `new`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::IncompatibleSchema)` listing
+    /// the changes of `new` which may reject such documents
+    pub fn check_backward_compatibility(
+        old: &Schema,
+        new: &Schema,
+    ) -> Result<(), IncompatibleSchema> {
+        compare(old.as_value(), new.as_value())
+    }
+
+    /// Check that every document valid under `new` is valid under `old`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::IncompatibleSchema)` listing
+    /// the changes of `new` which may produce documents rejected by `old`
+    pub fn check_forward_compatibility(
+        old: &Schema,
+        new: &Schema,
+    ) -> Result<(), IncompatibleSchema> {
+        compare(new.as_value(), old.as_value())
+    }
+}
+
+fn compare(narrow: &Value, wide: &Value) -> Result<(), IncompatibleSchema> {
+    let mut changes = Vec::new();
+    subset(narrow, wide, "#", &mut changes);
+    if changes.is_empty() {
+        Ok(())
+    } else {
+        Err(IncompatibleSchema { changes })
+    }
+}
+
+fn types(schema: &Map<String, Value>) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Values allowed by the `enum` or `const` keyword, if any.
+fn values(schema: &Map<String, Value>) -> Option<Vec<&Value>> {
+    match (schema.get("const"), schema.get("enum")) {
+        (Some(value), _) => Some(vec![value]),
+        (None, Some(Value::Array(values))) => Some(values.iter().collect()),
+        _ => None,
+    }
+}
+
+fn push(changes: &mut Vec<String>, location: &str, reason: &str) {
+    changes.push(format!("{}: {}", location, reason));
+}
+
+/// Record the reasons why some instances of `a` may not be instances of `b`.
+fn subset(a: &Value, b: &Value, location: &str, changes: &mut Vec<String>) {
+    let empty = Map::new();
+    let (a, b) = match (a, b) {
+        (_, Value::Bool(true)) | (Value::Bool(false), _) => return,
+        (_, Value::Bool(false)) => return push(changes, location, "every value is rejected"),
+        (Value::Bool(true), Value::Object(b)) => (&empty, b),
+        (Value::Object(a), Value::Object(b)) => (a, b),
+        _ => return,
+    };
+
+    // A finite set of values is compatible if each of them is
+    if let Some(values) = values(a) {
+        for value in values {
+            let mut violations = Vec::new();
+            validate::check(value, &Value::Object(b.clone()), "", &mut violations);
+            if !violations.is_empty() {
+                push(
+                    changes,
+                    location,
+                    &format!("value {} is no longer allowed", value),
+                );
+            }
+        }
+        return;
+    }
+    if values(b).is_some() {
+        push(changes, location, "values restricted to an enumeration");
+    }
+
+    let (ta, tb) = (types(a), types(b));
+    if !tb.is_empty() {
+        let allowed = |t: &&str| tb.contains(t) || (*t == "integer" && tb.contains(&"number"));
+        if ta.is_empty() {
+            push(
+                changes,
+                location,
+                &format!("type restricted to {}", tb.join(", ")),
+            );
+        } else {
+            for t in ta.iter().filter(|t| !allowed(t)) {
+                push(
+                    changes,
+                    location,
+                    &format!("type {} is no longer allowed", t),
+                );
+            }
+        }
+    }
+
+    for keyword in LOWER_BOUNDS {
+        match (
+            a.get(*keyword).and_then(Value::as_f64),
+            b.get(*keyword).and_then(Value::as_f64),
+        ) {
+            (_, None) => {}
+            (Some(x), Some(y)) if y <= x => {}
+            (_, Some(y)) => push(changes, location, &format!("{} raised to {}", keyword, y)),
+        }
+    }
+    for keyword in UPPER_BOUNDS {
+        match (
+            a.get(*keyword).and_then(Value::as_f64),
+            b.get(*keyword).and_then(Value::as_f64),
+        ) {
+            (_, None) => {}
+            (Some(x), Some(y)) if y >= x => {}
+            (_, Some(y)) => push(changes, location, &format!("{} lowered to {}", keyword, y)),
+        }
+    }
+    for keyword in EXACT {
+        match (a.get(*keyword), b.get(*keyword)) {
+            (_, None) | (_, Some(Value::Bool(false))) => {}
+            (Some(x), Some(y)) if x == y => {}
+            (_, Some(y)) => push(changes, location, &format!("{} {} added", keyword, y)),
+        }
+    }
+
+    let required = |schema: &Map<String, Value>| -> Vec<String> {
+        schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| {
+                r.iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let required_a = required(a);
+    for name in required(b).iter().filter(|name| !required_a.contains(name)) {
+        push(
+            changes,
+            location,
+            &format!("property {} became required", name),
+        );
+    }
+
+    let properties = |schema: &Map<String, Value>| {
+        schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let (props_a, props_b) = (properties(a), properties(b));
+    let additional_a = a
+        .get("additionalProperties")
+        .cloned()
+        .unwrap_or(Value::Bool(true));
+    let additional_b = b
+        .get("additionalProperties")
+        .cloned()
+        .unwrap_or(Value::Bool(true));
+    let properties_location = pointer_child(location, "properties");
+    for (name, schema_b) in &props_b {
+        let schema_a = props_a.get(name).unwrap_or(&additional_a);
+        subset(
+            schema_a,
+            schema_b,
+            &pointer_child(&properties_location, name),
+            changes,
+        );
+    }
+    for (name, schema_a) in props_a
+        .iter()
+        .filter(|(name, _)| !props_b.contains_key(*name))
+    {
+        subset(
+            schema_a,
+            &additional_b,
+            &pointer_child(&properties_location, name),
+            changes,
+        );
+    }
+    match (&additional_a, &additional_b) {
+        (Value::Bool(false), _) => {}
+        (_, Value::Bool(false)) => push(
+            changes,
+            location,
+            "additional properties are no longer allowed",
+        ),
+        (x, y) => subset(
+            x,
+            y,
+            &pointer_child(location, "additionalProperties"),
+            changes,
+        ),
+    }
+
+    match (a.get("items"), b.get("items")) {
+        (_, None) => {}
+        (Some(x), Some(y)) if x.is_object() || x.is_boolean() => {
+            subset(x, y, &pointer_child(location, "items"), changes)
+        }
+        (None, Some(y)) => subset(
+            &Value::Bool(true),
+            y,
+            &pointer_child(location, "items"),
+            changes,
+        ),
+        (_, Some(_)) => push(changes, location, "items changed"),
+    }
+
+    if let Some(Value::Array(branches)) = b.get("allOf") {
+        for (i, branch) in branches.iter().enumerate() {
+            let location = pointer_child(&pointer_child(location, "allOf"), &i.to_string());
+            subset(&Value::Object(a.clone()), branch, &location, changes);
+        }
+    }
+    for keyword in &["anyOf", "oneOf"] {
+        if let Some(Value::Array(branches)) = b.get(*keyword) {
+            if a.get(*keyword) != b.get(*keyword)
+                && !branches.iter().any(|branch| {
+                    let mut branch_changes = Vec::new();
+                    subset(
+                        &Value::Object(a.clone()),
+                        branch,
+                        location,
+                        &mut branch_changes,
+                    );
+                    branch_changes.is_empty()
+                })
+            {
+                push(changes, location, &format!("{} changed", keyword));
+            }
+        }
+    }
+    if let Some(not) = b.get("not") {
+        if a.get("not") != Some(not) {
+            push(changes, location, "not added");
+        }
+    }
+}
+
+/// Custom error type used when a schema is not compatible with another version of it.
+#[derive(Debug)]
+pub struct IncompatibleSchema {
+    changes: Vec<String>,
+}
+
+impl IncompatibleSchema {
+    /// Describe each incompatible change, located by the JSON Pointer
+    /// fragment of the subschema holding it.
+    pub fn changes(&self) -> &[String] {
+        &self.changes
+    }
+
+    /// Stable code of the error, `INCOMPATIBLE_SCHEMA`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::IncompatibleSchema
+    }
+
+    /// Represent the error as `Json`, with its code, message and changes.
+    pub fn to_json(&self) -> Json {
+        let details = vec![("changes", self.changes.clone().into())];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for IncompatibleSchema {}
+
+impl Display for IncompatibleSchema {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "IncompatibleSchema: {}", self.changes.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deserialise;
+
+    fn changes(old: &str, new: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let (old, new) = (
+            Schema::new(deserialise!(old)?),
+            Schema::new(deserialise!(new)?),
+        );
+        Ok(Schema::check_backward_compatibility(&old, &new)
+            .err()
+            .map(|err| err.changes().to_vec())
+            .unwrap_or_default())
+    }
+
+    #[test]
+    fn compatible_changes() -> Result<(), Box<dyn Error>> {
+        let old = r#"{
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer", "minimum": 1 },
+                "status": { "enum": ["active", "archived"] },
+                "tags": { "type": "array", "items": { "type": "string", "maxLength": 8 } }
+            },
+            "required": ["id", "status"],
+            "additionalProperties": false
+        }"#;
+        let new = r#"{
+            "type": ["object"],
+            "properties": {
+                "id": { "type": "number" },
+                "status": { "type": "string" },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "note": { "type": "string" }
+            },
+            "required": ["id"]
+        }"#;
+        assert_eq!(Vec::<String>::new(), changes(old, new)?);
+        assert!(changes(old, "{}")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn breaking_changes() -> Result<(), Box<dyn Error>> {
+        let old = r#"{
+            "properties": {
+                "id": { "type": ["integer", "string"] },
+                "status": { "enum": ["active", "archived"] },
+                "name": { "type": "string", "maxLength": 64 },
+                "tags": { "items": { "type": "string" } }
+            },
+            "required": ["id"]
+        }"#;
+        let new = r#"{
+            "properties": {
+                "id": { "type": "integer" },
+                "status": { "enum": ["active"] },
+                "name": { "type": "string", "maxLength": 32, "pattern": "^[a-z]+$" },
+                "tags": { "items": { "type": "string", "minLength": 1 } },
+                "email": { "type": "string" }
+            },
+            "required": ["id", "email"],
+            "additionalProperties": false
+        }"#;
+        let mut changes = changes(old, new)?;
+        changes.sort();
+        assert_eq!(
+            vec![
+                "#/properties/email: type restricted to string",
+                "#/properties/id: type string is no longer allowed",
+                "#/properties/name: maxLength lowered to 32",
+                "#/properties/name: pattern \"^[a-z]+$\" added",
+                "#/properties/status: value \"archived\" is no longer allowed",
+                "#/properties/tags/items: minLength raised to 1",
+                "#: additional properties are no longer allowed",
+                "#: property email became required",
+            ],
+            changes
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn forward_compatibility() -> Result<(), Box<dyn Error>> {
+        let old = Schema::new(deserialise!(
+            r#"{ "properties": { "n": { "type": "number" } } }"#
+        )?);
+        let new = Schema::new(deserialise!(
+            r#"{ "properties": { "n": { "type": "integer" } } }"#
+        )?);
+        assert!(!Schema::is_backward_compatible(&old, &new));
+        assert!(Schema::is_forward_compatible(&old, &new));
+        let err = Schema::check_backward_compatibility(&old, &new).unwrap_err();
+        assert_eq!(ErrorCode::IncompatibleSchema, err.code());
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Adding values under keys which may already exist, for documents
+//! assembled from several sources where silent overwrites hide mistakes.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+
+use crate::error::{self, ErrorCode};
+use crate::ser::{self, NonFinite};
+use crate::{merge_value, Json};
+
+/// Handling of the keys already present in a document when adding values.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Conflict {
+    /// Replace the existing value, like `Json::add`
+    #[default]
+    Replace,
+    /// Keep the existing value
+    Keep,
+    /// Deep merge the new value into the existing one, like `Json::merge`
+    Merge,
+    /// Fail with a `DuplicateKey` error
+    Fail,
+}
+
+/// Custom error type used when adding a value under a key which already exists.
+#[derive(Debug)]
+pub struct DuplicateKey {
+    key: String,
+}
+
+impl DuplicateKey {
+    /// Create a new `DuplicateKey` error given the existing key
+    pub fn new(key: &str) -> Self {
+        DuplicateKey { key: key.into() }
+    }
+
+    /// The key which already exists.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Stable code of the error, `DUPLICATE_KEY`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::DuplicateKey
+    }
+
+    /// Represent the error as `Json`, with its code, message and key.
+    pub fn to_json(&self) -> Json {
+        let details = vec![("key", self.key.clone().into())];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for DuplicateKey {}
+
+impl Display for DuplicateKey {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&format!("DuplicateKey: Key {} already exists", self.key))
+    }
+}
+
+impl Json {
+    /// Add a new item in a `Json` instance like `Json::add`,
+    /// unless the key already exists.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, ErrorCode};
+    ///
+    /// let mut config = inline!("port" => 8080);
+    ///
+    /// assert!(config.try_add("host", "localhost").is_ok());
+    /// let err = config.try_add("port", 80).unwrap_err();
+    /// assert_eq!(ErrorCode::DuplicateKey, err.code());
+    /// assert_eq!(8080, config.get::<u16>("port").unwrap());
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::DuplicateKey)` if the key already
+    /// exists, or the errors of `Json::add_with`
+    pub fn try_add<V: Serialize>(&mut self, k: &str, v: V) -> Result<(), crate::Error> {
+        if self.json_data.contains_key(self.key(k).as_ref()) {
+            return Err(DuplicateKey::new(k).into());
+        }
+        self.add_with(k, v, NonFinite::Null)
+    }
+
+    /// Add several items at once, resolving the keys which already exist
+    /// (or are repeated among the items) with the given policy. The items
+    /// are added in order and the document is left untouched on error.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Conflict};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut config = inline!("port" => 8080);
+    ///     let defaults = vec![("host", "localhost"), ("port", "80")];
+    ///
+    ///     config.add_all(defaults.clone(), Conflict::Keep)?;
+    ///     assert_eq!(inline!("host" => "localhost", "port" => 8080), config);
+    ///
+    ///     assert!(config.add_all(defaults, Conflict::Fail).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::DuplicateKey)` for the first key which
+    /// already exists with the `Conflict::Fail` policy, or the errors of `Json::add_with`
+    pub fn add_all<I, K, V>(&mut self, items: I, conflict: Conflict) -> Result<(), crate::Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: Serialize,
+    {
+        self.transaction(|tx| {
+            for (k, v) in items {
+                let k = k.as_ref();
+                let value = ser::to_value(&v, NonFinite::Null, tx.encoders.as_deref())?;
+                let existing = tx.json_data.get(tx.key(k).as_ref());
+                let value = match (existing, conflict) {
+                    (None, _) | (Some(_), Conflict::Replace) => value,
+                    (Some(_), Conflict::Keep) => continue,
+                    (Some(_), Conflict::Fail) => return Err(DuplicateKey::new(k).into()),
+                    (Some(existing), Conflict::Merge) => {
+                        let mut merged = existing.clone();
+                        merge_value(&mut merged, value);
+                        merged
+                    }
+                };
+                tx.store(k, value)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, KeyPolicy};
+    use std::error::Error;
+
+    #[test]
+    fn conflicts() -> Result<(), Box<dyn Error>> {
+        let mut base = inline!("a" => 1);
+        base.add("nested", inline!("x" => 1));
+        let items = || vec![("a", inline!("y" => 2)), ("nested", inline!("y" => 2))];
+
+        let mut json = base.clone();
+        json.add_all(items(), Conflict::Replace)?;
+        assert_eq!(inline!("y" => 2), json.get::<Json>("a")?);
+        assert_eq!(inline!("y" => 2), json.get::<Json>("nested")?);
+
+        let mut json = base.clone();
+        json.add_all(items(), Conflict::Keep)?;
+        assert_eq!(base, json);
+
+        let mut json = base.clone();
+        json.add_all(items(), Conflict::Merge)?;
+        assert_eq!(inline!("y" => 2), json.get::<Json>("a")?);
+        assert_eq!(inline!("x" => 1, "y" => 2), json.get::<Json>("nested")?);
+
+        let mut json = Json::new();
+        let err = json
+            .add_all(vec![("b", 1), ("a", 2), ("a", 3)], Conflict::Fail)
+            .unwrap_err();
+        assert_eq!("DuplicateKey: Key a already exists", err.to_string());
+        assert_eq!(Json::new(), json);
+
+        json.set_key_policy(KeyPolicy::no_whitespace())?;
+        assert!(json
+            .add_all(vec![("a", 1), ("b c", 2)], Conflict::Fail)
+            .is_err());
+        assert_eq!(Json::new(), json);
+        assert!(json.try_add("b c", 1).is_err());
+        Ok(())
+    }
+}
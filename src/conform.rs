@@ -0,0 +1,207 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Projection of documents on the properties declared by a schema, to
+//! make sure outgoing payloads only hold what their contract publishes.
+
+use serde_json::{Map, Value};
+
+use crate::path::pointer_child;
+use crate::schema::item_schema;
+use crate::validate::{ValidationError, Violation};
+use crate::{Json, Schema};
+
+/// The `properties` of a subschema and of its `allOf` branches.
+fn declared(schema: &Value) -> Vec<&Map<String, Value>> {
+    let mut properties: Vec<&Map<String, Value>> = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .into_iter()
+        .collect();
+    if let Some(branches) = schema.get("allOf").and_then(Value::as_array) {
+        properties.extend(branches.iter().flat_map(declared));
+    }
+    properties
+}
+
+/// Find the undeclared properties of `value`, recursively, removing them
+/// if asked to.
+fn conform(
+    value: &mut Value,
+    schema: &Value,
+    location: &str,
+    remove: bool,
+    extras: &mut Vec<Violation>,
+) {
+    match value {
+        Value::Object(map) => {
+            let properties = declared(schema);
+            if properties.is_empty() {
+                return;
+            }
+            let additional = schema.get("additionalProperties");
+            let mut undeclared = Vec::new();
+            for (name, item) in map.iter_mut() {
+                let item_location = pointer_child(location, name);
+                match (properties.iter().find_map(|p| p.get(name)), additional) {
+                    (Some(subschema), _) => {
+                        conform(item, subschema, &item_location, remove, extras)
+                    }
+                    (None, Some(Value::Bool(false))) | (None, None) => {
+                        undeclared.push(name.clone())
+                    }
+                    (None, Some(subschema)) => {
+                        conform(item, subschema, &item_location, remove, extras)
+                    }
+                }
+            }
+            for name in undeclared {
+                extras.push(Violation::new(
+                    location,
+                    "additionalProperties",
+                    false.into(),
+                    name.clone().into(),
+                ));
+                if remove {
+                    map.remove(&name);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                if let Some(subschema) = item_schema(schema, i) {
+                    conform(
+                        item,
+                        subschema,
+                        &pointer_child(location, &i.to_string()),
+                        remove,
+                        extras,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Json {
+    /// Remove the properties which are not declared by the given schema,
+    /// recursively. The objects whose schema doesn't declare `properties`
+    /// are kept as they are, and so are the properties allowed by
+    /// `additionalProperties`. Documents being maps, the order of the
+    /// properties is not the one of the schema.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, inline, Schema};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let schema = Schema::new(deserialise!(r#"{
+    ///         "properties": {
+    ///             "title": { "type": "string" },
+    ///             "author": { "properties": { "name": { "type": "string" } } }
+    ///         }
+    ///     }"#)?);
+    ///     let mut book = inline!(
+    ///         "title" => "Mostly Harmless",
+    ///         "author" => inline!("name" => "Douglas Adams", "email" => "douglas@example.com"),
+    ///         "internal_id" => 42
+    ///     );
+    ///     book.conform(&schema);
+    ///
+    ///     assert_eq!(
+    ///         inline!("title" => "Mostly Harmless", "author" => inline!("name" => "Douglas Adams")),
+    ///         book
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn conform(&mut self, schema: &Schema) {
+        self.watched(|json| {
+            let mut root = Value::Object(json.json_data.drain().collect());
+            conform(&mut root, schema.as_value(), "", true, &mut Vec::new());
+            if let Value::Object(map) = root {
+                json.json_data = map.into_iter().collect();
+            }
+        })
+    }
+
+    /// Check that the document only holds properties declared by the given
+    /// schema, like `Json::conform` but reporting them instead of removing them.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::ValidationError)` with an `additionalProperties`
+    /// violation for each undeclared property, located at its parent object
+    pub fn conform_strict(&self, schema: &Schema) -> Result<(), ValidationError> {
+        let mut root = Value::Object(
+            self.json_data
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        );
+        let mut extras = Vec::new();
+        conform(&mut root, schema.as_value(), "", false, &mut extras);
+        match extras.is_empty() {
+            true => Ok(()),
+            false => Err(ValidationError::new(extras)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deserialise;
+    use std::error::Error;
+
+    #[test]
+    fn extras() -> Result<(), Box<dyn Error>> {
+        let schema = Schema::new(deserialise!(
+            r#"{
+                "properties": { "items": { "items": { "properties": { "id": {} } } }, "meta": {} },
+                "allOf": [{ "properties": { "name": {} } }],
+                "additionalProperties": false
+            }"#
+        )?);
+        let mut json = deserialise!(
+            r#"{ "items": [{ "id": 1, "x": 2 }], "meta": { "any": 1 }, "name": "a", "extra": true }"#
+        )?;
+        let err = json.conform_strict(&schema).unwrap_err();
+        let mut violations: Vec<String> = err.violations().iter().map(|v| v.to_string()).collect();
+        violations.sort();
+        assert_eq!(
+            vec![
+                "additionalProperties at /: expected false but found \"extra\"",
+                "additionalProperties at /items/0: expected false but found \"x\"",
+            ],
+            violations
+        );
+        json.conform(&schema);
+        assert_eq!(
+            deserialise!(r#"{ "items": [{ "id": 1 }], "meta": { "any": 1 }, "name": "a" }"#)?,
+            json
+        );
+        assert!(json.conform_strict(&schema).is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Conversions between `Json` and the maps commonly holding members,
+//! ordered ones included, without inserting them one by one.
+
+use std::collections::{hash_map, BTreeMap, HashMap};
+use std::iter::FromIterator;
+use std::mem;
+
+use serde_json::{Map, Value};
+
+use crate::Json;
+
+/// Move the members of a `HashMap` into a new document.
+impl From<HashMap<String, Value>> for Json {
+    fn from(map: HashMap<String, Value>) -> Self {
+        Json::from_data(map)
+    }
+}
+
+/// Move the members of a `BTreeMap` into a new document.
+impl From<BTreeMap<String, Value>> for Json {
+    fn from(map: BTreeMap<String, Value>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+/// Move the members of a `serde_json::Map` into a new document.
+impl From<Map<String, Value>> for Json {
+    fn from(map: Map<String, Value>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+/// Collect members into a new document, from any map or iterator of pairs,
+/// such as an `indexmap::IndexMap<String, Value>`. The last value of
+/// a repeated key is kept.
+///
+/// # Example
+/// ```
+/// use json_ez::{inline, Json};
+///
+/// let json: Json = vec![("title", "Mostly Harmless"), ("author", "Douglas Adams")]
+///     .into_iter()
+///     .collect();
+///
+/// assert_eq!(inline!("title" => "Mostly Harmless", "author" => "Douglas Adams"), json);
+/// ```
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Json {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(members: I) -> Self {
+        Json::from_data(
+            members
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Iterate over the owned members of the document, in arbitrary order.
+impl IntoIterator for Json {
+    type Item = (String, Value);
+    type IntoIter = hash_map::IntoIter<String, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.json_data.into_iter()
+    }
+}
+
+impl Json {
+    /// Move all the members out of the document, in arbitrary order,
+    /// leaving it empty with its settings. Its annotations are removed.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    ///
+    /// let mut batch = inline!("a" => 1, "b" => 2);
+    /// let mut members: Vec<_> = batch.drain().collect();
+    /// members.sort_by(|a, b| a.0.cmp(&b.0));
+    ///
+    /// assert_eq!(vec![("a".to_string(), 1.into()), ("b".to_string(), 2.into())], members);
+    /// assert_eq!(Json::new(), batch);
+    /// ```
+    pub fn drain(&mut self) -> hash_map::IntoIter<String, Value> {
+        self.annotations.clear();
+        self.watched(|json| mem::take(&mut json.json_data))
+            .into_iter()
+    }
+
+    /// Move the members of the document into a `BTreeMap`, sorted by key.
+    pub fn into_btreemap(self) -> BTreeMap<String, Value> {
+        self.json_data.into_iter().collect()
+    }
+
+    /// Move the members of the document into a `HashMap`.
+    pub fn into_hashmap(self) -> HashMap<String, Value> {
+        self.json_data
+    }
+
+    /// Move the members of the document into a `serde_json::Map`.
+    pub fn into_map(self) -> Map<String, Value> {
+        self.json_data.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn conversions() {
+        let json = inline!("b" => 2, "a" => 1);
+        let sorted = json.clone().into_btreemap();
+        assert_eq!(vec!["a", "b"], sorted.keys().collect::<Vec<_>>());
+        assert_eq!(json, Json::from(sorted));
+        assert_eq!(json, Json::from(json.clone().into_hashmap()));
+        assert_eq!(json, Json::from(json.clone().into_map()));
+        assert_eq!(
+            inline!("a" => 3),
+            vec![("a", 1), ("a", 3)].into_iter().collect::<Json>()
+        );
+        let mut owned: Vec<(String, Value)> = json.into_iter().collect();
+        owned.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(vec![("a".into(), 1.into()), ("b".into(), 2.into())], owned);
+    }
+}
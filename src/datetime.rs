@@ -0,0 +1,420 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Minimal RFC 3339 date-time handling, enough to recognize and
+//! convert the timestamps found in documents.
+
+use serde_json::Value;
+
+use crate::path::pointer;
+use crate::{CoercionError, DateFormat, Error, Json};
+
+/// Seconds since the Unix epoch of `0000-01-01T00:00:00Z`.
+const MIN_SECONDS: i64 = -62_167_219_200;
+/// Seconds since the Unix epoch of `9999-12-31T23:59:59Z`.
+const MAX_SECONDS: i64 = 253_402_300_799;
+
+/// An instant, as a number of seconds and nanoseconds since the Unix epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DateTime {
+    seconds: i64,
+    nanos: u32,
+}
+
+impl DateTime {
+    /// Parse a RFC 3339 date-time such as `2005-04-28T20:15:00.5+02:00`.
+    pub(crate) fn parse(s: &str) -> Option<DateTime> {
+        let b = s.as_bytes();
+        if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || b[13] != b':' || b[16] != b':' {
+            return None;
+        }
+        if !matches!(b[10], b'T' | b't' | b' ') || !s.is_char_boundary(19) {
+            return None;
+        }
+        let year = digits(&s[0..4])?;
+        let month = digits(&s[5..7])?;
+        let day = digits(&s[8..10])?;
+        let hour = digits(&s[11..13])?;
+        let minute = digits(&s[14..16])?;
+        let second = digits(&s[17..19])?;
+        if !(1..=12).contains(&month)
+            || day < 1
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+        let mut rest = &s[19..];
+        let mut nanos = 0;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+            if len == 0 {
+                return None;
+            }
+            let mut padded = fraction[..len.min(9)].to_string();
+            while padded.len() < 9 {
+                padded.push('0');
+            }
+            nanos = padded.parse().ok()?;
+            rest = &fraction[len..];
+        }
+        let offset = match rest {
+            "Z" | "z" => 0,
+            _ => {
+                let b = rest.as_bytes();
+                if b.len() != 6 || b[3] != b':' {
+                    return None;
+                }
+                let sign = match b[0] {
+                    b'+' => 1,
+                    b'-' => -1,
+                    _ => return None,
+                };
+                let (hours, minutes) = (digits(&rest[1..3])?, digits(&rest[4..6])?);
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                sign * (hours * 3600 + minutes * 60)
+            }
+        };
+        let days = days_from_civil(year, month, day);
+        let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+        Some(DateTime { seconds, nanos })
+    }
+
+    /// Parse the timestamps commonly found in documents: RFC 3339 and
+    /// RFC 2822 date-times, `2005-04-28 20:15:00` date-times and
+    /// `2005-04-28` dates (both taken as UTC), and numbers of seconds or
+    /// milliseconds since the Unix epoch, as numbers or strings. Numbers
+    /// of magnitude 10^11 and more are taken as milliseconds.
+    pub(crate) fn parse_any(value: &Value) -> Option<DateTime> {
+        let s = match value {
+            Value::Number(n) => return DateTime::from_epoch(n.as_f64()?),
+            Value::String(s) => s.trim(),
+            _ => return None,
+        };
+        if let Ok(epoch) = s.parse::<f64>() {
+            return DateTime::from_epoch(epoch);
+        }
+        DateTime::parse(s)
+            .or_else(|| DateTime::parse(&format!("{}Z", s)))
+            .or_else(|| DateTime::parse(&format!("{}T00:00:00Z", s)))
+            .or_else(|| DateTime::parse_rfc2822(s))
+    }
+
+    /// An instant given as a number of seconds, or of milliseconds
+    /// if its magnitude is 10^11 or more, since the Unix epoch. Instants
+    /// out of the years 0000 to 9999 of RFC 3339 are rejected.
+    fn from_epoch(epoch: f64) -> Option<DateTime> {
+        if !epoch.is_finite() {
+            return None;
+        }
+        let seconds = if epoch.abs() >= 1e11 {
+            epoch / 1000.0
+        } else {
+            epoch
+        };
+        let whole = seconds.floor();
+        if !(MIN_SECONDS as f64..=MAX_SECONDS as f64).contains(&whole) {
+            return None;
+        }
+        let nanos = ((seconds - whole) * 1e9).round().min(999_999_999.0) as u32;
+        Some(DateTime {
+            seconds: whole as i64,
+            nanos,
+        })
+    }
+
+    /// Parse a RFC 2822 date-time such as `Thu, 28 Apr 2005 20:15:00 +0200`.
+    fn parse_rfc2822(s: &str) -> Option<DateTime> {
+        let s = match s.split_once(',') {
+            Some((_, rest)) => rest,
+            None => s,
+        };
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let (day, month, year, time, zone) = match parts.as_slice() {
+            [day, month, year, time, zone] => (day, month, year, time, zone),
+            _ => return None,
+        };
+        const MONTHS: [&str; 12] = [
+            "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+        ];
+        let month = MONTHS.iter().position(|m| m.eq_ignore_ascii_case(month))? + 1;
+        let time = match time.len() {
+            5 => format!("{}:00", time),
+            _ => time.to_string(),
+        };
+        let offset = match zone.to_ascii_uppercase().as_str() {
+            "UT" | "GMT" | "Z" => "Z".to_string(),
+            "EDT" => "-04:00".into(),
+            "EST" | "CDT" => "-05:00".into(),
+            "CST" | "MDT" => "-06:00".into(),
+            "MST" | "PDT" => "-07:00".into(),
+            "PST" => "-08:00".into(),
+            zone if zone.len() == 5 && zone.starts_with(['+', '-']) => {
+                format!("{}:{}", &zone[..3], &zone[3..])
+            }
+            _ => return None,
+        };
+        let day: u8 = day.parse().ok()?;
+        DateTime::parse(&format!(
+            "{}-{:02}-{:02}T{}{}",
+            year, month, day, time, offset
+        ))
+    }
+
+    pub(crate) fn epoch_seconds(self) -> i64 {
+        self.seconds
+    }
+
+    pub(crate) fn epoch_millis(self) -> Option<i64> {
+        self.seconds
+            .checked_mul(1000)?
+            .checked_add(i64::from(self.nanos / 1_000_000))
+    }
+
+    /// Format as a RFC 3339 date-time in UTC, with as many fractional
+    /// digits as needed.
+    pub(crate) fn to_rfc3339(self) -> String {
+        let days = self.seconds.div_euclid(86400);
+        let time = self.seconds.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        let mut formatted = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            time / 3600,
+            time / 60 % 60,
+            time % 60
+        );
+        if self.nanos > 0 {
+            let fraction = format!("{:09}", self.nanos);
+            formatted.push('.');
+            formatted.push_str(fraction.trim_end_matches('0'));
+        }
+        formatted.push('Z');
+        formatted
+    }
+}
+
+impl Json {
+    /// Rewrite the timestamps found at the given path patterns in the given
+    /// format, whatever their format: RFC 3339 and RFC 2822 date-times,
+    /// `2005-04-28 20:15:00` date-times and `2005-04-28` dates (both taken
+    /// as UTC), and numbers of seconds or milliseconds since the Unix epoch,
+    /// as numbers or strings (numbers of magnitude 10^11 and more being taken
+    /// as milliseconds). `DateFormat::Unchanged` leaves them as they are.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, DateFormat};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut events = deserialise!(r#"{
+    ///         "created_at": "Thu, 28 Apr 2005 20:15:00 +0200",
+    ///         "steps": [{ "timestamp": 1114712100 }, { "timestamp": "2005-04-28 18:15:00.5" }]
+    ///     }"#)?;
+    ///     events.normalize_datetimes(&["created_at", "steps.*.timestamp"], DateFormat::Rfc3339Utc)?;
+    ///
+    ///     assert_eq!(
+    ///         deserialise!(r#"{
+    ///             "created_at": "2005-04-28T18:15:00Z",
+    ///             "steps": [{ "timestamp": "2005-04-28T18:15:00Z" }, { "timestamp": "2005-04-28T18:15:00.5Z" }]
+    ///         }"#)?,
+    ///         events
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if a pattern cannot be
+    /// parsed or an `Err(json_ez::Error::Coercion)` listing the locations of
+    /// the matched values which are not timestamps, the others being rewritten
+    pub fn normalize_datetimes(
+        &mut self,
+        patterns: &[&str],
+        format: DateFormat,
+    ) -> Result<(), Error> {
+        let mut failures = Vec::new();
+        self.for_each_match(
+            patterns,
+            &mut |location, value| match DateTime::parse_any(value) {
+                Some(date) => {
+                    if let Some(formatted) = format.format(date) {
+                        *value = formatted;
+                    }
+                }
+                None => failures.push(format!(
+                    "{}: expected a timestamp but found {}",
+                    pointer(location),
+                    value
+                )),
+            },
+        )?;
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(CoercionError::new(failures).into())
+        }
+    }
+}
+
+fn digits(s: &str) -> Option<i64> {
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days since 1970-01-01 of a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Date of the proleptic Gregorian calendar given a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        let date = DateTime::parse("2005-04-28T20:15:00.5+02:00").unwrap();
+        assert_eq!(1114712100, date.epoch_seconds());
+        assert_eq!(Some(1114712100500), date.epoch_millis());
+        assert_eq!("2005-04-28T18:15:00.5Z", date.to_rfc3339());
+        let before_epoch = DateTime::parse("1969-12-31t23:59:59Z").unwrap();
+        assert_eq!("1969-12-31T23:59:59Z", before_epoch.to_rfc3339());
+        assert_eq!(
+            "2024-02-29T00:00:00Z",
+            DateTime::parse("2024-02-29 00:00:00Z")
+                .unwrap()
+                .to_rfc3339()
+        );
+    }
+
+    #[test]
+    fn parse_any() {
+        let parse = |value: Value| DateTime::parse_any(&value).map(DateTime::to_rfc3339);
+        let expected = Some("2005-04-28T18:15:00Z".to_string());
+        assert_eq!(expected, parse("2005-04-28T20:15:00+02:00".into()));
+        assert_eq!(expected, parse("Thu, 28 Apr 2005 20:15:00 +0200".into()));
+        assert_eq!(expected, parse("28 Apr 2005 11:15 PDT".into()));
+        assert_eq!(expected, parse("2005-04-28 18:15:00".into()));
+        assert_eq!(expected, parse(1114712100.into()));
+        assert_eq!(expected, parse(" 1114712100000".into()));
+        assert_eq!(
+            Some("2005-04-28T18:15:00.25Z".into()),
+            parse(1114712100.25.into())
+        );
+        assert_eq!(
+            Some("2005-04-28T00:00:00Z".into()),
+            parse("2005-04-28".into())
+        );
+        assert_eq!(None, parse("Thu, 28 Foo 2005 20:15:00 +0200".into()));
+        assert_eq!(None, parse("Mostly Harmless".into()));
+        assert_eq!(None, parse(true.into()));
+        assert_eq!(None, parse(1e300.into()));
+        assert_eq!(None, parse("9999999999999999999".into()));
+        assert_eq!(
+            Some("9999-12-31T23:59:59Z".into()),
+            parse(253402300799000_i64.into())
+        );
+        assert_eq!(
+            Some("0000-01-01T00:00:00Z".into()),
+            parse((-62167219200000_i64).into())
+        );
+    }
+
+    #[test]
+    fn normalize() {
+        let mut json = crate::inline!("a" => "2005-04-28", "b" => vec!["1114712100500", "soon"]);
+        let err = json
+            .normalize_datetimes(&["a", "b[*]"], DateFormat::EpochMillis)
+            .unwrap_err();
+        assert_eq!(
+            "CoercionError: Cannot convert /b/1: expected a timestamp but found \"soon\"",
+            err.to_string()
+        );
+        assert_eq!(
+            crate::inline!("a" => 1114646400000_i64, "b" => serde_json::json!([1114712100500_i64, "soon"])),
+            json
+        );
+    }
+
+    #[test]
+    fn invalid_date_times() {
+        for invalid in &[
+            "2005-04-28",
+            "2005-04-28T20:15:00",
+            "2023-02-29T00:00:00Z",
+            "2005-13-28T20:15:00Z",
+            "2005-04-28T20:15:00.Z",
+            "2005-04-28T20:15:00+2:00",
+            "Mostly Harmless",
+            "2005-04-28T20:15:0\u{e9}Z",
+        ] {
+            assert!(DateTime::parse(invalid).is_none(), "{}", invalid);
+        }
+    }
+}
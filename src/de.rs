@@ -0,0 +1,135 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Deserialization of Rust values straight from a `Json` instance,
+//! borrowing its content instead of cloning or reparsing it.
+
+use serde::de::value::MapDeserializer;
+use serde::de::{self, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::Error as SerdeError;
+
+use crate::Json;
+
+/// Deserialize any `T` from a borrowed `Json` instance, as from a JSON object.
+///
+/// # Example
+/// ```
+/// use json_ez::inline;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Novel<'a> {
+///     title: &'a str,
+///     read: bool,
+/// }
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let json = inline!("title" => "Mostly Harmless", "read" => true);
+///     let novel = Novel::deserialize(&json)?;
+///
+///     assert_eq!("Mostly Harmless", novel.title);
+///     assert!(novel.read);
+///
+///     Ok(())
+/// }
+/// ```
+impl<'de> de::Deserializer<'de> for &'de Json {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let entries = self.json_data.iter().map(|(k, v)| (k.as_str(), v));
+        let mut map = MapDeserializer::new(entries);
+        let value = visitor.visit_map(&mut map)?;
+        map.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        enum identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for &'de Json {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::inline;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+    use std::error::Error;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Movie<'a> {
+        title: &'a str,
+        release_date: u16,
+        #[serde(default)]
+        director: Option<String>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Wrapper<'a>(#[serde(borrow)] Movie<'a>);
+
+    #[test]
+    fn deserialize_borrowed() -> Result<(), Box<dyn Error>> {
+        let json =
+            inline!("title" => "The Hitchhiker's Guide to the Galaxy", "release_date" => 2005);
+        let movie = Movie {
+            title: "The Hitchhiker's Guide to the Galaxy",
+            release_date: 2005,
+            director: None,
+        };
+        assert_eq!(movie, Movie::deserialize(&json)?);
+        assert_eq!(Some(Wrapper(movie)), Option::<Wrapper>::deserialize(&json)?);
+        let map = BTreeMap::<String, serde_json::Value>::deserialize(&json)?;
+        assert_eq!(2, map.len());
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        let json = inline!("title" => 42, "release_date" => 2005);
+        assert!(Movie::deserialize(&json).is_err());
+        assert!(Vec::<u8>::deserialize(&json).is_err());
+        assert!(u8::deserialize(&json).is_err());
+    }
+}
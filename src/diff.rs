@@ -0,0 +1,407 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Structural differences between two documents and their rendering.
+
+use std::env;
+
+use serde_json::{json, Value};
+
+use crate::path::{self, Segment};
+use crate::Json;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// A single difference between two documents, located by its path.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Change<'a> {
+    Added(Vec<Segment>, &'a Value),
+    Removed(Vec<Segment>, &'a Value),
+    Changed(Vec<Segment>, &'a Value, &'a Value),
+}
+
+impl Change<'_> {
+    fn path(&self) -> &[Segment] {
+        match self {
+            Change::Added(path, _) | Change::Removed(path, _) | Change::Changed(path, _, _) => path,
+        }
+    }
+}
+
+/// List the differences turning `left` into `right`, sorted by path.
+pub(crate) fn changes<'a>(left: &'a Json, right: &'a Json) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    diff_objects(
+        left.json_data.iter(),
+        |k| right.json_data.get(k),
+        right.json_data.iter(),
+        |k| left.json_data.get(k),
+        &[],
+        &mut changes,
+    );
+    changes
+}
+
+fn diff_objects<'a, L, R, FL, FR>(
+    left: L,
+    in_right: FR,
+    right: R,
+    in_left: FL,
+    path: &[Segment],
+    changes: &mut Vec<Change<'a>>,
+) where
+    L: Iterator<Item = (&'a String, &'a Value)>,
+    R: Iterator<Item = (&'a String, &'a Value)>,
+    FL: Fn(&str) -> Option<&'a Value>,
+    FR: Fn(&str) -> Option<&'a Value>,
+{
+    let mut left: Vec<_> = left.collect();
+    left.sort_by(|a, b| a.0.cmp(b.0));
+    for (k, l) in left {
+        let path = child(path, Segment::Key(k.clone()));
+        match in_right(k) {
+            Some(r) => diff(l, r, path, changes),
+            None => changes.push(Change::Removed(path, l)),
+        }
+    }
+    let mut added: Vec<_> = right.filter(|(k, _)| in_left(k).is_none()).collect();
+    added.sort_by(|a, b| a.0.cmp(b.0));
+    for (k, r) in added {
+        changes.push(Change::Added(child(path, Segment::Key(k.clone())), r));
+    }
+}
+
+fn diff<'a>(left: &'a Value, right: &'a Value, path: Vec<Segment>, changes: &mut Vec<Change<'a>>) {
+    match (left, right) {
+        (Value::Object(l), Value::Object(r)) => diff_objects(
+            l.iter(),
+            |k| r.get(k),
+            r.iter(),
+            |k| l.get(k),
+            &path,
+            changes,
+        ),
+        (Value::Array(l), Value::Array(r)) => {
+            for (i, l) in l.iter().enumerate() {
+                let path = child(&path, Segment::Index(i));
+                match r.get(i) {
+                    Some(r) => diff(l, r, path, changes),
+                    None => changes.push(Change::Removed(path, l)),
+                }
+            }
+            for (i, r) in r.iter().enumerate().skip(l.len()) {
+                changes.push(Change::Added(child(&path, Segment::Index(i)), r));
+            }
+        }
+        (l, r) if l == r => {}
+        (l, r) => changes.push(Change::Changed(path, l, r)),
+    }
+}
+
+fn child(path: &[Segment], segment: Segment) -> Vec<Segment> {
+    let mut child = path.to_vec();
+    child.push(segment);
+    child
+}
+
+/// Render the given changes, one line per removed or added value.
+pub(crate) fn render(changes: &[Change], color: bool) -> String {
+    let line = |sign: char, path: &[Segment], value: &Value| {
+        let (start, end) = match (color, sign) {
+            (false, _) => ("", ""),
+            (true, '-') => (RED, RESET),
+            (true, _) => (GREEN, RESET),
+        };
+        format!(
+            "{}{}{}: {}{}\n",
+            start,
+            sign,
+            path::render(path),
+            value,
+            end
+        )
+    };
+    let mut out = String::new();
+    for change in changes {
+        match change {
+            Change::Removed(path, value) => out.push_str(&line('-', path, value)),
+            Change::Added(path, value) => out.push_str(&line('+', path, value)),
+            Change::Changed(path, left, right) => {
+                out.push_str(&line('-', path, left));
+                out.push_str(&line('+', path, right));
+            }
+        }
+    }
+    out
+}
+
+/// Render the given changes as a unified diff, grouped
+/// in hunks by top-level key.
+pub(crate) fn render_unified(changes: &[Change], color: bool) -> String {
+    if changes.is_empty() {
+        return String::new();
+    }
+    let paint = |style: &str, text: &str| {
+        if color {
+            format!("{}{}{}\n", style, text, RESET)
+        } else {
+            format!("{}\n", text)
+        }
+    };
+    let mut out = paint(BOLD, "--- left");
+    out.push_str(&paint(BOLD, "+++ right"));
+    let mut hunk = None;
+    for change in changes {
+        let root = &change.path()[..1];
+        if hunk != Some(root) {
+            out.push_str(&paint(CYAN, &format!("@@ {} @@", path::render(root))));
+            hunk = Some(root);
+        }
+        out.push_str(&render(std::slice::from_ref(change), color));
+    }
+    out
+}
+
+impl Json {
+    /// Render the differences turning `a` into `b` as a unified diff,
+    /// with one line per removed (`-`) or added (`+`) value located by
+    /// its path, grouped by top-level key. Return an empty string if both
+    /// documents are equal.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    ///
+    /// let a = inline!("title" => "Mostly Harmless", "read" => false);
+    /// let b = inline!("title" => "Mostly Harmless", "read" => true);
+    ///
+    /// assert_eq!(
+    ///     "--- left\n+++ right\n@@ read @@\n-read: false\n+read: true\n",
+    ///     Json::render_diff(&a, &b)
+    /// );
+    /// ```
+    pub fn render_diff(a: &Json, b: &Json) -> String {
+        render_unified(&changes(a, b), false)
+    }
+
+    /// Render the differences turning `a` into `b` like `Json::render_diff`,
+    /// colorized with ANSI escape codes for terminals.
+    pub fn render_diff_colored(a: &Json, b: &Json) -> String {
+        render_unified(&changes(a, b), true)
+    }
+
+    /// List the differences turning `old` into `new` under `changes`, in
+    /// the stable order of `Json::render_diff`, for audit logs. Each one is
+    /// an object with the `path` of the value, whose dots and brackets are
+    /// escaped when part of a key, its `op` (`added`, `removed` or `changed`)
+    /// and its value `before` and `after`, `null` when missing.
+    /// Unlike a JSON Patch, it's meant to be read rather than applied.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    /// use serde_json::json;
+    ///
+    /// let old = inline!("port" => 80, "debug" => true);
+    /// let new = inline!("port" => 8080, "hosts" => vec!["example.com"]);
+    ///
+    /// assert_eq!(
+    ///     inline!("changes" => json!([
+    ///         {"path": "debug", "op": "removed", "before": true, "after": null},
+    ///         {"path": "port", "op": "changed", "before": 80, "after": 8080},
+    ///         {"path": "hosts", "op": "added", "before": null, "after": ["example.com"]}
+    ///     ])),
+    ///     Json::changelog(&old, &new)
+    /// );
+    /// ```
+    pub fn changelog(old: &Json, new: &Json) -> Json {
+        let entries: Vec<Value> = changes(old, new)
+            .into_iter()
+            .map(|change| {
+                let (path, op, before, after) = match change {
+                    Change::Added(path, after) => (path, "added", &Value::Null, after),
+                    Change::Removed(path, before) => (path, "removed", before, &Value::Null),
+                    Change::Changed(path, before, after) => (path, "changed", before, after),
+                };
+                let path = path::render(&path);
+                json!({ "path": path, "op": op, "before": before, "after": after })
+            })
+            .collect();
+        let mut changelog = Json::new();
+        changelog.add("changes", entries);
+        changelog
+    }
+}
+
+/// Whether the diff should be colorized, honoring the `NO_COLOR` convention.
+pub(crate) fn use_color() -> bool {
+    env::var_os("NO_COLOR").is_none()
+}
+
+/// Assert that two `json_ez::Json` instances are structurally equal.
+///
+/// On failure, the panic message lists every differing path instead of
+/// both documents: removed (left only) values are prefixed with `-` and
+/// added (right only) ones with `+`. Output is colorized unless the
+/// `NO_COLOR` environment variable is set.
+///
+/// # Example
+/// ```
+/// use json_ez::{assert_json_eq, inline};
+///
+/// let left = inline!("title" => "Mostly Harmless", "release_date" => 1992);
+/// let right = inline!("release_date" => 1992, "title" => "Mostly Harmless");
+///
+/// assert_json_eq!(left, right);
+/// ```
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($left: expr, $right: expr $(,)?) => {{
+        if let Some(diff) = $crate::assert_json_eq_inner(&$left, &$right) {
+            panic!("assertion failed: `(left == right)`\n{}", diff);
+        }
+    }};
+    ($left: expr, $right: expr, $($arg: tt)+) => {{
+        if let Some(diff) = $crate::assert_json_eq_inner(&$left, &$right) {
+            panic!("assertion failed: `(left == right)`: {}\n{}", format_args!($($arg)+), diff);
+        }
+    }};
+}
+
+#[doc(hidden)]
+pub fn assert_json_eq_inner(left: &Json, right: &Json) -> Option<String> {
+    let changes = changes(left, right);
+    if changes.is_empty() {
+        None
+    } else {
+        Some(render(&changes, use_color()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn changes_by_path() {
+        let left = inline!(
+            "title" => "Mostly Harmless",
+            "tags" => vec!["novel"],
+            "movie" => inline!("release_date" => 2004, "director" => "Garth Jennings")
+        );
+        let right = inline!(
+            "title" => "Mostly Harmless",
+            "tags" => vec!["novel", "sci-fi"],
+            "movie" => inline!("release_date" => 2005),
+            "read" => true
+        );
+        assert_eq!(
+            "-movie.director: \"Garth Jennings\"\n\
+             -movie.release_date: 2004\n\
+             +movie.release_date: 2005\n\
+             +tags[1]: \"sci-fi\"\n\
+             +read: true\n",
+            render(&changes(&left, &right), false)
+        );
+        assert!(changes(&left, &left.clone()).is_empty());
+
+        let left = inline!("a.b" => 1, "a" => inline!("b" => 1));
+        let right = inline!("a.b" => 2, "a" => inline!("b" => 2));
+        assert_eq!(
+            "-a.b: 1\n+a.b: 2\n-a\\.b: 1\n+a\\.b: 2\n",
+            render(&changes(&left, &right), false)
+        );
+    }
+
+    #[test]
+    fn changelog() {
+        let old = inline!("tags" => vec!["novel"], "movie" => inline!("year" => 2004));
+        let new = inline!("tags" => vec!["novel", "sci-fi"], "movie" => inline!("year" => 2005));
+        let changelog = Json::changelog(&old, &new);
+        let paths: Vec<&str> = changelog.json_data["changes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|entry| entry["path"].as_str())
+            .collect();
+        assert_eq!(vec!["movie.year", "tags[1]"], paths);
+
+        let old = inline!("log.level" => "info", "log" => inline!("level" => "info"));
+        let new = inline!("log.level" => "debug", "log" => inline!("level" => "info"));
+        assert_eq!(
+            json!({"path": "log\\.level", "op": "changed", "before": "info", "after": "debug"}),
+            Json::changelog(&old, &new).json_data["changes"][0]
+        );
+        assert_eq!(
+            inline!("changes" => Vec::<Value>::new()),
+            Json::changelog(&old, &old)
+        );
+    }
+
+    #[test]
+    fn unified_diff() {
+        let left = inline!("movie" => inline!("release_date" => 2004, "title" => "H2G2"));
+        let right = inline!("movie" => inline!("release_date" => 2005), "tags" => vec!["novel"]);
+        assert_eq!(
+            "--- left\n+++ right\n\
+             @@ movie @@\n\
+             -movie.release_date: 2004\n\
+             +movie.release_date: 2005\n\
+             -movie.title: \"H2G2\"\n\
+             @@ tags @@\n\
+             +tags: [\"novel\"]\n",
+            Json::render_diff(&left, &right)
+        );
+        assert_eq!(
+            "\x1b[1m--- left\x1b[0m\n\x1b[1m+++ right\x1b[0m\n\
+             \x1b[36m@@ tags @@\x1b[0m\n\
+             \x1b[31m-tags: [\"novel\"]\x1b[0m\n",
+            Json::render_diff_colored(&right, &inline!("movie" => inline!("release_date" => 2005)))
+        );
+        assert!(Json::render_diff(&left, &left).is_empty());
+
+        let left = inline!("a.b" => 1, "a" => inline!("b" => 1));
+        let right = inline!("a.b" => 2, "a" => inline!("b" => 2));
+        assert_eq!(
+            "--- left\n+++ right\n\
+             @@ a @@\n-a.b: 1\n+a.b: 2\n\
+             @@ a\\.b @@\n-a\\.b: 1\n+a\\.b: 2\n",
+            Json::render_diff(&left, &right)
+        );
+    }
+
+    #[test]
+    fn assert_json_eq_ok() {
+        assert_json_eq!(inline!("a" => 1, "b" => 2), inline!("b" => 2, "a" => 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "with message\n")]
+    fn assert_json_eq_panics() {
+        assert_json_eq!(inline!("a" => 1), inline!("a" => 2), "with {}", "message");
+    }
+}
@@ -0,0 +1,403 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Comment and formatting preserving edition of JSONC documents, such
+//! as configuration files written and maintained by humans.
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{json, Value};
+
+use crate::lenient::{to_json, Parser, Span};
+use crate::path::Segment;
+use crate::{Error, Json};
+
+/// A JSONC document keeping its original text, so that it can be edited
+/// and written back without losing comments, key ordering or whitespace
+/// outside of the edited values.
+///
+/// Comments (`//` and `/* */`) and trailing commas are accepted.
+///
+/// # Example
+/// ```
+/// use json_ez::JsonDocument;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut document = JsonDocument::parse(
+///         "{\n  // Last read novel\n  \"title\": \"Mostly Harmless\",\n  \"read\": false, /* yet */\n}",
+///     )?;
+///
+///     assert_eq!("Mostly Harmless", &document.get::<String>("title")?);
+///     document.add("read", true);
+///     document.add("rating", 5);
+///
+///     assert_eq!(
+///         "{\n  // Last read novel\n  \"title\": \"Mostly Harmless\",\n  \"read\": true, /* yet */\n  \"rating\": 5,\n}",
+///         document.to_string()
+///     );
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonDocument {
+    text: String,
+    json: Json,
+    root: Range<usize>,
+    members: Vec<(String, Span)>,
+}
+
+impl JsonDocument {
+    /// Parse a JSONC document holding an object.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::Parse)` if the input is not valid JSONC
+    /// or an `Err(json_ez::Error::TypeMismatch)` if it doesn't hold an object
+    pub fn parse(input: &str) -> Result<JsonDocument, Error> {
+        let mut parser = Parser::new(input);
+        parser.comments = true;
+        parser.trailing_commas = true;
+        parser.spans = Some(Vec::new());
+        let json = to_json(parser.parse()?)?;
+        let mut root = 0..input.len();
+        let mut members = Vec::new();
+        for span in parser.spans.unwrap_or_default() {
+            match span.path.as_slice() {
+                [] => root = span.value,
+                [Segment::Key(key)] => members.push((key.clone(), span)),
+                _ => {}
+            }
+        }
+        Ok(JsonDocument {
+            text: input.into(),
+            json,
+            root,
+            members,
+        })
+    }
+
+    /// Get the value associated to the given key, like `Json::get`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist
+    pub fn get<T: DeserializeOwned>(&self, k: &str) -> Result<T, Error> {
+        self.json.get(k)
+    }
+
+    /// Add a new item in the document, or replace the value of an existing
+    /// one, like `Json::add`. Only the text of the replaced value is
+    /// rewritten, along with its comments if any. New items are appended
+    /// after the last one and its trailing comments, following its
+    /// indentation.
+    pub fn add<V: Serialize>(&mut self, k: &str, v: V) {
+        let value = json!(v);
+        match self.members.iter().rposition(|(key, _)| key == k) {
+            Some(i) => {
+                let span = &self.members[i].1;
+                let rendered = self.render(&value, &self.indent(span.start));
+                let range = span.value.clone();
+                self.splice(range.clone(), &rendered);
+                self.members[i].1.value = range.start..range.start + rendered.len();
+            }
+            None => self.append(k, &value),
+        }
+        self.json.add(k, value);
+    }
+
+    /// Borrow the parsed content of the document.
+    pub fn as_json(&self) -> &Json {
+        &self.json
+    }
+
+    fn newline(&self) -> &'static str {
+        if self.text.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    fn multiline(&self) -> bool {
+        self.text[self.root.clone()].contains('\n')
+    }
+
+    /// Indentation of the line starting at the given position,
+    /// defaulting to two spaces.
+    fn indent(&self, pos: usize) -> String {
+        let line = &self.text[..pos];
+        let line = &line[line.rfind('\n').map_or(0, |i| i + 1)..];
+        if !line.is_empty() && line.chars().all(|c| c == ' ' || c == '\t') {
+            line.into()
+        } else {
+            "  ".into()
+        }
+    }
+
+    fn render(&self, value: &Value, indent: &str) -> String {
+        let nested = match value {
+            Value::Array(items) => !items.is_empty(),
+            Value::Object(map) => !map.is_empty(),
+            _ => false,
+        };
+        if !nested || !self.multiline() {
+            return value.to_string();
+        }
+        let mut buffer = Vec::new();
+        let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+        value
+            .serialize(&mut serializer)
+            .expect("values are always serializable");
+        let pretty = String::from_utf8(buffer).expect("JSON output is valid UTF-8");
+        pretty.replace('\n', &format!("{}{}", self.newline(), indent))
+    }
+
+    /// Replace the given range of the text, shifting the spans following it
+    /// instead of parsing the document again.
+    fn splice(&mut self, range: Range<usize>, replacement: &str) {
+        let shift = |pos: &mut usize| {
+            if *pos >= range.end && *pos > range.start {
+                *pos = *pos - range.end + range.start + replacement.len();
+            }
+        };
+        shift(&mut self.root.start);
+        shift(&mut self.root.end);
+        for (_, span) in &mut self.members {
+            shift(&mut span.start);
+            shift(&mut span.value.start);
+            shift(&mut span.value.end);
+        }
+        self.text.replace_range(range, replacement);
+    }
+
+    /// Keep the span of a member inserted at the given position.
+    fn push_member(&mut self, k: &str, key: &str, member: &str, pos: usize) {
+        let span = Span {
+            path: vec![Segment::Key(k.into())],
+            start: pos,
+            value: pos + key.len() + 2..pos + member.len(),
+        };
+        self.members.push((k.into(), span));
+    }
+
+    fn append(&mut self, k: &str, value: &Value) {
+        let key = Value::String(k.into()).to_string();
+        let newline = self.newline();
+        let (start, end) = match self.members.last() {
+            Some((_, span)) => (span.start, span.value.end),
+            None => {
+                let close = self.root.end - 1;
+                let inner = &self.text[self.root.start + 1..close];
+                let indent = "  ";
+                let member = format!("{}: {}", key, self.render(value, indent));
+                match inner.rfind('\n') {
+                    Some(i) if inner[i + 1..].trim().is_empty() => {
+                        let pos = self.root.start + 1 + i + 1;
+                        let inserted = format!("{}{}{}", indent, member, newline);
+                        self.splice(pos..pos, &inserted);
+                        self.push_member(k, &key, &member, pos + indent.len());
+                    }
+                    _ => {
+                        let pos = self.root.start + 1 + inner.trim_end().len();
+                        let inserted = format!("{}{}{}{}", newline, indent, member, newline);
+                        self.splice(pos..close, &inserted);
+                        let at = pos + newline.len() + indent.len();
+                        self.push_member(k, &key, &member, at);
+                    }
+                }
+                return;
+            }
+        };
+        let after = self.text[end..].trim_start_matches([' ', '\t']);
+        let comma = after.starts_with(',');
+        let next = if comma {
+            self.text.len() - after.len() + 1
+        } else {
+            end
+        };
+        match line_end(&self.text, next) {
+            Some(eol) if self.multiline() => {
+                let indent = self.indent(start);
+                let member = format!("{}: {}", key, self.render(value, &indent));
+                let inserted = if comma {
+                    format!("{}{}{},", newline, indent, member)
+                } else {
+                    format!("{}{}{}", newline, indent, member)
+                };
+                self.splice(eol..eol, &inserted);
+                let at = eol + newline.len() + indent.len();
+                self.push_member(k, &key, &member, at);
+                if !comma {
+                    self.splice(end..end, ",");
+                }
+            }
+            _ => {
+                let member = format!("{}: {}", key, value);
+                let pos = comments_end(&self.text, next);
+                if comma {
+                    self.splice(pos..pos, &format!(" {},", member));
+                    self.push_member(k, &key, &member, pos + 1);
+                } else {
+                    self.splice(pos..pos, &format!(", {}", member));
+                    self.push_member(k, &key, &member, pos + 2);
+                }
+            }
+        }
+    }
+}
+
+impl Display for JsonDocument {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Find the end of the `/* */` comments following the given position on
+/// the same line, if any.
+fn comments_end(text: &str, mut pos: usize) -> usize {
+    loop {
+        let rest = text[pos..].trim_start_matches([' ', '\t']);
+        let comment = match rest.strip_prefix("/*").and_then(|c| c.find("*/")) {
+            Some(len) if !rest[..len + 4].contains('\n') => &rest[..len + 4],
+            _ => return pos,
+        };
+        pos = text.len() - rest.len() + comment.len();
+    }
+}
+
+/// Find the end of the line at the given position, provided that the rest
+/// of the line only holds whitespace and comments.
+fn line_end(text: &str, mut pos: usize) -> Option<usize> {
+    loop {
+        let rest = &text[pos..];
+        if rest.starts_with('\n') || rest.starts_with("\r\n") {
+            return Some(pos);
+        } else if rest.starts_with(' ') || rest.starts_with('\t') {
+            pos += 1;
+        } else if rest.starts_with("//") {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            return Some(pos + rest[..end].trim_end_matches('\r').len());
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let comment = &rest[..comment.find("*/")? + 4];
+            if comment.contains('\n') {
+                return None;
+            }
+            pos += comment.len();
+        } else {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    const SETTINGS: &str = "{\n    // Appearance\n    \"theme\": \"dark\", // or \"light\"\n    \"font\": {\n        \"size\": 12\n    },\n\n    /* Editor */\n    \"tabs\": false\n}\n";
+
+    #[test]
+    fn untouched_document_is_unchanged() -> Result<(), Box<dyn Error>> {
+        let document = JsonDocument::parse(SETTINGS)?;
+        assert_eq!(SETTINGS, document.to_string());
+        assert_eq!("dark", &document.get::<String>("theme")?);
+        assert_eq!(12, document.as_json().get::<Value>("font")?["size"]);
+        assert!(document.get::<bool>("missing").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn replace_values() -> Result<(), Box<dyn Error>> {
+        let mut document = JsonDocument::parse(SETTINGS)?;
+        document.add("theme", "light");
+        document.add("font", json!({ "size": 14, "family": "mono" }));
+        assert_eq!(
+            "{\n    // Appearance\n    \"theme\": \"light\", // or \"light\"\n    \"font\": {\n        \"family\": \"mono\",\n        \"size\": 14\n    },\n\n    /* Editor */\n    \"tabs\": false\n}\n",
+            document.to_string()
+        );
+        assert_eq!("light", &document.get::<String>("theme")?);
+        Ok(())
+    }
+
+    #[test]
+    fn append_values() -> Result<(), Box<dyn Error>> {
+        let mut document = JsonDocument::parse(SETTINGS)?;
+        document.add("ruler", vec![80]);
+        assert_eq!(
+            "{\n    // Appearance\n    \"theme\": \"dark\", // or \"light\"\n    \"font\": {\n        \"size\": 12\n    },\n\n    /* Editor */\n    \"tabs\": false,\n    \"ruler\": [\n        80\n    ]\n}\n",
+            document.to_string()
+        );
+
+        // Spans are kept up to date through edits
+        document.add("tabs", true);
+        document.add("ruler", 100);
+        document.add("wrap", "off");
+        assert_eq!(
+            "{\n    // Appearance\n    \"theme\": \"dark\", // or \"light\"\n    \"font\": {\n        \"size\": 12\n    },\n\n    /* Editor */\n    \"tabs\": true,\n    \"ruler\": 100,\n    \"wrap\": \"off\"\n}\n",
+            document.to_string()
+        );
+        assert_eq!(100, document.get::<u8>("ruler")?);
+
+        let mut inline = JsonDocument::parse(r#"{ "a": 1 /* one */ }"#)?;
+        inline.add("b", 2);
+        assert_eq!(r#"{ "a": 1 /* one */, "b": 2 }"#, inline.to_string());
+        inline.add("a", 0);
+        inline.add("c", 3);
+        assert_eq!(
+            r#"{ "a": 0 /* one */, "b": 2, "c": 3 }"#,
+            inline.to_string()
+        );
+
+        let mut inline = JsonDocument::parse(r#"{ "a": 1, /* one */ }"#)?;
+        inline.add("b", 2);
+        assert_eq!(r#"{ "a": 1, /* one */ "b": 2, }"#, inline.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn append_to_empty_objects() -> Result<(), Box<dyn Error>> {
+        let mut document = JsonDocument::parse("{}")?;
+        document.add("a", 1);
+        assert_eq!("{\n  \"a\": 1\n}", document.to_string());
+
+        let mut document = JsonDocument::parse("{\r\n  // Nothing yet\r\n}")?;
+        document.add("a", 1);
+        assert_eq!(
+            "{\r\n  // Nothing yet\r\n  \"a\": 1\r\n}",
+            document.to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_documents() {
+        assert!(JsonDocument::parse("{ \"a\": 1 /* unterminated }").is_err());
+        assert!(JsonDocument::parse("[1, 2]").is_err());
+        assert!(JsonDocument::parse("{a: 'x', b: True}").is_err());
+        assert!(JsonDocument::parse("{\"a\": 'x'}").is_err());
+        assert!(JsonDocument::parse("{\"a\": [1,],}").is_ok());
+    }
+}
@@ -0,0 +1,354 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! In-place updates of the values of a document, through guards
+//! keeping its caches, checks and watchers consistent.
+
+use std::fmt::{self, Debug, Formatter};
+use std::iter;
+use std::ops::{Deref, DerefMut};
+use std::thread;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::path::{self, parse_concrete, Segment};
+use crate::ser::{self, NonFinite};
+use crate::{InvalidPath, Json};
+
+/// Mutable handle on a value of a document, returned by `Json::get_mut`,
+/// `Json::get_path_mut` and `Json::entry`, dereferencing to the value.
+/// Once it's dropped, the updated member is checked against the key
+/// policy and the limits of the document, and its watchers are notified.
+/// The value is only copied beforehand if the document has a key policy
+/// or limits, to be restored if they are not followed.
+///
+/// # Panics
+/// Panic when dropped if the updated member doesn't follow the key policy
+/// of the document or exceeds its limits, like `Json::add`, once the
+/// previous value is restored
+pub struct ValueMut<'a> {
+    json: &'a mut Json,
+    key: String,
+    rest: Vec<Segment>,
+    previous: Option<Option<Value>>,
+    watched: Vec<Option<Value>>,
+}
+
+impl<'a> ValueMut<'a> {
+    /// A handle on the value of the given stored key, which must exist.
+    pub(crate) fn new(json: &'a mut Json, key: String) -> Self {
+        ValueMut::at(json, key, Vec::new())
+    }
+
+    /// A handle on the value at the given location in the value of the
+    /// given stored key, which must exist.
+    fn at(json: &'a mut Json, key: String, rest: Vec<Segment>) -> Self {
+        let checked = json.key_policy.is_some() || json.limits.is_some();
+        ValueMut {
+            previous: checked.then(|| json.json_data.get(&key).cloned()),
+            watched: json.watched_values(),
+            json,
+            key,
+            rest,
+        }
+    }
+}
+
+impl Deref for ValueMut<'_> {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        self.json
+            .json_data
+            .get(&self.key)
+            .and_then(|member| path::lookup(member, &self.rest))
+            .expect("the value of a ValueMut exists")
+    }
+}
+
+impl DerefMut for ValueMut<'_> {
+    fn deref_mut(&mut self) -> &mut Value {
+        let rest = &self.rest;
+        self.json
+            .json_data
+            .get_mut(&self.key)
+            .and_then(|member| path::lookup_mut(member, rest))
+            .expect("the value of a ValueMut exists")
+    }
+}
+
+impl Debug for ValueMut<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_tuple("ValueMut").field(&**self).finish()
+    }
+}
+
+impl Drop for ValueMut<'_> {
+    fn drop(&mut self) {
+        let json = &mut *self.json;
+        json.fingerprint.take();
+        json.tally.take();
+        if let Some(fragments) = &mut json.fragments {
+            fragments.invalidate(iter::once(self.key.as_str()));
+        }
+        #[cfg(feature = "unicode")]
+        if json.nfc_keys {
+            if let Some(value) = json.json_data.remove(&self.key) {
+                let value = crate::unicode::nfc_value(value);
+                json.json_data.insert(self.key.clone(), value);
+            }
+        }
+        if let Some(previous) = self.previous.take() {
+            let value = json.json_data.get(&self.key).unwrap_or(&Value::Null);
+            if let Err(err) = json.check_member(&self.key, value) {
+                match previous {
+                    Some(previous) => json.json_data.insert(self.key.clone(), previous),
+                    None => json.json_data.remove(&self.key),
+                };
+                if !thread::panicking() {
+                    panic!("{}", err);
+                }
+                return;
+            }
+        }
+        json.notify(std::mem::take(&mut self.watched));
+    }
+}
+
+/// Member of a document, possibly missing, returned by `Json::entry`
+/// to update it in place, like the entries of a `HashMap`.
+#[derive(Debug)]
+pub struct Entry<'a> {
+    json: &'a mut Json,
+    key: String,
+}
+
+impl<'a> Entry<'a> {
+    /// The key of the member, normalized if enabled.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Whether the member exists.
+    pub fn exists(&self) -> bool {
+        self.json.json_data.contains_key(&self.key)
+    }
+
+    /// Add the given value if the member is missing, then return a handle
+    /// on its value.
+    ///
+    /// # Panics
+    /// Panic if the default value cannot be serialized, if the key doesn't
+    /// follow the key policy of the document or if its limits would be
+    /// exceeded, like `Json::add`
+    pub fn or_insert<V: Serialize>(self, default: V) -> ValueMut<'a> {
+        self.or_insert_with(|| default)
+    }
+
+    /// Add the value returned by the given function if the member is
+    /// missing, then return a handle on its value, like `Entry::or_insert`.
+    ///
+    /// # Panics
+    /// Panic if the default value cannot be serialized, if the key doesn't
+    /// follow the key policy of the document or if its limits would be
+    /// exceeded, like `Json::add`
+    pub fn or_insert_with<V: Serialize, F: FnOnce() -> V>(self, default: F) -> ValueMut<'a> {
+        if !self.exists() {
+            let encoders = self.json.encoders.as_deref();
+            let value = ser::to_value(&default(), NonFinite::Null, encoders).unwrap();
+            if let Err(err) = self.json.store(&self.key, value) {
+                panic!("{}", err);
+            }
+        }
+        ValueMut::new(self.json, self.key)
+    }
+
+    /// Update the value of the member in place if it exists.
+    ///
+    /// # Panics
+    /// Panic if the updated member doesn't follow the key policy of the
+    /// document or exceeds its limits, like `Json::add`, once the previous
+    /// value is restored
+    pub fn and_modify<F: FnOnce(&mut Value)>(self, f: F) -> Self {
+        if self.exists() {
+            let mut value = ValueMut::new(self.json, self.key.clone());
+            f(&mut value);
+        }
+        self
+    }
+}
+
+impl Json {
+    /// A mutable handle on the value of the given key, if it exists, to
+    /// update it in place without copying it out of the document and back.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let mut movie = inline!("cast" => vec!["Mos Def"]);
+    /// if let Some(mut cast) = movie.get_mut("cast") {
+    ///     cast.as_array_mut().unwrap().push("Zooey Deschanel".into());
+    /// }
+    ///
+    /// assert_eq!(inline!("cast" => vec!["Mos Def", "Zooey Deschanel"]), movie);
+    /// ```
+    pub fn get_mut(&mut self, k: &str) -> Option<ValueMut<'_>> {
+        let key = self.key(k).into_owned();
+        match self.json_data.contains_key(&key) {
+            true => Some(ValueMut::new(self, key)),
+            false => None,
+        }
+    }
+
+    /// A mutable handle on the value at the given path, such as
+    /// `movie.cast[0]`, if it exists, like `Json::get_mut`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::InvalidPath)` if the path is invalid
+    /// or holds wildcards
+    pub fn get_path_mut(&mut self, path: &str) -> Result<Option<ValueMut<'_>>, InvalidPath> {
+        let mut segments = parse_concrete(path)?
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => Segment::Key(self.key(&key).into_owned()),
+                other => other,
+            });
+        let key = match segments.next() {
+            Some(Segment::Key(key)) => key,
+            _ => return Ok(None),
+        };
+        let rest: Vec<Segment> = segments.collect();
+        let exists = self
+            .json_data
+            .get(&key)
+            .and_then(|member| path::lookup(member, &rest))
+            .is_some();
+        match exists {
+            true => Ok(Some(ValueMut::at(self, key, rest))),
+            false => Ok(None),
+        }
+    }
+
+    /// The member with the given key, possibly missing, to update it in
+    /// place without copying its value out of the document and back.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let mut stats = inline!("hits" => 41);
+    /// for page in &["/", "/about"] {
+    ///     stats
+    ///         .entry("pages")
+    ///         .or_insert(Vec::<String>::new())
+    ///         .as_array_mut()
+    ///         .unwrap()
+    ///         .push(page.to_string().into());
+    /// }
+    /// stats
+    ///     .entry("hits")
+    ///     .and_modify(|hits| *hits = (hits.as_u64().unwrap_or_default() + 1).into())
+    ///     .or_insert(1);
+    ///
+    /// assert_eq!(inline!("hits" => 42, "pages" => vec!["/", "/about"]), stats);
+    /// ```
+    pub fn entry(&mut self, k: &str) -> Entry<'_> {
+        Entry {
+            key: self.key(k).into_owned(),
+            json: self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, KeyPolicy, Limits};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn entries() -> Result<(), crate::Error> {
+        let mut json = inline!("a" => inline!("b" => 1));
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&changes);
+        json.watch("a.b", move |_, b| seen.lock().unwrap().push(b.cloned()))?;
+        let fingerprint = json.fingerprint();
+
+        json.entry("a").or_insert(0)["b"] = 2.into();
+        assert_eq!(vec![Some(2.into())], *changes.lock().unwrap());
+        assert_ne!(fingerprint, json.fingerprint());
+        assert_eq!(
+            inline!("a" => inline!("b" => 2)).fingerprint(),
+            json.fingerprint()
+        );
+
+        let entry = json.entry("c").and_modify(|_| panic!("missing member"));
+        assert!(!entry.exists());
+        assert_eq!("c", entry.key());
+        assert_eq!(Value::Null, *json.entry("c").or_insert_with(|| None::<u8>));
+        Ok(())
+    }
+
+    #[test]
+    fn get_mut() -> Result<(), crate::Error> {
+        let mut json = inline!("a" => inline!("b" => vec![1, 2]));
+        json.set_incremental(true);
+        let serialized = json.to_vec()?;
+        *json.get_path_mut("a.b[1]")?.unwrap() = 3.into();
+        json.get_mut("a").unwrap()["c"] = true.into();
+        assert!(json.get_mut("x").is_none());
+        assert!(json.get_path_mut("a.b[2]")?.is_none());
+        assert!(json.get_path_mut("[0]")?.is_none());
+        assert!(json.get_path_mut("a.*").is_err());
+
+        let expected = inline!("a" => inline!("b" => vec![1, 3], "c" => true));
+        assert_ne!(serialized, json.to_vec()?);
+        assert_eq!(expected.to_vec()?, json.to_vec()?);
+        assert_eq!(expected.fingerprint(), json.fingerprint());
+        Ok(())
+    }
+
+    #[test]
+    fn checks() -> Result<(), crate::Error> {
+        let mut json = inline!("tags" => Vec::<String>::new());
+        json.set_limits(Limits::new().max_nodes(3))?;
+        json.set_key_policy(KeyPolicy::no_whitespace())?;
+        json.entry("tags")
+            .and_modify(|tags| *tags = vec!["a", "b"].into());
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            json.entry("tags")
+                .and_modify(|tags| *tags = vec!["a", "b", "c"].into());
+        }));
+        assert!(result.is_err());
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            json.entry("tags")
+                .and_modify(|tags| *tags = serde_json::json!({ "a b": 1 }));
+        }));
+        assert!(result.is_err());
+        assert_eq!(inline!("tags" => vec!["a", "b"]), json);
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! The error returned by the methods of this crate, and the stable codes
+//! identifying its kinds so that they can be mapped to API error payloads
+//! without parsing their messages.
+
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+use serde::{Serialize, Serializer};
+use serde_json::{Error as SerdeError, Value};
+
+use crate::{
+    ArithmeticOverflow, CoercionError, DuplicateKey, IncompatibleSchema, InvalidKey, InvalidPath,
+    InvalidSchema, Json, LimitExceeded, NotFound, ParseError, TypeMismatch, UnresolvedRef,
+    ValidationError, ValueMismatch,
+};
+
+/// Stable machine-readable code of an error, serialized
+/// in screaming snake case (`NOT_FOUND`).
+///
+/// New codes may be added, so matches on it need a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// A key is missing, see `NotFound`
+    NotFound,
+    /// A value doesn't have the expected type, see `TypeMismatch`
+    TypeMismatch,
+    /// A path cannot be parsed, see `InvalidPath`
+    InvalidPath,
+    /// A schema cannot be interpreted, see `InvalidSchema`
+    InvalidSchema,
+    /// An input cannot be parsed, see `ParseError`
+    ParseError,
+    /// Values cannot be converted to a schema, see `CoercionError`
+    CoercionFailed,
+    /// A document doesn't satisfy a schema, see `ValidationError`
+    ValidationFailed,
+    /// A value cannot be serialized or deserialized by `serde_json`
+    Json,
+    /// A file cannot be read
+    Io,
+    /// A key doesn't follow the key policy of a document, see `InvalidKey`
+    InvalidKey,
+    /// A document would exceed its limits, see `LimitExceeded`
+    LimitExceeded,
+    /// A signature doesn't match a document, see `InvalidSignature`
+    InvalidSignature,
+    /// A schema is not compatible with another version, see `IncompatibleSchema`
+    IncompatibleSchema,
+    /// A key already exists in a document, see `DuplicateKey`
+    DuplicateKey,
+    /// A value is not the expected one, see `ValueMismatch`
+    ValueMismatch,
+    /// A `$ref` cannot be resolved, see `UnresolvedRef`
+    UnresolvedRef,
+    /// The arithmetic on a member overflows, see `ArithmeticOverflow`
+    ArithmeticOverflow,
+}
+
+impl ErrorCode {
+    /// The code as a string, such as `NOT_FOUND`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::TypeMismatch => "TYPE_MISMATCH",
+            ErrorCode::InvalidPath => "INVALID_PATH",
+            ErrorCode::InvalidSchema => "INVALID_SCHEMA",
+            ErrorCode::ParseError => "PARSE_ERROR",
+            ErrorCode::CoercionFailed => "COERCION_FAILED",
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::Json => "JSON",
+            ErrorCode::Io => "IO",
+            ErrorCode::InvalidKey => "INVALID_KEY",
+            ErrorCode::LimitExceeded => "LIMIT_EXCEEDED",
+            ErrorCode::InvalidSignature => "INVALID_SIGNATURE",
+            ErrorCode::IncompatibleSchema => "INCOMPATIBLE_SCHEMA",
+            ErrorCode::DuplicateKey => "DUPLICATE_KEY",
+            ErrorCode::ValueMismatch => "VALUE_MISMATCH",
+            ErrorCode::UnresolvedRef => "UNRESOLVED_REF",
+            ErrorCode::ArithmeticOverflow => "ARITHMETIC_OVERFLOW",
+        }
+    }
+
+    /// The code of a boxed error returned by this crate, if it is one of its
+    /// errors, a `serde_json::Error` or a `std::io::Error`.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, ErrorCode};
+    ///
+    /// let err: Box<dyn std::error::Error> = inline!("title" => "Mostly Harmless")
+    ///     .get::<bool>("read")
+    ///     .unwrap_err()
+    ///     .into();
+    /// assert_eq!(Some(ErrorCode::NotFound), ErrorCode::of(err.as_ref()));
+    /// ```
+    pub fn of(err: &(dyn StdError + 'static)) -> Option<ErrorCode> {
+        let code = if let Some(err) = err.downcast_ref::<Error>() {
+            err.code()
+        } else if err.is::<NotFound>() {
+            ErrorCode::NotFound
+        } else if err.is::<TypeMismatch>() {
+            ErrorCode::TypeMismatch
+        } else if err.is::<InvalidPath>() {
+            ErrorCode::InvalidPath
+        } else if err.is::<InvalidSchema>() {
+            ErrorCode::InvalidSchema
+        } else if err.is::<ParseError>() {
+            ErrorCode::ParseError
+        } else if err.is::<CoercionError>() {
+            ErrorCode::CoercionFailed
+        } else if err.is::<ValidationError>() {
+            ErrorCode::ValidationFailed
+        } else if err.is::<SerdeError>() {
+            ErrorCode::Json
+        } else if err.is::<io::Error>() {
+            ErrorCode::Io
+        } else if err.is::<InvalidKey>() {
+            ErrorCode::InvalidKey
+        } else if err.is::<LimitExceeded>() {
+            ErrorCode::LimitExceeded
+        } else if err.is::<IncompatibleSchema>() {
+            ErrorCode::IncompatibleSchema
+        } else if err.is::<DuplicateKey>() {
+            ErrorCode::DuplicateKey
+        } else if err.is::<ValueMismatch>() {
+            ErrorCode::ValueMismatch
+        } else if err.is::<UnresolvedRef>() {
+            ErrorCode::UnresolvedRef
+        } else if err.is::<ArithmeticOverflow>() {
+            ErrorCode::ArithmeticOverflow
+        } else {
+            #[cfg(feature = "crypto")]
+            if err.is::<crate::InvalidSignature>() {
+                return Some(ErrorCode::InvalidSignature);
+            }
+            return None;
+        };
+        Some(code)
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Error returned by the methods of this crate, wrapping the
+/// error describing what went wrong.
+///
+/// It converts into a `Box<dyn std::error::Error>` like any other error,
+/// so the `?` operator keeps working in application code.
+///
+/// # Example
+/// ```
+/// use json_ez::{inline, Error, ErrorCode};
+///
+/// let json = inline!("title" => "Mostly Harmless");
+///
+/// match json.get::<String>("titel") {
+///     Err(Error::NotFound(err)) => assert_eq!(&["title".to_string()], err.suggestions()),
+///     other => panic!("unexpected {:?}", other),
+/// }
+/// assert_eq!(ErrorCode::Json, json.get::<bool>("title").unwrap_err().code());
+/// ```
+///
+/// New variants may be added, some of them only with optional features
+/// such as `crypto`, so matches on it need a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A key is missing
+    NotFound(NotFound),
+    /// A value doesn't have the expected type
+    TypeMismatch(TypeMismatch),
+    /// A path cannot be parsed
+    InvalidPath(InvalidPath),
+    /// A schema cannot be interpreted
+    InvalidSchema(InvalidSchema),
+    /// An input cannot be parsed
+    Parse(ParseError),
+    /// Values cannot be converted to a schema
+    Coercion(CoercionError),
+    /// A document doesn't satisfy a schema
+    Validation(ValidationError),
+    /// A value cannot be serialized or deserialized by `serde_json`
+    Json(SerdeError),
+    /// A file cannot be read
+    Io(io::Error),
+    /// A key doesn't follow the key policy of a document
+    InvalidKey(InvalidKey),
+    /// A document would exceed its limits
+    LimitExceeded(LimitExceeded),
+    /// A schema is not compatible with another version
+    IncompatibleSchema(IncompatibleSchema),
+    /// A key already exists
+    DuplicateKey(DuplicateKey),
+    /// A value is not the expected one
+    ValueMismatch(ValueMismatch),
+    /// A `$ref` of the document cannot be resolved
+    UnresolvedRef(UnresolvedRef),
+    /// The arithmetic on a member overflows
+    ArithmeticOverflow(ArithmeticOverflow),
+    /// A signature doesn't match a document
+    #[cfg(feature = "crypto")]
+    InvalidSignature(crate::InvalidSignature),
+}
+
+impl Error {
+    /// Stable code of the wrapped error.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::NotFound(err) => err.code(),
+            Error::TypeMismatch(err) => err.code(),
+            Error::InvalidPath(err) => err.code(),
+            Error::InvalidSchema(err) => err.code(),
+            Error::Parse(err) => err.code(),
+            Error::Coercion(err) => err.code(),
+            Error::Validation(err) => err.code(),
+            Error::Json(_) => ErrorCode::Json,
+            Error::Io(_) => ErrorCode::Io,
+            Error::InvalidKey(err) => err.code(),
+            Error::LimitExceeded(err) => err.code(),
+            Error::IncompatibleSchema(err) => err.code(),
+            Error::DuplicateKey(err) => err.code(),
+            Error::ValueMismatch(err) => err.code(),
+            Error::UnresolvedRef(err) => err.code(),
+            Error::ArithmeticOverflow(err) => err.code(),
+            #[cfg(feature = "crypto")]
+            Error::InvalidSignature(err) => err.code(),
+        }
+    }
+
+    /// Represent the wrapped error as `Json`, see the `to_json`
+    /// method of each error.
+    pub fn to_json(&self) -> Json {
+        match self {
+            Error::NotFound(err) => err.to_json(),
+            Error::TypeMismatch(err) => err.to_json(),
+            Error::InvalidPath(err) => err.to_json(),
+            Error::InvalidSchema(err) => err.to_json(),
+            Error::Parse(err) => err.to_json(),
+            Error::Coercion(err) => err.to_json(),
+            Error::Validation(err) => err.to_json(),
+            Error::Json(err) => to_json(self.code(), err, vec![]),
+            Error::Io(err) => to_json(self.code(), err, vec![]),
+            Error::InvalidKey(err) => err.to_json(),
+            Error::LimitExceeded(err) => err.to_json(),
+            Error::IncompatibleSchema(err) => err.to_json(),
+            Error::DuplicateKey(err) => err.to_json(),
+            Error::ValueMismatch(err) => err.to_json(),
+            Error::UnresolvedRef(err) => err.to_json(),
+            Error::ArithmeticOverflow(err) => err.to_json(),
+            #[cfg(feature = "crypto")]
+            Error::InvalidSignature(err) => err.to_json(),
+        }
+    }
+
+    fn inner(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        match self {
+            Error::NotFound(err) => err,
+            Error::TypeMismatch(err) => err,
+            Error::InvalidPath(err) => err,
+            Error::InvalidSchema(err) => err,
+            Error::Parse(err) => err,
+            Error::Coercion(err) => err,
+            Error::Validation(err) => err,
+            Error::Json(err) => err,
+            Error::Io(err) => err,
+            Error::InvalidKey(err) => err,
+            Error::LimitExceeded(err) => err,
+            Error::IncompatibleSchema(err) => err,
+            Error::DuplicateKey(err) => err,
+            Error::ValueMismatch(err) => err,
+            Error::UnresolvedRef(err) => err,
+            Error::ArithmeticOverflow(err) => err,
+            #[cfg(feature = "crypto")]
+            Error::InvalidSignature(err) => err,
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.inner().source()
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(self.inner(), f)
+    }
+}
+
+macro_rules! from_error {
+    ($($(#[$attr:meta])* $variant:ident($error:ty)),*) => {
+        $(
+            $(#[$attr])*
+            impl From<$error> for Error {
+                fn from(err: $error) -> Self {
+                    Error::$variant(err)
+                }
+            }
+        )*
+    };
+}
+
+from_error!(
+    NotFound(NotFound),
+    TypeMismatch(TypeMismatch),
+    InvalidPath(InvalidPath),
+    InvalidSchema(InvalidSchema),
+    Parse(ParseError),
+    Coercion(CoercionError),
+    Validation(ValidationError),
+    Json(SerdeError),
+    Io(io::Error),
+    InvalidKey(InvalidKey),
+    LimitExceeded(LimitExceeded),
+    IncompatibleSchema(IncompatibleSchema),
+    DuplicateKey(DuplicateKey),
+    ValueMismatch(ValueMismatch),
+    UnresolvedRef(UnresolvedRef),
+    ArithmeticOverflow(ArithmeticOverflow),
+    #[cfg(feature = "crypto")]
+    InvalidSignature(crate::InvalidSignature)
+);
+
+/// Build the `Json` representation of an error: its code, its
+/// message and the given details.
+pub(crate) fn to_json(code: ErrorCode, message: &dyn Display, details: Vec<(&str, Value)>) -> Json {
+    let mut json = Json::new();
+    json.add("code", code);
+    json.add("message", message.to_string());
+    for (k, v) in details {
+        json.add(k, v);
+    }
+    json
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{deserialise, inline, Schema};
+
+    #[test]
+    fn codes_of_boxed_errors() -> Result<(), Box<dyn StdError>> {
+        let json = inline!("title" => "Mostly Harmless");
+        let code = |err: Box<dyn StdError>| ErrorCode::of(err.as_ref());
+        assert_eq!(
+            Some(ErrorCode::Json),
+            code(json.get::<bool>("title").unwrap_err().into())
+        );
+        assert_eq!(
+            Some(ErrorCode::ParseError),
+            code(Box::new(ParseError::new("unexpected end of input", 1, 2)))
+        );
+        let schema = Schema::new(deserialise!(r#"{ "type": "string" }"#)?);
+        let err = json.validate(&schema).unwrap_err();
+        assert_eq!(Some(ErrorCode::ValidationFailed), code(Box::new(err)));
+        assert_eq!(None, code("other".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn errors_as_json() -> Result<(), Box<dyn StdError>> {
+        let err = TypeMismatch::new("object", &Value::Null);
+        assert_eq!(ErrorCode::TypeMismatch, err.code());
+        assert_eq!(
+            serde_json::json!({
+                "code": "TYPE_MISMATCH",
+                "message": "TypeMismatch: Expected object but found null",
+                "expected": "object",
+                "found": "null"
+            }),
+            serde_json::to_value(err.to_json())?
+        );
+        let err = ParseError::new("unexpected end of input", 1, 2);
+        assert_eq!("PARSE_ERROR", err.to_json().get::<String>("code")?);
+        assert_eq!(2, err.to_json().get::<usize>("column")?);
+        Ok(())
+    }
+
+    #[test]
+    fn wrapped_errors() -> Result<(), Box<dyn StdError>> {
+        let err = Json::from_kv_args(["a..b=1"]).unwrap_err();
+        assert!(matches!(err, Error::InvalidPath(_)));
+        assert_eq!(ErrorCode::InvalidPath, err.code());
+        assert_eq!("INVALID_PATH", err.to_json().get::<String>("code")?);
+
+        let err = Error::from(io::Error::new(io::ErrorKind::NotFound, "order.json"));
+        assert_eq!("order.json", err.to_string());
+        assert_eq!("IO", err.to_json().get::<String>("code")?);
+
+        fn send_sync<T: Send + Sync>() {}
+        send_sync::<Error>();
+        Ok(())
+    }
+}
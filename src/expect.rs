@@ -0,0 +1,411 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Lightweight checks of single members, for the contracts too small to
+//! be worth a schema. Failed checks are reported as a `ValidationError`
+//! holding a single violation, named after the matching schema keyword.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::{Bound, RangeBounds};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::datetime::DateTime;
+use crate::path::pointer_child;
+use crate::validate::{ValidationError, Violation};
+use crate::{type_name, Error, Json, NotFound};
+
+/// Format of the string values checked by `Json::expect_format`.
+/// Some variants only exist with the `regex` feature.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Format {
+    /// An email address such as `arthur@example.com`, without display name
+    Email,
+    /// An absolute URL with an authority, such as `https://example.com/path`
+    Url,
+    /// A UUID in its hyphenated form, such as `67e55044-10b1-426f-9247-bb680e5fe0c8`
+    Uuid,
+    /// A RFC 3339 full date, such as `2005-04-28`
+    Date,
+    /// A RFC 3339 date-time, such as `2005-04-28T20:15:00+02:00`
+    DateTime,
+    /// An IPv4 address in dotted decimal notation
+    Ipv4,
+    /// An IPv6 address
+    Ipv6,
+    /// A string matching a regular expression, anywhere in the string
+    /// unless it is anchored with `^` and `$`
+    #[cfg(feature = "regex")]
+    Pattern(regex::Regex),
+}
+
+impl Format {
+    /// Name of the format, as used by the `format` keyword of JSON Schema.
+    fn name(&self) -> &str {
+        match self {
+            Format::Email => "email",
+            Format::Url => "uri",
+            Format::Uuid => "uuid",
+            Format::Date => "date",
+            Format::DateTime => "date-time",
+            Format::Ipv4 => "ipv4",
+            Format::Ipv6 => "ipv6",
+            #[cfg(feature = "regex")]
+            Format::Pattern(regex) => regex.as_str(),
+        }
+    }
+
+    fn matches(&self, s: &str) -> bool {
+        match self {
+            Format::Email => is_email(s),
+            Format::Url => is_url(s),
+            Format::Uuid => is_uuid(s),
+            Format::Date => s.len() == 10 && DateTime::parse(&format!("{}T00:00:00Z", s)).is_some(),
+            Format::DateTime => DateTime::parse(s).is_some(),
+            Format::Ipv4 => s.parse::<Ipv4Addr>().is_ok(),
+            Format::Ipv6 => s.parse::<Ipv6Addr>().is_ok(),
+            #[cfg(feature = "regex")]
+            Format::Pattern(regex) => regex.is_match(s),
+        }
+    }
+}
+
+fn is_email(s: &str) -> bool {
+    match s.rsplit_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !local.contains(|c: char| c.is_whitespace() || c == '@')
+                && domain.contains('.')
+                && is_host(domain)
+        }
+        None => false,
+    }
+}
+
+fn is_host(s: &str) -> bool {
+    s.split('.').all(|label| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+    })
+}
+
+fn is_url(s: &str) -> bool {
+    let (scheme, rest) = match s.split_once("://") {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !authority.is_empty()
+        && !s.contains(char::is_whitespace)
+}
+
+fn is_uuid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    groups.iter().map(|g| g.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|g| g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+impl Json {
+    /// Check that the value stored under the given key is one of the
+    /// allowed strings, and return it.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let statuses = ["active", "archived", "deleted"];
+    /// let account = inline!("status" => "active", "plan" => "gold");
+    ///
+    /// assert_eq!("active", account.expect_one_of("status", &statuses).unwrap());
+    /// assert_eq!(
+    ///     "ValidationError: enum at /plan: expected [\"free\",\"pro\"] but found \"gold\"",
+    ///     account.expect_one_of("plan", &["free", "pro"]).unwrap_err().to_string()
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist
+    /// or an `Err(json_ez::Error::Validation)` if its value is not a string
+    /// (`type` violation) or not one of the allowed ones (`enum` violation)
+    pub fn expect_one_of(&self, k: &str, allowed: &[&str]) -> Result<&str, Error> {
+        let value = self.member(k)?;
+        match value.as_str() {
+            Some(s) if allowed.contains(&s) => Ok(s),
+            Some(_) => Err(violation(k, "enum", json!(allowed), value.clone())),
+            None => Err(violation(
+                k,
+                "type",
+                "string".into(),
+                type_name(value).into(),
+            )),
+        }
+    }
+
+    /// Check that the number stored under the given key is within the
+    /// given range, and return it.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let config = inline!("port" => 8080, "workers" => 0);
+    ///
+    /// assert_eq!(8080, config.expect_range("port", 1..=65535).unwrap());
+    /// assert_eq!(
+    ///     "ValidationError: minimum at /workers: expected 1 but found 0",
+    ///     config.expect_range("workers", 1..).unwrap_err().to_string()
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist,
+    /// an `Err(json_ez::Error::Json)` if its value cannot be deserialized as
+    /// a `T` or an `Err(json_ez::Error::Validation)` if it is out of range
+    /// (`minimum`, `exclusiveMinimum`, `maximum` or `exclusiveMaximum` violation)
+    pub fn expect_range<T, R>(&self, k: &str, range: R) -> Result<T, Error>
+    where
+        T: DeserializeOwned + PartialOrd + Serialize,
+        R: RangeBounds<T>,
+    {
+        let value = self.member(k)?;
+        let n = T::deserialize(value)?;
+        let bound = |keyword: &str, bound: &T| {
+            let expected = serde_json::to_value(bound).unwrap_or(Value::Null);
+            Err(violation(k, keyword, expected, value.clone()))
+        };
+        match range.start_bound() {
+            Bound::Included(min) if n < *min => return bound("minimum", min),
+            Bound::Excluded(min) if n <= *min => return bound("exclusiveMinimum", min),
+            _ => {}
+        }
+        match range.end_bound() {
+            Bound::Included(max) if n > *max => bound("maximum", max),
+            Bound::Excluded(max) if n >= *max => bound("exclusiveMaximum", max),
+            _ => Ok(n),
+        }
+    }
+
+    /// Check that the length of the string (in characters), array or
+    /// object stored under the given key is within the given range, and
+    /// return it.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let user = inline!("name" => "", "roles" => vec!["admin"]);
+    ///
+    /// assert_eq!(1, user.expect_len("roles", 1..).unwrap());
+    /// assert_eq!(
+    ///     "ValidationError: minLength at /name: expected 1 but found 0",
+    ///     user.expect_len("name", 1..=64).unwrap_err().to_string()
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or
+    /// an `Err(json_ez::Error::Validation)` if its value has no length
+    /// (`type` violation) or if it is out of range (`minLength`, `maxLength`,
+    /// `minItems`, `maxItems`, `minProperties` or `maxProperties` violation)
+    pub fn expect_len<R: RangeBounds<usize>>(&self, k: &str, range: R) -> Result<usize, Error> {
+        let value = self.member(k)?;
+        let (length, keywords) = match value {
+            Value::String(s) => (s.chars().count(), ["minLength", "maxLength"]),
+            Value::Array(items) => (items.len(), ["minItems", "maxItems"]),
+            Value::Object(map) => (map.len(), ["minProperties", "maxProperties"]),
+            other => {
+                let expected = json!(["string", "array", "object"]);
+                return Err(violation(k, "type", expected, type_name(other).into()));
+            }
+        };
+        // Length bounds are reported inclusive, like the schema keywords
+        let min = match range.start_bound() {
+            Bound::Included(min) => Some(*min),
+            Bound::Excluded(min) => Some(min.saturating_add(1)),
+            Bound::Unbounded => None,
+        };
+        let max = match range.end_bound() {
+            Bound::Included(max) => Some(*max),
+            Bound::Excluded(max) => Some(max.saturating_sub(1)),
+            Bound::Unbounded => None,
+        };
+        match (min, max) {
+            _ if range.contains(&length) => Ok(length),
+            (Some(min), _) if length < min => {
+                Err(violation(k, keywords[0], min.into(), length.into()))
+            }
+            (_, max) => Err(violation(k, keywords[1], max.into(), length.into())),
+        }
+    }
+
+    /// Check that the string stored under the given key has the given
+    /// format, and return it.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Format};
+    ///
+    /// let user = inline!("email" => "arthur@example.com", "website" => "example.com");
+    ///
+    /// assert!(user.expect_format("email", Format::Email).is_ok());
+    /// assert_eq!(
+    ///     "ValidationError: format at /website: expected \"uri\" but found \"example.com\"",
+    ///     user.expect_format("website", Format::Url).unwrap_err().to_string()
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist
+    /// or an `Err(json_ez::Error::Validation)` if its value is not a string
+    /// (`type` violation) or doesn't have the format (`format` violation,
+    /// or `pattern` for `Format::Pattern`)
+    pub fn expect_format(&self, k: &str, format: Format) -> Result<&str, Error> {
+        let value = self.member(k)?;
+        let keyword = match format {
+            #[cfg(feature = "regex")]
+            Format::Pattern(_) => "pattern",
+            _ => "format",
+        };
+        match value.as_str() {
+            Some(s) if format.matches(s) => Ok(s),
+            Some(_) => Err(violation(k, keyword, format.name().into(), value.clone())),
+            None => Err(violation(
+                k,
+                "type",
+                "string".into(),
+                type_name(value).into(),
+            )),
+        }
+    }
+
+    /// The value stored under the given key.
+    pub(crate) fn member(&self, k: &str) -> Result<&Value, Error> {
+        match self.json_data.get(self.key(k).as_ref()) {
+            Some(value) => Ok(value),
+            None => Err(NotFound::new(k.into(), self)?.into()),
+        }
+    }
+}
+
+fn violation(k: &str, keyword: &str, expected: Value, actual: Value) -> Error {
+    let location = pointer_child("", k);
+    ValidationError::new(vec![Violation::new(&location, keyword, expected, actual)]).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, ErrorCode};
+
+    #[test]
+    fn one_of() {
+        let json = inline!("status" => 1);
+        let err = json.expect_one_of("status", &["active"]).unwrap_err();
+        assert_eq!(ErrorCode::ValidationFailed, err.code());
+        assert_eq!(
+            "ValidationError: type at /status: expected \"string\" but found \"number\"",
+            err.to_string()
+        );
+        let err = json.expect_one_of("state", &["active"]).unwrap_err();
+        assert_eq!(ErrorCode::NotFound, err.code());
+    }
+
+    #[test]
+    fn ranges() {
+        let json = inline!("ratio" => 0.5, "tags" => inline!("a" => 1), "count" => 3);
+        assert_eq!(0.5, json.expect_range("ratio", 0.0..1.0).unwrap());
+        assert_eq!(
+            "ValidationError: exclusiveMaximum at /ratio: expected 0.5 but found 0.5",
+            json.expect_range("ratio", ..0.5).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "ValidationError: maxProperties at /tags: expected 0 but found 1",
+            json.expect_len("tags", ..1).unwrap_err().to_string()
+        );
+        let after_max = (Bound::Excluded(usize::MAX), Bound::Unbounded);
+        assert_eq!(
+            format!(
+                "ValidationError: minProperties at /tags: expected {} but found 1",
+                usize::MAX
+            ),
+            json.expect_len("tags", after_max).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "ValidationError: type at /count: expected [\"string\",\"array\",\"object\"] but found \"number\"",
+            json.expect_len("count", 1..).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn formats() {
+        let cases = [
+            (Format::Email, "ford.prefect+guide@betelgeuse.example", true),
+            (Format::Email, "ford@localhost", false),
+            (Format::Email, "ford prefect@example.com", false),
+            (
+                Format::Url,
+                "https://example.com:8080/guide?page=42#towel",
+                true,
+            ),
+            (Format::Url, "mailto:ford@example.com", false),
+            (Format::Url, "https:///path", false),
+            (Format::Uuid, "67e55044-10b1-426f-9247-bb680e5fe0c8", true),
+            (Format::Uuid, "67e5504410b1426f9247bb680e5fe0c8", false),
+            (Format::Date, "2005-04-28", true),
+            (Format::Date, "2005-02-30", false),
+            (Format::DateTime, "2005-04-28T20:15:00+02:00", true),
+            (Format::DateTime, "2005-04-28", false),
+            (Format::Ipv4, "192.168.0.42", true),
+            (Format::Ipv4, "192.168.0.256", false),
+            (Format::Ipv6, "::1", true),
+            (Format::Ipv6, "192.168.0.42", false),
+        ];
+        for (format, s, valid) in cases.iter() {
+            assert_eq!(*valid, format.matches(s), "{} {:?}", s, format);
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn patterns() {
+        let json = inline!("sku" => "HHGG-42");
+        let pattern = |p| Format::Pattern(regex::Regex::new(p).unwrap());
+        assert!(json.expect_format("sku", pattern(r"^[A-Z]+-\d+$")).is_ok());
+        assert_eq!(
+            "ValidationError: pattern at /sku: expected \"^\\\\d+$\" but found \"HHGG-42\"",
+            json.expect_format("sku", pattern(r"^\d+$"))
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,502 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Random documents generation driven by a JSON Schema.
+//!
+//! Supported keywords are `type`, `enum`, `const`, `properties`, `required`,
+//! `items`, `minItems`, `maxItems`, `uniqueItems`, `minLength`, `maxLength`,
+//! `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `multipleOf`,
+//! `format`, `oneOf`, `anyOf` and `allOf`.
+
+use rand::{seq::IndexedRandom, Rng};
+use serde_json::{Map, Number, Value};
+
+use crate::schema::{types, InvalidSchema, Schema};
+use crate::{Error, Json, TypeMismatch};
+
+const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+const DEFAULT_MAX_LENGTH: u64 = 16;
+const DEFAULT_MAX_ITEMS: u64 = 4;
+const DEFAULT_RANGE: f64 = 1000.0;
+const MAX_UNIQUE_ATTEMPTS: usize = 100;
+
+impl Json {
+    /// Generate a random `Json` instance satisfying the given schema,
+    /// which describes an object.
+    ///
+    /// Optional properties are included randomly, required ones always are.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, Json, Schema};
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let schema = Schema::new(deserialise!(r#"{
+    ///         "type": "object",
+    ///         "properties": {
+    ///             "id": { "type": "string", "format": "uuid" },
+    ///             "status": { "enum": ["active", "archived"] },
+    ///             "quantity": { "type": "integer", "minimum": 1, "maximum": 10 }
+    ///         },
+    ///         "required": ["id", "status", "quantity"]
+    ///     }"#)?);
+    ///     let mut rng = StdRng::seed_from_u64(42);
+    ///
+    ///     let order = Json::generate(&schema, &mut rng)?;
+    ///     let quantity: u8 = order.get("quantity")?;
+    ///
+    ///     assert!((1..=10).contains(&quantity));
+    ///     assert_eq!(36, order.get::<String>("id")?.len());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidSchema)` if the schema contains
+    /// unsatisfiable constraints or an `Err(json_ez::Error::TypeMismatch)` if
+    /// it doesn't describe an object
+    pub fn generate<R: Rng + ?Sized>(schema: &Schema, rng: &mut R) -> Result<Json, Error> {
+        match generate(schema.as_value(), "#", rng)? {
+            Value::Object(map) => Ok(Json::from_data(map.into_iter().collect())),
+            other => Err(TypeMismatch::new("object", &other).into()),
+        }
+    }
+}
+
+fn generate<R: Rng + ?Sized>(
+    schema: &Value,
+    location: &str,
+    rng: &mut R,
+) -> Result<Value, InvalidSchema> {
+    let schema = match schema {
+        Value::Bool(true) => return Ok(Value::Null),
+        Value::Bool(false) => return Err(InvalidSchema::new(location, "false schema")),
+        Value::Object(schema) => schema,
+        _ => return Err(InvalidSchema::new(location, "schema must be an object")),
+    };
+
+    if let Some(value) = schema.get("const") {
+        return Ok(value.clone());
+    }
+    if let Some(values) = schema.get("enum") {
+        return values
+            .as_array()
+            .and_then(|values| values.choose(rng))
+            .cloned()
+            .ok_or_else(|| InvalidSchema::new(location, "enum must be a non empty array"));
+    }
+    for keyword in &["oneOf", "anyOf"] {
+        if let Some(branches) = schema.get(*keyword) {
+            let branches = branches
+                .as_array()
+                .filter(|b| !b.is_empty())
+                .ok_or_else(|| {
+                    InvalidSchema::new(location, &format!("{} must be a non empty array", keyword))
+                })?;
+            let i = rng.random_range(0..branches.len());
+            let branch = merge(schema, &[&branches[i]]);
+            return generate(&branch, &format!("{}/{}/{}", location, keyword, i), rng);
+        }
+    }
+    if let Some(branches) = schema.get("allOf") {
+        let branches: Vec<&Value> = branches.as_array().into_iter().flatten().collect();
+        return generate(&merge(schema, &branches), location, rng);
+    }
+
+    let wrapped = Value::Object(schema.clone());
+    let mut allowed = types(&wrapped);
+    if allowed.is_empty() {
+        allowed = if schema.contains_key("properties") {
+            vec!["object"]
+        } else if schema.contains_key("items") {
+            vec!["array"]
+        } else {
+            vec!["null", "boolean", "integer", "string"]
+        };
+    }
+    let kind = *allowed.choose(rng).expect("allowed types are never empty");
+
+    match kind {
+        "null" => Ok(Value::Null),
+        "boolean" => Ok(Value::Bool(rng.random())),
+        "integer" => integer(schema, location, rng),
+        "number" => number(schema, location, rng),
+        "string" => string(schema, location, rng),
+        "array" => array(schema, location, rng),
+        "object" => object(schema, &wrapped, location, rng),
+        other => Err(InvalidSchema::new(
+            location,
+            &format!("unknown type {}", other),
+        )),
+    }
+}
+
+/// Names listed by the `required` keyword of a (sub)schema.
+fn required(schema: &Value) -> Vec<&str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Merge the given branches into their parent schema, combining `properties`
+/// and `required` and letting branches override any other keyword.
+fn merge(parent: &Map<String, Value>, branches: &[&Value]) -> Value {
+    let mut merged = parent.clone();
+    for keyword in &["oneOf", "anyOf", "allOf"] {
+        merged.remove(*keyword);
+    }
+    for branch in branches.iter().filter_map(|b| b.as_object()) {
+        for (k, v) in branch {
+            match (k.as_str(), merged.get_mut(k), v) {
+                ("properties", Some(Value::Object(existing)), Value::Object(new)) => {
+                    existing.extend(new.clone())
+                }
+                ("required", Some(Value::Array(existing)), Value::Array(new)) => {
+                    existing.extend(new.iter().cloned())
+                }
+                _ => {
+                    merged.insert(k.clone(), v.clone());
+                }
+            }
+        }
+    }
+    Value::Object(merged)
+}
+
+/// A numeric bound and whether it is exclusive.
+type Bound = Option<(f64, bool)>;
+
+fn bound(schema: &Map<String, Value>, inclusive: &str, exclusive: &str) -> Bound {
+    match schema.get(exclusive) {
+        Some(Value::Number(n)) => n.as_f64().map(|n| (n, true)),
+        _ => schema
+            .get(inclusive)
+            .and_then(Value::as_f64)
+            .map(|n| (n, false)),
+    }
+}
+
+fn range(schema: &Map<String, Value>) -> (Bound, Bound) {
+    (
+        bound(schema, "minimum", "exclusiveMinimum"),
+        bound(schema, "maximum", "exclusiveMaximum"),
+    )
+}
+
+fn integer<R: Rng + ?Sized>(
+    schema: &Map<String, Value>,
+    location: &str,
+    rng: &mut R,
+) -> Result<Value, InvalidSchema> {
+    let empty = || InvalidSchema::new(location, "empty integer range");
+    // Casts saturate, bounds beyond the range of an i64 are clamped to it
+    let (min, max) = range(schema);
+    let min = match min {
+        Some((n, true)) => (n.floor() as i64).checked_add(1).ok_or_else(empty)?,
+        Some((n, false)) => n.ceil() as i64,
+        None => max.map_or(0, |(n, _)| (n as i64).saturating_sub(DEFAULT_RANGE as i64)),
+    };
+    let max = match max {
+        Some((n, true)) => (n.ceil() as i64).checked_sub(1).ok_or_else(empty)?,
+        Some((n, false)) => n.floor() as i64,
+        None => min.saturating_add(DEFAULT_RANGE as i64),
+    };
+    let step = schema
+        .get("multipleOf")
+        .and_then(Value::as_i64)
+        .filter(|s| *s > 0)
+        .unwrap_or(1);
+    let (low, high) = (div_ceil(min, step), max.div_euclid(step));
+    if low > high {
+        return Err(empty());
+    }
+    Ok(Value::from(rng.random_range(low..=high) * step))
+}
+
+/// The quotient of `n` by the positive `d`, rounded up.
+fn div_ceil(n: i64, d: i64) -> i64 {
+    n.div_euclid(d) + i64::from(n.rem_euclid(d) != 0)
+}
+
+fn number<R: Rng + ?Sized>(
+    schema: &Map<String, Value>,
+    location: &str,
+    rng: &mut R,
+) -> Result<Value, InvalidSchema> {
+    let (min, max) = range(schema);
+    let low = min.map_or_else(|| max.map_or(0.0, |(n, _)| n - DEFAULT_RANGE), |(n, _)| n);
+    let high = max.map_or(low + DEFAULT_RANGE, |(n, _)| n);
+    if low > high || (low == high && (min.is_some_and(|b| b.1) || max.is_some_and(|b| b.1))) {
+        return Err(InvalidSchema::new(location, "empty number range"));
+    }
+    let step = schema
+        .get("multipleOf")
+        .and_then(Value::as_f64)
+        .filter(|s| *s > 0.0);
+    if let Some(step) = step {
+        let (mut first, mut last) = ((low / step).ceil(), (high / step).floor());
+        if first * step <= low && min.is_some_and(|b| b.1) {
+            first += 1.0;
+        }
+        if last * step >= high && max.is_some_and(|b| b.1) {
+            last -= 1.0;
+        }
+        if first > last {
+            return Err(InvalidSchema::new(location, "empty number range"));
+        }
+        let k = between(rng, first, last).round().clamp(first, last);
+        return Ok(Number::from_f64(k * step).map_or(Value::Null, Value::Number));
+    }
+    let mut n = between(rng, low, high);
+    let excluded = |bound: Bound| bound.is_some_and(|b| b.1 && b.0 == n);
+    if excluded(min) || excluded(max) {
+        n = low / 2.0 + high / 2.0;
+    }
+    Ok(Number::from_f64(n).map_or(Value::Null, Value::Number))
+}
+
+/// A random number between the given bounds, which may be too far apart for
+/// their difference to be finite.
+fn between<R: Rng + ?Sized>(rng: &mut R, low: f64, high: f64) -> f64 {
+    let t: f64 = rng.random();
+    (low * (1.0 - t) + high * t).clamp(low, high)
+}
+
+fn string<R: Rng + ?Sized>(
+    schema: &Map<String, Value>,
+    location: &str,
+    rng: &mut R,
+) -> Result<Value, InvalidSchema> {
+    let formatted = match schema.get("format").and_then(Value::as_str) {
+        Some("uuid") => Some(uuid(rng)),
+        Some("email") => Some(format!("{}@example.com", word(rng, 8).to_lowercase())),
+        Some("uri") | Some("url") => Some(format!("https://example.com/{}", word(rng, 8))),
+        Some("hostname") => Some(format!("{}.example.com", word(rng, 8).to_lowercase())),
+        Some("ipv4") => Some(format!(
+            "{}.{}.{}.{}",
+            rng.random::<u8>(),
+            rng.random::<u8>(),
+            rng.random::<u8>(),
+            rng.random::<u8>()
+        )),
+        Some("date") => Some(date(rng)),
+        Some("time") => Some(time(rng)),
+        Some("date-time") => Some(format!("{}T{}Z", date(rng), time(rng))),
+        _ => None,
+    };
+    if let Some(s) = formatted {
+        return Ok(Value::String(s));
+    }
+
+    let min = schema.get("minLength").and_then(Value::as_u64).unwrap_or(0);
+    let max = schema
+        .get("maxLength")
+        .and_then(Value::as_u64)
+        .unwrap_or_else(|| min.max(1) + DEFAULT_MAX_LENGTH);
+    if min > max {
+        return Err(InvalidSchema::new(location, "minLength exceeds maxLength"));
+    }
+    let len = rng.random_range(min..=max) as usize;
+    Ok(Value::String(word(rng, len)))
+}
+
+fn word<R: Rng + ?Sized>(rng: &mut R, len: usize) -> String {
+    (0..len)
+        .map(|_| *ALPHABET.choose(rng).expect("alphabet is not empty") as char)
+        .collect()
+}
+
+fn uuid<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let mut bytes: [u8; 16] = rng.random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn date<R: Rng + ?Sized>(rng: &mut R) -> String {
+    format!(
+        "{:04}-{:02}-{:02}",
+        rng.random_range(2000..=2030),
+        rng.random_range(1..=12),
+        rng.random_range(1..=28)
+    )
+}
+
+fn time<R: Rng + ?Sized>(rng: &mut R) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        rng.random_range(0..24),
+        rng.random_range(0..60),
+        rng.random_range(0..60)
+    )
+}
+
+fn array<R: Rng + ?Sized>(
+    schema: &Map<String, Value>,
+    location: &str,
+    rng: &mut R,
+) -> Result<Value, InvalidSchema> {
+    let min = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+    let max = schema
+        .get("maxItems")
+        .and_then(Value::as_u64)
+        .unwrap_or(min + DEFAULT_MAX_ITEMS);
+    if min > max {
+        return Err(InvalidSchema::new(location, "minItems exceeds maxItems"));
+    }
+    let len = rng.random_range(min..=max) as usize;
+    let unique = schema.get("uniqueItems") == Some(&Value::Bool(true));
+    let items = schema.get("items").cloned().unwrap_or(Value::Bool(true));
+    let location = format!("{}/items", location);
+
+    let mut values = Vec::with_capacity(len);
+    let mut attempts = 0;
+    while values.len() < len {
+        let value = generate(&items, &location, rng)?;
+        if unique && values.contains(&value) {
+            attempts += 1;
+            if attempts > MAX_UNIQUE_ATTEMPTS {
+                return Err(InvalidSchema::new(
+                    &location,
+                    "cannot generate unique items",
+                ));
+            }
+            continue;
+        }
+        values.push(value);
+    }
+    Ok(Value::Array(values))
+}
+
+fn object<R: Rng + ?Sized>(
+    schema: &Map<String, Value>,
+    wrapped: &Value,
+    location: &str,
+    rng: &mut R,
+) -> Result<Value, InvalidSchema> {
+    let required = required(wrapped);
+    let mut map = Map::new();
+    if let Some(Value::Object(properties)) = schema.get("properties") {
+        for (name, property) in properties {
+            if required.contains(&name.as_str()) || rng.random_bool(0.5) {
+                let location = format!("{}/properties/{}", location, name);
+                map.insert(name.clone(), generate(property, &location, rng)?);
+            }
+        }
+    }
+    Ok(Value::Object(map))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deserialise;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashSet;
+    use std::error::Error;
+
+    fn schema(s: &str) -> Schema {
+        Schema::new(deserialise!(s).unwrap())
+    }
+
+    #[test]
+    fn generate_respects_constraints() -> Result<(), Box<dyn Error>> {
+        let schema = schema(
+            r#"{
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "minLength": 3, "maxLength": 5 },
+                    "even": { "type": "integer", "exclusiveMinimum": 0, "maximum": 10, "multipleOf": 2 },
+                    "ratio": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "half": { "type": "number", "exclusiveMinimum": 0, "maximum": 2, "multipleOf": 0.5 },
+                    "wide": { "type": "number", "minimum": -1.7e308, "maximum": 1.7e308 },
+                    "huge": { "type": "integer", "minimum": -1e30, "exclusiveMaximum": -9.2e18 },
+                    "tags": {
+                        "type": "array",
+                        "items": { "enum": ["a", "b", "c"] },
+                        "minItems": 2,
+                        "maxItems": 3,
+                        "uniqueItems": true
+                    },
+                    "nested": {
+                        "properties": { "at": { "type": "string", "format": "date-time" } },
+                        "required": ["at"]
+                    }
+                },
+                "required": ["name", "even", "ratio", "half", "wide", "huge", "tags", "nested"]
+            }"#,
+        );
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            let json = Json::generate(&schema, &mut rng)?;
+            assert!((3..=5).contains(&json.get::<String>("name")?.len()));
+            let even = json.get::<u8>("even")?;
+            assert!(even % 2 == 0 && (2..=10).contains(&even));
+            assert!((0.0..=1.0).contains(&json.get::<f64>("ratio")?));
+            let half = json.get::<f64>("half")?;
+            assert!([0.5, 1.0, 1.5, 2.0].contains(&half));
+            assert!(json.get::<f64>("wide")?.is_finite());
+            assert!(json.get::<i64>("huge")? < -9_200_000_000_000_000_000);
+            let tags = json.get::<Vec<String>>("tags")?;
+            assert!((2..=3).contains(&tags.len()));
+            assert_eq!(tags.len(), tags.iter().collect::<HashSet<_>>().len());
+            assert_eq!(20, json.get::<Json>("nested")?.get::<String>("at")?.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn generate_errors() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let err = Json::generate(&schema(r#"{ "type": "string" }"#), &mut rng).unwrap_err();
+        assert_eq!(
+            "TypeMismatch: Expected object but found string",
+            format!("{}", err)
+        );
+
+        let unsatisfiable = schema(
+            r#"{ "properties": { "n": { "type": "integer", "minimum": 5, "maximum": 1 } }, "required": ["n"] }"#,
+        );
+        let err = Json::generate(&unsatisfiable, &mut rng).unwrap_err();
+        assert_eq!(
+            "InvalidSchema: empty integer range at #/properties/n",
+            format!("{}", err)
+        );
+
+        let unsatisfiable = schema(
+            r#"{ "properties": { "n": { "type": "integer", "exclusiveMinimum": 1e30 } }, "required": ["n"] }"#,
+        );
+        assert!(Json::generate(&unsatisfiable, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Flattening of the fields of structs and maps into documents, to build
+//! payloads from several typed sources.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::ser::{self, NonFinite};
+use crate::{Error, Json, TypeMismatch};
+
+impl Json {
+    /// Add each field of the given struct or map as a member of the
+    /// document, replacing the values already stored under their keys like
+    /// `Json::add`. The fields are all added, or none on error.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct User {
+    ///     id: u32,
+    ///     name: String,
+    /// }
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let user = User { id: 42, name: "Arthur".into() };
+    ///     let mut payload = inline!("event" => "signup");
+    ///     payload.add_fields(&user)?;
+    ///     payload.add_fields_prefixed("meta.", &inline!("source" => "web"))?;
+    ///
+    ///     assert_eq!(
+    ///         inline!("event" => "signup", "id" => 42, "name" => "Arthur", "meta.source" => "web"),
+    ///         payload
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::TypeMismatch)` if the source is not
+    /// serialized as an object, or the errors of `Json::add_with`
+    pub fn add_fields<T: Serialize + ?Sized>(&mut self, source: &T) -> Result<(), Error> {
+        self.add_fields_prefixed("", source)
+    }
+
+    /// Add each field of the given struct or map like `Json::add_fields`,
+    /// with the given prefix added to their keys, see `Json::prefixed`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::TypeMismatch)` if the source is not
+    /// serialized as an object, or the errors of `Json::add_with`
+    pub fn add_fields_prefixed<T: Serialize + ?Sized>(
+        &mut self,
+        prefix: &str,
+        source: &T,
+    ) -> Result<(), Error> {
+        let fields = match ser::to_value(source, NonFinite::Null, self.encoders.as_deref())? {
+            Value::Object(fields) => fields,
+            other => return Err(TypeMismatch::new("object", &other).into()),
+        };
+        self.transaction(|tx| {
+            for (k, v) in fields {
+                tx.store(&format!("{}{}", prefix, k), v)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, KeyPolicy};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn fields() -> Result<(), Error> {
+        let mut json = inline!("a" => 1);
+        let mut map = BTreeMap::new();
+        map.insert("a", 2);
+        map.insert("b", 3);
+        json.add_fields(&map)?;
+        assert_eq!(inline!("a" => 2, "b" => 3), json);
+
+        let err = json.add_fields(&vec![1, 2]).unwrap_err();
+        assert_eq!(
+            "TypeMismatch: Expected object but found array",
+            err.to_string()
+        );
+
+        json.set_key_policy(KeyPolicy::no_whitespace())?;
+        assert!(json.add_fields_prefixed("my ", &inline!("c" => 4)).is_err());
+        assert_eq!(inline!("a" => 2, "b" => 3), json);
+        Ok(())
+    }
+}
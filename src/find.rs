@@ -0,0 +1,167 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Search of the locations matched by a path pattern, following only the
+//! branches of the document which can match it.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::path::{parse, render, Segment};
+use crate::{InvalidPath, Json};
+
+/// A value whose children can be searched, the document itself included.
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Root(&'a HashMap<String, Value>),
+    Value(&'a Value),
+}
+
+impl<'a> Node<'a> {
+    /// The children of the node matched by a segment, with their own segment.
+    fn children(self, segment: &Segment) -> Vec<(Segment, &'a Value)> {
+        let key = |k: &String| Segment::Key(k.clone());
+        match (self, segment) {
+            (Node::Root(map), Segment::Key(k)) => {
+                map.get(k).map(|v| (key(k), v)).into_iter().collect()
+            }
+            (Node::Value(Value::Object(map)), Segment::Key(k)) => {
+                map.get(k).map(|v| (key(k), v)).into_iter().collect()
+            }
+            (Node::Value(Value::Array(items)), Segment::Index(i)) => items
+                .get(*i)
+                .map(|v| (Segment::Index(*i), v))
+                .into_iter()
+                .collect(),
+            (_, Segment::Key(_)) | (_, Segment::Index(_)) => Vec::new(),
+            (Node::Root(map), _) => map.iter().map(|(k, v)| (key(k), v)).collect(),
+            (Node::Value(Value::Object(map)), _) => map.iter().map(|(k, v)| (key(k), v)).collect(),
+            (Node::Value(Value::Array(items)), _) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (Segment::Index(i), v))
+                .collect(),
+            (Node::Value(_), _) => Vec::new(),
+        }
+    }
+}
+
+fn find(
+    node: Node,
+    location: &mut Vec<Segment>,
+    pattern: &[Segment],
+    found: &mut Vec<Vec<Segment>>,
+) {
+    let (segment, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => {
+            if !location.is_empty() {
+                found.push(location.clone());
+            }
+            return;
+        }
+    };
+    if *segment == Segment::Recursive {
+        find(node, location, rest, found);
+    }
+    let rest = match segment {
+        Segment::Recursive => pattern,
+        _ => rest,
+    };
+    for (child_segment, child) in node.children(segment) {
+        location.push(child_segment);
+        find(Node::Value(child), location, rest, found);
+        location.pop();
+    }
+}
+
+impl Json {
+    /// The concrete paths of all the values matched by the given path
+    /// pattern, sorted. Use `*` (or `[*]`) to match any key or index and
+    /// `**` to match any number of nested levels.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::deserialise;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let pod = deserialise!(r#"{
+    ///         "spec": { "containers": [
+    ///             { "env": [{ "name": "HOME" }, { "name": "PATH" }] },
+    ///             { "env": [{ "name": "LANG" }] }
+    ///         ] }
+    ///     }"#)?;
+    ///
+    ///     assert_eq!(
+    ///         vec![
+    ///             "spec.containers[0].env[0].name",
+    ///             "spec.containers[0].env[1].name",
+    ///             "spec.containers[1].env[0].name",
+    ///         ],
+    ///         pod.find_paths("spec.containers[*].env[*].name")?
+    ///     );
+    ///     assert_eq!(3, pod.find_paths("**.name")?.len());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::InvalidPath)` if the pattern cannot be parsed
+    pub fn find_paths(&self, pattern: &str) -> Result<Vec<String>, InvalidPath> {
+        let pattern = parse(pattern)?;
+        let mut found = Vec::new();
+        find(
+            Node::Root(&self.json_data),
+            &mut Vec::new(),
+            &pattern,
+            &mut found,
+        );
+        // Several recursive segments may reach a location in several ways
+        found.sort();
+        found.dedup();
+        Ok(found.iter().map(|location| render(location)).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::deserialise;
+    use std::error::Error;
+
+    #[test]
+    fn patterns() -> Result<(), Box<dyn Error>> {
+        let json = deserialise!(r#"{ "a": { "b": [{ "a": { "c": 1 } }, 2], "c": 3 }, "c": 4 }"#)?;
+        assert_eq!(vec!["a.b[0].a.c", "a.c", "c"], json.find_paths("**.c")?);
+        assert_eq!(vec!["a.b[0].a.c"], json.find_paths("**.a.**.a.*")?);
+        assert_eq!(vec!["a.b[0]", "a.b[1]"], json.find_paths("a.b.*")?);
+        assert!(json.find_paths("a.b[5]")?.is_empty());
+        assert!(json.find_paths("a..b").is_err());
+
+        let json = deserialise!(r#"{ "": { "a": 1 }, "a": 2 }"#)?;
+        let mut paths = json.find_paths("**.a")?;
+        paths.sort();
+        assert_eq!(vec![r#"[""].a"#, "a"], paths);
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Short fingerprints of documents, such as HTTP entity tags, kept up to
+//! date as members are added instead of hashing the whole document again.
+
+use serde_json::Value;
+
+use crate::canonical::write_member;
+use crate::Json;
+
+/// 64-bit FNV-1a hash of the given bytes.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Hash of the canonical serialization of a member. The fingerprint of a
+/// document being the sum of the hashes of its members, it doesn't
+/// depend on their order and is updated by adding and subtracting them.
+pub(crate) fn member_hash(k: &str, v: &Value) -> u64 {
+    let mut member = String::new();
+    write_member(&mut member, k, v);
+    fnv1a(member.as_bytes())
+}
+
+impl Json {
+    /// Short hash of the content of the document, as 16 hexadecimal digits,
+    /// suitable for weak HTTP entity tags (`W/"..."`). Equal documents have
+    /// the same fingerprint, whatever the order of their keys or the way their
+    /// numbers are written, across processes and versions of this crate.
+    ///
+    /// The fingerprint is computed once and then kept up to date by
+    /// `Json::add` and the like, by only hashing the replaced and the new
+    /// value; other changes compute it again on the next call.
+    /// It's not a cryptographic hash, see `Json::sign` for that.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let mut json = inline!("title" => "Mostly Harmless", "rating" => 4);
+    /// let etag = json.fingerprint();
+    ///
+    /// assert_eq!(etag, inline!("rating" => 4.0, "title" => "Mostly Harmless").fingerprint());
+    ///
+    /// json.add("rating", 5);
+    /// assert_ne!(etag, json.fingerprint());
+    /// json.add("rating", 4);
+    /// assert_eq!(etag, json.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> String {
+        let fingerprint = self.fingerprint.get_or_init(|| match &self.fragments {
+            Some(fragments) => fragments.fingerprint(self),
+            None => self
+                .json_data
+                .iter()
+                .fold(0, |sum, (k, v)| sum.wrapping_add(member_hash(k, v))),
+        });
+        format!("{:016x}", fingerprint)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn fingerprints() {
+        assert_eq!("af63dc4c8601ec8c", format!("{:016x}", fnv1a(b"a")));
+        let mut json = inline!("a" => 1);
+        let initial = json.fingerprint();
+        json.add("b", vec![1, 2]);
+        let incremental = json.fingerprint();
+        assert_eq!(
+            incremental,
+            inline!("a" => 1, "b" => vec![1, 2]).fingerprint()
+        );
+        assert_ne!(initial, incremental);
+
+        json.merge(inline!("a" => 2));
+        assert_eq!(
+            inline!("a" => 2, "b" => vec![1, 2]).fingerprint(),
+            json.fingerprint()
+        );
+        assert_eq!("0000000000000000", Json::new().fingerprint());
+    }
+}
@@ -0,0 +1,285 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Truncation of documents to fit a cap on their serialized size, such
+//! as the payload limits of telemetry backends.
+
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+use crate::limits::{LimitExceeded, Usage};
+use crate::path::{self, Segment};
+use crate::Json;
+
+/// How `Json::fit_to` truncates the values of a document.
+///
+/// By default, both strings and arrays are truncated, `…` marks the end of
+/// the truncated ones and `"truncated": true` is added to the document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TruncatePolicy {
+    marker: String,
+    flag: Option<String>,
+    strings: bool,
+    arrays: bool,
+}
+
+impl Default for TruncatePolicy {
+    fn default() -> Self {
+        TruncatePolicy {
+            marker: "…".into(),
+            flag: Some("truncated".into()),
+            strings: true,
+            arrays: true,
+        }
+    }
+}
+
+impl TruncatePolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        TruncatePolicy::default()
+    }
+
+    /// Mark the end of the truncated strings and arrays with the given
+    /// marker, appended to strings and added as the last item of arrays.
+    pub fn marker(mut self, marker: &str) -> Self {
+        self.marker = marker.into();
+        self
+    }
+
+    /// Add `true` under the given key to the truncated documents.
+    pub fn flag(mut self, key: &str) -> Self {
+        self.flag = Some(key.into());
+        self
+    }
+
+    /// Don't flag the truncated documents.
+    pub fn no_flag(mut self) -> Self {
+        self.flag = None;
+        self
+    }
+
+    /// Only truncate strings.
+    pub fn strings_only(mut self) -> Self {
+        self.arrays = false;
+        self.strings = true;
+        self
+    }
+
+    /// Only truncate arrays.
+    pub fn arrays_only(mut self) -> Self {
+        self.strings = false;
+        self.arrays = true;
+        self
+    }
+}
+
+/// Truncation of a document, remembering the values already truncated.
+struct Truncation<'a> {
+    policy: &'a TruncatePolicy,
+    truncated: BTreeSet<Vec<Segment>>,
+}
+
+impl Truncation<'_> {
+    /// Number of chars or items of a value which can be truncated,
+    /// the marker excluded.
+    fn length(&self, value: &Value, location: &[Segment]) -> usize {
+        let marked = usize::from(self.truncated.contains(location));
+        match value {
+            Value::String(s) if self.policy.strings => {
+                s.chars().count() - marked * self.policy.marker.chars().count()
+            }
+            Value::Array(items) if self.policy.arrays => items.len() - marked,
+            _ => 0,
+        }
+    }
+
+    /// The location and size of the largest value which can be truncated.
+    fn largest(
+        &self,
+        value: &Value,
+        location: &mut Vec<Segment>,
+        largest: &mut Option<(usize, Vec<Segment>)>,
+    ) {
+        if self.length(value, location) > 0 {
+            let size = Usage::of(value).bytes();
+            if largest.as_ref().is_none_or(|(max, _)| size > *max) {
+                *largest = Some((size, location.clone()));
+            }
+        }
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map {
+                    location.push(Segment::Key(k.clone()));
+                    self.largest(v, location, largest);
+                    location.pop();
+                }
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    location.push(Segment::Index(i));
+                    self.largest(item, location, largest);
+                    location.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Halve the given value, marking its end.
+    fn halve(&mut self, value: &mut Value, location: Vec<Segment>) {
+        let kept = self.length(value, &location) / 2;
+        let marker = &self.policy.marker;
+        match value {
+            Value::String(s) => {
+                let mut halved: String = s.chars().take(kept).collect();
+                halved.push_str(marker);
+                *s = halved;
+            }
+            Value::Array(items) => {
+                items.truncate(kept);
+                items.push(marker.as_str().into());
+            }
+            _ => unreachable!("only strings and arrays are truncated"),
+        }
+        self.truncated.insert(location);
+    }
+}
+
+impl Json {
+    /// Truncate the document until its compact serialization, without
+    /// profile, takes at most the given number of bytes. The largest string
+    /// or array is halved at each step, and its end marked, as set by the
+    /// given policy. The document is left unchanged if it already fits.
+    /// Return whether it was truncated.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, TruncatePolicy};
+    ///
+    /// fn main() -> Result<(), json_ez::Error> {
+    ///     let mut event = inline!(
+    ///         "name" => "upload",
+    ///         "logs" => vec!["line 1", "line 2", "line 3", "line 4", "line 5", "line 6"]
+    ///     );
+    ///
+    ///     assert!(event.fit_to(76, &TruncatePolicy::new().marker("..."))?);
+    ///     assert_eq!(
+    ///         inline!(
+    ///             "name" => "upload",
+    ///             "logs" => vec!["line 1", "line 2", "line 3", "..."],
+    ///             "truncated" => true
+    ///         ),
+    ///         event
+    ///     );
+    ///     assert!(!event.fit_to(76, &TruncatePolicy::new())?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::LimitExceeded)` if the document cannot
+    /// fit even once all its strings and arrays are truncated, or an
+    /// `Err(json_ez::Error::InvalidKey)` if the flag doesn't follow its key
+    /// policy, in which cases the document is left unchanged
+    pub fn fit_to(&mut self, max_bytes: usize, policy: &TruncatePolicy) -> Result<bool, Error> {
+        if Usage::members(self.json_data.iter()).bytes() <= max_bytes {
+            return Ok(false);
+        }
+        let mut root: Map<String, Value> = self
+            .json_data
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        if let Some(flag) = &policy.flag {
+            self.check_member(flag, &Value::Bool(true))?;
+            root.insert(flag.clone(), Value::Bool(true));
+        }
+        let mut root = Value::Object(root);
+        let mut truncation = Truncation {
+            policy,
+            truncated: BTreeSet::new(),
+        };
+        loop {
+            let size = Usage::of(&root).bytes();
+            if size <= max_bytes {
+                break;
+            }
+            let mut largest = None;
+            if let Value::Object(map) = &root {
+                for (k, v) in map.iter().filter(|(k, _)| Some(*k) != policy.flag.as_ref()) {
+                    let mut location = vec![Segment::Key(k.clone())];
+                    truncation.largest(v, &mut location, &mut largest);
+                }
+            }
+            let location = match largest {
+                Some((_, location)) => location,
+                None => return Err(LimitExceeded::new("bytes", max_bytes, size).into()),
+            };
+            let value = path::lookup_mut(&mut root, &location).expect("the location exists");
+            truncation.halve(value, location);
+        }
+        if let Value::Object(map) = root {
+            for (k, v) in map {
+                if self.json_data.get(&k) != Some(&v) {
+                    self.store(&k, v)?;
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn fit() -> Result<(), Error> {
+        let text = "a".repeat(100);
+        let mut json = inline!("text" => text.clone(), "tags" => vec!["x"; 3]);
+        assert!(json.fit_to(60, &TruncatePolicy::new().no_flag().strings_only())?);
+        let size = Usage::members(json.json_data.iter()).bytes();
+        assert!(size <= 60, "{} bytes", size);
+        assert_eq!(vec!["x"; 3], json.get::<Vec<String>>("tags")?);
+        let truncated = json.get::<String>("text")?;
+        assert_eq!(format!("{}…", "a".repeat(25)), truncated);
+
+        let mut json = inline!("text" => text);
+        json.fit_to(40, &TruncatePolicy::new().flag("cut"))?;
+        assert_eq!(Some(&Value::Bool(true)), json.json_data.get("cut"));
+        assert!(Usage::members(json.json_data.iter()).bytes() <= 40);
+
+        let mut json = inline!("count" => 123_456_789, "tags" => vec!["x"; 4]);
+        let before = json.clone();
+        assert!(matches!(
+            json.fit_to(10, &TruncatePolicy::new()),
+            Err(Error::LimitExceeded(_))
+        ));
+        assert_eq!(before, json);
+        Ok(())
+    }
+}
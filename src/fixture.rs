@@ -0,0 +1,119 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Test fixtures loading helpers.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use crate::{deserialise_inner, Error, Json};
+
+/// Already loaded fixtures, by path.
+static FIXTURES: OnceLock<Mutex<HashMap<PathBuf, Json>>> = OnceLock::new();
+
+impl Json {
+    /// Load a `Json` instance from a fixture file.
+    /// Each file is read and parsed only once per process,
+    /// subsequent calls return a copy of the cached document.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use json_ez::{inline, Json};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let order = Json::fixture("tests/fixtures/order.json")?
+    ///         .override_with(inline!("status" => "cancelled"));
+    ///
+    ///     assert_eq!("cancelled", &order.get::<String>("status")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::Io)` if the file cannot be read or an
+    /// `Err(json_ez::Error::Json)` if it doesn't contain a JSON object
+    pub fn fixture<P: AsRef<Path>>(path: P) -> Result<Json, Error> {
+        let path = path.as_ref();
+        let cache = FIXTURES.get_or_init(Default::default);
+        if let Some(json) = cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(path)
+        {
+            return Ok(json.clone());
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        let json = deserialise_inner(content)?;
+        cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(path.into(), json.clone());
+        Ok(json)
+    }
+
+    /// Deep merge the given overrides into this `Json` instance
+    /// and return it, see `Json::merge`.
+    pub fn override_with(mut self, overrides: Json) -> Json {
+        self.merge(overrides);
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+    use std::error::Error;
+
+    #[test]
+    fn fixture_with_overrides() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join("json_ez_fixture_order.json");
+        fs::write(
+            &path,
+            r#"{ "status": "paid", "customer": { "name": "Arthur", "vip": false } }"#,
+        )?;
+
+        let order = Json::fixture(&path)?
+            .override_with(inline!("status" => "cancelled", "customer" => inline!("vip" => true)));
+        assert_eq!("cancelled", &order.get::<String>("status")?);
+        let customer = order.get::<Json>("customer")?;
+        assert_eq!("Arthur", &customer.get::<String>("name")?);
+        assert!(customer.get::<bool>("vip")?);
+
+        // Served from cache, overrides didn't leak into it
+        fs::remove_file(&path)?;
+        assert_eq!("paid", &Json::fixture(&path)?.get::<String>("status")?);
+        Ok(())
+    }
+
+    #[test]
+    fn fixture_not_found() {
+        let err = Json::fixture("does/not/exist.json").unwrap_err();
+        assert!(format!("{}", err).starts_with("does/not/exist.json: "));
+    }
+}
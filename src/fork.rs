@@ -0,0 +1,242 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Copy-on-write forks of a shared document, to derive many variants
+//! of a large base document without cloning it for each of them.
+
+use std::collections::HashMap;
+use std::iter;
+use std::sync::Arc;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::limits::Usage;
+use crate::{ser, Error, Json, NonFinite, NotFound};
+
+/// Logically independent copy of a shared `Json` instance, only holding
+/// the members it changed: the others are read from the base document.
+///
+/// The members are the unit of sharing, a member being copied
+/// as a whole the first time it's changed with `JsonFork::update`.
+#[derive(Debug, Clone)]
+pub struct JsonFork {
+    base: Arc<Json>,
+    // Changed members, `None` standing for the removed ones
+    changes: HashMap<String, Option<Value>>,
+}
+
+impl Json {
+    /// Fork a shared document: the fork starts as a copy of it, without
+    /// copying anything, and later changes affect the fork only.
+    /// The fork follows the settings (key policy, limits...) of the document.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    /// use std::sync::Arc;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let base = Arc::new(inline!("plan" => "free", "quota" => 10));
+    ///
+    ///     let mut tenant = base.fork();
+    ///     tenant.add("plan", "pro");
+    ///     tenant.update("quota", |quota| *quota = (quota.as_u64().unwrap() * 10).into())?;
+    ///
+    ///     assert_eq!(inline!("plan" => "pro", "quota" => 100), tenant.to_json());
+    ///     assert_eq!("free", &base.get::<String>("plan")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn fork(self: &Arc<Self>) -> JsonFork {
+        JsonFork {
+            base: Arc::clone(self),
+            changes: HashMap::new(),
+        }
+    }
+}
+
+impl JsonFork {
+    /// The document this fork derives from.
+    pub fn base(&self) -> &Arc<Json> {
+        &self.base
+    }
+
+    fn value(&self, k: &str) -> Option<&Value> {
+        match self.changes.get(k) {
+            Some(change) => change.as_ref(),
+            None => self.base.json_data.get(k),
+        }
+    }
+
+    fn members(&self) -> impl Iterator<Item = (&String, &Value)> {
+        let changed = self
+            .changes
+            .iter()
+            .filter_map(|(k, v)| v.as_ref().map(|v| (k, v)));
+        let shared = self
+            .base
+            .json_data
+            .iter()
+            .filter(move |(k, _)| !self.changes.contains_key(*k));
+        changed.chain(shared)
+    }
+
+    fn store(&mut self, k: String, value: Value) -> Result<(), Error> {
+        let value = match self.base.nfc_keys {
+            #[cfg(feature = "unicode")]
+            true => crate::unicode::nfc_value(value),
+            _ => value,
+        };
+        if let Some(policy) = &self.base.key_policy {
+            policy.check_key("", &k, &value)?;
+        }
+        if let Some(limits) = &self.base.limits {
+            let others = self.members().filter(|(key, _)| **key != k);
+            limits.check(&Usage::members(others.chain(iter::once((&k, &value)))))?;
+        }
+        self.changes.insert(k, Some(value));
+        Ok(())
+    }
+
+    /// Add a new item to the fork, like `Json::add`.
+    ///
+    /// # Panics
+    /// Panic if a key doesn't follow the key policy of the
+    /// base document or if its limits would be exceeded
+    pub fn add<V: Serialize>(&mut self, k: &str, v: V) {
+        let value = ser::to_value(&v, NonFinite::Null, self.base.encoders.as_deref()).unwrap();
+        if let Err(err) = self.store(self.base.key(k).into_owned(), value) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Change the value stored under the given key in place, the value
+    /// being copied from the base document first if needed. The fork is
+    /// left untouched if the changed value is rejected.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist,
+    /// an `Err(json_ez::Error::InvalidKey)` if a key doesn't follow the key
+    /// policy of the base document or an `Err(json_ez::Error::LimitExceeded)`
+    /// if its limits would be exceeded
+    pub fn update<F: FnOnce(&mut Value)>(&mut self, k: &str, f: F) -> Result<(), Error> {
+        let key = self.base.key(k).into_owned();
+        let mut value = match self.value(&key) {
+            Some(value) => value.clone(),
+            None => return Err(NotFound::in_members(k.into(), self.members())?.into()),
+        };
+        f(&mut value);
+        self.store(key, value)
+    }
+
+    /// Remove the given key from the fork, returning whether it existed.
+    pub fn remove(&mut self, k: &str) -> bool {
+        let key = self.base.key(k).into_owned();
+        let existed = self.value(&key).is_some();
+        if self.base.json_data.contains_key(&key) {
+            self.changes.insert(key, None);
+        } else {
+            self.changes.remove(&key);
+        }
+        existed
+    }
+
+    /// Get the value associated to the given key, like `Json::get`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::Json)` if its value cannot be deserialized as a `T`
+    pub fn get<T: DeserializeOwned>(&self, k: &str) -> Result<T, Error> {
+        match self.value(&self.base.key(k)) {
+            Some(value) => Ok(T::deserialize(value)?),
+            None => Err(NotFound::in_members(k.into(), self.members())?.into()),
+        }
+    }
+
+    /// Copy the fork into an owned `Json` instance, with the settings
+    /// and annotations of the base document.
+    pub fn to_json(&self) -> Json {
+        let data = self
+            .members()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        Json {
+            json_data: data,
+            ..self.base.settings()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, ErrorCode, Limits};
+    use std::error::Error;
+
+    #[test]
+    fn forks() -> Result<(), Box<dyn Error>> {
+        let mut base = inline!("name" => "base", "tags" => vec!["a"]);
+        base.add("settings", inline!("theme" => "dark"));
+        let base = Arc::new(base);
+
+        let mut fork = base.fork();
+        fork.update("tags", |tags| tags.as_array_mut().unwrap().push("b".into()))?;
+        assert!(fork.remove("name"));
+        assert!(!fork.remove("name"));
+        fork.add("name", "tenant");
+        fork.add("extra", true);
+        assert!(fork.remove("extra"));
+
+        assert_eq!(vec!["a", "b"], fork.get::<Vec<String>>("tags")?);
+        assert_eq!(vec!["a"], base.get::<Vec<String>>("tags")?);
+        assert_eq!("tenant", fork.get::<String>("name")?);
+        assert_eq!(
+            ErrorCode::NotFound,
+            fork.get::<bool>("extra").unwrap_err().code()
+        );
+        match fork.update("tag", |_| {}).unwrap_err() {
+            crate::Error::NotFound(err) => assert_eq!(["tags"], err.suggestions()),
+            err => panic!("unexpected error {}", err),
+        }
+        assert_eq!(2, fork.changes.len());
+
+        let mut expected = inline!("name" => "tenant", "tags" => vec!["a", "b"]);
+        expected.add("settings", inline!("theme" => "dark"));
+        assert_eq!(expected, fork.to_json());
+        Ok(())
+    }
+
+    #[test]
+    fn fork_limits() {
+        let mut base = inline!("a" => vec![1]);
+        base.set_limits(Limits::new().max_nodes(3)).unwrap();
+        let mut fork = Arc::new(base).fork();
+        let err = fork
+            .update("a", |a| *a = serde_json::json!([1, 2, 3]))
+            .unwrap_err();
+        assert_eq!(ErrorCode::LimitExceeded, err.code());
+        assert_eq!(vec![1], fork.get::<Vec<u8>>("a").unwrap());
+        assert!(fork.to_json().limits.is_some());
+    }
+}
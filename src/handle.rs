@@ -0,0 +1,123 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Cheap, allocation-free probing of the values of a document, for code
+//! matching on values rather than deserializing them.
+
+use serde_json::{Map, Value};
+
+use crate::Json;
+
+/// Borrowed handle on the value stored under a key, possibly missing,
+/// returned by `Json::value`. Its accessors mirror the ones of
+/// `serde_json::Value`, returning `None` if the value is missing or
+/// doesn't have the requested type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueRef<'a> {
+    value: Option<&'a Value>,
+}
+
+impl<'a> ValueRef<'a> {
+    /// The underlying value, if any.
+    pub fn get(&self) -> Option<&'a Value> {
+        self.value
+    }
+
+    /// Whether the value exists, `null` included.
+    pub fn exists(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Whether the value exists and is `null`.
+    pub fn is_null(&self) -> bool {
+        self.value.is_some_and(Value::is_null)
+    }
+
+    /// The value as a string slice, if it's a string.
+    pub fn as_str(&self) -> Option<&'a str> {
+        self.value.and_then(Value::as_str)
+    }
+
+    /// The value as an `i64`, if it's an integer which fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.value.and_then(Value::as_i64)
+    }
+
+    /// The value as a `u64`, if it's a non-negative integer which fits.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.value.and_then(Value::as_u64)
+    }
+
+    /// The value as a `f64`, if it's a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.value.and_then(Value::as_f64)
+    }
+
+    /// The value as a boolean, if it's a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        self.value.and_then(Value::as_bool)
+    }
+
+    /// The items of the value, if it's an array.
+    pub fn as_array(&self) -> Option<&'a Vec<Value>> {
+        self.value.and_then(Value::as_array)
+    }
+
+    /// The members of the value, if it's an object.
+    pub fn as_object(&self) -> Option<&'a Map<String, Value>> {
+        self.value.and_then(Value::as_object)
+    }
+
+    /// Handle on the value stored under the given key of this value,
+    /// missing if this value is not an object.
+    pub fn value(&self, k: &str) -> ValueRef<'a> {
+        ValueRef {
+            value: self.value.and_then(|v| v.get(k)),
+        }
+    }
+}
+
+impl Json {
+    /// Handle on the value stored under the given key, to probe its
+    /// type and content without any allocation, unlike `Json::get`
+    /// which builds an error for each failed probe.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let event = inline!("kind" => "click", "target" => inline!("x" => 12, "y" => 40));
+    ///
+    /// let description = match event.value("kind").as_str() {
+    ///     Some("click") => format!("click at {:?}", event.value("target").value("x").as_i64()),
+    ///     Some(other) => other.to_string(),
+    ///     None => "unknown".to_string(),
+    /// };
+    /// assert_eq!("click at Some(12)", description);
+    /// assert!(!event.value("missing").exists());
+    /// ```
+    pub fn value(&self, k: &str) -> ValueRef<'_> {
+        ValueRef {
+            value: self.json_data.get(self.key(k).as_ref()),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Conversions between flat documents and HTTP headers, as pairs of names
+//! and values which `http::HeaderMap` can be iterated as and collected from.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{Error, Json, TypeMismatch};
+
+impl Json {
+    /// Build a document from HTTP headers, such as the ones of an
+    /// `http::HeaderMap`. Names are lowercased, values which are not valid
+    /// UTF-8 are converted lossily and the values of a repeated name are
+    /// stored as an array, in order.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let headers = vec![
+    ///     ("Content-Type", "application/json"),
+    ///     ("Accept", "text/html"),
+    ///     ("Accept", "application/json"),
+    /// ];
+    ///
+    /// assert_eq!(
+    ///     inline!(
+    ///         "content-type" => "application/json",
+    ///         "accept" => vec!["text/html", "application/json"]
+    ///     ),
+    ///     json_ez::Json::from_headers(headers)
+    /// );
+    /// ```
+    pub fn from_headers<I, K, V>(headers: I) -> Json
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<[u8]>,
+    {
+        let mut members: HashMap<String, Value> = HashMap::new();
+        for (name, value) in headers {
+            let value = Value::String(String::from_utf8_lossy(value.as_ref()).into_owned());
+            let name = name.as_ref().to_ascii_lowercase();
+            match members.get_mut(&name) {
+                Some(Value::Array(values)) => values.push(value),
+                Some(first) => *first = Value::Array(vec![first.take(), value]),
+                None => {
+                    members.insert(name, value);
+                }
+            }
+        }
+        Json::from_data(members)
+    }
+
+    /// Represent the document as HTTP headers, sorted by name, such as
+    /// for `http::HeaderMap`. Each member gives a header, or one header per
+    /// item if it holds an array. Numbers and booleans are written as JSON.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let json = inline!("x-retries" => 3, "accept" => vec!["text/html", "application/json"]);
+    ///
+    ///     assert_eq!(
+    ///         vec![
+    ///             ("accept".to_string(), "text/html".to_string()),
+    ///             ("accept".to_string(), "application/json".to_string()),
+    ///             ("x-retries".to_string(), "3".to_string()),
+    ///         ],
+    ///         json.to_headers()?
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::TypeMismatch)` if a member holds a
+    /// `null`, an object or an array with items other than scalars
+    pub fn to_headers(&self) -> Result<Vec<(String, String)>, Error> {
+        let mut names: Vec<&String> = self.json_data.keys().collect();
+        names.sort();
+        let mut headers = Vec::new();
+        for name in names {
+            match &self.json_data[name] {
+                Value::Array(values) => {
+                    for value in values {
+                        headers.push((name.clone(), header_value(value)?));
+                    }
+                }
+                value => headers.push((name.clone(), header_value(value)?)),
+            }
+        }
+        Ok(headers)
+    }
+}
+
+fn header_value(value: &Value) -> Result<String, Error> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        other => Err(TypeMismatch::new("string", other).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn round_trip() -> Result<(), Error> {
+        let json = inline!("accept" => vec!["a", "b", "c"], "host" => "example.com");
+        assert_eq!(json, Json::from_headers(json.to_headers()?));
+        assert_eq!(
+            inline!("x-raw" => "caf\u{fffd}"),
+            Json::from_headers(vec![("X-Raw", b"caf\xe9")])
+        );
+        let err = inline!("x-meta" => inline!("a" => 1))
+            .to_headers()
+            .unwrap_err();
+        assert_eq!(
+            "TypeMismatch: Expected string but found object",
+            err.to_string()
+        );
+        Ok(())
+    }
+}
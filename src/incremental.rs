@@ -0,0 +1,169 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Caching of the serialization and the hash of each member of a document,
+//! for documents changing rarely but serialized or hashed often.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::fingerprint::member_hash;
+use crate::Json;
+
+/// Serialization and hash of a member, each computed when first needed.
+#[derive(Debug, Clone, Default)]
+struct Fragment {
+    bytes: Option<Vec<u8>>,
+    hash: Option<u64>,
+}
+
+/// Cache of the fragments of the members of a document, by key.
+#[derive(Debug, Default)]
+pub(crate) struct Fragments {
+    members: Mutex<HashMap<String, Fragment>>,
+}
+
+impl Clone for Fragments {
+    fn clone(&self) -> Self {
+        Fragments {
+            members: Mutex::new(self.lock().clone()),
+        }
+    }
+}
+
+impl Fragments {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Fragment>> {
+        // Fragments are only inserted whole: a poisoned cache is still consistent
+        self.members.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Forget the fragments of all the members.
+    pub(crate) fn clear(&mut self) {
+        self.members
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    /// Forget the fragments of the given members.
+    pub(crate) fn invalidate<'a>(&mut self, keys: impl IntoIterator<Item = &'a str>) {
+        let members = self
+            .members
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        for k in keys {
+            members.remove(k);
+        }
+    }
+
+    /// Serialize a document, reusing the serialization of its members.
+    pub(crate) fn to_vec(&self, json: &Json) -> Vec<u8> {
+        let mut members = self.lock();
+        let mut out = vec![b'{'];
+        for (i, (k, v)) in json.json_data.iter().enumerate() {
+            if i > 0 {
+                out.push(b',');
+            }
+            serde_json::to_writer(&mut out, k).expect("keys are serializable");
+            out.push(b':');
+            let fragment = members.entry(k.clone()).or_default();
+            let bytes = fragment
+                .bytes
+                .get_or_insert_with(|| serde_json::to_vec(v).expect("values are serializable"));
+            out.extend_from_slice(bytes);
+        }
+        out.push(b'}');
+        out
+    }
+
+    /// Fingerprint of a document, reusing the hashes of its members.
+    pub(crate) fn fingerprint(&self, json: &Json) -> u64 {
+        let mut members = self.lock();
+        json.json_data.iter().fold(0, |sum, (k, v)| {
+            let fragment = members.entry(k.clone()).or_default();
+            sum.wrapping_add(*fragment.hash.get_or_insert_with(|| member_hash(k, v)))
+        })
+    }
+}
+
+impl Json {
+    /// Cache the serialization and the hash of each member of the document,
+    /// only computing them again for the members changed since. It speeds
+    /// up `Json::to_vec` (unless the document has a profile) and
+    /// `Json::fingerprint` for documents changing rarely, at the expense of
+    /// the memory held by the cache. Members changed by `Json::add`,
+    /// `Json::merge` and the like are the unit of invalidation, other
+    /// changes invalidate the whole cache.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let mut catalog = inline!("books" => vec!["Mostly Harmless"; 1000], "updated" => 1);
+    /// catalog.set_incremental(true);
+    ///
+    /// let before = catalog.to_vec().unwrap();
+    /// // Only the new value is serialized
+    /// catalog.add("updated", 2);
+    ///
+    /// assert_eq!(before.len(), catalog.to_vec().unwrap().len());
+    /// ```
+    pub fn set_incremental(&mut self, enabled: bool) {
+        match (enabled, &self.fragments) {
+            (true, None) => self.fragments = Some(Fragments::default()),
+            (false, _) => self.fragments = None,
+            (true, Some(_)) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::inline;
+
+    #[test]
+    fn invalidation() {
+        let mut json = inline!("a" => 1, "b" => inline!("c" => 2));
+        json.set_incremental(true);
+        let cached = |json: &crate::Json| {
+            let fragments = json.fragments.as_ref().unwrap();
+            let mut keys: Vec<String> = fragments.lock().keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        let fingerprint = json.fingerprint();
+        assert_eq!(serde_json::to_vec(&json).unwrap(), json.to_vec().unwrap());
+        assert_eq!(vec!["a", "b"], cached(&json));
+
+        json.add("a", 3);
+        assert_eq!(vec!["b"], cached(&json));
+        json.merge(inline!("b" => inline!("d" => 4)));
+        assert!(cached(&json).is_empty());
+
+        json.to_vec().unwrap();
+        json.add("a", 1);
+        json.merge(inline!("b" => inline!("c" => 2)));
+        json.clear();
+        assert!(cached(&json).is_empty());
+        assert_ne!(fingerprint, json.fingerprint());
+    }
+}
@@ -0,0 +1,693 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Tolerant parsing of almost-JSON documents, such as snippets written
+//! by humans or copied from Python and JavaScript sources.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use serde_json::{Map, Number, Value};
+
+use std::ops::Range;
+
+use crate::error::{self, ErrorCode};
+use crate::path::Segment;
+use crate::ser::NonFinite;
+use crate::{Json, TypeMismatch};
+
+/// Kinds of repairs applied by `Json::from_str_lenient`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepairKind {
+    /// A trailing comma before a closing bracket or brace was removed
+    TrailingComma,
+    /// A single-quoted string was converted into a double-quoted one
+    SingleQuotedString,
+    /// An unquoted object key was quoted
+    UnquotedKey,
+    /// A Python literal (`True`, `False` or `None`) was converted
+    PythonLiteral,
+}
+
+impl RepairKind {
+    fn problem(self) -> &'static str {
+        match self {
+            RepairKind::TrailingComma => "trailing comma",
+            RepairKind::SingleQuotedString => "single-quoted string",
+            RepairKind::UnquotedKey => "unquoted key",
+            RepairKind::PythonLiteral => "Python literal",
+        }
+    }
+}
+
+impl Display for RepairKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            RepairKind::TrailingComma => "Removed trailing comma",
+            RepairKind::SingleQuotedString => "Converted single-quoted string",
+            RepairKind::UnquotedKey => "Quoted unquoted key",
+            RepairKind::PythonLiteral => "Converted Python literal",
+        })
+    }
+}
+
+/// A repair applied to the input of `Json::from_str_lenient`,
+/// located by line and column (both starting at 1).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repair {
+    kind: RepairKind,
+    line: usize,
+    column: usize,
+}
+
+impl Repair {
+    /// Kind of the applied repair.
+    pub fn kind(&self) -> RepairKind {
+        self.kind
+    }
+
+    /// Line of the repaired input.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Column of the repaired input.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for Repair {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.kind, self.line, self.column
+        )
+    }
+}
+
+/// Custom error type used when an input cannot be parsed, even leniently.
+#[derive(Debug)]
+pub struct ParseError {
+    reason: String,
+    line: usize,
+    column: usize,
+}
+
+impl ParseError {
+    /// Create a new `ParseError` given the reason of the failure and its location
+    pub fn new(reason: &str, line: usize, column: usize) -> Self {
+        ParseError {
+            reason: reason.into(),
+            line,
+            column,
+        }
+    }
+
+    /// Stable code of the error, `PARSE_ERROR`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::ParseError
+    }
+
+    /// Represent the error as `Json`, with its code, message, reason and location.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("reason", self.reason.clone().into()),
+            ("line", self.line.into()),
+            ("column", self.column.into()),
+        ];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for ParseError {}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&format!(
+            "ParseError: {} at line {}, column {}",
+            self.reason, self.line, self.column
+        ))
+    }
+}
+
+impl Json {
+    /// Parse an almost-JSON string, tolerating trailing commas, single-quoted
+    /// strings, unquoted keys and Python literals (`True`, `False`, `None`).
+    /// Return the parsed `Json` instance along with the list of repairs applied.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{Json, RepairKind};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (json, repairs) = Json::from_str_lenient("{ title: 'Mostly Harmless', read: True, }")?;
+    ///
+    ///     assert_eq!("Mostly Harmless", &json.get::<String>("title")?);
+    ///     assert!(json.get::<bool>("read")?);
+    ///     assert_eq!(5, repairs.len());
+    ///     assert_eq!(RepairKind::TrailingComma, repairs[4].kind());
+    ///     assert_eq!("Removed trailing comma at line 1, column 39", repairs[4].to_string());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::Parse)` if the input cannot be repaired
+    /// or an `Err(json_ez::Error::TypeMismatch)` if it doesn't hold an object
+    pub fn from_str_lenient(input: &str) -> Result<(Json, Vec<Repair>), crate::Error> {
+        let mut parser = Parser::new(input);
+        parser.lenient = true;
+        let json = to_json(parser.parse()?)?;
+        Ok((json, parser.repairs))
+    }
+
+    /// Parse a JSON string which may contain the `NaN`, `Infinity` and
+    /// `-Infinity` tokens emitted by Python and JavaScript, converting
+    /// them according to the given policy.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{Json, NonFinite};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let input = r#"{ "mean": NaN, "max": Infinity, "min": -Infinity }"#;
+    ///     let json = Json::from_str_non_finite(input, NonFinite::String)?;
+    ///
+    ///     assert_eq!("NaN", &json.get::<String>("mean")?);
+    ///     assert!(Json::from_str_non_finite(input, NonFinite::Error).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::Parse)` if the input is not valid JSON or
+    /// holds non-finite numbers with the `NonFinite::Error` policy, or an
+    /// `Err(json_ez::Error::TypeMismatch)` if it doesn't hold an object
+    pub fn from_str_non_finite(input: &str, policy: NonFinite) -> Result<Json, crate::Error> {
+        let mut parser = Parser::new(input);
+        parser.non_finite = Some(policy);
+        Ok(to_json(parser.parse()?)?)
+    }
+}
+
+pub(crate) fn to_json(value: Value) -> Result<Json, TypeMismatch> {
+    match value {
+        Value::Object(map) => Ok(Json::from_data(map.into_iter().collect())),
+        other => Err(TypeMismatch::new("object", &other)),
+    }
+}
+
+/// Maximum number of nested objects and arrays, like `serde_json`,
+/// deeper inputs being rejected before they overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// A recursive descent parser over the bytes of the input,
+/// strict unless configured otherwise.
+pub(crate) struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Whether almost-JSON input should be repaired
+    pub(crate) lenient: bool,
+    /// Policy applied to non-finite numbers tokens, rejected if missing
+    pub(crate) non_finite: Option<NonFinite>,
+    /// Whether `//` and `/* */` comments should be skipped
+    pub(crate) comments: bool,
+    /// Whether trailing commas are allowed without being lenient
+    pub(crate) trailing_commas: bool,
+    /// Location of every parsed value, recorded only if present
+    pub(crate) spans: Option<Vec<Span>>,
+    pub(crate) repairs: Vec<Repair>,
+    path: Vec<Segment>,
+}
+
+/// Location of a parsed value in the input.
+#[derive(Debug, Clone)]
+pub(crate) struct Span {
+    pub(crate) path: Vec<Segment>,
+    /// Start of the member, which is the start of its key for object members
+    pub(crate) start: usize,
+    pub(crate) value: Range<usize>,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Parser {
+            input,
+            pos: 0,
+            lenient: false,
+            non_finite: None,
+            comments: false,
+            trailing_commas: false,
+            spans: None,
+            repairs: Vec::new(),
+            path: Vec::new(),
+        }
+    }
+
+    /// Parse the whole input as a single value.
+    pub(crate) fn parse(&mut self) -> Result<Value, ParseError> {
+        let value = self.value(None)?;
+        self.whitespace();
+        if self.pos < self.input.len() {
+            return Err(self.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn location(&self, pos: usize) -> (usize, usize) {
+        let before = &self.input[..pos];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+        (line, column)
+    }
+
+    fn error(&self, reason: &str) -> ParseError {
+        let (line, column) = self.location(self.pos);
+        ParseError::new(reason, line, column)
+    }
+
+    fn repair(&mut self, kind: RepairKind, pos: usize) -> Result<(), ParseError> {
+        let (line, column) = self.location(pos);
+        if !self.lenient {
+            let reason = format!("unexpected {}", kind.problem());
+            return Err(ParseError::new(&reason, line, column));
+        }
+        self.repairs.push(Repair { kind, line, column });
+        Ok(())
+    }
+
+    fn non_finite(&mut self, name: &str, pos: usize) -> Result<Value, ParseError> {
+        let (line, column) = self.location(pos);
+        match self.non_finite {
+            Some(policy) => policy
+                .apply(name)
+                .map_err(|e| ParseError::new(&e.to_string(), line, column)),
+            None => Err(ParseError::new("unknown literal", line, column)),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') => self.pos += 1,
+                Some(b'/') if self.comments => {
+                    let rest = &self.input[self.pos..];
+                    let end = if rest.starts_with("//") {
+                        rest.find('\n').unwrap_or(rest.len())
+                    } else if let Some(comment) = rest.strip_prefix("/*") {
+                        comment.find("*/").map_or(rest.len(), |end| end + 4)
+                    } else {
+                        return;
+                    };
+                    self.pos += end;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), ParseError> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    /// Parse a value, given the start of its enclosing member if any.
+    fn value(&mut self, member: Option<usize>) -> Result<Value, ParseError> {
+        self.whitespace();
+        let start = self.pos;
+        let value = match self.peek() {
+            Some(b'{') | Some(b'[') if self.path.len() >= MAX_DEPTH => {
+                Err(self.error("recursion limit exceeded"))
+            }
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') | Some(b'\'') => self.string().map(Value::String),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.literal(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }?;
+        if let Some(spans) = self.spans.as_mut() {
+            spans.push(Span {
+                path: self.path.clone(),
+                start: member.unwrap_or(start),
+                value: start..self.pos,
+            });
+        }
+        Ok(value)
+    }
+
+    fn identifier(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c == b'-' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn literal(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        let value = match self.identifier() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "null" => Value::Null,
+            name @ "NaN" | name @ "Infinity" => self.non_finite(name, start)?,
+            python @ "True" | python @ "False" | python @ "None" => {
+                self.repair(RepairKind::PythonLiteral, start)?;
+                match python {
+                    "True" => Value::Bool(true),
+                    "False" => Value::Bool(false),
+                    _ => Value::Null,
+                }
+            }
+            _ => {
+                self.pos = start;
+                return Err(self.error("unknown literal"));
+            }
+        };
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        if self.input[start..].starts_with("-Infinity") {
+            self.pos += "-Infinity".len();
+            return self.non_finite("-Infinity", start);
+        }
+        while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E')
+        | Some(b'0'..=b'9') = self.peek()
+        {
+            self.pos += 1;
+        }
+        match serde_json::from_str::<Number>(&self.input[start..self.pos]) {
+            Ok(n) => Ok(Value::Number(n)),
+            Err(_) => {
+                self.pos = start;
+                Err(self.error("invalid number"))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        let quote = self.peek().expect("string starts with a quote");
+        if quote == b'\'' {
+            self.repair(RepairKind::SingleQuotedString, start)?;
+        }
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let rest = &self.input[self.pos..];
+            let c = match rest.chars().next() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string")),
+            };
+            if c < ' ' && !self.lenient {
+                return Err(self.error("control character in string"));
+            }
+            self.pos += c.len_utf8();
+            match c {
+                c if c as u32 == quote as u32 => return Ok(s),
+                '\\' => s.push(self.escape()?),
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, ParseError> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err(self.error("unterminated string")),
+        };
+        self.pos += 1;
+        let escaped = match c {
+            b'"' => '"',
+            b'\'' if self.lenient => '\'',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.hex()?;
+                if (0xD800..0xDC00).contains(&high) && self.input[self.pos..].starts_with("\\u") {
+                    self.pos += 2;
+                    let low = self.hex()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("unpaired surrogate in unicode escape"));
+                    }
+                    let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                    return std::char::from_u32(code)
+                        .ok_or_else(|| self.error("invalid unicode escape"));
+                }
+                return std::char::from_u32(high)
+                    .ok_or_else(|| self.error("invalid unicode escape"));
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("invalid escape"));
+            }
+        };
+        Ok(escaped)
+    }
+
+    fn hex(&mut self) -> Result<u32, ParseError> {
+        let digits = self.input.get(self.pos..self.pos + 4);
+        match digits.and_then(|d| u32::from_str_radix(d, 16).ok()) {
+            Some(code) => {
+                self.pos += 4;
+                Ok(code)
+            }
+            None => Err(self.error("invalid unicode escape")),
+        }
+    }
+
+    fn key(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(b'"') | Some(b'\'') => self.string(),
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' || c == b'$' => {
+                let start = self.pos;
+                let key = self.identifier().to_string();
+                self.repair(RepairKind::UnquotedKey, start)?;
+                Ok(key)
+            }
+            _ => Err(self.error("expected key")),
+        }
+    }
+
+    /// Consume a separating comma, if any, and report whether the
+    /// collection continues, repairing trailing commas.
+    fn separator(&mut self, close: u8) -> Result<bool, ParseError> {
+        self.whitespace();
+        match self.peek() {
+            Some(b',') => {
+                let comma = self.pos;
+                self.pos += 1;
+                self.whitespace();
+                if self.peek() == Some(close) {
+                    if !self.trailing_commas {
+                        self.repair(RepairKind::TrailingComma, comma)?;
+                    }
+                    self.pos += 1;
+                    Ok(false)
+                } else {
+                    Ok(true)
+                }
+            }
+            Some(c) if c == close => {
+                self.pos += 1;
+                Ok(false)
+            }
+            _ => Err(self.error(&format!("expected ',' or '{}'", close as char))),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, ParseError> {
+        self.expect(b'{')?;
+        let mut map = Map::new();
+        self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(map));
+        }
+        loop {
+            self.whitespace();
+            let start = self.pos;
+            let key = self.key()?;
+            self.whitespace();
+            self.expect(b':')?;
+            self.path.push(Segment::Key(key));
+            let value = self.value(Some(start))?;
+            if let Some(Segment::Key(key)) = self.path.pop() {
+                map.insert(key, value);
+            }
+            if !self.separator(b'}')? {
+                return Ok(Value::Object(map));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            self.path.push(Segment::Index(items.len()));
+            let value = self.value(None);
+            self.path.pop();
+            items.push(value?);
+            if !self.separator(b']')? {
+                return Ok(Value::Array(items));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strict_json_needs_no_repair() -> Result<(), Box<dyn Error>> {
+        let (json, repairs) = Json::from_str_lenient(
+            r#"{ "title": "Don’t \"panic\"", "rating": -4.5e1, "tags": [], "movie": { "id": null } }"#,
+        )?;
+        assert!(repairs.is_empty());
+        assert_eq!("Don\u{2019}t \"panic\"", &json.get::<String>("title")?);
+        assert_eq!(-45.0, json.get::<f64>("rating")?);
+        Ok(())
+    }
+
+    #[test]
+    fn repairs_are_reported() -> Result<(), Box<dyn Error>> {
+        let (json, repairs) = Json::from_str_lenient(
+            "{\n  release_date: 1992,\n  'tags': ['novel', \"sci-fi\",],\n  movie: None,\n}",
+        )?;
+        assert_eq!(1992, json.get::<u16>("release_date")?);
+        assert_eq!(vec!["novel", "sci-fi"], json.get::<Vec<String>>("tags")?);
+        assert!(json.get::<Option<String>>("movie")?.is_none());
+        let repairs: Vec<String> = repairs.iter().map(|r| r.to_string()).collect();
+        assert_eq!(
+            vec![
+                "Quoted unquoted key at line 2, column 3",
+                "Converted single-quoted string at line 3, column 3",
+                "Converted single-quoted string at line 3, column 12",
+                "Removed trailing comma at line 3, column 29",
+                "Quoted unquoted key at line 4, column 3",
+                "Converted Python literal at line 4, column 10",
+                "Removed trailing comma at line 4, column 14",
+            ],
+            repairs
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unrepairable_input() {
+        let err = Json::from_str_lenient("{ 'a': 1\n  'b': 2 }").unwrap_err();
+        assert_eq!(
+            "ParseError: expected ',' or '}' at line 2, column 3",
+            format!("{}", err)
+        );
+        assert!(Json::from_str_lenient("{ a: undefined }").is_err());
+        assert!(Json::from_str_lenient("[1, 2]").is_err());
+        assert!(Json::from_str_lenient("{} {}").is_err());
+        assert!(Json::from_str_lenient("{ 'a': NaN }").is_err());
+        assert!(Json::from_str_lenient(r#"{"a":"\uDBFF\u0000"}"#).is_err());
+        assert!(Json::from_str_lenient(r#"{"a":"\uDBFF"}"#).is_err());
+        assert!(Json::from_str_lenient(r#"{"a":"\uDC00"}"#).is_err());
+        let nested = format!("{{\"a\":{}{}}}", "[".repeat(10_000), "]".repeat(10_000));
+        assert!(Json::from_str_lenient(&nested).is_err());
+        let nested = format!("{{\"a\":{}{}}}", "[".repeat(127), "]".repeat(127));
+        assert!(Json::from_str_lenient(&nested).is_ok());
+    }
+
+    #[test]
+    fn unicode_escapes() -> Result<(), Box<dyn Error>> {
+        let (json, _) = Json::from_str_lenient(r#"{"a":"\uD83D\uDE80"}"#)?;
+        assert_eq!("\u{1F680}", &json.get::<String>("a")?);
+        let err = Json::from_str_non_finite("{\"a\":\"\t\"}", NonFinite::Null).unwrap_err();
+        assert_eq!(
+            "ParseError: control character in string at line 1, column 7",
+            format!("{}", err)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn non_finite_tokens() -> Result<(), Box<dyn Error>> {
+        let input = "{\n  \"values\": [NaN, Infinity, -Infinity, -1.5]\n}";
+        let json = Json::from_str_non_finite(input, NonFinite::Null)?;
+        assert_eq!(
+            vec![None, None, None, Some(-1.5)],
+            json.get::<Vec<Option<f64>>>("values")?
+        );
+        let json = Json::from_str_non_finite(input, NonFinite::String)?;
+        assert_eq!(
+            r#"["NaN","Infinity","-Infinity",-1.5]"#,
+            json.get::<serde_json::Value>("values")?.to_string()
+        );
+        assert_eq!(
+            "ParseError: Cannot represent non-finite number NaN at line 2, column 14",
+            format!(
+                "{}",
+                Json::from_str_non_finite(input, NonFinite::Error).unwrap_err()
+            )
+        );
+        // Other repairs are not allowed
+        let err = Json::from_str_non_finite("{ 'a': NaN }", NonFinite::Null).unwrap_err();
+        assert_eq!(
+            "ParseError: unexpected single-quoted string at line 1, column 3",
+            format!("{}", err)
+        );
+        let err = Json::from_str_non_finite(r#"{"a": "it\'s"}"#, NonFinite::Null).unwrap_err();
+        assert_eq!(
+            "ParseError: invalid escape at line 1, column 11",
+            format!("{}", err)
+        );
+        let (json, _) = Json::from_str_lenient(r#"{"a": "it\'s"}"#)?;
+        assert_eq!("it's", &json.get::<String>("a")?);
+        Ok(())
+    }
+}
@@ -112,17 +112,149 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! ## Paths
+//! Some methods address values nested in a document using paths made of
+//! dot separated keys, where array elements are addressed by their index
+//! between brackets: `movie.cast[0].name`. When a path is used as a pattern,
+//! `*` (or `[*]`) matches any single key or index and `**` matches any number
+//! of nested levels. Dots and brackets that are part of a key can be escaped
+//! with a backslash: `headers.content\.type`.
 
-use std::collections::HashMap;
-use std::error::Error;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::error::Error as StdError;
 use std::fmt::{self, Display, Formatter};
+use std::iter;
+use std::sync::{Arc, OnceLock};
+
+use fingerprint::member_hash;
+use limits::{Tally, Usage};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{error::Error as SerdeError, from_str, to_string, value::Value, Map};
+
+mod annotate;
+#[cfg(feature = "arrow")]
+mod arrow;
+mod borrowed;
+#[cfg(feature = "bytes")]
+mod buffer;
+mod build;
+mod canonical;
+mod column;
+mod compact;
+mod compare;
+mod compat;
+mod conflict;
+mod conform;
+mod convert;
+mod datetime;
+mod de;
+mod diff;
+mod document;
+mod entry;
+mod error;
+mod expect;
+#[cfg(feature = "rand")]
+mod fake;
+mod fields;
+mod find;
+mod fingerprint;
+mod fit;
+mod fixture;
+mod fork;
+mod handle;
+mod headers;
+mod incremental;
+mod lenient;
+mod limits;
+mod lossy;
+mod nested;
+mod number;
+mod openapi;
+mod page;
+mod partition;
+mod path;
+mod payload;
+mod pointer;
+mod policy;
+mod pool;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod prefix;
+mod problem;
+mod profile;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+mod provenance;
+#[cfg(feature = "redis")]
+mod redis;
+mod refs;
+mod sanitize;
+mod schema;
+mod ser;
+mod shape;
+#[cfg(feature = "crypto")]
+mod sign;
+mod snapshot;
+mod sorted;
+mod span;
+mod stream;
+mod strict;
+mod strings;
+mod transform;
+#[cfg(feature = "unicode")]
+mod unicode;
+mod validate;
+mod validator;
+mod view;
+mod watch;
 
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use serde_json::{
-    error::Error as SerdeError,
-    from_str, json, to_string,
-    value::{from_value, Value},
-};
+pub use build::RepeatedKeys;
+pub use compare::{Comparison, Matcher};
+pub use compat::IncompatibleSchema;
+pub use conflict::{Conflict, DuplicateKey};
+#[doc(hidden)]
+pub use diff::assert_json_eq_inner;
+pub use document::JsonDocument;
+pub use entry::{Entry, ValueMut};
+pub use error::{Error, ErrorCode};
+pub use expect::Format;
+pub use fit::TruncatePolicy;
+pub use fork::JsonFork;
+pub use handle::ValueRef;
+pub use lenient::{ParseError, Repair, RepairKind};
+pub use limits::{LimitExceeded, Limits};
+pub use lossy::NumberLocale;
+#[doc(hidden)]
+pub use nested::{get_path_inner, IntoPathStep, PathStep};
+pub use number::{ArithmeticOverflow, JsonNumber};
+pub use openapi::OpenApiValidator;
+pub use page::Page;
+pub use path::InvalidPath;
+pub use payload::PayloadBuilder;
+pub use pointer::ValueMismatch;
+pub use policy::{InvalidKey, KeyPolicy};
+pub use pool::JsonPool;
+pub use problem::{Problem, ProblemBuilder};
+pub use profile::{DateFormat, KeyCase, Profile};
+pub use refs::UnresolvedRef;
+pub use sanitize::Sanitizer;
+pub use schema::{CoercionError, InvalidSchema, Schema};
+pub use ser::{Encoders, NonFinite};
+pub use shape::{Shape, ShapeChange};
+#[cfg(feature = "crypto")]
+pub use sign::{DetachedSignature, InvalidSignature, SignatureAlgorithm, SigningKey, VerifyingKey};
+pub use snapshot::Snapshot;
+pub use sorted::KeyOrder;
+pub use span::SourceSpan;
+pub use stream::Chunks;
+pub use strings::StringOp;
+pub use transform::Transform;
+pub use validate::{ValidationError, Violation};
+pub use validator::{Extracted, Validator};
+pub use view::JsonView;
 
 /// A struct offering a user friendly abstraction to JSON object.
 /// Acting as a wrapper of an inner `HashMap<String, serde_json::value::Value>`
@@ -156,38 +288,591 @@ use serde_json::{
 ///     Ok(())
 /// }
 /// ```
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone)]
 pub struct Json {
-    #[serde(flatten)]
     json_data: HashMap<String, Value>,
+    encoders: Option<Arc<Encoders>>,
+    profile: Option<Profile>,
+    nfc_keys: bool,
+    compact: bool,
+    key_policy: Option<KeyPolicy>,
+    limits: Option<Limits>,
+    annotations: BTreeMap<Vec<path::Segment>, Value>,
+    spans: Option<Arc<BTreeMap<Vec<path::Segment>, SourceSpan>>>,
+    watchers: Vec<watch::Watcher>,
+    fingerprint: OnceLock<u64>,
+    tally: OnceLock<Tally>,
+    fragments: Option<incremental::Fragments>,
 }
 
 impl Json {
     /// Simple constructor to create a new `Json` instance and
     /// initialise the inner `HashMap<String, serde_json::Value>`
     pub fn new() -> Self {
+        Json::from_data(HashMap::new())
+    }
+
+    pub(crate) fn from_data(json_data: HashMap<String, Value>) -> Self {
+        Json {
+            json_data,
+            encoders: None,
+            profile: None,
+            nfc_keys: false,
+            compact: false,
+            key_policy: None,
+            limits: None,
+            annotations: BTreeMap::new(),
+            spans: None,
+            watchers: Vec::new(),
+            fingerprint: OnceLock::new(),
+            tally: OnceLock::new(),
+            fragments: None,
+        }
+    }
+
+    /// An empty document with the settings and annotations of this one.
+    pub(crate) fn settings(&self) -> Self {
         Json {
             json_data: HashMap::new(),
+            encoders: self.encoders.clone(),
+            profile: self.profile.clone(),
+            nfc_keys: self.nfc_keys,
+            compact: self.compact,
+            key_policy: self.key_policy.clone(),
+            limits: self.limits.clone(),
+            annotations: self.annotations.clone(),
+            spans: self.spans.clone(),
+            watchers: Vec::new(),
+            fingerprint: OnceLock::new(),
+            tally: OnceLock::new(),
+            fragments: self.fragments.as_ref().map(|_| Default::default()),
         }
     }
 
     /// Add a new item in a `Json` instance.
     /// If the given key already exists in document,
     /// the associated value will be updated with the new one.
+    ///
+    /// # Panics
+    /// Panic if a key doesn't follow the key policy of the document or if
+    /// its limits would be exceeded, see `Json::add_with` to handle it instead
     pub fn add<V: Serialize>(&mut self, k: &str, v: V) {
-        self.json_data.insert(k.into(), json!(v));
+        let value = ser::to_value(&v, NonFinite::Null, self.encoders.as_deref()).unwrap();
+        if let Err(err) = self.store(k, value) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Add a new item in a `Json` instance like `Json::add`, returning
+    /// the value previously stored under the key, if any.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::Json;
+    ///
+    /// let mut config = Json::new();
+    ///
+    /// assert_eq!(None, config.insert("port", 8080));
+    /// if let Some(previous) = config.insert("port", 80) {
+    ///     assert_eq!(8080, previous);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    /// Panic if a key doesn't follow the key policy of the document or if
+    /// its limits would be exceeded, see `Json::add_with` to handle it instead
+    pub fn insert<V: Serialize>(&mut self, k: &str, v: V) -> Option<Value> {
+        let value = ser::to_value(&v, NonFinite::Null, self.encoders.as_deref()).unwrap();
+        match self.store(k, value) {
+            Ok(previous) => previous,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Attach a registry of custom encodings to this document, applied
+    /// by `Json::add` and `Json::add_with` before the ones installed crate-wide.
+    pub fn set_encoders(&mut self, encoders: Encoders) {
+        self.encoders = Some(Arc::new(encoders));
+    }
+
+    /// Add a new item in a `Json` instance like `Json::add`, applying the
+    /// given policy to the non-finite floats (`NaN`, `Infinity`...) found in
+    /// the value instead of silently replacing them with `null`.
+    /// # Errors
+    /// Return an `Err(json_ez::Error::Json)` if the value holds a
+    /// non-finite float with the `NonFinite::Error` policy or
+    /// cannot be represented as JSON, an `Err(json_ez::Error::InvalidKey)`
+    /// if a key doesn't follow the key policy of the document or an
+    /// `Err(json_ez::Error::LimitExceeded)` if its limits would be exceeded
+    pub fn add_with<V: Serialize>(
+        &mut self,
+        k: &str,
+        v: V,
+        non_finite: NonFinite,
+    ) -> Result<(), Error> {
+        let value = ser::to_value(&v, non_finite, self.encoders.as_deref())?;
+        self.store(k, value).map(drop)
     }
 
     /// Get value associated to the given key from a `Json` instance.
     /// # Errors
-    /// Return an `Err(json_ez::error::NotFound)` if the given
-    /// key doesn't exists in the current `Json` instance
-    pub fn get<T: DeserializeOwned>(&self, k: &str) -> Result<T, Box<dyn Error>> {
-        let value = match self.json_data.get(k.into()) {
+    /// Return an `Err(json_ez::Error::NotFound)` if the given
+    /// key doesn't exists in the current `Json` instance or an
+    /// `Err(json_ez::Error::Json)` if its value cannot be
+    /// deserialized as a `T`
+    pub fn get<T: DeserializeOwned>(&self, k: &str) -> Result<T, Error> {
+        let value = match self.json_data.get(self.key(k).as_ref()) {
             Some(v) => v,
-            None => return Err(Box::new(NotFound::new(k.into(), &self)?)),
+            None => return Err(NotFound::new(k.into(), self)?.into()),
         };
-        Ok(from_value(value.clone()).unwrap())
+        Ok(T::deserialize(value)?)
+    }
+
+    /// Get value associated to the given key like `Json::get`, or `None`
+    /// if the key doesn't exist. Unlike a `NotFound` error, which holds a
+    /// copy of the document, a missing key costs nothing.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, ErrorCode};
+    ///
+    /// let user = inline!("name" => "Ford", "age" => "unknown");
+    ///
+    /// assert_eq!(Some("Ford".to_string()), user.try_get("name").unwrap());
+    /// assert_eq!(None, user.try_get::<String>("email").unwrap());
+    /// assert_eq!(ErrorCode::Json, user.try_get::<u8>("age").unwrap_err().code());
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::Json)` if the value
+    /// cannot be deserialized as a `T`
+    pub fn try_get<T: DeserializeOwned>(&self, k: &str) -> Result<Option<T>, Error> {
+        match self.json_data.get(self.key(k).as_ref()) {
+            Some(value) => Ok(Some(T::deserialize(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The key under which `k` is stored, normalized if enabled.
+    fn key<'a>(&self, k: &'a str) -> Cow<'a, str> {
+        match self.nfc_keys {
+            #[cfg(feature = "unicode")]
+            true => unicode::nfc(k),
+            _ => Cow::Borrowed(k),
+        }
+    }
+
+    /// Store a value under the given key, returning the previous one.
+    fn store(&mut self, k: &str, value: Value) -> Result<Option<Value>, Error> {
+        let value = match self.nfc_keys {
+            #[cfg(feature = "unicode")]
+            true => unicode::nfc_value(value),
+            _ => value,
+        };
+        let k = self.key(k).into_owned();
+        self.check_member(&k, &value)?;
+        Ok(self.replace(k, Some(value)))
+    }
+
+    /// Check a member against the key policy and the limits of the document,
+    /// as if it replaced the one with the same key.
+    fn check_member(&self, k: &str, value: &Value) -> Result<(), Error> {
+        if let Some(policy) = &self.key_policy {
+            policy.check_key("", k, value)?;
+        }
+        if let Some(limits) = &self.limits {
+            let mut tally = self
+                .tally
+                .get_or_init(|| Tally::of(self.json_data.iter()))
+                .clone();
+            if let Some(previous) = self.json_data.get(k) {
+                tally.remove(k, previous);
+            }
+            tally.insert(k, value);
+            limits.check(&tally.usage())?;
+        }
+        Ok(())
+    }
+
+    /// Replace or remove the member with the given (normalized) key,
+    /// returning the previous value.
+    fn replace(&mut self, k: String, value: Option<Value>) -> Option<Value> {
+        // Update the fingerprint with the replaced and new members only
+        let fingerprint = self.fingerprint.get().map(|sum| {
+            let previous = self.json_data.get(&k).map_or(0, |v| member_hash(&k, v));
+            let next = value.as_ref().map_or(0, |v| member_hash(&k, v));
+            sum.wrapping_sub(previous).wrapping_add(next)
+        });
+        // Same for the usage, tallied once limits are checked
+        let tally = self.tally.take().map(|mut tally| {
+            if let Some(previous) = self.json_data.get(&k) {
+                tally.remove(&k, previous);
+            }
+            if let Some(value) = &value {
+                tally.insert(&k, value);
+            }
+            tally
+        });
+        // Keep the cached fragments of the other members
+        let mut fragments = self.fragments.take();
+        if let Some(fragments) = &mut fragments {
+            fragments.invalidate(iter::once(k.as_str()));
+        }
+        let previous = self.watched(|json| match (value, json.compact) {
+            (Some(value), true) => json
+                .json_data
+                .insert(compact::shrink_key(k), compact::shrink_value(value)),
+            (Some(value), false) => json.json_data.insert(k, value),
+            (None, _) => json.json_data.remove(&k),
+        });
+        self.fragments = fragments;
+        if let Some(fingerprint) = fingerprint {
+            let _ = self.fingerprint.set(fingerprint);
+        }
+        if let Some(tally) = tally {
+            let _ = self.tally.set(tally);
+        }
+        previous
+    }
+
+    /// Remove the given key from the document, returning its value if it
+    /// existed. The annotations of the value are removed too.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let mut user = inline!("name" => "Arthur", "password" => "42");
+    ///
+    /// assert_eq!(Some("42".into()), user.remove("password"));
+    /// assert_eq!(None, user.remove("password"));
+    /// assert_eq!(inline!("name" => "Arthur"), user);
+    /// ```
+    pub fn remove(&mut self, k: &str) -> Option<Value> {
+        let k = self.key(k).into_owned();
+        if !self.json_data.contains_key(&k) {
+            return None;
+        }
+        let root = path::Segment::Key(k.clone());
+        self.annotations
+            .retain(|path, _| path.first() != Some(&root));
+        self.replace(k, None)
+    }
+
+    /// Deep merge another `Json` instance into this one.
+    /// Nested objects are merged recursively, any other value
+    /// from `other` replaces the existing one, and so do its annotations.
+    ///
+    /// # Panics
+    /// Panic if a key of `other` doesn't follow the key policy of the
+    /// document or if the result would exceed the limits of the document
+    pub fn merge(&mut self, other: Json) {
+        self.combine(other, merge_value);
+    }
+
+    /// Move the members of another `Json` instance into this one, replacing
+    /// the values already stored under their keys as a whole, along with
+    /// its annotations. Unlike `Json::merge`, nested objects are not merged,
+    /// which makes it cheaper for documents whose keys don't overlap.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let mut book = inline!("title" => "Mostly Harmless", "meta" => inline!("pages" => 240));
+    /// book.append(inline!("author" => "Douglas Adams", "meta" => inline!("isbn" => "0330323113")));
+    ///
+    /// assert_eq!(
+    ///     inline!(
+    ///         "title" => "Mostly Harmless",
+    ///         "author" => "Douglas Adams",
+    ///         "meta" => inline!("isbn" => "0330323113")
+    ///     ),
+    ///     book
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    /// Panic if a key of `other` doesn't follow the key policy of the
+    /// document or if the result would exceed the limits of the document
+    pub fn append(&mut self, other: Json) {
+        self.combine(other, |existing, value| *existing = value);
+    }
+
+    /// Add the members of another document, combining the values of the
+    /// keys present in both with the given function.
+    fn combine(&mut self, other: Json, combine: fn(&mut Value, Value)) {
+        if let Some(policy) = &self.key_policy {
+            for (k, v) in &other.json_data {
+                if let Err(err) = policy.check_key("", k, v) {
+                    panic!("{}", err);
+                }
+            }
+        }
+        // Merge into a copy when the result must be checked before being
+        // kept, or compared with the current data for the watchers
+        let mut data = match self.limits.is_some() || !self.watchers.is_empty() {
+            true => self.json_data.clone(),
+            false => std::mem::take(&mut self.json_data),
+        };
+        let mut changed = Vec::new();
+        for (k, v) in other.json_data {
+            let (k, v) = match self.nfc_keys {
+                #[cfg(feature = "unicode")]
+                true => (self.key(&k).into_owned(), unicode::nfc_value(v)),
+                _ => (k, v),
+            };
+            if self.fragments.is_some() {
+                changed.push(k.clone());
+            }
+            match data.get_mut(&k) {
+                Some(existing) => combine(existing, v),
+                None => {
+                    data.insert(k, v);
+                }
+            }
+        }
+        if let Some(limits) = &self.limits {
+            if let Err(err) = limits.check(&Usage::members(data.iter())) {
+                panic!("{}", err);
+            }
+        }
+        let mut fragments = self.fragments.take();
+        if let Some(fragments) = &mut fragments {
+            fragments.invalidate(changed.iter().map(String::as_str));
+        }
+        self.watched(|json| json.json_data = data);
+        self.fragments = fragments;
+        if self.compact {
+            self.shrink_to_fit();
+        }
+        self.annotations.extend(other.annotations);
+    }
+
+    /// Apply several updates at once: the closure works on a copy of
+    /// the document, which replaces the current one only if it returns `Ok`.
+    /// Otherwise the document is left untouched and the error is returned.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut json = inline!("title" => "Mostly Harmless", "read" => false);
+    ///
+    ///     let result = json.transaction(|tx| {
+    ///         tx.add("read", true);
+    ///         tx.get::<u16>("release_date")
+    ///     });
+    ///
+    ///     assert!(result.is_err());
+    ///     assert!(!json.get::<bool>("read")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn transaction<F, R, E>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Json) -> Result<R, E>,
+    {
+        let mut tx = self.clone();
+        tx.watchers = Vec::new();
+        let result = f(&mut tx)?;
+        self.commit(tx);
+        Ok(result)
+    }
+
+    /// Serialize this `Json` instance as JSON bytes, like `serialise!`
+    /// but without going through a `String`.
+    ///
+    /// # Errors
+    /// Return an `Err(serde_json::error::Error)` if the document
+    /// cannot be represented as JSON
+    pub fn to_vec(&self) -> Result<Vec<u8>, SerdeError> {
+        match &self.profile {
+            Some(profile) => self.to_string_with(profile).map(String::into_bytes),
+            None => match &self.fragments {
+                Some(fragments) => Ok(fragments.to_vec(self)),
+                None => serde_json::to_vec(self),
+            },
+        }
+    }
+
+    /// Deserialize a `Json` instance from JSON bytes, without
+    /// validating them as UTF-8 beforehand.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let json = Json::from_slice(br#"{ "title": "Mostly Harmless" }"#)?;
+    ///
+    ///     assert_eq!(json, Json::from_slice(&json.to_vec()?)?);
+    ///     assert_eq!(inline!("title" => "Mostly Harmless"), json);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::Json)` if the bytes
+    /// are not a JSON object
+    pub fn from_slice(bytes: &[u8]) -> Result<Json, Error> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Lazily deserialize the `Json` instances of a stream of JSON objects,
+    /// concatenated or separated by any whitespace (newline-delimited
+    /// streams included). The iterator ends after the first error.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let input = r#"{"id": 1}{"id": 2} {"id": 3}"#;
+    ///     let documents = Json::from_str_multi(input).collect::<Result<Vec<_>, _>>()?;
+    ///
+    ///     assert_eq!(3, documents.len());
+    ///     assert_eq!(inline!("id" => 3), documents[2]);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Yield an `Err(json_ez::Error::Json)` for the first value
+    /// which is not a valid JSON object
+    pub fn from_str_multi(input: &str) -> impl Iterator<Item = Result<Json, Error>> + '_ {
+        serde_json::Deserializer::from_str(input)
+            .into_iter::<Json>()
+            .map(|result| result.map_err(Error::from))
+    }
+}
+
+pub(crate) fn merge_value(target: &mut Value, source: Value) {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (k, v) in source {
+                match target.get_mut(&k) {
+                    Some(existing) => merge_value(existing, v),
+                    None => {
+                        target.insert(k, v);
+                    }
+                }
+            }
+        }
+        (target, source) => *target = source,
+    }
+}
+
+impl PartialEq for Json {
+    fn eq(&self, other: &Json) -> bool {
+        self.json_data == other.json_data
+    }
+}
+
+/// Format the document as JSON with sorted keys, indented
+/// with the alternate flag (`{:#?}`).
+impl fmt::Debug for Json {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let sorted: BTreeMap<&String, &Value> = self.json_data.iter().collect();
+        let json = if f.alternate() {
+            serde_json::to_string_pretty(&sorted)
+        } else {
+            to_string(&sorted)
+        };
+        f.write_str(&json.map_err(|_| fmt::Error)?)
+    }
+}
+
+impl Extend<Json> for Json {
+    /// Append the members of each document in turn, see `Json::append`.
+    fn extend<I: IntoIterator<Item = Json>>(&mut self, iter: I) {
+        for other in iter {
+            self.append(other);
+        }
+    }
+}
+
+impl Default for Json {
+    fn default() -> Self {
+        Json::new()
+    }
+}
+
+/// Serialize the document as a plain map of its items, so that it can be
+/// flattened into another struct with `#[serde(flatten)]`.
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.json_data.serialize(serializer)
+    }
+}
+
+/// Deserialize a document from any map, such as a JSON object.
+/// Flattened into a struct with `#[serde(flatten)]`, it collects the
+/// fields that are not part of the struct, which are written back
+/// when the struct is serialized.
+///
+/// # Example
+/// ```
+/// use json_ez::Json;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Order {
+///     id: u32,
+///     #[serde(flatten)]
+///     extra: Json,
+/// }
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let order: Order = serde_json::from_str(r#"{ "id": 42, "note": "gift" }"#)?;
+///
+///     assert_eq!(42, order.id);
+///     assert_eq!("gift", &order.extra.get::<String>("note")?);
+///     assert_eq!(r#"{"id":42,"note":"gift"}"#, serde_json::to_string(&order)?);
+///
+///     Ok(())
+/// }
+/// ```
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HashMap::deserialize(deserializer).map(Json::from_data)
+    }
+}
+
+/// Deserialize a `Json` instance from JSON text, like `deserialise!`.
+///
+/// # Example
+/// ```
+/// use json_ez::Json;
+/// use std::convert::TryFrom;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let json = Json::try_from(r#"{ "title": "Mostly Harmless" }"#)?;
+///
+///     assert_eq!("Mostly Harmless", &json.get::<String>("title")?);
+///     assert!(Json::try_from("[42]").is_err());
+///
+///     Ok(())
+/// }
+/// ```
+impl TryFrom<&str> for Json {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Error> {
+        Ok(from_str(s)?)
+    }
+}
+
+/// Deserialize a `Json` instance from JSON text, like `deserialise!`.
+impl TryFrom<String> for Json {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Error> {
+        Json::try_from(s.as_str())
     }
 }
 
@@ -196,29 +881,193 @@ impl Json {
 pub struct NotFound {
     key: String,
     json: String,
+    suggestions: Vec<String>,
 }
 
 impl NotFound {
     /// Create a new `NotFound` error given the errored key and the targeted JSON object
-    pub fn new(key: String, json: &Json) -> Result<Self, Box<dyn Error>> {
+    pub fn new(key: String, json: &Json) -> Result<Self, SerdeError> {
+        let suggestions = suggestions(&key, json.json_data.keys());
         Ok(NotFound {
             key,
             json: serialise!(json)?,
+            suggestions,
+        })
+    }
+
+    /// Create a new `NotFound` error given the path to a missing value, its
+    /// last step and the value it's missing from
+    pub(crate) fn at_path(
+        path: String,
+        step: &path::Segment,
+        parent: &Value,
+    ) -> Result<Self, SerdeError> {
+        let suggestions = match (step, parent) {
+            (path::Segment::Key(key), Value::Object(map)) => suggestions(key, map.keys()),
+            _ => Vec::new(),
+        };
+        Ok(NotFound {
+            key: path,
+            json: serde_json::to_string(parent)?,
+            suggestions,
+        })
+    }
+
+    /// Create a new `NotFound` error given the errored key and the members of
+    /// the object it's missing from, borrowed rather than copied in a `Json`
+    pub(crate) fn in_members<'a, I>(key: String, members: I) -> Result<Self, SerdeError>
+    where
+        I: Iterator<Item = (&'a String, &'a Value)>,
+    {
+        let members: BTreeMap<&String, &Value> = members.collect();
+        let suggestions = suggestions(&key, members.keys().copied());
+        Ok(NotFound {
+            key,
+            json: serde_json::to_string(&members)?,
+            suggestions,
         })
     }
+
+    /// Create a new `NotFound` error given the errored key and the nested object it's missing from
+    pub(crate) fn in_object(key: String, object: &Map<String, Value>) -> Result<Self, SerdeError> {
+        let suggestions = suggestions(&key, object.keys());
+        Ok(NotFound {
+            key,
+            json: serde_json::to_string(object)?,
+            suggestions,
+        })
+    }
+
+    /// Existing keys closest to the missing one, likely meant instead of it.
+    pub fn suggestions(&self) -> &[String] {
+        &self.suggestions
+    }
+
+    /// Stable code of the error, `NOT_FOUND`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::NotFound
+    }
+
+    /// Represent the error as `Json`, with its code, message and key.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("key", self.key.clone().into()),
+            ("suggestions", self.suggestions.clone().into()),
+        ];
+        error::to_json(self.code(), self, details)
+    }
 }
 
-impl Error for NotFound {}
+impl StdError for NotFound {}
 
 impl Display for NotFound {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         f.write_str(&format!(
             "NotFound: Cannot found key {} in {}",
             self.key, self.json
+        ))?;
+        if !self.suggestions.is_empty() {
+            let quoted: Vec<String> = self
+                .suggestions
+                .iter()
+                .map(|s| format!("{:?}", s))
+                .collect();
+            write!(f, "; did you mean {}?", quoted.join(" or "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of single character insertions, deletions, substitutions
+/// or transpositions turning `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Keys closest to the missing one, within a third of its length.
+fn suggestions<'a, I: Iterator<Item = &'a String>>(key: &str, keys: I) -> Vec<String> {
+    let max = (key.chars().count() / 3).max(1);
+    let mut closest: Vec<(usize, &String)> = keys
+        .map(|k| (edit_distance(key, k), k))
+        .filter(|(d, _)| *d <= max)
+        .collect();
+    let best = closest.iter().map(|(d, _)| *d).min();
+    closest.retain(|(d, _)| Some(*d) == best);
+    let mut suggestions: Vec<String> = closest.into_iter().map(|(_, k)| k.clone()).collect();
+    suggestions.sort();
+    suggestions
+}
+
+/// Custom error type used when a JSON value does not have the expected type.
+#[derive(Debug)]
+pub struct TypeMismatch {
+    expected: String,
+    found: String,
+}
+
+impl TypeMismatch {
+    /// Create a new `TypeMismatch` error given the expected type and the offending value
+    pub fn new(expected: &str, found: &Value) -> Self {
+        TypeMismatch {
+            expected: expected.into(),
+            found: type_name(found).into(),
+        }
+    }
+
+    /// Stable code of the error, `TYPE_MISMATCH`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::TypeMismatch
+    }
+
+    /// Represent the error as `Json`, with its code, message, expected and found types.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("expected", self.expected.clone().into()),
+            ("found", self.found.clone().into()),
+        ];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl StdError for TypeMismatch {}
+
+impl Display for TypeMismatch {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&format!(
+            "TypeMismatch: Expected {} but found {}",
+            self.expected, self.found
         ))
     }
 }
 
+/// Name of the JSON type of the given value, as used in error messages.
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
 /// Create a new `json_ez::Json` using the PHP array syntax.
 /// It makes complex JSON document inline declaration easier and more readable.
 ///
@@ -345,7 +1194,44 @@ macro_rules! serialise {
 
 #[doc(hidden)]
 pub fn serialise_inner(json: &Json) -> Result<String, SerdeError> {
-    to_string(&json)
+    match &json.profile {
+        Some(profile) => json.to_string_with(profile),
+        None => to_string(&json),
+    }
+}
+
+/// Get the value nested in a `json_ez::Json` instance at the given steps,
+/// keys and array indexes separated by `/`, deserialized as the given type.
+///
+/// # Example
+/// ```
+/// use json_ez::{deserialise, get_path, Json};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let json = deserialise!(r#"{
+///         "movie": { "cast": [{ "name": "Mos Def", "role": "Ford Prefect" }] }
+///     }"#)?;
+///
+///     let name = get_path!(json, "movie" / "cast" / 0 / "name" => String)?;
+///     assert_eq!("Mos Def", &name);
+///
+///     let err = get_path!(json, "movie" / "cast" / 1 / "name" => String).unwrap_err();
+///     assert!(err.to_string().starts_with("NotFound: Cannot found key movie.cast[1]"));
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// # Errors
+/// Return an `Err(json_ez::Error::NotFound)` carrying the path up to the
+/// first missing step or an `Err(json_ez::Error::Json)` if the value
+/// cannot be deserialized as the given type
+#[macro_export]
+macro_rules! get_path {
+    ($json: expr, $($step: tt)/+ => $t: ty) => {{
+        use $crate::IntoPathStep;
+        $crate::get_path_inner::<$t>(&$json, &[$($step.path_step()),+])
+    }};
 }
 
 #[cfg(test)]
@@ -369,6 +1255,23 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn json_add_with_non_finite() -> Result<(), Box<dyn Error>> {
+        let mut json = Json::new();
+        json.add_with("mean", f64::NAN, NonFinite::String)?;
+        json.add_with("samples", vec![1.0, f64::INFINITY], NonFinite::Null)?;
+        assert_eq!("NaN", &json.get::<String>("mean")?);
+        assert_eq!(
+            vec![Some(1.0), None],
+            json.get::<Vec<Option<f64>>>("samples")?
+        );
+        assert!(json
+            .add_with("max", f64::INFINITY, NonFinite::Error)
+            .is_err());
+        assert!(json.get::<f64>("max").is_err());
+        Ok(())
+    }
+
     #[test]
     fn json_get_ok() -> Result<(), Box<dyn Error>> {
         let json = inline!("a" => "valid", "json" => "object");
@@ -390,6 +1293,192 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn json_merge() -> Result<(), Box<dyn Error>> {
+        let mut json = inline!(
+            "title" => "Mostly Harmless",
+            "movie" => inline!("title" => "The Hitchhiker's Guide to the Galaxy", "release_date" => 2004)
+        );
+        json.merge(inline!("read" => true, "movie" => inline!("release_date" => 2005)));
+        assert!(json.get::<bool>("read")?);
+        let movie = json.get::<Json>("movie")?;
+        assert_eq!(2005, movie.get::<u16>("release_date")?);
+        assert_eq!(
+            "The Hitchhiker's Guide to the Galaxy",
+            &movie.get::<String>("title")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn json_append() -> Result<(), Box<dyn Error>> {
+        let mut json =
+            inline!("movie" => inline!("title" => "The Hitchhiker's Guide to the Galaxy"));
+        json.extend(vec![
+            inline!("read" => true),
+            inline!("movie" => inline!("release_date" => 2005)),
+        ]);
+        assert!(json.get::<bool>("read")?);
+        assert_eq!(inline!("release_date" => 2005), json.get::<Json>("movie")?);
+        Ok(())
+    }
+
+    #[test]
+    fn json_debug() {
+        let json = inline!("title" => "Mostly Harmless", "movie" => inline!("release_date" => 2005, "cast" => vec!["Martin Freeman"]));
+        assert_eq!(
+            r#"{"movie":{"cast":["Martin Freeman"],"release_date":2005},"title":"Mostly Harmless"}"#,
+            format!("{:?}", json)
+        );
+        assert_eq!(
+            "{\n  \"movie\": {\n    \"cast\": [\n      \"Martin Freeman\"\n    ],\n    \"release_date\": 2005\n  },\n  \"title\": \"Mostly Harmless\"\n}",
+            format!("{:#?}", json)
+        );
+    }
+
+    #[test]
+    fn not_found_suggestions() {
+        let json = inline!("release_date" => 2005, "title" => "H2G2", "tilte" => "typo");
+        let err = NotFound::new("relese_date".into(), &json).unwrap();
+        assert_eq!(vec!["release_date"], err.suggestions());
+        assert!(err
+            .to_string()
+            .ends_with("; did you mean \"release_date\"?"));
+        let err = NotFound::new("titel".into(), &json).unwrap();
+        assert_eq!(vec!["title"], err.suggestions());
+        let err = NotFound::new("date".into(), &inline!("data" => 1, "dare" => 2)).unwrap();
+        assert_eq!(vec!["dare", "data"], err.suggestions());
+        let err = NotFound::new("director".into(), &json).unwrap();
+        assert!(err.suggestions().is_empty());
+        assert_eq!(0, edit_distance("", ""));
+        assert_eq!(3, edit_distance("abc", ""));
+    }
+
+    #[test]
+    fn json_unwind_safe() {
+        fn unwind_safe<T: std::panic::UnwindSafe + std::panic::RefUnwindSafe>() {}
+        unwind_safe::<Json>();
+        let json = inline!("title" => "Mostly Harmless");
+        let title = std::panic::catch_unwind(|| json.get::<String>("title").unwrap());
+        assert_eq!("Mostly Harmless", title.unwrap());
+    }
+
+    #[test]
+    fn json_remove() {
+        let mut json = inline!("title" => "Mostly Harmless", "read" => true);
+        let fingerprint = json.fingerprint();
+        json.add("rating", 5);
+        assert_eq!(Some(5.into()), json.remove("rating"));
+        assert_eq!(fingerprint, json.fingerprint());
+        assert_eq!(None, json.remove("rating"));
+        assert_eq!(inline!("title" => "Mostly Harmless", "read" => true), json);
+    }
+
+    #[test]
+    fn json_try_get() {
+        let json = inline!("rating" => 5, "note" => None::<String>);
+        assert_eq!(Some(5), json.try_get::<u8>("rating").unwrap());
+        assert_eq!(None, json.try_get::<u8>("read").unwrap());
+        assert_eq!(Some(None), json.try_get::<Option<String>>("note").unwrap());
+        assert!(json.try_get::<String>("note").is_err());
+    }
+
+    #[test]
+    fn json_transaction() -> Result<(), Box<dyn Error>> {
+        let mut json = inline!("title" => "Mostly Harmless");
+        let rating = json.transaction(|tx| -> Result<u8, crate::Error> {
+            tx.add("read", true);
+            tx.merge(inline!("rating" => 5));
+            tx.get("rating")
+        })?;
+        assert_eq!(5, rating);
+        assert!(json.get::<bool>("read")?);
+
+        let before = json.clone();
+        let result: Result<(), &str> = json.transaction(|tx| {
+            tx.add("read", false);
+            tx.merge(inline!("title" => "And Another Thing..."));
+            Err("rollback")
+        });
+        assert_eq!(Err("rollback"), result);
+        assert_eq!(before, json);
+        Ok(())
+    }
+
+    #[test]
+    fn json_bytes() -> Result<(), Box<dyn Error>> {
+        let mut json = inline!("title" => "Mostly Harmless", "rating" => 4.5);
+        assert_eq!(json, Json::from_slice(&json.to_vec()?)?);
+        json.set_profile(Profile::new().float_precision(0));
+        assert_eq!(serialise!(json)?.into_bytes(), json.to_vec()?);
+        assert!(Json::from_slice(b"[1, 2]").is_err());
+        assert!(Json::from_slice(b"{\"a\": \"\xff\"}").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn json_insert() {
+        let mut json = inline!("a" => 1);
+        assert_eq!(Some(Value::from(1)), json.insert("a", "one"));
+        assert_eq!(None, json.insert("b", vec![2]));
+        assert_eq!(inline!("a" => "one", "b" => vec![2]), json);
+    }
+
+    #[test]
+    fn json_from_str_multi() -> Result<(), Box<dyn Error>> {
+        let documents =
+            Json::from_str_multi("{\"a\": 1}\n{\"a\": 2}{}  ").collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            vec![inline!("a" => 1), inline!("a" => 2), Json::new()],
+            documents
+        );
+        let mut documents = Json::from_str_multi("{\"a\": 1} [1] {\"a\": 2}");
+        assert!(documents.next().unwrap().is_ok());
+        assert!(documents.next().unwrap().is_err());
+        assert!(documents.next().is_none());
+        assert_eq!(0, Json::from_str_multi(" \n").count());
+        Ok(())
+    }
+
+    #[test]
+    fn json_try_from_str() -> Result<(), Box<dyn Error>> {
+        fn parse<T: TryFrom<String>>(s: &str) -> Option<T> {
+            T::try_from(s.to_string()).ok()
+        }
+        assert_eq!(
+            Some(inline!("a" => vec![1, 2])),
+            parse::<Json>(r#"{"a": [1, 2]}"#)
+        );
+        assert_eq!(None, parse::<Json>("{"));
+        let err = Json::try_from("null").unwrap_err();
+        assert_eq!(ErrorCode::Json, err.code());
+        Ok(())
+    }
+
+    #[test]
+    fn json_flattened() -> Result<(), Box<dyn Error>> {
+        #[derive(Serialize, Deserialize)]
+        struct Novel {
+            title: String,
+            #[serde(flatten)]
+            extra: Json,
+        }
+
+        let input =
+            r#"{ "title": "Mostly Harmless", "read": false, "movie": { "release_date": 2005 } }"#;
+        let novel: Novel = from_str(input)?;
+        assert_eq!("Mostly Harmless", &novel.title);
+        assert_eq!(
+            inline!("read" => false, "movie" => inline!("release_date" => 2005)),
+            novel.extra
+        );
+        assert_eq!(deserialise!(input)?, from_str(&to_string(&novel)?)?);
+
+        let novel: Novel = from_str(r#"{ "title": "And Another Thing..." }"#)?;
+        assert_eq!(r#"{"title":"And Another Thing..."}"#, to_string(&novel)?);
+        Ok(())
+    }
+
     #[test]
     fn inline_declaration() -> Result<(), Box<dyn Error>> {
         let json = inline!("a" => "valid", "json" => "object");
@@ -399,6 +1488,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn deserialise_ok() -> Result<(), Box<dyn Error>> {
         let json_string = r#"{ "valid_json": true }"#;
         let json: Result<Json, serde_json::error::Error> = deserialise!(json_string);
@@ -415,6 +1505,25 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    #[cfg(feature = "raw-number")]
+    fn raw_number_round_trip() -> Result<(), Box<dyn Error>> {
+        let json_string = r#"{"price":1.10,"ratio":1e-7,"id":123456789012345678901234567890}"#;
+        let mut json = deserialise!(json_string)?;
+        assert_eq!(1.1, json.get::<f64>("price")?);
+
+        let price = json.get::<Value>("price")?;
+        json.add_with("price_copy", &price, NonFinite::Null)?;
+        assert_eq!("1.10", json.get::<Value>("price_copy")?.to_string());
+        json.json_data.remove("price_copy");
+        assert_eq!(json_string.len(), serialise!(json)?.len());
+
+        // Exponent markers are normalized
+        let json = deserialise!(r#"{"quantity":1E3}"#)?;
+        assert_eq!(r#"{"quantity":1e+3}"#, serialise!(json)?);
+        Ok(())
+    }
+
     #[test]
     fn serialise() -> Result<(), Box<dyn Error>> {
         let json = inline!("valid" => "json");
@@ -0,0 +1,388 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Caps on the size of a document, enforced as it is mutated so that
+//! it cannot grow unboundedly inside a long-running process.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+use crate::error::{self, ErrorCode};
+use crate::Json;
+
+/// Maximum depth, number of values and serialized size of a document,
+/// attached to it with `Json::set_limits`.
+///
+/// The depth counts the nested objects and arrays, the document itself
+/// being at depth 1. The number of values doesn't count the document itself.
+/// The size is the one of the compact serialization, `serialise!` output.
+///
+/// # Example
+/// ```
+/// use json_ez::{Json, Limits, NonFinite};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut events = Json::new();
+///     events.set_limits(Limits::new().max_nodes(3))?;
+///
+///     events.add_with("ids", vec![1, 2], NonFinite::Null)?;
+///     let err = events.add_with("ids", vec![1, 2, 3], NonFinite::Null).unwrap_err();
+///
+///     assert_eq!(
+///         "LimitExceeded: Document would hold 4 values, the maximum being 3",
+///         err.to_string()
+///     );
+///     assert_eq!(vec![1, 2], events.get::<Vec<u8>>("ids")?);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Limits {
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+    max_bytes: Option<usize>,
+}
+
+impl Limits {
+    /// Create limits allowing documents of any size.
+    pub fn new() -> Self {
+        Limits::default()
+    }
+
+    /// Cap the nesting depth of objects and arrays.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Cap the total number of values, nested ones included.
+    pub fn max_nodes(mut self, nodes: usize) -> Self {
+        self.max_nodes = Some(nodes);
+        self
+    }
+
+    /// Cap the size of the serialized document, in bytes.
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// The maximum nesting depth, if capped.
+    pub(crate) fn depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Check the usage of a document against the limits.
+    pub(crate) fn check(&self, usage: &Usage) -> Result<(), LimitExceeded> {
+        let caps = [
+            ("depth", self.max_depth, usage.depth),
+            ("nodes", self.max_nodes, usage.nodes),
+            ("bytes", self.max_bytes, usage.bytes),
+        ];
+        for (limit, max, actual) in caps {
+            if max.is_some_and(|max| actual > max) {
+                return Err(LimitExceeded::new(limit, max.unwrap_or_default(), actual));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Depth, number of values and serialized size of a document or value.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Usage {
+    depth: usize,
+    nodes: usize,
+    bytes: usize,
+}
+
+impl Usage {
+    /// Usage of a single value.
+    pub(crate) fn of(value: &Value) -> Usage {
+        match value {
+            Value::Object(map) => {
+                let mut usage = Usage::members(map.iter());
+                usage.nodes += 1;
+                usage
+            }
+            Value::Array(values) => {
+                let mut usage = Usage {
+                    depth: 1,
+                    nodes: 1,
+                    bytes: 2 + values.len().saturating_sub(1),
+                };
+                for value in values {
+                    usage.add(Usage::of(value), 0);
+                }
+                usage
+            }
+            scalar => Usage {
+                depth: 0,
+                nodes: 1,
+                bytes: serialized_len(scalar),
+            },
+        }
+    }
+
+    /// Size of the compact serialization, in bytes.
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Usage of an object holding the given members, not counting the object itself.
+    pub(crate) fn members<'a, I>(members: I) -> Usage
+    where
+        I: Iterator<Item = (&'a String, &'a Value)>,
+    {
+        let mut usage = Usage {
+            depth: 1,
+            nodes: 0,
+            bytes: 1,
+        };
+        for (k, v) in members {
+            // Key, colon and comma or closing brace
+            usage.add(Usage::of(v), serialized_len(k) + 2);
+        }
+        usage.bytes = usage.bytes.max(2);
+        usage
+    }
+
+    fn add(&mut self, child: Usage, bytes: usize) {
+        self.depth = self.depth.max(child.depth + 1);
+        self.nodes += child.nodes;
+        self.bytes += child.bytes + bytes;
+    }
+}
+
+/// Usage of the members of a document, kept up to date as they are
+/// replaced so that checking a new member doesn't walk the other ones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tally {
+    nodes: usize,
+    bytes: usize,
+    /// Number of members by depth
+    depths: BTreeMap<usize, usize>,
+}
+
+impl Tally {
+    /// Tally of the given members.
+    pub(crate) fn of<'a, I>(members: I) -> Tally
+    where
+        I: Iterator<Item = (&'a String, &'a Value)>,
+    {
+        let mut tally = Tally::default();
+        for (k, v) in members {
+            tally.insert(k, v);
+        }
+        tally
+    }
+
+    /// Account for a new member.
+    pub(crate) fn insert(&mut self, k: &str, v: &Value) {
+        let usage = Usage::of(v);
+        self.nodes += usage.nodes;
+        // Key, colon and comma or closing brace
+        self.bytes += usage.bytes + serialized_len(k) + 2;
+        *self.depths.entry(usage.depth).or_default() += 1;
+    }
+
+    /// Stop accounting for a member given to `Tally::insert` before.
+    pub(crate) fn remove(&mut self, k: &str, v: &Value) {
+        let usage = Usage::of(v);
+        self.nodes -= usage.nodes;
+        self.bytes -= usage.bytes + serialized_len(k) + 2;
+        if let Some(count) = self.depths.get_mut(&usage.depth) {
+            *count -= 1;
+            if *count == 0 {
+                self.depths.remove(&usage.depth);
+            }
+        }
+    }
+
+    /// Usage of an object holding the tallied members, as given by
+    /// `Usage::members`.
+    pub(crate) fn usage(&self) -> Usage {
+        Usage {
+            depth: self.depths.keys().next_back().map_or(1, |depth| depth + 1),
+            nodes: self.nodes,
+            bytes: (self.bytes + 1).max(2),
+        }
+    }
+}
+
+/// Length of the compact serialization of a value.
+fn serialized_len<T: serde::Serialize + ?Sized>(value: &T) -> usize {
+    struct Counter(usize);
+    impl Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut counter = Counter(0);
+    serde_json::to_writer(&mut counter, value).expect("JSON values are always serializable");
+    counter.0
+}
+
+/// Custom error type used when a mutation would make a document exceed its limits.
+#[derive(Debug)]
+pub struct LimitExceeded {
+    limit: String,
+    max: usize,
+    actual: usize,
+}
+
+impl LimitExceeded {
+    /// Create a new `LimitExceeded` error given the exceeded limit
+    /// (`depth`, `nodes` or `bytes`), its maximum and the actual value
+    pub fn new(limit: &str, max: usize, actual: usize) -> Self {
+        LimitExceeded {
+            limit: limit.into(),
+            max,
+            actual,
+        }
+    }
+
+    /// Stable code of the error, `LIMIT_EXCEEDED`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::LimitExceeded
+    }
+
+    /// Represent the error as `Json`, with its code, message, limit, maximum and actual value.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("limit", self.limit.clone().into()),
+            ("max", self.max.into()),
+            ("actual", self.actual.into()),
+        ];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for LimitExceeded {}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let what = match self.limit.as_str() {
+            "depth" => "be nested",
+            "nodes" => "hold",
+            _ => "take",
+        };
+        let unit = match self.limit.as_str() {
+            "depth" => "levels deep",
+            "nodes" => "values",
+            _ => "bytes",
+        };
+        write!(
+            f,
+            "LimitExceeded: Document would {} {} {}, the maximum being {}",
+            what, self.actual, unit, self.max
+        )
+    }
+}
+
+impl Json {
+    /// Enforce the given limits on this document: `Json::add_with` fails
+    /// and `Json::add` and `Json::merge` panic instead of exceeding them.
+    /// Added and replaced members are checked against a running usage of
+    /// the document, only `Json::merge` and `Json::append` walk their result.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::LimitExceeded)` if the document already
+    /// exceeds the limits, in which case they are not set
+    pub fn set_limits(&mut self, limits: Limits) -> Result<(), LimitExceeded> {
+        limits.check(&Usage::members(self.json_data.iter()))?;
+        self.limits = Some(limits);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, serialise, NonFinite};
+
+    #[test]
+    fn usage() -> Result<(), Box<dyn Error>> {
+        let json = inline!(
+            "title" => "Mostly Harmless",
+            "tags" => vec![inline!("name" => "sf\n"), Json::new()],
+            "empty" => Vec::<u8>::new()
+        );
+        let usage = Usage::members(json.json_data.iter());
+        assert_eq!(serialise!(json)?.len(), usage.bytes);
+        assert_eq!(3, usage.depth);
+        assert_eq!(6, usage.nodes);
+        assert_eq!(2, Usage::members(Json::new().json_data.iter()).bytes);
+
+        let mut tally = Tally::of(json.json_data.iter());
+        assert_eq!(usage, tally.usage());
+        tally.remove("tags", &json.json_data["tags"]);
+        tally.insert("tags", &Value::Null);
+        let json = inline!("title" => "Mostly Harmless", "tags" => None::<u8>, "empty" => Vec::<u8>::new());
+        assert_eq!(Usage::members(json.json_data.iter()), tally.usage());
+        Ok(())
+    }
+
+    #[test]
+    fn limits_enforced() -> Result<(), Box<dyn Error>> {
+        let mut json = inline!("a" => inline!("b" => 1));
+        assert_eq!(
+            "LimitExceeded: Document would be nested 2 levels deep, the maximum being 1",
+            json.set_limits(Limits::new().max_depth(1))
+                .unwrap_err()
+                .to_string()
+        );
+
+        json.set_limits(Limits::new().max_depth(3).max_bytes(30))?;
+        json.add_with("c", vec![vec![1]], NonFinite::Null)?;
+        let err = json
+            .add_with("d", vec![vec![vec![1]]], NonFinite::Null)
+            .unwrap_err();
+        assert_eq!(ErrorCode::LimitExceeded, err.code());
+        let err = json
+            .add_with("d", "a long string", NonFinite::Null)
+            .unwrap_err();
+        assert_eq!(30, err.to_json().get::<usize>("max")?);
+        assert_eq!(
+            inline!("a" => inline!("b" => 1), "c" => vec![vec![1]]),
+            json
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "LimitExceeded: Document would hold 3 values, the maximum being 2")]
+    fn merge_panics() {
+        let mut json = inline!("a" => inline!("b" => 1));
+        json.set_limits(Limits::new().max_nodes(2)).unwrap();
+        json.merge(inline!("a" => inline!("c" => 2)));
+    }
+}
@@ -0,0 +1,250 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Lossy lookups, converting the values stored with the wrong type such as
+//! numbers and booleans written as strings by spreadsheets and forms.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::schema::coerce_value;
+use crate::{Error, Json, NotFound};
+
+/// Separators used to write numbers, to read numeric strings
+/// such as `1,234.56` or `1 234,56` with `Json::get_lossy_with`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberLocale {
+    decimal: char,
+    groups: Vec<char>,
+}
+
+impl NumberLocale {
+    /// Numbers written with the given decimal separator, whose
+    /// digits may be grouped with any of the given separators.
+    pub fn new(decimal: char, groups: &[char]) -> Self {
+        NumberLocale {
+            decimal,
+            groups: groups.to_vec(),
+        }
+    }
+
+    /// Numbers written like `1,234.56`, as in English.
+    pub fn english() -> Self {
+        NumberLocale::new('.', &[','])
+    }
+
+    /// Numbers written like `1 234,56`, as in French, spaces
+    /// (non-breaking ones included) grouping the digits.
+    pub fn french() -> Self {
+        NumberLocale::new(',', &[' ', '\u{a0}', '\u{202f}'])
+    }
+
+    /// Numbers written like `1.234,56`, as in German.
+    pub fn german() -> Self {
+        NumberLocale::new(',', &['.'])
+    }
+
+    /// Numbers written like `1'234.56`, as in Swiss locales.
+    pub fn swiss() -> Self {
+        NumberLocale::new('.', &['\''])
+    }
+
+    /// The number written in this locale as it's written in JSON, if it's one:
+    /// the group separators must split the integer part in groups of three
+    /// digits, the first one holding one to three digits.
+    fn normalize(&self, s: &str) -> Option<String> {
+        let s = s.trim();
+        let (integer, fraction) = match s.split_once(self.decimal) {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (s, None),
+        };
+        let is_group = |c: char| self.groups.contains(&c);
+        if fraction.is_some_and(|fraction| fraction.contains(is_group)) {
+            return None;
+        }
+        let digits = integer.trim_start_matches(['-', '+']);
+        let mut normalized = String::with_capacity(s.len());
+        normalized.push_str(&integer[..integer.len() - digits.len()]);
+        if digits.contains(is_group) {
+            for (i, group) in digits.split(is_group).enumerate() {
+                let size = if i == 0 { 1..=3 } else { 3..=3 };
+                if !size.contains(&group.len()) || !group.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                normalized.push_str(group);
+            }
+        } else {
+            normalized.push_str(digits);
+        }
+        if let Some(fraction) = fraction {
+            normalized.push('.');
+            normalized.push_str(fraction);
+        }
+        Some(normalized)
+    }
+}
+
+impl Json {
+    /// Get value associated to the given key like `Json::get`, converting
+    /// the value if it cannot be deserialized as a `T` as is: numbers and
+    /// booleans written as strings (`"42"`, `"true"`, `"on"`...) are
+    /// converted, and so are numbers and booleans expected as strings.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let row = inline!("quantity" => " 12", "paid" => "true", "zip" => 75001);
+    ///
+    ///     assert_eq!(12, row.get_lossy::<u32>("quantity")?);
+    ///     assert!(row.get_lossy::<bool>("paid")?);
+    ///     assert_eq!("75001", &row.get_lossy::<String>("zip")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::Json)` if the value cannot be converted to a `T`
+    pub fn get_lossy<T: DeserializeOwned>(&self, k: &str) -> Result<T, Error> {
+        self.lossy(k, None)
+    }
+
+    /// Get value associated to the given key like `Json::get_lossy`, numbers
+    /// written as strings being read with the separators of the given locale.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, NumberLocale};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let row = inline!("price" => "1 234,56", "total" => "1,234.56");
+    ///
+    ///     assert_eq!(1234.56, row.get_lossy_with::<f64>("price", &NumberLocale::french())?);
+    ///     assert_eq!(1234.56, row.get_lossy_with::<f64>("total", &NumberLocale::english())?);
+    ///     assert!(row.get_lossy_with::<f64>("total", &NumberLocale::french()).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::Json)` if the value cannot be converted to a `T`
+    pub fn get_lossy_with<T: DeserializeOwned>(
+        &self,
+        k: &str,
+        locale: &NumberLocale,
+    ) -> Result<T, Error> {
+        self.lossy(k, Some(locale))
+    }
+
+    fn lossy<T: DeserializeOwned>(
+        &self,
+        k: &str,
+        locale: Option<&NumberLocale>,
+    ) -> Result<T, Error> {
+        let value = match self.json_data.get(self.key(k).as_ref()) {
+            Some(v) => v,
+            None => return Err(NotFound::new(k.into(), self)?.into()),
+        };
+        let err = match T::deserialize(value) {
+            Ok(t) => return Ok(t),
+            Err(err) => err,
+        };
+        let value = match (value, locale) {
+            (Value::String(s), Some(locale)) => match locale.normalize(s) {
+                Some(s) => Value::String(s),
+                None => return Err(err.into()),
+            },
+            (value, _) => value.clone(),
+        };
+        ["integer", "number", "boolean", "string"]
+            .iter()
+            .filter_map(|t| coerce_value(&value, t))
+            .find_map(|converted| T::deserialize(converted).ok())
+            .ok_or_else(|| err.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, ErrorCode};
+
+    #[test]
+    fn lossy() {
+        let json = inline!("n" => "-3", "f" => "2.5", "b" => "off", "s" => true, "x" => "1.2.3");
+        assert_eq!(-3, json.get_lossy::<i8>("n").unwrap());
+        assert_eq!(2.5, json.get_lossy::<f32>("f").unwrap());
+        assert!(!json.get_lossy::<bool>("b").unwrap());
+        assert_eq!("true", json.get_lossy::<String>("s").unwrap());
+        assert_eq!(
+            ErrorCode::Json,
+            json.get_lossy::<f64>("x").unwrap_err().code()
+        );
+        assert_eq!(
+            ErrorCode::NotFound,
+            json.get_lossy::<f64>("y").unwrap_err().code()
+        );
+    }
+
+    #[test]
+    fn locales() {
+        let normalize = |locale: NumberLocale, s: &str| locale.normalize(s);
+        assert_eq!(
+            Some("-1234.5".into()),
+            normalize(NumberLocale::german(), "-1.234,5")
+        );
+        assert_eq!(
+            Some("1234567".into()),
+            normalize(NumberLocale::swiss(), "1'234'567")
+        );
+        assert_eq!(
+            Some("1234.5".into()),
+            normalize(NumberLocale::french(), "1\u{202f}234,5")
+        );
+        assert_eq!(None, normalize(NumberLocale::english(), "1,234.5,6"));
+        assert_eq!(None, normalize(NumberLocale::english(), ",5"));
+        assert_eq!(None, normalize(NumberLocale::german(), "1.5"));
+        assert_eq!(None, normalize(NumberLocale::english(), "12,5"));
+        assert_eq!(None, normalize(NumberLocale::english(), "1234,567"));
+
+        let json = inline!("n" => "12,5", "total" => 3);
+        assert_eq!(
+            12.5,
+            json.get_lossy_with::<f64>("n", &NumberLocale::german())
+                .unwrap()
+        );
+        assert_eq!(
+            3,
+            json.get_lossy_with::<u8>("total", &NumberLocale::german())
+                .unwrap()
+        );
+        assert!(json.get_lossy::<f64>("n").is_err());
+        assert!(json
+            .get_lossy_with::<f64>("n", &NumberLocale::english())
+            .is_err());
+    }
+}
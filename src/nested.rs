@@ -0,0 +1,398 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Lookups and updates of the values nested in a document, given the
+//! steps to them.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::path::{self, parse_concrete, render, Segment};
+use crate::ser::{self, NonFinite};
+use crate::{Error, Json, NotFound, TypeMismatch};
+
+/// A step of a path given to `get_path!`: a key or an array index.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathStep<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Conversion of the steps of a path given to `get_path!`.
+#[doc(hidden)]
+pub trait IntoPathStep {
+    fn path_step(&self) -> PathStep<'_>;
+}
+
+impl IntoPathStep for &str {
+    fn path_step(&self) -> PathStep<'_> {
+        PathStep::Key(self)
+    }
+}
+
+impl IntoPathStep for String {
+    fn path_step(&self) -> PathStep<'_> {
+        PathStep::Key(self)
+    }
+}
+
+impl IntoPathStep for usize {
+    fn path_step(&self) -> PathStep<'_> {
+        PathStep::Index(*self)
+    }
+}
+
+#[doc(hidden)]
+pub fn get_path_inner<T: DeserializeOwned>(json: &Json, steps: &[PathStep]) -> Result<T, Error> {
+    let segments: Vec<Segment> = steps
+        .iter()
+        .map(|step| match step {
+            PathStep::Key(key) => Segment::Key(json.key(key).into_owned()),
+            PathStep::Index(i) => Segment::Index(*i),
+        })
+        .collect();
+    json.get_at(&segments)
+}
+
+impl Json {
+    /// Get the value at the given path, such as `movie.cast[0].name`,
+    /// deserialized as a `T` like `Json::get`. Use `get_path!` to build
+    /// the path from variables without escaping their keys.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::deserialise;
+    ///
+    /// fn main() -> Result<(), json_ez::Error> {
+    ///     let json = deserialise!(r#"{"movie": {"cast": [{"name": "Mos Def"}]}}"#)?;
+    ///
+    ///     assert_eq!("Mos Def", json.get_path::<String>("movie.cast[0].name")?);
+    ///     assert_eq!(
+    ///         "NotFound: Cannot found key movie.crew in {\"cast\":[{\"name\":\"Mos Def\"}]}",
+    ///         json.get_path::<String>("movie.crew.name").unwrap_err().to_string()
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if the path is invalid
+    /// or holds wildcards, an `Err(json_ez::Error::NotFound)` with the path
+    /// up to the first missing step if there's no value at the path or an
+    /// `Err(json_ez::Error::Json)` if the value cannot be deserialized as a `T`
+    pub fn get_path<T: DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let segments = self.normalized(parse_concrete(path)?);
+        self.get_at(&segments)
+    }
+
+    /// Set the value at the given path, such as `config.server.port`, creating
+    /// the missing objects on the way like `mkdir -p`, and the missing arrays
+    /// for indexes, padded with `null`s. The path is checked against the key
+    /// policy and the limits of the document like `Json::add_with`, and
+    /// nothing is changed if it fails.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, Json};
+    ///
+    /// fn main() -> Result<(), json_ez::Error> {
+    ///     let mut json = Json::new();
+    ///     json.set_path("config.server.port", 8080)?;
+    ///     json.set_path("config.server.hosts[1]", "example.com")?;
+    ///
+    ///     assert_eq!(
+    ///         deserialise!(r#"{"config": {"server": {"port": 8080, "hosts": [null, "example.com"]}}}"#)?,
+    ///         json
+    ///     );
+    ///     assert!(json.set_path("config.server.port.number", 80).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if the path is invalid,
+    /// holds wildcards or an index too far past the end of an array, an
+    /// `Err(json_ez::Error::TypeMismatch)` if a value on the way is neither
+    /// `null` nor the object or array the path goes through, an
+    /// `Err(json_ez::Error::Json)` if the value cannot be
+    /// represented as JSON, an `Err(json_ez::Error::InvalidKey)` if a key
+    /// doesn't follow the key policy of the document or an
+    /// `Err(json_ez::Error::LimitExceeded)` if its limits would be exceeded
+    pub fn set_path<V: Serialize>(&mut self, path: &str, v: V) -> Result<(), Error> {
+        let segments = parse_concrete(path)?;
+        let first = match segments.first() {
+            Some(Segment::Key(key)) => key,
+            _ => return Err(TypeMismatch::new("array", &Value::Object(Map::new())).into()),
+        };
+        let value = ser::to_value(&v, NonFinite::Null, self.encoders.as_deref())?;
+        // Update a copy of the member, stored as a whole once updated
+        let mut member = self
+            .json_data
+            .get(self.key(first).as_ref())
+            .cloned()
+            .unwrap_or(Value::Null);
+        path::insert(&mut member, &segments, 1, value)?;
+        self.store(first, member).map(drop)
+    }
+
+    /// Remove the value at the given path, such as `config.server.port`,
+    /// returning it if it existed. Removing an array element shifts the
+    /// following ones, along with their annotations. The annotations of
+    /// the value are removed too.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::deserialise;
+    ///
+    /// fn main() -> Result<(), json_ez::Error> {
+    ///     let mut json = deserialise!(r#"{"server": {"port": 80, "hosts": ["a", "b"]}}"#)?;
+    ///
+    ///     assert_eq!(Some(80.into()), json.remove_path("server.port")?);
+    ///     assert_eq!(Some("a".into()), json.remove_path("server.hosts[0]")?);
+    ///     assert_eq!(None, json.remove_path("server.port")?);
+    ///     assert_eq!(deserialise!(r#"{"server": {"hosts": ["b"]}}"#)?, json);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if the path is invalid
+    /// or holds wildcards
+    pub fn remove_path(&mut self, path: &str) -> Result<Option<Value>, Error> {
+        let location = self.normalized(parse_concrete(path)?);
+        let (first, rest, last) = match location.as_slice() {
+            [Segment::Key(first)] => return Ok(self.remove(first)),
+            [Segment::Key(first), rest @ .., last] => (first, rest, last),
+            _ => return Ok(None),
+        };
+        let mut member = match self.json_data.get(first) {
+            Some(member) => member.clone(),
+            None => return Ok(None),
+        };
+        let removed = match (path::lookup_mut(&mut member, rest), last) {
+            (Some(Value::Object(map)), Segment::Key(key)) => map.remove(key),
+            (Some(Value::Array(items)), Segment::Index(i)) if *i < items.len() => {
+                Some(items.remove(*i))
+            }
+            _ => None,
+        };
+        if removed.is_some() {
+            self.store(first, member)?;
+            self.annotations
+                .retain(|path, _| !path.starts_with(&location));
+            if let Segment::Index(i) = last {
+                self.shift_annotations(&location[..location.len() - 1], *i);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Move the annotations of the elements following the removed element
+    /// `removed` of the array at the given location to their new index.
+    fn shift_annotations(&mut self, array: &[Segment], removed: usize) {
+        let shifted: Vec<Vec<Segment>> = self
+            .annotations
+            .keys()
+            .filter(|path| {
+                path.starts_with(array)
+                    && matches!(path.get(array.len()), Some(Segment::Index(j)) if *j > removed)
+            })
+            .cloned()
+            .collect();
+        for mut path in shifted {
+            let metadata = self.annotations.remove(&path);
+            if let (Some(Segment::Index(j)), Some(metadata)) = (path.get_mut(array.len()), metadata)
+            {
+                *j -= 1;
+                self.annotations.insert(path, metadata);
+            }
+        }
+    }
+
+    /// The given segments with their keys normalized if enabled.
+    fn normalized(&self, segments: Vec<Segment>) -> Vec<Segment> {
+        segments
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Key(key) => Segment::Key(self.key(&key).into_owned()),
+                other => other,
+            })
+            .collect()
+    }
+
+    /// Get the value at the given concrete location, deserialized as a `T`.
+    /// A missing value is reported with the path to it, up to the first missing step.
+    pub(crate) fn get_at<T: DeserializeOwned>(&self, segments: &[Segment]) -> Result<T, Error> {
+        let (first, rest) = match segments.split_first() {
+            Some((Segment::Key(key), rest)) => (key, rest),
+            Some(_) => return Err(NotFound::new(render(segments), self)?.into()),
+            None => return Ok(T::deserialize(&serde_json::to_value(self)?)?),
+        };
+        let mut value = match self.json_data.get(first) {
+            Some(value) => value,
+            None => return Err(NotFound::new(first.clone(), self)?.into()),
+        };
+        for (i, segment) in rest.iter().enumerate() {
+            let next = match (segment, value) {
+                (Segment::Key(key), Value::Object(map)) => map.get(key),
+                (Segment::Index(i), Value::Array(items)) => items.get(*i),
+                _ => None,
+            };
+            value = match next {
+                Some(next) => next,
+                None => {
+                    let path = render(&segments[..i + 2]);
+                    return Err(NotFound::at_path(path, segment, value)?.into());
+                }
+            };
+        }
+        Ok(T::deserialize(value)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{deserialise, get_path, Error, Json};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn get_paths() -> Result<(), Box<dyn std::error::Error>> {
+        let json =
+            deserialise!(r#"{ "movie": { "cast": [{ "name": "Mos Def" }], "title": "H2G2" } }"#)?;
+        let index = 0;
+        let key = String::from("name");
+        assert_eq!(
+            "Mos Def",
+            get_path!(json, "movie" / "cast" / index / key => String)?
+        );
+        assert_eq!("H2G2", get_path!(json, "movie" / "title" => String)?);
+        assert_eq!(1, get_path!(json, "movie" / "cast" => Vec<Json>)?.len());
+
+        let err = get_path!(json, "movie" / "cast" / 1 / "name" => String).unwrap_err();
+        assert_eq!(
+            "NotFound: Cannot found key movie.cast[1] in [{\"name\":\"Mos Def\"}]",
+            err.to_string()
+        );
+        let err = get_path!(json, "movie" / "titel" / "x" => String).unwrap_err();
+        match err {
+            Error::NotFound(err) => assert_eq!(&["title".to_string()], err.suggestions()),
+            other => panic!("unexpected {:?}", other),
+        }
+        let err = get_path!(json, "movie" / "title" / "x" => String).unwrap_err();
+        assert_eq!(
+            "NotFound: Cannot found key movie.title.x in \"H2G2\"",
+            err.to_string()
+        );
+        assert!(get_path!(json, "film" => Json).is_err());
+        assert!(get_path!(json, "movie" / "title" => u8).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn set_path() -> Result<(), Box<dyn std::error::Error>> {
+        let mut json = deserialise!(r#"{ "a": { "b": 1 }, "s": "x" }"#)?;
+        json.set_path("a.c.d", true)?;
+        json.set_path("e[0]", 2)?;
+        assert_eq!(
+            deserialise!(r#"{ "a": { "b": 1, "c": { "d": true } }, "e": [2], "s": "x" }"#)?,
+            json
+        );
+        assert!(matches!(
+            json.set_path("s.t", 1),
+            Err(Error::TypeMismatch(_))
+        ));
+        assert!(matches!(
+            json.set_path("[0]", 1),
+            Err(Error::TypeMismatch(_))
+        ));
+        assert!(matches!(
+            json.set_path("e[18446744073709551615]", 1),
+            Err(Error::InvalidPath(_))
+        ));
+
+        json.set_limits(crate::Limits::new().max_depth(3))?;
+        assert!(matches!(
+            json.set_path("a.c.f.g", 1),
+            Err(Error::LimitExceeded(_))
+        ));
+        assert!(json.get_path::<Json>("a.c.f").is_err());
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&changes);
+        json.watch("a.b", move |_, b| seen.lock().unwrap().push(b.cloned()))?;
+        let before = json.fingerprint();
+        json.set_path("a.b", 2)?;
+        json.set_path("a.c", 3)?;
+        assert_ne!(before, json.fingerprint());
+        assert_eq!(vec![Some(2.into())], *changes.lock().unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn remove_paths() -> Result<(), Box<dyn std::error::Error>> {
+        let mut json = deserialise!(r#"{ "a": { "b": [1, { "c": 2 }] }, "d": 3 }"#)?;
+        json.annotate("a.b[1].c", "note")?;
+        json.annotate("d", "note")?;
+        assert_eq!(Some(2.into()), json.remove_path("a.b[1].c")?);
+        assert_eq!(None, json.remove_path("a.b[2]")?);
+        assert_eq!(None, json.remove_path("a.x.y")?);
+        assert_eq!(None, json.remove_path("[0]")?);
+        assert_eq!(Some(3.into()), json.remove_path("d")?);
+        assert!(json.annotations().is_empty());
+        assert_eq!(deserialise!(r#"{ "a": { "b": [1, {}] } }"#)?, json);
+        assert!(json.remove_path("a.*").is_err());
+
+        let mut json = deserialise!(r#"{ "a": [{ "b": 0 }, 1, { "b": 2 }, 3] }"#)?;
+        json.annotate("a[1]", "one")?;
+        json.annotate("a[2].b", "two")?;
+        json.annotate("a[3]", "three")?;
+        json.annotate("a", "array")?;
+        assert_eq!(Some(1.into()), json.remove_path("a[1]")?);
+        assert_eq!(Some("two".to_string()), json.annotation("a[1].b")?);
+        assert_eq!(Some("three".to_string()), json.annotation("a[2]")?);
+        assert_eq!(Some("array".to_string()), json.annotation("a")?);
+        assert_eq!(None, json.annotation::<String>("a[3]")?);
+        assert_eq!(3, json.annotations().len());
+        Ok(())
+    }
+
+    #[test]
+    fn get_path_method() -> Result<(), Box<dyn std::error::Error>> {
+        let json = deserialise!(r#"{ "a.b": { "c": [1, 2] } }"#)?;
+        assert_eq!(2, json.get_path::<u8>(r"a\.b.c[1]")?);
+        assert_eq!(vec![1, 2], json.get_path::<Vec<u8>>(r"a\.b.c")?);
+        assert!(matches!(
+            json.get_path::<u8>("a.b.c"),
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            json.get_path::<u8>("a.*"),
+            Err(Error::InvalidPath(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,410 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Numbers of documents, with their exact representation and checked
+//! arithmetic, without dropping down to `serde_json::Number`.
+
+use std::convert::TryFrom;
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{Error as SerdeError, Number, Value};
+
+use crate::error::{self, ErrorCode};
+use crate::ser::non_finite_name;
+use crate::{Error, Json, TypeMismatch};
+
+/// Custom error type used when the arithmetic on a member overflows.
+#[derive(Debug)]
+pub struct ArithmeticOverflow {
+    key: String,
+    operation: String,
+    value: JsonNumber,
+    operand: JsonNumber,
+}
+
+impl ArithmeticOverflow {
+    /// Create a new `ArithmeticOverflow` error given the key of the member,
+    /// the operation (`"add"`, `"sub"` or `"mul"`), its value and the operand
+    pub fn new(key: &str, operation: &str, value: JsonNumber, operand: JsonNumber) -> Self {
+        ArithmeticOverflow {
+            key: key.into(),
+            operation: operation.into(),
+            value,
+            operand,
+        }
+    }
+
+    /// The key of the member which was kept unchanged.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The operation which overflowed.
+    pub fn operation(&self) -> &str {
+        &self.operation
+    }
+
+    /// Stable code of the error, `ARITHMETIC_OVERFLOW`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::ArithmeticOverflow
+    }
+
+    /// Represent the error as `Json`, with its code, message, key,
+    /// operation, value and operand.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("key", self.key.clone().into()),
+            ("operation", self.operation.clone().into()),
+            ("value", self.value.clone().into()),
+            ("operand", self.operand.clone().into()),
+        ];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl StdError for ArithmeticOverflow {}
+
+impl Display for ArithmeticOverflow {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&format!(
+            "ArithmeticOverflow: Cannot {} {} and {} under {:?}",
+            self.operation, self.value, self.operand, self.key
+        ))
+    }
+}
+
+/// A JSON number, returned by `Json::get_number`. Integers are kept
+/// exactly, as an `i64` or a `u64`, and the other numbers as a `f64`.
+///
+/// # Example
+/// ```
+/// use json_ez::{deserialise, JsonNumber};
+///
+/// fn main() -> Result<(), json_ez::Error> {
+///     let json = deserialise!(r#"{"id": 18446744073709551615, "price": 2.5}"#)?;
+///     let id = json.get_number("id")?;
+///
+///     assert!(id.is_u64() && !id.is_i64());
+///     assert_eq!(Some(u64::MAX), id.as_u64());
+///     assert_eq!(None, id.checked_add(&1.into()));
+///     assert_eq!(
+///         JsonNumber::from_f64(5.0),
+///         json.get_number("price")?.checked_mul(&2.into())
+///     );
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JsonNumber(Number);
+
+/// Operands of an arithmetic operation, exact if both are integers.
+enum Operands {
+    Integers(i128, i128),
+    Floats(f64, f64),
+}
+
+impl JsonNumber {
+    /// Whether the number is an integer which fits in an `i64`.
+    pub fn is_i64(&self) -> bool {
+        self.0.is_i64()
+    }
+
+    /// Whether the number is a non-negative integer which fits in a `u64`.
+    pub fn is_u64(&self) -> bool {
+        self.0.is_u64()
+    }
+
+    /// Whether the number is neither an `i64` nor a `u64`.
+    pub fn is_f64(&self) -> bool {
+        self.0.is_f64()
+    }
+
+    /// The number as an `i64`, if it's an integer which fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.0.as_i64()
+    }
+
+    /// The number as a `u64`, if it's a non-negative integer which fits.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.0.as_u64()
+    }
+
+    /// The number as an `i128`, if it's an `i64` or a `u64`, which
+    /// all fit exactly.
+    pub fn as_i128(&self) -> Option<i128> {
+        match (self.0.as_i64(), self.0.as_u64()) {
+            (Some(i), _) => Some(i.into()),
+            (_, Some(u)) => Some(u.into()),
+            _ => None,
+        }
+    }
+
+    /// The number as a `f64`, possibly rounded for large integers.
+    pub fn as_f64(&self) -> f64 {
+        self.0.as_f64().unwrap_or(f64::NAN)
+    }
+
+    /// The underlying `serde_json::Number`.
+    pub fn as_number(&self) -> &Number {
+        &self.0
+    }
+
+    /// Create a number from a `f64`, if it's finite.
+    pub fn from_f64(f: f64) -> Option<Self> {
+        Number::from_f64(f).map(JsonNumber)
+    }
+
+    fn operands(&self, other: &JsonNumber) -> Operands {
+        match (self.as_i128(), other.as_i128()) {
+            (Some(a), Some(b)) => Operands::Integers(a, b),
+            _ => Operands::Floats(self.as_f64(), other.as_f64()),
+        }
+    }
+
+    /// Build the result of an exact operation, if it fits in an `i64` or a `u64`.
+    fn integer(result: Option<i128>) -> Option<Self> {
+        let result = result?;
+        if let Ok(i) = i64::try_from(result) {
+            return Some(i.into());
+        }
+        u64::try_from(result).ok().map(Into::into)
+    }
+
+    /// Add two numbers, exactly if both are integers. Return `None` if
+    /// the integer result overflows an `i64` or a `u64`, or if the float
+    /// one is not finite.
+    pub fn checked_add(&self, other: &JsonNumber) -> Option<Self> {
+        match self.operands(other) {
+            Operands::Integers(a, b) => JsonNumber::integer(a.checked_add(b)),
+            Operands::Floats(a, b) => JsonNumber::from_f64(a + b),
+        }
+    }
+
+    /// Subtract a number, like `JsonNumber::checked_add`.
+    pub fn checked_sub(&self, other: &JsonNumber) -> Option<Self> {
+        match self.operands(other) {
+            Operands::Integers(a, b) => JsonNumber::integer(a.checked_sub(b)),
+            Operands::Floats(a, b) => JsonNumber::from_f64(a - b),
+        }
+    }
+
+    /// Multiply by a number, like `JsonNumber::checked_add`.
+    pub fn checked_mul(&self, other: &JsonNumber) -> Option<Self> {
+        match self.operands(other) {
+            Operands::Integers(a, b) => JsonNumber::integer(a.checked_mul(b)),
+            Operands::Floats(a, b) => JsonNumber::from_f64(a * b),
+        }
+    }
+}
+
+impl Display for JsonNumber {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl From<Number> for JsonNumber {
+    fn from(n: Number) -> Self {
+        JsonNumber(n)
+    }
+}
+
+impl From<JsonNumber> for Number {
+    fn from(n: JsonNumber) -> Self {
+        n.0
+    }
+}
+
+impl From<JsonNumber> for Value {
+    fn from(n: JsonNumber) -> Self {
+        Value::Number(n.0)
+    }
+}
+
+macro_rules! from_integers {
+    ($($t: ty),*) => {
+        $(
+            impl From<$t> for JsonNumber {
+                fn from(n: $t) -> Self {
+                    JsonNumber(n.into())
+                }
+            }
+        )*
+    };
+}
+
+from_integers!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+/// Convert a float, failing for non-finite ones which have no JSON representation.
+impl TryFrom<f64> for JsonNumber {
+    type Error = SerdeError;
+
+    fn try_from(f: f64) -> Result<Self, SerdeError> {
+        match JsonNumber::from_f64(f) {
+            Some(n) => Ok(n),
+            None => Err(SerdeError::custom(format!(
+                "Cannot represent non-finite number {}",
+                non_finite_name(f)
+            ))),
+        }
+    }
+}
+
+impl Json {
+    /// Get the number stored under the given key.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or
+    /// an `Err(json_ez::Error::TypeMismatch)` if its value is not a number
+    pub fn get_number(&self, k: &str) -> Result<JsonNumber, Error> {
+        match self.member(k)? {
+            Value::Number(n) => Ok(JsonNumber(n.clone())),
+            other => Err(TypeMismatch::new("number", other).into()),
+        }
+    }
+
+    /// Add the given number to the one stored under the given key, and
+    /// return the result. Integers stay exact, see `JsonNumber::checked_add`.
+    /// Floats are given as a `JsonNumber`, converted with `JsonNumber::try_from`
+    /// as non-finite ones are not numbers in JSON.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, JsonNumber};
+    /// use std::convert::TryFrom;
+    ///
+    /// fn main() -> Result<(), json_ez::Error> {
+    ///     let mut stats = inline!("visits" => 41, "ratio" => 0.5, "left" => 0u8);
+    ///
+    ///     assert_eq!(42, stats.incr("visits", 1)?.as_i64().unwrap());
+    ///     stats.mul("ratio", 3)?;
+    ///     stats.incr("ratio", JsonNumber::try_from(0.25)?)?;
+    ///     assert_eq!(1.75, stats.get::<f64>("ratio")?);
+    ///     assert_eq!(-1, stats.decr("left", 1)?.as_i64().unwrap());
+    ///     assert!(stats.mul("visits", i64::MAX).is_err());
+    ///     assert_eq!(42, stats.get::<i64>("visits")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist, an
+    /// `Err(json_ez::Error::TypeMismatch)` if its value is not a number, an
+    /// `Err(json_ez::Error::ArithmeticOverflow)` if the result overflows, or
+    /// the errors of `Json::add_with`. The document is unchanged on error.
+    pub fn incr<N: Into<JsonNumber>>(&mut self, k: &str, by: N) -> Result<JsonNumber, Error> {
+        self.compute(k, "add", by.into(), JsonNumber::checked_add)
+    }
+
+    /// Subtract the given number from the one stored under the given key,
+    /// like `Json::incr`.
+    ///
+    /// # Errors
+    /// Same as `Json::incr`
+    pub fn decr<N: Into<JsonNumber>>(&mut self, k: &str, by: N) -> Result<JsonNumber, Error> {
+        self.compute(k, "sub", by.into(), JsonNumber::checked_sub)
+    }
+
+    /// Multiply the number stored under the given key by the given one,
+    /// like `Json::incr`.
+    ///
+    /// # Errors
+    /// Same as `Json::incr`
+    pub fn mul<N: Into<JsonNumber>>(&mut self, k: &str, by: N) -> Result<JsonNumber, Error> {
+        self.compute(k, "mul", by.into(), JsonNumber::checked_mul)
+    }
+
+    fn compute(
+        &mut self,
+        k: &str,
+        operation: &str,
+        operand: JsonNumber,
+        op: fn(&JsonNumber, &JsonNumber) -> Option<JsonNumber>,
+    ) -> Result<JsonNumber, Error> {
+        let value = self.get_number(k)?;
+        match op(&value, &operand) {
+            Some(result) => {
+                self.store(k, result.clone().into())?;
+                Ok(result)
+            }
+            None => Err(ArithmeticOverflow::new(k, operation, value, operand).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn arithmetic() {
+        let n = |i: i64| JsonNumber::from(i);
+        assert_eq!(Some(n(-1)), n(1).checked_sub(&n(2)));
+        assert_eq!(Some(n(i64::MIN)), n(i64::MIN + 1).checked_sub(&n(1)));
+        assert_eq!(None, n(i64::MIN).checked_sub(&n(1)));
+        assert_eq!(
+            Some(JsonNumber::from(u64::MAX)),
+            JsonNumber::from(u64::MAX - 1).checked_add(&n(1))
+        );
+        assert_eq!(None, n(i64::MAX).checked_mul(&n(4)));
+        let f = |f: f64| JsonNumber::try_from(f).unwrap();
+        assert_eq!(Some(f(1.5)), n(1).checked_add(&f(0.5)));
+        assert_eq!(None, f(f64::MAX).checked_mul(&n(2)));
+        assert!(JsonNumber::try_from(f64::NAN).is_err());
+        assert!(JsonNumber::try_from(f64::NEG_INFINITY).is_err());
+        assert!(n(2).checked_mul(&n(3)).unwrap().is_i64());
+        assert_eq!("-3", n(-3).to_string());
+    }
+
+    #[test]
+    fn get_number() {
+        let json = inline!("n" => 1.25, "s" => "1");
+        let number = json.get_number("n").unwrap();
+        assert!(number.is_f64());
+        assert_eq!(None, number.as_i128());
+        assert_eq!(Value::from(1.25), Value::from(number));
+        assert!(json.get_number("s").is_err());
+        assert!(json.get_number("x").is_err());
+    }
+
+    #[test]
+    fn counters() {
+        let mut json = inline!("n" => u64::MAX - 1, "s" => "1");
+        assert_eq!(Some(u64::MAX), json.incr("n", 1).unwrap().as_u64());
+        let err = json.incr("n", 1u8).unwrap_err();
+        assert_eq!(ErrorCode::ArithmeticOverflow, err.code());
+        assert_eq!(u64::MAX, json.get::<u64>("n").unwrap());
+        assert_eq!(Some(0), json.mul("n", 0).unwrap().as_u64());
+        assert_eq!(
+            ErrorCode::TypeMismatch,
+            json.decr("s", 1).unwrap_err().code()
+        );
+        assert_eq!(ErrorCode::NotFound, json.decr("x", 1).unwrap_err().code());
+    }
+}
@@ -0,0 +1,422 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Validation of HTTP interactions against the operations of an
+//! OpenAPI 3 document, such as the ones recorded by a test harness.
+
+use std::borrow::Cow;
+
+use serde_json::{json, Value};
+
+use crate::error::Error;
+use crate::path::pointer_child;
+use crate::refs::Resolver;
+use crate::validate::{Checker, ValidationError, Violation};
+use crate::{InvalidSchema, Json};
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Make the schemas using the `nullable` keyword of OpenAPI 3.0 accept
+/// `null` as JSON Schema does.
+fn nullable(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            if map.get("nullable") == Some(&Value::Bool(true)) {
+                if let Some(reference) = map.remove("$ref") {
+                    let any_of = json!([{ "$ref": reference }, {"type": "null"}]);
+                    map.insert("anyOf".into(), any_of);
+                }
+                match map.get_mut("type") {
+                    Some(Value::String(t)) => {
+                        let t = Value::String(std::mem::take(t));
+                        map.insert("type".into(), json!([t, "null"]));
+                    }
+                    Some(Value::Array(types)) => types.push("null".into()),
+                    _ => {}
+                }
+                if let Some(Value::Array(allowed)) = map.get_mut("enum") {
+                    allowed.push(Value::Null);
+                }
+            }
+            map.values_mut().for_each(nullable);
+        }
+        Value::Array(items) => items.iter_mut().for_each(nullable),
+        _ => {}
+    }
+}
+
+/// The schema of a JSON body among the given `content` of a request
+/// or a response, `application/json` first.
+fn body_schema(content: Option<&Value>) -> Option<&Value> {
+    let content = content?.as_object()?;
+    content
+        .get("application/json")
+        .and_then(|media_type| media_type.get("schema"))
+        .or_else(|| {
+            content
+                .iter()
+                .filter(|(media_type, _)| media_type.ends_with("json"))
+                .find_map(|(_, media_type)| media_type.get("schema"))
+        })
+}
+
+/// Validator of requests and responses against the operations of an
+/// OpenAPI 3 document, its `$ref` references being followed as they are
+/// met, so that recursive schemas are supported.
+///
+/// # Example
+/// ```
+/// use json_ez::{deserialise, inline, OpenApiValidator};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let validator = OpenApiValidator::from_spec(deserialise!(r##"{
+///         "openapi": "3.0.3",
+///         "paths": {
+///             "/pets/{id}": {
+///                 "put": {
+///                     "requestBody": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/Pet"}}}},
+///                     "responses": {"2XX": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/Pet"}}}}}
+///                 }
+///             }
+///         },
+///         "components": {"schemas": {"Pet": {
+///             "type": "object",
+///             "required": ["name"],
+///             "properties": {"name": {"type": "string"}, "tag": {"type": "string", "nullable": true}}
+///         }}}
+///     }"##)?)?;
+///
+///     let pet = inline!("name" => "Marvin", "tag" => None::<String>);
+///     validator.validate_request("PUT", "/pets/42", &pet)?;
+///     validator.validate_response("PUT", "/pets/42", 201, &pet)?;
+///
+///     let err = validator.validate_request("PUT", "/pets/42", &inline!("tag" => 1)).unwrap_err();
+///     assert_eq!(
+///         "ValidationError: required at /: expected \"name\" but found null, \
+///          type at /tag: expected [\"string\",\"null\"] but found \"number\"",
+///         err.to_string()
+///     );
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenApiValidator {
+    spec: Json,
+    resolver: Resolver,
+}
+
+impl OpenApiValidator {
+    /// Create a validator of the operations described by the given
+    /// OpenAPI 3 document.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidSchema)` if the document is not
+    /// an OpenAPI 3 document with `paths`, or an
+    /// `Err(json_ez::Error::UnresolvedRef)` if a `$ref` cannot be resolved
+    pub fn from_spec(mut spec: Json) -> Result<Self, Error> {
+        let version = spec.json_data.get("openapi").and_then(Value::as_str);
+        if !version.is_some_and(|v| v.starts_with("3.")) {
+            return Err(InvalidSchema::new("/openapi", "expected an OpenAPI 3 document").into());
+        }
+        if !spec.json_data.get("paths").is_some_and(Value::is_object) {
+            return Err(InvalidSchema::new("/paths", "expected an object").into());
+        }
+        spec.json_data.values_mut().for_each(nullable);
+        let resolver = Resolver::new(&spec);
+        for (k, v) in &spec.json_data {
+            check_refs(&spec, &resolver, v, &pointer_child("", k))?;
+        }
+        Ok(OpenApiValidator { spec, resolver })
+    }
+
+    /// The given value, or the one it targets if it's a reference, such as
+    /// the `$ref` of path items, request bodies and responses.
+    fn resolved<'a>(&'a self, value: &'a Value) -> Cow<'a, Value> {
+        let mut current = Cow::Borrowed(value);
+        let mut location = String::new();
+        // References are checked by `OpenApiValidator::from_spec`, bound
+        // the chain in case they are cyclic
+        for _ in 0..32 {
+            let reference = match current.get("$ref").and_then(Value::as_str) {
+                Some(reference) => reference.to_string(),
+                None => break,
+            };
+            match self.resolver.target(&self.spec, &reference, &location) {
+                Ok((pointer, target)) => (location, current) = (pointer, target),
+                Err(_) => break,
+            }
+        }
+        current
+    }
+
+    /// The operation matching a method and a path, ignoring its query
+    /// string. Concrete paths are preferred over templated ones, such as
+    /// `/pets/mine` over `/pets/{id}`.
+    fn operation(&self, method: &str, path: &str) -> Result<Cow<'_, Value>, ValidationError> {
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').collect();
+        let paths = self.spec.json_data["paths"]
+            .as_object()
+            .expect("paths are checked by from_spec");
+        let item = paths
+            .iter()
+            .filter_map(|(template, item)| {
+                let parts: Vec<&str> = template.split('/').collect();
+                let mut literals = 0;
+                for (part, segment) in parts.iter().zip(&segments) {
+                    if part.starts_with('{') && part.ends_with('}') && !segment.is_empty() {
+                        continue;
+                    }
+                    if part != segment {
+                        return None;
+                    }
+                    literals += 1;
+                }
+                Some((literals, item)).filter(|_| parts.len() == segments.len())
+            })
+            .max_by_key(|(literals, _)| *literals)
+            .map(|(_, item)| item);
+        let item = item.ok_or_else(|| {
+            let templates = paths.keys().cloned().collect::<Vec<_>>();
+            violation("path", templates.into(), path.into())
+        })?;
+        let item = self.resolved(item);
+        let method = method.to_lowercase();
+        let methods: Vec<&str> = METHODS
+            .iter()
+            .copied()
+            .filter(|m| item.get(m).is_some())
+            .collect();
+        let operation = match item {
+            Cow::Borrowed(item) => item.get(&method).map(Cow::Borrowed),
+            Cow::Owned(mut item) => item.get_mut(&method).map(|o| Cow::Owned(o.take())),
+        };
+        operation.ok_or_else(|| violation("method", methods.into(), method.as_str().into()))
+    }
+
+    /// Validate the body of a request against the JSON schema of the
+    /// request body of the matching operation, if any.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::ValidationError)` listing the violations
+    /// of the body, or with a `path` or `method` violation if the document
+    /// has no matching operation
+    pub fn validate_request(
+        &self,
+        method: &str,
+        path: &str,
+        body: &Json,
+    ) -> Result<(), ValidationError> {
+        let operation = self.operation(method, path)?;
+        let request = operation.get("requestBody").map(|r| self.resolved(r));
+        let schema = body_schema(request.as_ref().and_then(|r| r.get("content")));
+        self.validate_body(body, schema)
+    }
+
+    /// Validate the body of a response against the JSON schema of the
+    /// matching operation for its status code, looked up as is, then as a
+    /// range (`2XX`), then as `default`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::ValidationError)` listing the violations of
+    /// the body, or with a `path`, `method` or `status` violation if the
+    /// document has no matching response
+    pub fn validate_response(
+        &self,
+        method: &str,
+        path: &str,
+        status: u16,
+        body: &Json,
+    ) -> Result<(), ValidationError> {
+        let operation = self.operation(method, path)?;
+        let responses = operation.get("responses");
+        let codes = [
+            status.to_string(),
+            format!("{}XX", status / 100),
+            "default".into(),
+        ];
+        let response = codes
+            .iter()
+            .find_map(|code| responses.and_then(|responses| responses.get(code)))
+            .ok_or_else(|| {
+                let declared = responses.and_then(Value::as_object);
+                let codes = declared.map(|r| r.keys().cloned().collect::<Vec<_>>());
+                violation("status", codes.unwrap_or_default().into(), status.into())
+            })?;
+        let response = self.resolved(response);
+        self.validate_body(body, body_schema(response.get("content")))
+    }
+
+    fn validate_body(&self, body: &Json, schema: Option<&Value>) -> Result<(), ValidationError> {
+        let schema = match schema {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+        let root = Value::Object(
+            body.json_data
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        );
+        let mut violations = Vec::new();
+        Checker::with_refs(&self.spec, &self.resolver).check(&root, schema, "", &mut violations);
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(ValidationError::new(violations)),
+        }
+    }
+}
+
+fn violation(keyword: &str, expected: Value, actual: Value) -> ValidationError {
+    ValidationError::new(vec![Violation::new("", keyword, expected, actual)])
+}
+
+/// Check that every `$ref` of the document can be resolved.
+fn check_refs(
+    spec: &Json,
+    resolver: &Resolver,
+    value: &Value,
+    location: &str,
+) -> Result<(), Error> {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get("$ref") {
+                resolver.target(spec, reference, location)?;
+            }
+            map.iter()
+                .try_for_each(|(k, v)| check_refs(spec, resolver, v, &pointer_child(location, k)))
+        }
+        Value::Array(items) => items.iter().enumerate().try_for_each(|(i, v)| {
+            check_refs(spec, resolver, v, &pointer_child(location, &i.to_string()))
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{deserialise, inline};
+
+    fn validator() -> OpenApiValidator {
+        let spec = deserialise!(
+            r#"{
+                "openapi": "3.1.0",
+                "paths": {
+                    "/pets/{id}": {
+                        "get": {"responses": {
+                            "200": {"content": {"application/json": {"schema": {"required": ["name"]}}}},
+                            "default": {"content": {"application/problem+json": {"schema": {"required": ["title"]}}}}
+                        }}
+                    },
+                    "/pets/mine": {"get": {"responses": {"200": {}}}}
+                }
+            }"#
+        )
+        .unwrap();
+        OpenApiValidator::from_spec(spec).unwrap()
+    }
+
+    #[test]
+    fn operations() {
+        let validator = validator();
+        let pet = inline!("name" => "Marvin");
+        assert!(validator
+            .validate_response("get", "/pets/1?full=true", 200, &pet)
+            .is_ok());
+        assert!(validator
+            .validate_response("GET", "/pets/mine", 200, &Json::new())
+            .is_ok());
+        assert!(validator
+            .validate_response("GET", "/pets/1", 200, &Json::new())
+            .is_err());
+        assert!(validator
+            .validate_response("GET", "/pets/1", 404, &pet)
+            .is_err());
+        assert!(validator.validate_request("GET", "/pets/1", &pet).is_ok());
+
+        let keyword = |result: Result<(), ValidationError>| {
+            result.unwrap_err().violations()[0].keyword().to_string()
+        };
+        assert_eq!(
+            "path",
+            keyword(validator.validate_request("GET", "/pets", &pet))
+        );
+        assert_eq!(
+            "path",
+            keyword(validator.validate_request("GET", "/pets/", &pet))
+        );
+        assert_eq!(
+            "method",
+            keyword(validator.validate_request("POST", "/pets/1", &pet))
+        );
+    }
+
+    #[test]
+    fn specs() {
+        let spec = inline!("swagger" => "2.0", "paths" => Json::new());
+        assert!(matches!(
+            OpenApiValidator::from_spec(spec),
+            Err(Error::InvalidSchema(_))
+        ));
+        let spec = deserialise!(r##"{"openapi": "3.0.0", "paths": {"/": {"$ref": "#/x"}}}"##);
+        assert!(matches!(
+            OpenApiValidator::from_spec(spec.unwrap()),
+            Err(Error::UnresolvedRef(_))
+        ));
+    }
+
+    #[test]
+    fn recursive_schemas() {
+        let spec = deserialise!(
+            r##"{
+                "openapi": "3.0.0",
+                "paths": {"/tree": {"$ref": "#/components/pathItems/tree"}},
+                "components": {
+                    "pathItems": {"tree": {"post": {"requestBody": {"$ref": "#/components/requestBodies/node"}}}},
+                    "requestBodies": {"node": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/node"}}}}},
+                    "schemas": {"node": {
+                        "required": ["name"],
+                        "properties": {
+                            "children": {"type": "array", "items": {"$ref": "#/components/schemas/node"}},
+                            "parent": {"$ref": "#/components/schemas/node", "nullable": true}
+                        }
+                    }}
+                }
+            }"##
+        );
+        let validator = OpenApiValidator::from_spec(spec.unwrap()).unwrap();
+        let tree =
+            deserialise!(r#"{"name": "root", "parent": null, "children": [{"name": "leaf"}]}"#);
+        assert!(validator
+            .validate_request("POST", "/tree", &tree.unwrap())
+            .is_ok());
+        let tree = deserialise!(r#"{"name": "root", "children": [{"children": []}]}"#);
+        let err = validator
+            .validate_request("POST", "/tree", &tree.unwrap())
+            .unwrap_err();
+        assert_eq!(err.violations()[0].location(), "/children/0");
+    }
+}
@@ -0,0 +1,155 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Cursor-based pagination envelopes, holding a page of items as
+//! `{"data": [...], "next_cursor": "...", "total": 42}`.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::Json;
+
+/// Page of items read from a pagination envelope by `Json::unwrap_page`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    items: Vec<T>,
+    next_cursor: Option<String>,
+    total: Option<u64>,
+}
+
+impl<T> Page<T> {
+    /// The items of the page.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// The cursor of the next page, `None` on the last one.
+    pub fn next_cursor(&self) -> Option<&str> {
+        self.next_cursor.as_deref()
+    }
+
+    /// The total number of items of all the pages, if known.
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Whether another page follows this one.
+    pub fn has_more(&self) -> bool {
+        self.next_cursor.is_some()
+    }
+
+    /// Move the items out of the page.
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl Json {
+    /// Create a pagination envelope holding the given items under `data`,
+    /// the cursor of the next page under `next_cursor` and the total
+    /// number of items under `total`, both `null` when unknown.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, Json};
+    ///
+    /// fn main() -> Result<(), json_ez::Error> {
+    ///     let envelope = Json::paginated(vec!["Mostly Harmless"], Some("b2Zmc2V0PTU"), Some(5));
+    ///     assert_eq!(
+    ///         deserialise!(r#"{"data":["Mostly Harmless"],"next_cursor":"b2Zmc2V0PTU","total":5}"#)?,
+    ///         envelope
+    ///     );
+    ///
+    ///     let page = envelope.unwrap_page::<String>()?;
+    ///     assert_eq!(["Mostly Harmless"], page.items());
+    ///     assert_eq!(Some("b2Zmc2V0PTU"), page.next_cursor());
+    ///     assert_eq!(Some(5), page.total());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    /// Panic if an item cannot be serialized, like `Json::add`
+    pub fn paginated<I>(items: I, next_cursor: Option<&str>, total: Option<u64>) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        let mut json = Json::new();
+        json.add("data", items.into_iter().collect::<Vec<_>>());
+        json.add("next_cursor", next_cursor);
+        json.add("total", total);
+        json
+    }
+
+    /// Read a pagination envelope as written by `Json::paginated`. The
+    /// `next_cursor` and `total` members may be missing or `null`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the document has no
+    /// `data` member or an `Err(json_ez::Error::Json)` if a member cannot
+    /// be deserialized
+    pub fn unwrap_page<T: DeserializeOwned>(&self) -> Result<Page<T>, Error> {
+        Ok(Page {
+            items: self.get("data")?,
+            next_cursor: self.get_optional("next_cursor")?,
+            total: self.get_optional("total")?,
+        })
+    }
+
+    /// The value of a member which may be missing or `null`.
+    fn get_optional<T: DeserializeOwned>(&self, k: &str) -> Result<Option<T>, Error> {
+        Ok(self.try_get::<Option<T>>(k)?.flatten())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn pages() -> Result<(), Error> {
+        let last = Json::paginated([1, 2], None, None);
+        assert_eq!(
+            inline!("data" => vec![1, 2], "next_cursor" => None::<String>, "total" => None::<u64>),
+            last
+        );
+        let page = last.unwrap_page::<u8>()?;
+        assert!(!page.has_more());
+        assert_eq!(vec![1, 2], page.into_items());
+
+        let bare = inline!("data" => Vec::<u8>::new());
+        assert_eq!(None, bare.unwrap_page::<u8>()?.total());
+        assert!(matches!(
+            Json::new().unwrap_page::<u8>(),
+            Err(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            inline!("data" => vec!["a"]).unwrap_page::<u8>(),
+            Err(Error::Json(_))
+        ));
+        Ok(())
+    }
+}
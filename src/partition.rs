@@ -0,0 +1,197 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Splitting a document in two, such as its public and internal fields.
+
+use serde_json::{Map, Value};
+
+use crate::path::{matches, parse, steps, Segment};
+use crate::{Error, Json};
+
+/// Split a value at the given location in its matched and other parts.
+fn split(
+    value: Value,
+    location: &mut Vec<Segment>,
+    patterns: &[Vec<Segment>],
+) -> (Option<Value>, Option<Value>) {
+    if patterns
+        .iter()
+        .any(|pattern| matches(pattern, &steps(location)))
+    {
+        return (Some(value), None);
+    }
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            let (mut matched, mut rest) = (Map::new(), Map::new());
+            for (k, v) in map {
+                location.push(Segment::Key(k.clone()));
+                let (m, r) = split(v, location, patterns);
+                location.pop();
+                if let Some(m) = m {
+                    matched.insert(k.clone(), m);
+                }
+                if let Some(r) = r {
+                    rest.insert(k, r);
+                }
+            }
+            let side = |map: Map<String, Value>| match map.is_empty() {
+                true => None,
+                false => Some(Value::Object(map)),
+            };
+            (side(matched), side(rest))
+        }
+        Value::Array(items) if !items.is_empty() => {
+            let (mut matched, mut rest) = (Vec::new(), Vec::new());
+            for (i, item) in items.into_iter().enumerate() {
+                location.push(Segment::Index(i));
+                let (m, r) = split(item, location, patterns);
+                location.pop();
+                matched.push(m);
+                rest.push(r);
+            }
+            let side = |items: Vec<Option<Value>>| match items.iter().all(Option::is_none) {
+                true => None,
+                false => Some(Value::Array(
+                    items.into_iter().map(Option::unwrap_or_default).collect(),
+                )),
+            };
+            (side(matched), side(rest))
+        }
+        other => (None, Some(other)),
+    }
+}
+
+impl Json {
+    /// Split the document in two in a single pass: the members for which the
+    /// given predicate returns `true`, and the others. Both documents keep the
+    /// settings of this one.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let user = inline!("name" => "Arthur", "_password_hash" => "...", "_role" => "admin");
+    ///
+    /// let (internal, public) = user.partition(|k, _| k.starts_with('_'));
+    /// assert_eq!(inline!("name" => "Arthur"), public);
+    /// assert_eq!(inline!("_password_hash" => "...", "_role" => "admin"), internal);
+    /// ```
+    pub fn partition<F>(self, mut predicate: F) -> (Json, Json)
+    where
+        F: FnMut(&str, &Value) -> bool,
+    {
+        let (mut matched, mut rest) = (self.settings(), self.settings());
+        for (k, v) in self.json_data {
+            match predicate(&k, &v) {
+                true => matched.json_data.insert(k, v),
+                false => rest.json_data.insert(k, v),
+            };
+        }
+        (matched, rest)
+    }
+
+    /// Split the document in two: the values matched by any of the given path
+    /// patterns, nested ones included, and the others. Both documents keep
+    /// the structure of this one, the objects left empty being removed.
+    /// Array elements keep their index on both sides, the elements with
+    /// nothing left on a side being replaced by `null` on that side.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, inline, Json};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let order = deserialise!(r#"{
+    ///         "id": 42,
+    ///         "customer": { "name": "Ford", "card": "4242" },
+    ///         "lines": [{ "sku": "TOWEL", "cost": 3 }]
+    ///     }"#)?;
+    ///
+    ///     let (internal, public) = order.partition_paths(&["customer.card", "lines[*].cost"])?;
+    ///     assert_eq!(
+    ///         deserialise!(r#"{ "customer": { "card": "4242" }, "lines": [{ "cost": 3 }] }"#)?,
+    ///         internal
+    ///     );
+    ///     assert_eq!(
+    ///         deserialise!(r#"{ "id": 42, "customer": { "name": "Ford" }, "lines": [{ "sku": "TOWEL" }] }"#)?,
+    ///         public
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if a pattern cannot be parsed
+    pub fn partition_paths(self, patterns: &[&str]) -> Result<(Json, Json), Error> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| parse(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (mut matched, mut rest) = (self.settings(), self.settings());
+        let mut location = Vec::new();
+        for (k, v) in self.json_data {
+            location.push(Segment::Key(k.clone()));
+            let (m, r) = split(v, &mut location, &patterns);
+            location.pop();
+            if let Some(m) = m {
+                matched.json_data.insert(k.clone(), m);
+            }
+            if let Some(r) = r {
+                rest.json_data.insert(k, r);
+            }
+        }
+        Ok((matched, rest))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{deserialise, inline};
+    use std::error::Error;
+
+    #[test]
+    fn partitions() -> Result<(), Box<dyn Error>> {
+        let json = inline!("a" => 1, "b" => "two", "c" => 3);
+        let (numbers, others) = json.partition(|_, v| v.is_number());
+        assert_eq!(inline!("a" => 1, "c" => 3), numbers);
+        assert_eq!(inline!("b" => "two"), others);
+
+        let json = deserialise!(
+            r#"{ "a": { "secret": 1, "b": { "secret": 2 } }, "c": [1, { "secret": 3 }, { "d": 4 }], "e": {}, "f": [null] }"#
+        )?;
+        let (secrets, rest) = json.partition_paths(&["**.secret"])?;
+        assert_eq!(
+            deserialise!(
+                r#"{ "a": { "secret": 1, "b": { "secret": 2 } }, "c": [null, { "secret": 3 }, null] }"#
+            )?,
+            secrets
+        );
+        assert_eq!(
+            deserialise!(r#"{ "c": [1, null, { "d": 4 }], "e": {}, "f": [null] }"#)?,
+            rest
+        );
+        assert!(Json::new().partition_paths(&["a..b"]).is_err());
+        Ok(())
+    }
+}
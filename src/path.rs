@@ -0,0 +1,516 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Paths addressing values nested in a document.
+//!
+//! A path is a list of dot separated keys, where array elements are
+//! addressed by their index between brackets: `movie.cast[0].name`.
+//! Patterns may also use `*` (or `[*]`) to match any single key or index
+//! and `**` to match any number of nested levels. Dots and brackets that
+//! are part of a key can be escaped with a backslash, as can the keys
+//! named `*` or `**` (`\*`). Empty keys are written `[""]`.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use serde_json::{Map, Value};
+
+use crate::error::{self, ErrorCode};
+use crate::{Json, TypeMismatch};
+
+/// A single step of a path pattern.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+    Recursive,
+}
+
+/// A single step of a concrete location in a document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Step<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Custom error type used when a path cannot be parsed.
+#[derive(Debug)]
+pub struct InvalidPath {
+    path: String,
+    reason: String,
+}
+
+impl InvalidPath {
+    /// Create a new `InvalidPath` error given the faulty path and the reason of the failure
+    pub fn new(path: &str, reason: &str) -> Self {
+        InvalidPath {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Stable code of the error, `INVALID_PATH`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::InvalidPath
+    }
+
+    /// Represent the error as `Json`, with its code, message, path and reason.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("path", self.path.clone().into()),
+            ("reason", self.reason.clone().into()),
+        ];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for InvalidPath {}
+
+impl Display for InvalidPath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&format!(
+            "InvalidPath: Cannot parse path {}, {}",
+            self.path, self.reason
+        ))
+    }
+}
+
+/// Parse a path, or a path pattern, into its segments.
+pub(crate) fn parse(path: &str) -> Result<Vec<Segment>, InvalidPath> {
+    let mut segments = Vec::new();
+    let mut chars = path.chars().peekable();
+    let mut key = String::new();
+    // Whether the current segment holds something (key, escape or brackets)
+    let mut pending = false;
+    // Whether the current key holds an escape, making `\*` a literal key
+    let mut escaped = false;
+
+    let flush = |key: &mut String, escaped: &mut bool, segments: &mut Vec<Segment>| {
+        let segment = match key.as_str() {
+            "*" if !*escaped => Segment::Wildcard,
+            "**" if !*escaped => Segment::Recursive,
+            _ => Segment::Key(key.clone()),
+        };
+        segments.push(segment);
+        key.clear();
+        *escaped = false;
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) => {
+                    key.push(c);
+                    pending = true;
+                    escaped = true;
+                }
+                None => return Err(InvalidPath::new(path, "dangling escape character")),
+            },
+            '.' => {
+                if !pending {
+                    return Err(InvalidPath::new(path, "empty key"));
+                }
+                if !key.is_empty() {
+                    flush(&mut key, &mut escaped, &mut segments);
+                }
+                pending = false;
+            }
+            '[' => {
+                if !pending && !segments.is_empty() {
+                    return Err(InvalidPath::new(path, "empty key"));
+                }
+                if !key.is_empty() {
+                    flush(&mut key, &mut escaped, &mut segments);
+                }
+                let mut index = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => index.push(c),
+                        None => return Err(InvalidPath::new(path, "unclosed bracket")),
+                    }
+                }
+                let segment = match index.as_str() {
+                    "*" => Segment::Wildcard,
+                    "\"\"" => Segment::Key(String::new()),
+                    _ => Segment::Index(index.parse().map_err(|_| {
+                        InvalidPath::new(path, &format!("invalid index {}", index))
+                    })?),
+                };
+                segments.push(segment);
+                pending = true;
+                if let Some(next) = chars.peek() {
+                    if *next != '.' && *next != '[' {
+                        return Err(InvalidPath::new(path, "unexpected character after index"));
+                    }
+                }
+            }
+            c => {
+                key.push(c);
+                pending = true;
+            }
+        }
+    }
+    if !pending {
+        return Err(InvalidPath::new(path, "empty key"));
+    }
+    if !key.is_empty() {
+        flush(&mut key, &mut escaped, &mut segments);
+    }
+    Ok(segments)
+}
+
+/// Parse a concrete path, which cannot hold wildcards.
+pub(crate) fn parse_concrete(path: &str) -> Result<Vec<Segment>, InvalidPath> {
+    let segments = parse(path)?;
+    if segments
+        .iter()
+        .any(|s| matches!(s, Segment::Wildcard | Segment::Recursive))
+    {
+        return Err(InvalidPath::new(path, "wildcards are not allowed"));
+    }
+    Ok(segments)
+}
+
+/// Render concrete segments as a path, escaping the
+/// characters of the keys which are part of the syntax.
+pub(crate) fn render(segments: &[Segment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        let key: String = match segment {
+            Segment::Index(i) => {
+                path.push_str(&format!("[{}]", i));
+                continue;
+            }
+            Segment::Key(key) if key.is_empty() => {
+                path.push_str("[\"\"]");
+                continue;
+            }
+            Segment::Key(key) if key == "*" || key == "**" => format!("\\{}", key),
+            Segment::Key(key) => key
+                .chars()
+                .flat_map(|c| match c {
+                    '.' | '[' | ']' | '\\' => vec!['\\', c],
+                    c => vec![c],
+                })
+                .collect(),
+            Segment::Wildcard => "*".into(),
+            Segment::Recursive => "**".into(),
+        };
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(&key);
+    }
+    path
+}
+
+/// How far past the end of an array `insert` may set an element, the
+/// gap being padded with `null`s. Paths may come from untrusted input,
+/// such as query strings, and must not allocate arbitrarily large arrays.
+pub(crate) const MAX_PADDING: usize = 1024;
+
+/// Set the value at the given concrete location, starting from its segment
+/// at position `at`, creating the missing objects and arrays, arrays being
+/// padded with `null`s.
+pub(crate) fn insert(
+    slot: &mut Value,
+    segments: &[Segment],
+    at: usize,
+    value: Value,
+) -> Result<(), crate::Error> {
+    match segments.get(at) {
+        None => {
+            *slot = value;
+            Ok(())
+        }
+        Some(Segment::Key(key)) => {
+            if slot.is_null() {
+                *slot = Value::Object(Map::new());
+            }
+            match slot {
+                Value::Object(map) => {
+                    let slot = map.entry(key.clone()).or_insert(Value::Null);
+                    insert(slot, segments, at + 1, value)
+                }
+                other => Err(TypeMismatch::new("object", other).into()),
+            }
+        }
+        Some(Segment::Index(i)) => {
+            if slot.is_null() {
+                *slot = Value::Array(Vec::new());
+            }
+            match slot {
+                Value::Array(items) => {
+                    if *i > items.len().saturating_add(MAX_PADDING) {
+                        let reason =
+                            format!("index more than {} past the end of the array", MAX_PADDING);
+                        return Err(InvalidPath::new(&render(&segments[..=at]), &reason).into());
+                    }
+                    if items.len() <= *i {
+                        items.resize(i + 1, Value::Null);
+                    }
+                    insert(&mut items[*i], segments, at + 1, value)
+                }
+                other => Err(TypeMismatch::new("array", other).into()),
+            }
+        }
+        Some(_) => unreachable!("concrete paths hold no wildcards"),
+    }
+}
+
+impl Json {
+    /// Set the value at the given concrete location, like `path::insert`.
+    pub(crate) fn insert_at(
+        &mut self,
+        segments: &[Segment],
+        value: Value,
+    ) -> Result<(), crate::Error> {
+        match segments.first() {
+            Some(Segment::Key(key)) => {
+                let slot = self.json_data.entry(key.clone()).or_insert(Value::Null);
+                insert(slot, segments, 1, value)
+            }
+            _ => Err(TypeMismatch::new("array", &Value::Object(Map::new())).into()),
+        }
+    }
+
+    /// Call `f` with the location and the value of every value matched by
+    /// any of the given patterns, like `path::for_each_match`.
+    pub(crate) fn for_each_match(
+        &mut self,
+        patterns: &[&str],
+        f: &mut dyn FnMut(&[Segment], &mut Value),
+    ) -> Result<(), InvalidPath> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| parse(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.watched(|json| {
+            for (k, v) in json.json_data.iter_mut() {
+                let mut location = vec![Segment::Key(k.clone())];
+                for_each_match(v, &mut location, &patterns, f);
+            }
+        });
+        Ok(())
+    }
+
+    /// The value at the given concrete location, like `path::lookup`.
+    pub(crate) fn value_at(&self, segments: &[Segment]) -> Option<&Value> {
+        match segments.split_first() {
+            Some((Segment::Key(key), rest)) => lookup(self.json_data.get(key)?, rest),
+            _ => None,
+        }
+    }
+}
+
+/// The value at the given concrete location, if any.
+pub(crate) fn lookup<'a>(value: &'a Value, segments: &[Segment]) -> Option<&'a Value> {
+    segments
+        .iter()
+        .try_fold(value, |value, segment| match (segment, value) {
+            (Segment::Key(key), Value::Object(map)) => map.get(key),
+            (Segment::Index(i), Value::Array(items)) => items.get(*i),
+            _ => None,
+        })
+}
+
+/// The mutable value at the given concrete location, if any.
+pub(crate) fn lookup_mut<'a>(value: &'a mut Value, segments: &[Segment]) -> Option<&'a mut Value> {
+    segments
+        .iter()
+        .try_fold(value, |value, segment| match (segment, value) {
+            (Segment::Key(key), Value::Object(map)) => map.get_mut(key),
+            (Segment::Index(i), Value::Array(items)) => items.get_mut(*i),
+            _ => None,
+        })
+}
+
+/// Append a reference token to a JSON Pointer, escaping `~` and `/`.
+pub(crate) fn pointer_child(pointer: &str, token: &str) -> String {
+    format!(
+        "{}/{}",
+        pointer,
+        token.replace('~', "~0").replace('/', "~1")
+    )
+}
+
+/// The steps of a concrete location.
+pub(crate) fn steps(location: &[Segment]) -> Vec<Step<'_>> {
+    location
+        .iter()
+        .map(|segment| match segment {
+            Segment::Key(key) => Step::Key(key),
+            Segment::Index(i) => Step::Index(*i),
+            _ => unreachable!("concrete locations hold no wildcards"),
+        })
+        .collect()
+}
+
+/// The JSON Pointer of a concrete location.
+pub(crate) fn pointer(location: &[Segment]) -> String {
+    location
+        .iter()
+        .fold(String::new(), |pointer, segment| match segment {
+            Segment::Key(key) => pointer_child(&pointer, key),
+            Segment::Index(i) => pointer_child(&pointer, &i.to_string()),
+            _ => unreachable!("concrete locations hold no wildcards"),
+        })
+}
+
+/// Call `f` with the location of every value nested in `value` (itself
+/// included) which is matched by any of the given patterns, and the value.
+pub(crate) fn for_each_match(
+    value: &mut Value,
+    location: &mut Vec<Segment>,
+    patterns: &[Vec<Segment>],
+    f: &mut dyn FnMut(&[Segment], &mut Value),
+) {
+    if patterns
+        .iter()
+        .any(|pattern| matches(pattern, &steps(location)))
+    {
+        f(location, value);
+    }
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                location.push(Segment::Key(k.clone()));
+                for_each_match(v, location, patterns, f);
+                location.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                location.push(Segment::Index(i));
+                for_each_match(item, location, patterns, f);
+                location.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether the given concrete location is matched by a parsed pattern.
+pub(crate) fn matches(pattern: &[Segment], location: &[Step]) -> bool {
+    match (pattern.first(), location.first()) {
+        (None, None) => true,
+        (Some(Segment::Recursive), _) => {
+            matches(&pattern[1..], location)
+                || (!location.is_empty() && matches(pattern, &location[1..]))
+        }
+        (None, Some(_)) | (Some(_), None) => false,
+        (Some(segment), Some(step)) => {
+            let matched = match (segment, step) {
+                (Segment::Wildcard, _) => true,
+                (Segment::Key(k), Step::Key(s)) => k == s,
+                (Segment::Index(i), Step::Index(j)) => i == j,
+                _ => false,
+            };
+            matched && matches(&pattern[1..], &location[1..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_paths() {
+        assert_eq!(
+            vec![
+                Segment::Key("movie".into()),
+                Segment::Key("cast".into()),
+                Segment::Index(0),
+                Segment::Wildcard,
+                Segment::Recursive,
+                Segment::Key("a.b".into()),
+            ],
+            parse(r"movie.cast[0].*.**.a\.b").unwrap()
+        );
+        assert_eq!(
+            vec![Segment::Index(1), Segment::Wildcard],
+            parse("[1][*]").unwrap()
+        );
+        assert_eq!(
+            "InvalidPath: Cannot parse path a..b, empty key",
+            format!("{}", parse("a..b").unwrap_err())
+        );
+        assert!(parse("").is_err());
+        let path = r"movie.cast[0].*.**.a\.b\[c\]\\d";
+        assert_eq!(path, render(&parse(path).unwrap()));
+        assert_eq!("[1].*", render(&parse("[1][*]").unwrap()));
+        assert!(parse("a[x]").is_err());
+        assert!(parse("a[0").is_err());
+        assert!(parse("a[0]b").is_err());
+        assert!(parse("a.[0]").is_err());
+        assert_eq!(
+            vec![Segment::Key("*".into()), Segment::Key("**".into())],
+            parse(r"\*.\**").unwrap()
+        );
+        assert_eq!(r"\*.\**", render(&parse(r"\*.*\*").unwrap()));
+        let empty = vec![Segment::Key("".into()), Segment::Key("a".into())];
+        assert_eq!(r#"[""].a"#, render(&empty));
+        assert_eq!(empty, parse(&render(&empty)).unwrap());
+        assert_eq!(r#"a[""][""]"#, render(&parse(r#"a[""][""]"#).unwrap()));
+    }
+
+    #[test]
+    fn insert_values() {
+        let mut value = Value::Null;
+        let path = parse_concrete("movie.cast[1].name").unwrap();
+        insert(&mut value, &path, 0, "Mos Def".into()).unwrap();
+        assert_eq!(
+            serde_json::json!({ "movie": { "cast": [null, { "name": "Mos Def" }] } }),
+            value
+        );
+        let err = insert(
+            &mut value,
+            &parse_concrete("movie[0]").unwrap(),
+            0,
+            1.into(),
+        );
+        assert_eq!(
+            "TypeMismatch: Expected array but found object",
+            err.unwrap_err().to_string()
+        );
+        let path = parse_concrete("movie.cast[18446744073709551615]").unwrap();
+        let err = insert(&mut value, &path, 0, 1.into()).unwrap_err();
+        assert_eq!(ErrorCode::InvalidPath, err.code());
+        let path = parse_concrete("movie.cast[1026]").unwrap();
+        assert!(insert(&mut value, &path, 0, 1.into()).is_ok());
+        assert!(parse_concrete("movie.*.name").is_err());
+    }
+
+    #[test]
+    fn match_paths() {
+        let location = [Step::Key("items"), Step::Index(2), Step::Key("updated_at")];
+        assert!(matches(&parse("items[2].updated_at").unwrap(), &location));
+        assert!(matches(&parse("items.*.updated_at").unwrap(), &location));
+        assert!(matches(&parse("**.updated_at").unwrap(), &location));
+        assert!(matches(&parse("items.**").unwrap(), &location));
+        assert!(!matches(&parse("*.updated_at").unwrap(), &location));
+        assert!(!matches(&parse("items[1].updated_at").unwrap(), &location));
+    }
+}
@@ -0,0 +1,131 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Building of documents whose required keys are declared up front, so
+//! that incomplete payloads cannot be sent.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::validate::{ValidationError, Violation};
+use crate::Json;
+
+/// Builder of a document declaring its required keys, which refuses to
+/// build it until they are all set.
+///
+/// # Example
+/// ```
+/// use json_ez::{inline, PayloadBuilder};
+///
+/// let builder = PayloadBuilder::new(&["amount", "currency"]).set("amount", 42);
+/// assert_eq!(vec!["currency"], builder.missing());
+///
+/// let err = builder.clone().build().unwrap_err();
+/// assert_eq!("ValidationError: required at /: expected \"currency\" but found null", err.to_string());
+///
+/// let payment = builder.set("currency", "EUR").set("note", "tip").build().unwrap();
+/// assert_eq!(inline!("amount" => 42, "currency" => "EUR", "note" => "tip"), payment);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PayloadBuilder {
+    required: Vec<String>,
+    json: Json,
+}
+
+impl PayloadBuilder {
+    /// Create a builder requiring the given keys.
+    pub fn new(required: &[&str]) -> Self {
+        PayloadBuilder {
+            required: required.iter().map(|k| k.to_string()).collect(),
+            json: Json::new(),
+        }
+    }
+
+    /// Require another key.
+    pub fn require(mut self, k: &str) -> Self {
+        self.required.push(k.into());
+        self
+    }
+
+    /// Set the value of a key, required or not, like `Json::add`.
+    ///
+    /// # Panics
+    /// Panic if the value cannot be serialized, like `Json::add`
+    pub fn set<V: Serialize>(mut self, k: &str, v: V) -> Self {
+        self.json.add(k, v);
+        self
+    }
+
+    /// The required keys which are not set yet, in the order they were required.
+    pub fn missing(&self) -> Vec<&str> {
+        self.required
+            .iter()
+            .filter(|k| !self.json.json_data.contains_key(*k))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Build the document, once all the required keys are set (`null` counts).
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::ValidationError)` with a `required` violation
+    /// for each missing key
+    pub fn build(self) -> Result<Json, ValidationError> {
+        let missing: Vec<Violation> = self
+            .missing()
+            .into_iter()
+            .map(|k| Violation::new("", "required", k.into(), Value::Null))
+            .collect();
+        match missing.is_empty() {
+            true => Ok(self.json),
+            false => Err(ValidationError::new(missing)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn payload() {
+        let builder = PayloadBuilder::new(&["to", "subject"])
+            .require("body")
+            .set("subject", None::<String>);
+        assert_eq!(vec!["to", "body"], builder.missing());
+        let missing: Vec<Value> = builder
+            .clone()
+            .build()
+            .unwrap_err()
+            .violations()
+            .iter()
+            .map(|v| v.expected().clone())
+            .collect();
+        assert_eq!(vec![Value::from("to"), Value::from("body")], missing);
+
+        let mail = builder
+            .set("to", "ford@betelgeuse.org")
+            .set("body", "")
+            .build();
+        assert_eq!(3, mail.unwrap().json_data.len());
+    }
+}
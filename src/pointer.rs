@@ -0,0 +1,398 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! JSON Pointers (RFC 6901) addressing values of a document, relative
+//! ones included, and the `test` operation of JSON Patch (RFC 6902)
+//! checking the value at one.
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{self, ErrorCode};
+use crate::path::pointer_child;
+use crate::ser::{self, NonFinite};
+use crate::{InvalidPath, Json, NotFound};
+
+/// Custom error type used when the value at a location is not the expected one.
+#[derive(Debug)]
+pub struct ValueMismatch {
+    pointer: String,
+    expected: Value,
+    actual: Option<Value>,
+}
+
+impl ValueMismatch {
+    /// Create a new `ValueMismatch` error given the location of the value as
+    /// a JSON Pointer, the expected value and the actual one (`None` if missing)
+    pub fn new(pointer: &str, expected: Value, actual: Option<Value>) -> Self {
+        ValueMismatch {
+            pointer: pointer.into(),
+            expected,
+            actual,
+        }
+    }
+
+    /// Location of the value, as a JSON Pointer.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    /// The expected value.
+    pub fn expected(&self) -> &Value {
+        &self.expected
+    }
+
+    /// The actual value, `None` if missing.
+    pub fn actual(&self) -> Option<&Value> {
+        self.actual.as_ref()
+    }
+
+    /// Stable code of the error, `VALUE_MISMATCH`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::ValueMismatch
+    }
+
+    /// Represent the error as `Json`, with its code, message, pointer,
+    /// expected and actual values.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("pointer", self.pointer.clone().into()),
+            ("expected", self.expected.clone()),
+            ("actual", self.actual.clone().unwrap_or(Value::Null)),
+        ];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for ValueMismatch {}
+
+impl Display for ValueMismatch {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let actual = match &self.actual {
+            Some(actual) => actual.to_string(),
+            None => "nothing".into(),
+        };
+        f.write_str(&format!(
+            "ValueMismatch: Expected {} at {} but found {}",
+            self.expected, self.pointer, actual
+        ))
+    }
+}
+
+/// A Relative JSON Pointer, split in its number of levels up, its index
+/// manipulation and either the `#` of key lookups or a JSON Pointer.
+fn parse_relative(relative: &str) -> Result<(usize, i64, &str), InvalidPath> {
+    let invalid = |reason| InvalidPath::new(relative, reason);
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let end = digits(relative);
+    let up = &relative[..end];
+    if up.is_empty() || (up.len() > 1 && up.starts_with('0')) {
+        return Err(invalid(
+            "a relative JSON Pointer starts with a number of levels up",
+        ));
+    }
+    let up = up.parse().map_err(|_| invalid("too many levels up"))?;
+    let mut rest = &relative[end..];
+    let mut shift = 0;
+    if let Some(sign @ ('+' | '-')) = rest.chars().next() {
+        let end = digits(&rest[1..]) + 1;
+        let offset: i64 = match &rest[1..end] {
+            "" => return Err(invalid("an index manipulation is a number")),
+            n if n.len() > 1 && n.starts_with('0') => {
+                return Err(invalid("an index manipulation has no leading zeros"))
+            }
+            n => n
+                .parse()
+                .map_err(|_| invalid("index manipulation too large"))?,
+        };
+        shift = if sign == '-' { -offset } else { offset };
+        rest = &rest[end..];
+    }
+    if !(rest.is_empty() || rest == "#" || rest.starts_with('/')) {
+        return Err(invalid(
+            "a relative JSON Pointer ends with # or a JSON Pointer",
+        ));
+    }
+    Ok((up, shift, rest))
+}
+
+/// Whether two values are equal as defined by the `test` operation:
+/// numbers are compared by value, so `1` equals `1.0`.
+fn same(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l == r || l.as_f64() == r.as_f64(),
+        (Value::Array(l), Value::Array(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(l, r)| same(l, r))
+        }
+        (Value::Object(l), Value::Object(r)) => {
+            l.len() == r.len() && l.iter().all(|(k, l)| r.get(k).is_some_and(|r| same(l, r)))
+        }
+        (left, right) => left == right,
+    }
+}
+
+impl Json {
+    /// The value at the given JSON Pointer, the empty pointer
+    /// addressing the whole document.
+    pub(crate) fn at_pointer(&self, pointer: &str) -> Result<Option<Cow<'_, Value>>, InvalidPath> {
+        let rest = match pointer.strip_prefix('/') {
+            Some(rest) => rest,
+            None if pointer.is_empty() => {
+                let root = self.json_data.clone().into_iter().collect();
+                return Ok(Some(Cow::Owned(Value::Object(root))));
+            }
+            None => return Err(InvalidPath::new(pointer, "a JSON Pointer starts with /")),
+        };
+        if rest.contains('~') && rest.split('~').skip(1).any(|s| !s.starts_with(['0', '1'])) {
+            return Err(InvalidPath::new(pointer, "~ must be escaped as ~0"));
+        }
+        let (token, nested) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+        let key = token.replace("~1", "/").replace("~0", "~");
+        Ok(self
+            .json_data
+            .get(self.key(&key).as_ref())
+            .and_then(|value| value.pointer(nested))
+            .map(Cow::Borrowed))
+    }
+
+    /// Get the value at the given Relative JSON Pointer, such as `1/title`
+    /// or `0#`, evaluated from the value at the cursor, itself given as a
+    /// JSON Pointer, and deserialize it as a `T`. A pointer ending with `#`
+    /// gives the key of the value it reaches in its parent object, or its
+    /// index in its parent array.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::deserialise;
+    ///
+    /// fn main() -> Result<(), json_ez::Error> {
+    ///     let json = deserialise!(r#"{"cast": [{"name": "Mos Def"}, {"name": "Zooey"}], "title": "H2G2"}"#)?;
+    ///
+    ///     assert_eq!("H2G2", json.get_relative::<String>("/cast/1", "2/title")?);
+    ///     assert_eq!("Mos Def", json.get_relative::<String>("/cast/1", "0-1/name")?);
+    ///     assert_eq!(1, json.get_relative::<usize>("/cast/1/name", "1#")?);
+    ///     assert_eq!("cast", json.get_relative::<String>("/cast/1", "1#")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if a pointer cannot be
+    /// parsed, an `Err(json_ez::Error::NotFound)` if there's no value at the
+    /// cursor or at the pointer, which may go above the document or outside
+    /// of an array, or an `Err(json_ez::Error::Json)` if the value cannot be
+    /// deserialized as a `T`
+    pub fn get_relative<T: DeserializeOwned>(
+        &self,
+        cursor: &str,
+        relative: &str,
+    ) -> Result<T, crate::Error> {
+        let (up, shift, rest) = parse_relative(relative)?;
+        if self.at_pointer(cursor)?.is_none() {
+            return Err(NotFound::new(cursor.into(), self)?.into());
+        }
+        let not_found = || NotFound::new(format!("{} from {}", relative, cursor), self);
+        let mut tokens: Vec<String> = cursor
+            .split('/')
+            .skip(1)
+            .map(|t| t.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        if up > tokens.len() {
+            return Err(not_found()?.into());
+        }
+        tokens.truncate(tokens.len() - up);
+        let parent = match tokens.split_last() {
+            Some((_, parents)) => {
+                let pointer = parents
+                    .iter()
+                    .fold(String::new(), |p, t| pointer_child(&p, t));
+                self.at_pointer(&pointer)?
+            }
+            None => None,
+        };
+        let in_array = parent.as_ref().is_some_and(|parent| parent.is_array());
+        if shift != 0 {
+            let index = match (in_array, tokens.last().map(|t| t.parse::<i64>())) {
+                (true, Some(Ok(index))) => index.checked_add(shift),
+                _ => None,
+            };
+            let index = match index {
+                Some(index) if index >= 0 => index,
+                _ => return Err(not_found()?.into()),
+            };
+            if let Some(last) = tokens.last_mut() {
+                *last = index.to_string();
+            }
+        }
+        if rest == "#" {
+            let key = match (tokens.last(), in_array) {
+                (None, _) => return Err(not_found()?.into()),
+                (Some(index), true) => Value::from(index.parse::<u64>().unwrap_or_default()),
+                (Some(key), false) => Value::from(key.as_str()),
+            };
+            return Ok(T::deserialize(key)?);
+        }
+        let pointer = tokens
+            .iter()
+            .fold(String::new(), |p, t| pointer_child(&p, t))
+            + rest;
+        match self.at_pointer(&pointer)? {
+            Some(value) => Ok(T::deserialize(value.as_ref())?),
+            None => Err(not_found()?.into()),
+        }
+    }
+
+    /// Check that the value at the given JSON Pointer is equal to the expected
+    /// one, like the `test` operation of JSON Patch: numbers are compared by
+    /// value, so `1` equals `1.0`. Useful to check that a document didn't
+    /// change before updating it.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Error};
+    ///
+    /// let order = inline!("status" => "pending", "version" => 3);
+    ///
+    /// assert!(order.assert_value_at("/version", 3).is_ok());
+    /// match order.assert_value_at("/status", "paid") {
+    ///     Err(Error::ValueMismatch(err)) => assert_eq!(Some(&"pending".into()), err.actual()),
+    ///     other => panic!("unexpected {:?}", other),
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::ValueMismatch)` if the value is missing
+    /// or different, an `Err(json_ez::Error::InvalidPath)` if the pointer cannot
+    /// be parsed or an `Err(json_ez::Error::Json)` if the expected value cannot
+    /// be represented as JSON
+    pub fn assert_value_at<V: Serialize>(
+        &self,
+        pointer: &str,
+        expected: V,
+    ) -> Result<(), crate::Error> {
+        let expected = ser::to_value(&expected, NonFinite::Error, self.encoders.as_deref())?;
+        match self.at_pointer(pointer)? {
+            Some(actual) if same(&actual, &expected) => Ok(()),
+            actual => {
+                Err(ValueMismatch::new(pointer, expected, actual.map(Cow::into_owned)).into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{deserialise, ErrorCode};
+    use std::error::Error;
+
+    #[test]
+    fn pointers() -> Result<(), Box<dyn Error>> {
+        let json = deserialise!(r#"{ "a/b": { "c~d": [1, 2.5] }, "e": 1.0 }"#)?;
+        let value = |pointer: &str| json.at_pointer(pointer).unwrap().map(Cow::into_owned);
+        assert_eq!(Some(2.5.into()), value("/a~1b/c~0d/1"));
+        assert_eq!(None, value("/a~1b/c~0d/2"));
+        assert_eq!(None, value("/a"));
+        assert_eq!(Some(serde_json::to_value(&json)?), value(""));
+        assert!(json.at_pointer("a").is_err());
+        assert!(json.at_pointer("/a~2").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn relative_pointers() -> Result<(), Box<dyn Error>> {
+        // Examples of the Relative JSON Pointer specification
+        let json = deserialise!(
+            r#"{ "foo": ["bar", "baz"], "highly": { "nested": { "objects": true } } }"#
+        )?;
+        let from = |cursor: &str, relative: &str| json.get_relative::<Value>(cursor, relative);
+        assert_eq!(Value::from("baz"), from("/foo/1", "0")?);
+        assert_eq!(Value::from("bar"), from("/foo/1", "1/0")?);
+        assert_eq!(Value::from("bar"), from("/foo/1", "0-1")?);
+        assert_eq!(
+            Value::from(true),
+            from("/foo/1", "2/highly/nested/objects")?
+        );
+        assert_eq!(Value::from(1), from("/foo/1", "0#")?);
+        assert_eq!(Value::from(0), from("/foo/1", "0-1#")?);
+        assert_eq!(Value::from("foo"), from("/foo/1", "1#")?);
+        assert_eq!(Value::from(true), from("/highly/nested", "0/objects")?);
+        assert_eq!(
+            Value::from(true),
+            from("/highly/nested", "1/nested/objects")?
+        );
+        assert_eq!(Value::from("bar"), from("/highly/nested", "2/foo/0")?);
+        assert_eq!(Value::from("nested"), from("/highly/nested", "0#")?);
+        assert_eq!(Value::from("highly"), from("/highly/nested", "1#")?);
+
+        for missing in [
+            ("/foo/1", "3"),
+            ("/foo/1", "0+1"),
+            ("/foo/0", "0-1"),
+            ("/foo/1", "0+9223372036854775807"),
+            ("", "0#"),
+        ] {
+            let err = from(missing.0, missing.1).unwrap_err();
+            assert_eq!(ErrorCode::NotFound, err.code());
+        }
+        assert_eq!(ErrorCode::NotFound, from("/bar", "0").unwrap_err().code());
+        for invalid in ["", "01", "/foo", "0+", "0-01", "1foo", "0##"] {
+            let err = from("/foo/1", invalid).unwrap_err();
+            assert_eq!(ErrorCode::InvalidPath, err.code());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn assert_values() -> Result<(), Box<dyn Error>> {
+        let json = deserialise!(r#"{ "a": { "b": [1, { "c": 2 }] }, "e": 1.0 }"#)?;
+        json.assert_value_at("/e", 1)?;
+        json.assert_value_at("/a/b", serde_json::json!([1.0, { "c": 2 }]))?;
+        let err = json.assert_value_at("/a/b/1/c", 3).unwrap_err();
+        assert_eq!(
+            "ValueMismatch: Expected 3 at /a/b/1/c but found 2",
+            err.to_string()
+        );
+        let err = json.assert_value_at("/a/x", "x").unwrap_err();
+        assert_eq!(
+            "ValueMismatch: Expected \"x\" at /a/x but found nothing",
+            err.to_string()
+        );
+        assert_eq!(
+            ErrorCode::InvalidPath,
+            json.assert_value_at("a", 1).unwrap_err().code()
+        );
+        assert!(json
+            .assert_value_at("", serde_json::json!({ "e": 1 }))
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Rules enforced on the keys of a document, so that badly named keys are
+//! rejected as soon as they are added instead of reaching the payloads.
+
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::error::{self, ErrorCode};
+use crate::path::pointer_child;
+use crate::{Json, KeyCase};
+
+/// A rule every key of a document must follow, nested ones included.
+///
+/// # Example
+/// ```
+/// use json_ez::{inline, Json, KeyCase, KeyPolicy, NonFinite};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut order = Json::new();
+///     order.set_key_policy(KeyPolicy::case(KeyCase::Snake))?;
+///
+///     order.add_with("order_id", 42, NonFinite::Null)?;
+///     let err = order
+///         .add_with("items", vec![inline!("unitPrice" => 9.99)], NonFinite::Null)
+///         .unwrap_err();
+///
+///     assert_eq!(
+///         "InvalidKey: Key unitPrice at /items/0 is not snake case",
+///         err.to_string()
+///     );
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct KeyPolicy {
+    rule: String,
+    check: Arc<dyn Fn(&str) -> bool + Send + Sync + UnwindSafe + RefUnwindSafe>,
+}
+
+impl KeyPolicy {
+    /// Create a new policy accepting the keys for which the predicate
+    /// returns `true`, the rule describing them in error messages. The
+    /// predicate must be unwind safe, as the documents holding it are.
+    pub fn new<F>(rule: &str, check: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + UnwindSafe + RefUnwindSafe + 'static,
+    {
+        KeyPolicy {
+            rule: rule.into(),
+            check: Arc::new(check),
+        }
+    }
+
+    /// Reject empty keys and keys holding whitespace.
+    pub fn no_whitespace() -> Self {
+        KeyPolicy::new("non empty and without whitespace", |k| {
+            !k.is_empty() && !k.chars().any(char::is_whitespace)
+        })
+    }
+
+    /// Accept only the keys already written in the given case,
+    /// such as `order_id` for `KeyCase::Snake`.
+    pub fn case(case: KeyCase) -> Self {
+        let rule = match case {
+            KeyCase::Unchanged => "any",
+            KeyCase::Camel => "camel case",
+            KeyCase::Pascal => "pascal case",
+            KeyCase::Snake => "snake case",
+            KeyCase::ScreamingSnake => "screaming snake case",
+            KeyCase::Kebab => "kebab case",
+        };
+        KeyPolicy::new(rule, move |k| !k.is_empty() && case.apply(k) == k)
+    }
+
+    /// Accept only the keys matching the given regular expression.
+    #[cfg(feature = "regex")]
+    pub fn matching(regex: regex::Regex) -> Self {
+        KeyPolicy::new(&format!("matching {}", regex), move |k| regex.is_match(k))
+    }
+
+    /// Check the keys of the given value, nested in the document at `location`.
+    pub(crate) fn check(&self, location: &str, value: &Value) -> Result<(), InvalidKey> {
+        match value {
+            Value::Object(map) => map
+                .iter()
+                .try_for_each(|(k, v)| self.check_key(location, k, v)),
+            Value::Array(values) => values
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, v)| self.check(&pointer_child(location, &i.to_string()), v)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check a key and the keys of its value, the key being
+    /// a member of the object nested at `location`.
+    pub(crate) fn check_key(&self, location: &str, k: &str, v: &Value) -> Result<(), InvalidKey> {
+        if !(self.check)(k) {
+            return Err(InvalidKey::new(k, location, &self.rule));
+        }
+        self.check(&pointer_child(location, k), v)
+    }
+}
+
+impl Debug for KeyPolicy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("KeyPolicy")
+            .field("rule", &self.rule)
+            .finish()
+    }
+}
+
+/// Custom error type used when a key doesn't follow the key policy of a document.
+#[derive(Debug)]
+pub struct InvalidKey {
+    key: String,
+    location: String,
+    rule: String,
+}
+
+impl InvalidKey {
+    /// Create a new `InvalidKey` error given the rejected key, the JSON Pointer
+    /// of the object holding it and the rule it doesn't follow
+    pub fn new(key: &str, location: &str, rule: &str) -> Self {
+        InvalidKey {
+            key: key.into(),
+            location: location.into(),
+            rule: rule.into(),
+        }
+    }
+
+    /// Stable code of the error, `INVALID_KEY`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::InvalidKey
+    }
+
+    /// Represent the error as `Json`, with its code, message, key, location and rule.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("key", self.key.clone().into()),
+            ("location", self.location.clone().into()),
+            ("rule", self.rule.clone().into()),
+        ];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for InvalidKey {}
+
+impl Display for InvalidKey {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let location = if self.location.is_empty() {
+            "/"
+        } else {
+            &self.location
+        };
+        write!(
+            f,
+            "InvalidKey: Key {} at {} is not {}",
+            self.key, location, self.rule
+        )
+    }
+}
+
+impl Json {
+    /// Enforce the given policy on the keys of this document: the keys
+    /// added by `Json::add_with` and `Json::add` are checked, nested ones
+    /// included. Check a parsed document by setting its policy.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::InvalidKey)` if a key already in the document
+    /// doesn't follow the policy, in which case it is not set
+    pub fn set_key_policy(&mut self, policy: KeyPolicy) -> Result<(), InvalidKey> {
+        let mut keys: Vec<&String> = self.json_data.keys().collect();
+        keys.sort();
+        for k in keys {
+            policy.check_key("", k, &self.json_data[k])?;
+        }
+        self.key_policy = Some(policy);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{deserialise, inline, NonFinite};
+
+    #[test]
+    fn builtin_policies() {
+        let snake = KeyPolicy::case(KeyCase::Snake);
+        assert!((snake.check)("order_id"));
+        assert!(!(snake.check)("orderId"));
+        assert!(!(snake.check)(""));
+        let camel = KeyPolicy::case(KeyCase::Camel);
+        assert!((camel.check)("orderId"));
+        assert!(!(camel.check)("order id"));
+        let spaces = KeyPolicy::no_whitespace();
+        assert!((spaces.check)("order-id"));
+        assert!(!(spaces.check)("order id "));
+    }
+
+    #[test]
+    fn policy_enforced() -> Result<(), Box<dyn Error>> {
+        let mut json = deserialise!(r#"{ "tags": [{ "first name": "Arthur" }] }"#)?;
+        let err = json.set_key_policy(KeyPolicy::no_whitespace()).unwrap_err();
+        assert_eq!(
+            "InvalidKey: Key first name at /tags/0 is not non empty and without whitespace",
+            err.to_string()
+        );
+        assert_eq!("/tags/0", err.to_json().get::<String>("location")?);
+
+        let mut json = Json::new();
+        json.set_key_policy(KeyPolicy::new("lowercase", |k| k == k.to_lowercase()))?;
+        json.add("title", "Mostly Harmless");
+        let err = json.add_with("Read", true, NonFinite::Null).unwrap_err();
+        assert_eq!(ErrorCode::InvalidKey, err.code());
+        assert_eq!(inline!("title" => "Mostly Harmless"), json);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidKey: Key Read at / is not lowercase")]
+    fn add_panics() {
+        let mut json = Json::new();
+        json.set_key_policy(KeyPolicy::new("lowercase", |k| k == k.to_lowercase()))
+            .unwrap();
+        json.add("Read", true);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_policy() -> Result<(), Box<dyn Error>> {
+        let mut json = Json::new();
+        json.set_key_policy(KeyPolicy::matching(regex::Regex::new("^[a-z]+$")?))?;
+        json.add_with("read", true, NonFinite::Null)?;
+        let err = json.add_with("r3ad", true, NonFinite::Null).unwrap_err();
+        assert_eq!(
+            "InvalidKey: Key r3ad at / is not matching ^[a-z]+$",
+            err.to_string()
+        );
+        Ok(())
+    }
+}
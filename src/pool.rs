@@ -0,0 +1,134 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Reuse of documents in hot loops, keeping the memory allocated
+//! for their members instead of allocating it for each document.
+
+use std::mem;
+use std::sync::Mutex;
+
+use crate::Json;
+
+impl Json {
+    /// Remove all the members and annotations of the document, keeping
+    /// its settings and the memory allocated for its members.
+    pub fn clear(&mut self) {
+        self.watched(|json| json.json_data.clear());
+        self.annotations.clear();
+    }
+}
+
+/// Pool of empty documents, recycling the memory allocated for their
+/// members. It can be shared between threads.
+///
+/// # Example
+/// ```
+/// use json_ez::JsonPool;
+///
+/// let pool = JsonPool::new(16);
+/// for id in 0..1000 {
+///     let mut message = pool.get();
+///     message.add("id", id);
+///     // ... send the message ...
+///     pool.recycle(message);
+/// }
+/// assert_eq!(1, pool.idle());
+/// ```
+#[derive(Debug, Default)]
+pub struct JsonPool {
+    idle: Mutex<Vec<Json>>,
+    max_idle: usize,
+}
+
+impl JsonPool {
+    /// Create a pool keeping at most the given number of idle documents.
+    pub fn new(max_idle: usize) -> Self {
+        JsonPool {
+            idle: Mutex::new(Vec::new()),
+            max_idle,
+        }
+    }
+
+    /// An empty document with the default settings, like `Json::new`,
+    /// recycled if the pool holds one.
+    pub fn get(&self) -> Json {
+        self.lock().pop().unwrap_or_default()
+    }
+
+    /// Give a document back to the pool, which empties it and resets
+    /// its settings. It's dropped if the pool is full.
+    pub fn recycle(&self, mut json: Json) {
+        let mut idle = self.lock();
+        if idle.len() < self.max_idle {
+            let mut data = mem::take(&mut json.json_data);
+            data.clear();
+            idle.push(Json::from_data(data));
+        }
+    }
+
+    /// Number of idle documents held by the pool.
+    pub fn idle(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Json>> {
+        // Documents are only pushed and popped: a poisoned pool is still consistent
+        self.idle
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, KeyPolicy};
+
+    #[test]
+    fn clear() {
+        let mut json = inline!("a" => 1, "b" => 2);
+        json.annotate("a", "note").unwrap();
+        json.clear();
+        assert_eq!(Json::new(), json);
+        assert!(json.annotations().is_empty());
+    }
+
+    #[test]
+    fn pool() {
+        let pool = JsonPool::new(1);
+        let mut json = pool.get();
+        json.set_key_policy(KeyPolicy::no_whitespace()).unwrap();
+        for i in 0..100 {
+            json.add(&i.to_string(), i);
+        }
+        let capacity = json.json_data.capacity();
+        pool.recycle(json);
+        pool.recycle(Json::new());
+        assert_eq!(1, pool.idle());
+
+        let mut json = pool.get();
+        assert_eq!(Json::new(), json);
+        assert_eq!(capacity, json.json_data.capacity());
+        json.add("a b", 1);
+        assert_eq!(0, pool.idle());
+    }
+}
@@ -0,0 +1,106 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! `postgres-types` support, allowing `json_ez::Json` to be bound to and
+//! read from `json` and `jsonb` columns.
+
+use std::error::Error;
+use std::io::Read;
+
+use bytes::{BufMut, BytesMut};
+use postgres_types::{accepts, to_sql_checked, FromSql, IsNull, ToSql, Type};
+use serde_json::from_slice;
+
+use crate::Json;
+
+/// Only version 1 of the `jsonb` binary format exists so far.
+const JSONB_VERSION: u8 = 1;
+
+impl ToSql for Json {
+    /// The document is written as `Json::to_vec` does, following its
+    /// profile if any.
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        if *ty == Type::JSONB {
+            out.put_u8(JSONB_VERSION);
+        }
+        out.put_slice(&self.to_vec()?);
+        Ok(IsNull::No)
+    }
+
+    accepts!(JSON, JSONB);
+    to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for Json {
+    fn from_sql(ty: &Type, mut raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        if *ty == Type::JSONB {
+            let mut version = [0; 1];
+            raw.read_exact(&mut version)?;
+            if version[0] != JSONB_VERSION {
+                return Err(format!("Unsupported jsonb encoding version {}", version[0]).into());
+            }
+        }
+        Ok(from_slice(raw)?)
+    }
+
+    accepts!(JSON, JSONB);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, KeyCase, Profile};
+
+    #[test]
+    fn sql_round_trip() -> Result<(), Box<dyn Error + Sync + Send>> {
+        for ty in &[Type::JSON, Type::JSONB] {
+            let mut buffer = BytesMut::new();
+            let json = inline!("title" => "Mostly Harmless", "release_date" => 1992);
+            json.to_sql_checked(ty, &mut buffer)?;
+
+            let json = Json::from_sql(ty, &buffer)?;
+            assert_eq!(1992, json.get::<u16>("release_date").unwrap());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sql_profile() -> Result<(), Box<dyn Error + Sync + Send>> {
+        let mut json = inline!("release_date" => 1992);
+        json.set_profile(Profile::new().key_case(KeyCase::ScreamingSnake));
+        let mut buffer = BytesMut::new();
+        json.to_sql_checked(&Type::JSON, &mut buffer)?;
+        assert_eq!(&br#"{"RELEASE_DATE":1992}"#[..], &buffer[..]);
+        Ok(())
+    }
+
+    #[test]
+    fn sql_types() {
+        assert!(<Json as ToSql>::accepts(&Type::JSONB));
+        assert!(!<Json as ToSql>::accepts(&Type::TEXT));
+        assert!(Json::from_sql(&Type::JSONB, br#"{"a":1}"#).is_err());
+    }
+}
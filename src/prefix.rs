@@ -0,0 +1,100 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Namespacing of flat documents by key prefix, such as
+//! `aws.region` and `aws.profile` in configuration maps.
+
+use crate::Json;
+
+impl Json {
+    /// The members whose key starts with the given prefix, with the
+    /// prefix stripped from their keys. The document keeps the settings
+    /// of this one.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let config = inline!("aws.region" => "eu-west-3", "aws.profile" => "ops", "debug" => true);
+    ///
+    /// assert_eq!(
+    ///     inline!("region" => "eu-west-3", "profile" => "ops"),
+    ///     config.prefixed("aws.")
+    /// );
+    /// ```
+    pub fn prefixed(&self, prefix: &str) -> Json {
+        let mut json = self.settings();
+        json.json_data = self
+            .json_data
+            .iter()
+            .filter_map(|(k, v)| k.strip_prefix(prefix).map(|k| (k.to_string(), v.clone())))
+            .collect();
+        json
+    }
+
+    /// Merge another document into this one like `Json::merge`, after adding
+    /// the given prefix to its keys: the inverse of `Json::prefixed`.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let mut config = inline!("debug" => true);
+    /// config.merge_prefixed("aws.", inline!("region" => "eu-west-3"));
+    ///
+    /// assert_eq!(inline!("aws.region" => "eu-west-3", "debug" => true), config);
+    /// ```
+    ///
+    /// # Panics
+    /// Panic if a key doesn't follow the key policy of the
+    /// document or if the result would exceed its limits
+    pub fn merge_prefixed(&mut self, prefix: &str, other: Json) {
+        let data = other
+            .json_data
+            .into_iter()
+            .map(|(k, v)| (format!("{}{}", prefix, k), v))
+            .collect();
+        self.merge(Json::from_data(data));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn prefixes() {
+        let mut json = inline!("db.host" => "localhost", "db.port" => 5432, "dbname" => "app");
+        let db = json.prefixed("db.");
+        assert_eq!(inline!("host" => "localhost", "port" => 5432), db);
+        assert_eq!(json, json.prefixed(""));
+        assert_eq!(Json::new(), json.prefixed("cache."));
+
+        json.merge_prefixed("db.", inline!("port" => 5433, "user" => "admin"));
+        assert_eq!(
+            inline!("host" => "localhost", "port" => 5433, "user" => "admin"),
+            json.prefixed("db.")
+        );
+        assert_eq!("app", json.get::<String>("dbname").unwrap());
+    }
+}
@@ -0,0 +1,237 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Problem details documents (RFC 7807), the standard bodies of HTTP
+//! API errors.
+
+use std::convert::TryFrom;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::Json;
+
+/// Members defined by RFC 7807, the other ones being extensions.
+const MEMBERS: [&str; 5] = ["type", "title", "status", "detail", "instance"];
+
+/// Builder of a problem details document, created by `Json::problem`.
+///
+/// # Example
+/// ```
+/// use json_ez::{inline, Json};
+///
+/// let body: Json = Json::problem(403, "Insufficient credit")
+///     .problem_type("https://example.com/probs/out-of-credit")
+///     .detail("Your current balance is 30, but that costs 50.")
+///     .instance("/account/12345/msgs/abc")
+///     .extension("balance", 30)
+///     .build();
+///
+/// assert_eq!(
+///     inline!(
+///         "type" => "https://example.com/probs/out-of-credit",
+///         "title" => "Insufficient credit",
+///         "status" => 403,
+///         "detail" => "Your current balance is 30, but that costs 50.",
+///         "instance" => "/account/12345/msgs/abc",
+///         "balance" => 30
+///     ),
+///     body
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProblemBuilder {
+    json: Json,
+}
+
+impl ProblemBuilder {
+    /// Set the URI reference identifying the problem type, `about:blank`
+    /// if it's not set.
+    pub fn problem_type(mut self, uri: &str) -> Self {
+        self.json.add("type", uri);
+        self
+    }
+
+    /// Set the explanation specific to this occurrence of the problem.
+    pub fn detail(mut self, detail: &str) -> Self {
+        self.json.add("detail", detail);
+        self
+    }
+
+    /// Set the URI reference identifying this occurrence of the problem.
+    pub fn instance(mut self, uri: &str) -> Self {
+        self.json.add("instance", uri);
+        self
+    }
+
+    /// Add an extension member, replacing any member with the same key.
+    ///
+    /// # Panics
+    /// Panic if the value cannot be serialized, like `Json::add`
+    pub fn extension<V: Serialize>(mut self, k: &str, v: V) -> Self {
+        self.json.add(k, v);
+        self
+    }
+
+    /// The problem details document.
+    pub fn build(self) -> Json {
+        self.json
+    }
+}
+
+impl From<ProblemBuilder> for Json {
+    fn from(builder: ProblemBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Typed view of a document as problem details, returned by
+/// `Json::as_problem`. As required by RFC 7807, the members whose
+/// values don't have the expected type are ignored.
+///
+/// # Example
+/// ```
+/// use json_ez::{deserialise, Problem};
+///
+/// fn main() -> Result<(), json_ez::Error> {
+///     let body = deserialise!(r#"{"title": "Not Found", "status": 404, "trace_id": "4bf92f35"}"#)?;
+///     let problem = body.as_problem();
+///
+///     assert_eq!("about:blank", problem.problem_type());
+///     assert_eq!(Some(404), problem.status());
+///     assert_eq!(None, problem.detail());
+///     assert_eq!(Some(&"4bf92f35".into()), problem.extension("trace_id"));
+///     assert_eq!("application/problem+json", Problem::CONTENT_TYPE);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Problem<'a> {
+    json: &'a Json,
+}
+
+impl<'a> Problem<'a> {
+    /// Media type of problem details documents.
+    pub const CONTENT_TYPE: &'static str = "application/problem+json";
+
+    fn string(&self, k: &str) -> Option<&'a str> {
+        self.json.json_data.get(k).and_then(Value::as_str)
+    }
+
+    /// The URI reference identifying the problem type, `about:blank`
+    /// by default.
+    pub fn problem_type(&self) -> &'a str {
+        self.string("type").unwrap_or("about:blank")
+    }
+
+    /// The short summary of the problem type.
+    pub fn title(&self) -> Option<&'a str> {
+        self.string("title")
+    }
+
+    /// The HTTP status code.
+    pub fn status(&self) -> Option<u16> {
+        let status = self.json.json_data.get("status").and_then(Value::as_u64)?;
+        u16::try_from(status).ok()
+    }
+
+    /// The explanation specific to this occurrence of the problem.
+    pub fn detail(&self) -> Option<&'a str> {
+        self.string("detail")
+    }
+
+    /// The URI reference identifying this occurrence of the problem.
+    pub fn instance(&self) -> Option<&'a str> {
+        self.string("instance")
+    }
+
+    /// The value of an extension member.
+    pub fn extension(&self, k: &str) -> Option<&'a Value> {
+        match MEMBERS.contains(&k) {
+            true => None,
+            false => self.json.json_data.get(k),
+        }
+    }
+
+    /// The extension members, in arbitrary order.
+    pub fn extensions(&self) -> impl Iterator<Item = (&'a str, &'a Value)> {
+        self.json
+            .json_data
+            .iter()
+            .filter(|(k, _)| !MEMBERS.contains(&k.as_str()))
+            .map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+impl Json {
+    /// Start building a problem details document (RFC 7807) with the
+    /// given HTTP status code and short summary of the problem type.
+    pub fn problem(status: u16, title: &str) -> ProblemBuilder {
+        let mut json = Json::new();
+        json.add("title", title);
+        json.add("status", status);
+        ProblemBuilder { json }
+    }
+
+    /// View this document as problem details (RFC 7807), such as the
+    /// body of an error response.
+    pub fn as_problem(&self) -> Problem<'_> {
+        Problem { json: self }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn mistyped_members() {
+        let json = inline!(
+            "type" => 42,
+            "status" => 70000,
+            "title" => None::<String>,
+            "instance" => "/orders/42",
+            "retry_after" => 30
+        );
+        let problem = json.as_problem();
+        assert_eq!("about:blank", problem.problem_type());
+        assert_eq!(None, problem.status());
+        assert_eq!(None, problem.title());
+        assert_eq!(Some("/orders/42"), problem.instance());
+        assert_eq!(None, problem.extension("type"));
+        assert_eq!(
+            vec![("retry_after", &Value::from(30))],
+            problem.extensions().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let json: Json = Json::problem(500, "Internal Server Error").into();
+        let problem = json.as_problem();
+        assert_eq!(Some(500), problem.status());
+        assert_eq!(Some("Internal Server Error"), problem.title());
+        assert_eq!(0, problem.extensions().count());
+    }
+}
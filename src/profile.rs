@@ -0,0 +1,671 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Serialization profiles, adapting the output of a document to the
+//! conventions expected by its consumers.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use serde::ser::Error as _;
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter};
+use serde_json::{Error as SerdeError, Map, Value};
+
+use crate::datetime::DateTime;
+use crate::Json;
+
+/// Casing applied to the keys of the serialized objects.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum KeyCase {
+    /// Keep keys as they are
+    #[default]
+    Unchanged,
+    /// `releaseDate`
+    Camel,
+    /// `ReleaseDate`
+    Pascal,
+    /// `release_date`
+    Snake,
+    /// `RELEASE_DATE`
+    ScreamingSnake,
+    /// `release-date`
+    Kebab,
+}
+
+impl KeyCase {
+    pub(crate) fn apply(self, key: &str) -> String {
+        let words = words(key);
+        match self {
+            KeyCase::Unchanged => key.into(),
+            KeyCase::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+                .collect(),
+            KeyCase::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+            KeyCase::Snake => words.join("_"),
+            KeyCase::ScreamingSnake => words.join("_").to_uppercase(),
+            KeyCase::Kebab => words.join("-"),
+        }
+    }
+}
+
+/// Split a key in lowercase words, on separators and case changes:
+/// `HTTPServer_port` gives `http`, `server` and `port`.
+fn words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c == ' ' || c == '.' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_numeric()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Format applied to the RFC 3339 date-time strings of the serialized documents.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DateFormat {
+    /// Keep date-times as they are
+    #[default]
+    Unchanged,
+    /// RFC 3339 strings converted to UTC: `2005-04-28T18:15:00Z`
+    Rfc3339Utc,
+    /// Number of seconds since the Unix epoch
+    EpochSeconds,
+    /// Number of milliseconds since the Unix epoch
+    EpochMillis,
+}
+
+impl DateFormat {
+    fn apply(self, s: String) -> Value {
+        let date = match self {
+            DateFormat::Unchanged => None,
+            _ => DateTime::parse(&s),
+        };
+        date.and_then(|date| self.format(date))
+            .unwrap_or(Value::String(s))
+    }
+
+    /// The given instant in this format, `None` for `DateFormat::Unchanged`
+    /// or if it cannot be represented in this format.
+    pub(crate) fn format(self, date: DateTime) -> Option<Value> {
+        match self {
+            DateFormat::Unchanged => None,
+            DateFormat::Rfc3339Utc => Some(Value::String(date.to_rfc3339())),
+            DateFormat::EpochSeconds => Some(date.epoch_seconds().into()),
+            DateFormat::EpochMillis => date.epoch_millis().map(Value::from),
+        }
+    }
+}
+
+/// Conventions applied when serializing a document, either given to
+/// `Json::to_string_with` or attached to a document with `Json::set_profile`.
+///
+/// # Example
+/// ```
+/// use json_ez::{inline, DateFormat, KeyCase, Profile};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let json = inline!(
+///         "release_date" => "2005-04-28T20:15:00+02:00",
+///         "sequel_title" => None::<String>
+///     );
+///     let profile = Profile::new()
+///         .skip_nulls()
+///         .key_case(KeyCase::Camel)
+///         .date_format(DateFormat::EpochSeconds);
+///
+///     assert_eq!(r#"{"releaseDate":1114712100}"#, json.to_string_with(&profile)?);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Profile {
+    skip_nulls: bool,
+    key_case: KeyCase,
+    date_format: DateFormat,
+    float_precision: Option<usize>,
+    no_exponent: bool,
+    escape_html: bool,
+    ascii_only: bool,
+    width: Option<usize>,
+}
+
+impl Profile {
+    /// Create a profile keeping documents as they are.
+    pub fn new() -> Self {
+        Profile::default()
+    }
+
+    /// Omit the object members holding `null`, such as `None` values.
+    /// Array elements are always kept.
+    pub fn skip_nulls(mut self) -> Self {
+        self.skip_nulls = true;
+        self
+    }
+
+    /// Convert the keys of all objects to the given casing.
+    pub fn key_case(mut self, key_case: KeyCase) -> Self {
+        self.key_case = key_case;
+        self
+    }
+
+    /// Convert the RFC 3339 date-time strings to the given format.
+    pub fn date_format(mut self, date_format: DateFormat) -> Self {
+        self.date_format = date_format;
+        self
+    }
+
+    /// Write floats with exactly the given number of decimals, rounding
+    /// them if needed. Scientific notation is never used in that case.
+    pub fn float_precision(mut self, decimals: usize) -> Self {
+        self.float_precision = Some(decimals);
+        self
+    }
+
+    /// Write floats without scientific notation: `0.0000001`
+    /// instead of `1e-7`.
+    pub fn no_exponent(mut self) -> Self {
+        self.no_exponent = true;
+        self
+    }
+
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029 in strings, so that the
+    /// output can be embedded in HTML documents and JavaScript sources.
+    pub fn escape_html(mut self) -> Self {
+        self.escape_html = true;
+        self
+    }
+
+    /// Escape all non-ASCII characters in strings as `\uXXXX`,
+    /// using surrogate pairs outside of the Basic Multilingual Plane.
+    pub fn ascii_only(mut self) -> Self {
+        self.ascii_only = true;
+        self
+    }
+
+    /// Indent the output by two spaces, keeping the arrays and objects which
+    /// fit in the given number of columns on a single line, such as
+    /// `"tags": ["a", "b"]`. Short documents produce smaller diffs this way
+    /// than with one line per value.
+    pub fn pretty(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    fn escaped(&self, c: char) -> bool {
+        (self.ascii_only && !c.is_ascii())
+            || (self.escape_html && matches!(c, '<' | '>' | '&' | '\u{2028}' | '\u{2029}'))
+    }
+
+    /// Apply the profile to the given value, failing if several keys of an
+    /// object have the same name once cased.
+    fn apply(&self, value: Value) -> Result<Value, SerdeError> {
+        Ok(match value {
+            Value::Object(map) => {
+                let mut cased = Map::new();
+                let mut originals = HashMap::new();
+                for (k, v) in map {
+                    if self.skip_nulls && v.is_null() {
+                        continue;
+                    }
+                    let key = self.key_case.apply(&k);
+                    if let Some(other) = originals.insert(key.clone(), k.clone()) {
+                        return Err(SerdeError::custom(format!(
+                            "keys {:?} and {:?} both become {:?}",
+                            other, k, key
+                        )));
+                    }
+                    cased.insert(key, self.apply(v)?);
+                }
+                Value::Object(cased)
+            }
+            Value::Array(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|v| self.apply(v))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::String(s) => self.date_format.apply(s),
+            other => other,
+        })
+    }
+}
+
+/// Compact formatter writing numbers as required by a profile, optionally
+/// with a space after commas and colons (`[1, 2]`, `{"a": 1}`).
+struct ProfileFormatter<'a> {
+    profile: &'a Profile,
+    spaced: bool,
+}
+
+impl Formatter for ProfileFormatter<'_> {
+    fn begin_array_value<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        match (first, self.spaced) {
+            (true, _) => Ok(()),
+            (false, true) => writer.write_all(b", "),
+            (false, false) => writer.write_all(b","),
+        }
+    }
+
+    fn begin_object_key<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.begin_array_value(writer, first)
+    }
+
+    fn begin_object_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        match self.spaced {
+            true => writer.write_all(b": "),
+            false => writer.write_all(b":"),
+        }
+    }
+
+    fn write_f32<W: ?Sized + Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        self.write_f64(writer, value.into())
+    }
+
+    fn write_f64<W: ?Sized + Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        match self.profile.float_precision {
+            Some(decimals) => write!(writer, "{:.*}", decimals, value),
+            None if self.profile.no_exponent => {
+                let plain = value.to_string();
+                if plain.contains('.') {
+                    writer.write_all(plain.as_bytes())
+                } else {
+                    write!(writer, "{}.0", plain)
+                }
+            }
+            None => CompactFormatter.write_f64(writer, value),
+        }
+    }
+
+    fn write_string_fragment<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        fragment: &str,
+    ) -> io::Result<()> {
+        let mut start = 0;
+        for (i, c) in fragment.char_indices() {
+            if self.profile.escaped(c) {
+                writer.write_all(&fragment.as_bytes()[start..i])?;
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    write!(writer, "\\u{:04x}", unit)?;
+                }
+                start = i + c.len_utf8();
+            }
+        }
+        writer.write_all(&fragment.as_bytes()[start..])
+    }
+
+    /// Write the textual numbers of the `raw-number` feature, formatting
+    /// the floats only if the profile requires it.
+    fn write_number_str<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        value: &str,
+    ) -> io::Result<()> {
+        let reformat = self.profile.float_precision.is_some() || self.profile.no_exponent;
+        match value.parse::<f64>() {
+            Ok(float) if reformat && value.contains(['.', 'e', 'E']) => {
+                self.write_f64(writer, float)
+            }
+            _ => writer.write_all(value.as_bytes()),
+        }
+    }
+}
+
+fn write_compact<T: Serialize>(
+    value: &T,
+    profile: &Profile,
+    spaced: bool,
+) -> Result<String, SerdeError> {
+    let mut output = Vec::new();
+    let formatter = ProfileFormatter { profile, spaced };
+    let mut serializer = serde_json::Serializer::with_formatter(&mut output, formatter);
+    value.serialize(&mut serializer)?;
+    Ok(String::from_utf8(output).expect("JSON output is valid UTF-8"))
+}
+
+/// Output of a single line attempt, failing as soon as it holds more
+/// characters than allowed.
+struct Capped {
+    output: Vec<u8>,
+    chars: usize,
+    max: usize,
+    exceeded: bool,
+}
+
+impl Write for Capped {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Count the first byte of each UTF-8 sequence only
+        self.chars += buf.iter().filter(|b| (*b & 0xc0) != 0x80).count();
+        if self.chars > self.max {
+            self.exceeded = true;
+            return Err(io::Error::other("line too long"));
+        }
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Write a value on a single line like `write_compact`, unless it takes
+/// more than the given number of characters, without writing the rest.
+fn write_single_line(
+    value: &Value,
+    profile: &Profile,
+    max: usize,
+) -> Result<Option<String>, SerdeError> {
+    let mut capped = Capped {
+        output: Vec::new(),
+        chars: 0,
+        max,
+        exceeded: false,
+    };
+    let formatter = ProfileFormatter {
+        profile,
+        spaced: true,
+    };
+    let result = value.serialize(&mut serde_json::Serializer::with_formatter(
+        &mut capped,
+        formatter,
+    ));
+    match result {
+        Ok(()) => Ok(Some(
+            String::from_utf8(capped.output).expect("JSON output is valid UTF-8"),
+        )),
+        Err(_) if capped.exceeded => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Write a value indented at the given level, on a single line if it fits
+/// in the width from the given column, one line per nested value otherwise.
+fn write_pretty(
+    out: &mut String,
+    value: &Value,
+    profile: &Profile,
+    width: usize,
+    indent: usize,
+    column: usize,
+) -> Result<(), SerdeError> {
+    let is_empty = match value {
+        Value::Array(items) => items.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => true,
+    };
+    if is_empty {
+        out.push_str(&write_compact(value, profile, true)?);
+        return Ok(());
+    }
+    if let Some(single_line) = write_single_line(value, profile, width.saturating_sub(column))? {
+        out.push_str(&single_line);
+        return Ok(());
+    }
+    let padding = "  ".repeat(indent + 1);
+    let (open, close) = if value.is_array() {
+        ('[', ']')
+    } else {
+        ('{', '}')
+    };
+    out.push(open);
+    let mut first = true;
+    let mut line = |out: &mut String, key: Option<&String>, value: &Value| {
+        out.push_str(if first { "\n" } else { ",\n" });
+        first = false;
+        out.push_str(&padding);
+        let mut column = padding.len();
+        if let Some(key) = key {
+            let key = write_compact(key, profile, true)?;
+            column += key.chars().count() + 2;
+            out.push_str(&key);
+            out.push_str(": ");
+        }
+        // Leave room for the trailing comma
+        write_pretty(
+            out,
+            value,
+            profile,
+            width.saturating_sub(1),
+            indent + 1,
+            column,
+        )
+    };
+    match value {
+        Value::Array(items) => items.iter().try_for_each(|v| line(out, None, v))?,
+        Value::Object(map) => map.iter().try_for_each(|(k, v)| line(out, Some(k), v))?,
+        _ => unreachable!("scalars are written on a single line"),
+    }
+    out.push('\n');
+    out.push_str(&"  ".repeat(indent));
+    out.push(close);
+    Ok(())
+}
+
+impl Json {
+    /// Serialize the document following the conventions of the given profile.
+    ///
+    /// # Errors
+    /// Return an `Err(serde_json::error::Error)` if the document
+    /// cannot be serialized, or if several keys of an object have the same
+    /// name once cased by the profile, such as `user_id` and `userId` with
+    /// `KeyCase::Camel`
+    pub fn to_string_with(&self, profile: &Profile) -> Result<String, SerdeError> {
+        let map: Map<String, Value> = self
+            .json_data
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let value = profile.apply(Value::Object(map))?;
+        match profile.width {
+            Some(width) => {
+                let mut output = String::new();
+                write_pretty(&mut output, &value, profile, width, 0, 0)?;
+                Ok(output)
+            }
+            None => write_compact(&value, profile, false),
+        }
+    }
+
+    /// Attach a profile to this document, followed by `serialise!`.
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = Some(profile);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, serialise};
+    use std::error::Error;
+
+    #[test]
+    fn key_cases() {
+        let cases = vec![
+            (KeyCase::Camel, "httpServerPort2"),
+            (KeyCase::Pascal, "HttpServerPort2"),
+            (KeyCase::Snake, "http_server_port2"),
+            (KeyCase::ScreamingSnake, "HTTP_SERVER_PORT2"),
+            (KeyCase::Kebab, "http-server-port2"),
+            (KeyCase::Unchanged, "HTTPServer_port2"),
+        ];
+        for (case, expected) in cases {
+            assert_eq!(expected, case.apply("HTTPServer_port2"));
+        }
+        assert_eq!("release_date", KeyCase::Snake.apply("releaseDate"));
+        assert_eq!("releaseDate", KeyCase::Camel.apply("release-date"));
+    }
+
+    #[test]
+    fn nested_values() -> Result<(), Box<dyn Error>> {
+        let json = inline!(
+            "novels" => vec![inline!("read_at" => "2019-01-01T10:00:00.250Z", "rating" => None::<u8>)],
+            "sequel" => None::<String>
+        );
+        let profile = Profile::new()
+            .key_case(KeyCase::Pascal)
+            .date_format(DateFormat::EpochMillis);
+        assert_eq!(
+            r#"{"Novels":[{"Rating":null,"ReadAt":1546336800250}],"Sequel":null}"#,
+            json.to_string_with(&profile)?
+        );
+        let profile = Profile::new()
+            .skip_nulls()
+            .date_format(DateFormat::Rfc3339Utc);
+        assert_eq!(
+            r#"{"novels":[{"read_at":"2019-01-01T10:00:00.25Z"}]}"#,
+            json.to_string_with(&profile)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn float_formats() -> Result<(), Box<dyn Error>> {
+        let json = inline!("values" => vec![1e-7, 2.0, 4.26451, 1.5e22]);
+        assert_eq!(
+            r#"{"values":[1e-7,2.0,4.26451,1.5e+22]}"#,
+            json.to_string_with(&Profile::new())?
+        );
+        assert_eq!(
+            r#"{"values":[0.0000001,2.0,4.26451,15000000000000000000000.0]}"#,
+            json.to_string_with(&Profile::new().no_exponent())?
+        );
+        assert_eq!(
+            r#"{"values":[0.00,2.00,4.26,15000000000000000000000.00]}"#,
+            json.to_string_with(&Profile::new().float_precision(2))?
+        );
+        assert_eq!(
+            r#"{"values":[0,2,4,15000000000000000000000]}"#,
+            json.to_string_with(&Profile::new().float_precision(0))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pretty() -> Result<(), Box<dyn Error>> {
+        let json = crate::deserialise!(
+            r#"{"tags": ["a", "b"], "cast": [{"name": "Mos Def", "role": "Ford Prefect"}, {"name": "Zooey Deschanel"}], "empty": {}}"#
+        )?;
+        assert_eq!(
+            "{\n  \"cast\": [\n    {\"name\": \"Mos Def\", \"role\": \"Ford Prefect\"},\n    \
+             {\"name\": \"Zooey Deschanel\"}\n  ],\n  \"empty\": {},\n  \"tags\": [\"a\", \"b\"]\n}",
+            json.to_string_with(&Profile::new().pretty(50))?
+        );
+        assert_eq!(
+            "{\n  \"cast\": [\n    {\n      \"name\": \"Mos Def\",\n      \"role\": \"Ford Prefect\"\n    },\n    \
+             {\"name\": \"Zooey Deschanel\"}\n  ],\n  \"empty\": {},\n  \"tags\": [\"a\", \"b\"]\n}",
+            json.to_string_with(&Profile::new().pretty(40))?
+        );
+        let short = inline!("a" => 1);
+        assert_eq!(
+            r#"{"a": 1}"#,
+            short.to_string_with(&Profile::new().pretty(80))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn escapes() -> Result<(), Box<dyn Error>> {
+        let json = inline!("quote" => "<b>Don\u{2019}t Panic</b> & \u{1f4d6}\u{2028}\"");
+        assert_eq!(
+            "{\"quote\":\"<b>Don\u{2019}t Panic</b> & \u{1f4d6}\u{2028}\\\"\"}",
+            json.to_string_with(&Profile::new())?
+        );
+        assert_eq!(
+            "{\"quote\":\"\\u003cb\\u003eDon\u{2019}t Panic\\u003c/b\\u003e \\u0026 \u{1f4d6}\\u2028\\\"\"}",
+            json.to_string_with(&Profile::new().escape_html())?
+        );
+        assert_eq!(
+            r#"{"quote":"<b>Don\u2019t Panic</b> & \ud83d\udcd6\u2028\""}"#,
+            json.to_string_with(&Profile::new().ascii_only())?
+        );
+        let escaped = json.to_string_with(&Profile::new().escape_html().ascii_only())?;
+        assert!(escaped.is_ascii() && !escaped.contains('<'));
+        assert_eq!(json, crate::deserialise!(escaped)?);
+        Ok(())
+    }
+
+    #[test]
+    fn key_collisions() {
+        let json = inline!("user_id" => 1, "userId" => 2);
+        let err = json
+            .to_string_with(&Profile::new().key_case(KeyCase::Camel))
+            .unwrap_err();
+        assert!(err.to_string().contains("both become \"userId\""));
+        let nested = inline!("user" => json);
+        assert!(nested
+            .to_string_with(&Profile::new().key_case(KeyCase::Camel))
+            .is_err());
+    }
+
+    #[test]
+    fn attached_profile() -> Result<(), Box<dyn Error>> {
+        let mut json = inline!("title" => "Mostly Harmless", "sequel" => None::<String>);
+        json.set_profile(
+            Profile::new()
+                .skip_nulls()
+                .key_case(KeyCase::ScreamingSnake),
+        );
+        assert_eq!(r#"{"TITLE":"Mostly Harmless"}"#, serialise!(json)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Conversions between `json_ez::Json` and the `google.protobuf.Struct`
+//! well-known types provided by `prost-types`.
+//!
+//! Protocol Buffers only know about double precision numbers, so integral
+//! numbers coming from a `Struct` are turned back into JSON integers
+//! to keep `get::<u32>` and friends working.
+
+use std::convert::TryFrom;
+
+use prost_types::{value::Kind, ListValue, NullValue, Struct};
+use serde_json::{Map, Number, Value};
+
+use crate::{Json, TypeMismatch};
+
+/// Largest integer exactly representable by a `f64` (2^53).
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+impl From<Json> for Struct {
+    fn from(json: Json) -> Self {
+        Struct {
+            fields: json
+                .json_data
+                .into_iter()
+                .map(|(k, v)| (k, to_prost(v)))
+                .collect(),
+        }
+    }
+}
+
+impl From<Struct> for Json {
+    fn from(s: Struct) -> Self {
+        Json::from_data(
+            s.fields
+                .into_iter()
+                .map(|(k, v)| (k, from_prost(v)))
+                .collect(),
+        )
+    }
+}
+
+impl From<Json> for prost_types::Value {
+    fn from(json: Json) -> Self {
+        prost_types::Value {
+            kind: Some(Kind::StructValue(json.into())),
+        }
+    }
+}
+
+impl TryFrom<prost_types::Value> for Json {
+    type Error = TypeMismatch;
+
+    /// Only `StructValue` kinds can be converted into a `Json` document.
+    fn try_from(value: prost_types::Value) -> Result<Self, Self::Error> {
+        match value.kind {
+            Some(Kind::StructValue(s)) => Ok(s.into()),
+            kind => Err(TypeMismatch::new(
+                "object",
+                &from_prost(prost_types::Value { kind }),
+            )),
+        }
+    }
+}
+
+/// Convert a `serde_json::Value` into its `prost_types::Value` counterpart.
+pub(crate) fn to_prost(value: Value) -> prost_types::Value {
+    let kind = match value {
+        Value::Null => Kind::NullValue(NullValue::NullValue as i32),
+        Value::Bool(b) => Kind::BoolValue(b),
+        Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or(0.0)),
+        Value::String(s) => Kind::StringValue(s),
+        Value::Array(a) => Kind::ListValue(ListValue {
+            values: a.into_iter().map(to_prost).collect(),
+        }),
+        Value::Object(o) => Kind::StructValue(Struct {
+            fields: o.into_iter().map(|(k, v)| (k, to_prost(v))).collect(),
+        }),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+/// Convert a `prost_types::Value` into its `serde_json::Value` counterpart.
+///
+/// Non-finite numbers and values without kind are mapped to `null`.
+pub(crate) fn from_prost(value: prost_types::Value) -> Value {
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => Value::Null,
+        Some(Kind::BoolValue(b)) => Value::Bool(b),
+        Some(Kind::NumberValue(n)) => from_f64(n),
+        Some(Kind::StringValue(s)) => Value::String(s),
+        Some(Kind::ListValue(l)) => Value::Array(l.values.into_iter().map(from_prost).collect()),
+        Some(Kind::StructValue(s)) => Value::Object(
+            s.fields
+                .into_iter()
+                .map(|(k, v)| (k, from_prost(v)))
+                .collect::<Map<String, Value>>(),
+        ),
+    }
+}
+
+fn from_f64(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER {
+        Value::Number((n as i64).into())
+    } else {
+        Number::from_f64(n).map_or(Value::Null, Value::Number)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+    use std::error::Error;
+
+    #[test]
+    fn struct_round_trip() -> Result<(), Box<dyn Error>> {
+        let json = inline!(
+            "title" => "Mostly Harmless",
+            "release_date" => 1992,
+            "rating" => 4.5,
+            "read" => false,
+            "tags" => vec!["novel", "sci-fi"],
+            "movie" => inline!("title" => "The Hitchhiker's Guide to the Galaxy")
+        );
+        let s = Struct::from(json);
+        assert_eq!(
+            Some(Kind::NumberValue(1992.0)),
+            s.fields["release_date"].kind
+        );
+
+        let json = Json::from(s);
+        assert_eq!(1992, json.get::<u16>("release_date")?);
+        assert_eq!(4.5, json.get::<f64>("rating")?);
+        assert!(!json.get::<bool>("read")?);
+        assert_eq!(vec!["novel", "sci-fi"], json.get::<Vec<String>>("tags")?);
+        assert_eq!(
+            "The Hitchhiker's Guide to the Galaxy",
+            &json.get::<Json>("movie")?.get::<String>("title")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn value_conversion() -> Result<(), Box<dyn Error>> {
+        let value = prost_types::Value::from(inline!("answer" => 42));
+        let json = Json::try_from(value)?;
+        assert_eq!(42, json.get::<u8>("answer")?);
+
+        let not_a_struct = prost_types::Value {
+            kind: Some(Kind::StringValue("answer".into())),
+        };
+        let err = Json::try_from(not_a_struct).unwrap_err();
+        assert_eq!(
+            "TypeMismatch: Expected object but found string",
+            format!("{}", err)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Deep merges of layered documents reporting where each value comes
+//! from, such as configuration files overriding each other.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::path::{render, Segment};
+use crate::Json;
+
+/// Record the source of the values `incoming` will set once merged into
+/// `existing`, at the given location.
+fn trace(
+    existing: Option<&Value>,
+    incoming: &Value,
+    location: &mut Vec<Segment>,
+    source: &str,
+    sources: &mut BTreeMap<Vec<Segment>, String>,
+) {
+    if let (Some(Value::Object(existing)), Value::Object(incoming)) = (existing, incoming) {
+        // An empty object is not a leaf anymore once members are merged in
+        if !incoming.is_empty() {
+            sources.remove(location.as_slice());
+        }
+        for (k, v) in incoming {
+            location.push(Segment::Key(k.clone()));
+            trace(existing.get(k), v, location, source, sources);
+            location.pop();
+        }
+        return;
+    }
+    // The existing value is replaced as a whole, along with its sources
+    let replaced: Vec<Vec<Segment>> = sources
+        .range(location.clone()..)
+        .map(|(path, _)| path)
+        .take_while(|path| path.starts_with(location))
+        .cloned()
+        .collect();
+    for path in replaced {
+        sources.remove(&path);
+    }
+    record(incoming, location, source, sources);
+}
+
+/// Record the source of all the leaves of a value, empty objects included.
+fn record(
+    value: &Value,
+    location: &mut Vec<Segment>,
+    source: &str,
+    sources: &mut BTreeMap<Vec<Segment>, String>,
+) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (k, v) in map {
+                location.push(Segment::Key(k.clone()));
+                record(v, location, source, sources);
+                location.pop();
+            }
+        }
+        _ => {
+            sources.insert(location.clone(), source.into());
+        }
+    }
+}
+
+impl Json {
+    /// Deep merge the given layers in order, like `Json::merge`, and report
+    /// the name of the layer which supplied the value of each path of the
+    /// result. Nested objects are reported member by member, while arrays
+    /// and other values are reported as a whole.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    ///
+    /// let defaults = inline!("server" => inline!("host" => "0.0.0.0", "port" => 80));
+    /// let local = inline!("server" => inline!("port" => 8080), "debug" => true);
+    ///
+    /// let (config, sources) = Json::merge_layers(vec![("defaults.json", defaults), ("local.json", local)]);
+    ///
+    /// assert_eq!(8080, config.get::<Json>("server").unwrap().get::<u16>("port").unwrap());
+    /// assert_eq!(
+    ///     vec![
+    ///         ("debug", "local.json"),
+    ///         ("server.host", "defaults.json"),
+    ///         ("server.port", "local.json"),
+    ///     ],
+    ///     sources
+    ///         .iter()
+    ///         .map(|(path, source)| (path.as_str(), source.as_str()))
+    ///         .collect::<Vec<_>>()
+    /// );
+    /// ```
+    pub fn merge_layers<I, S>(layers: I) -> (Json, BTreeMap<String, String>)
+    where
+        I: IntoIterator<Item = (S, Json)>,
+        S: AsRef<str>,
+    {
+        let mut merged = Json::new();
+        let mut sources = BTreeMap::new();
+        for (source, layer) in layers {
+            for (k, v) in &layer.json_data {
+                let mut location = vec![Segment::Key(k.clone())];
+                trace(
+                    merged.json_data.get(k),
+                    v,
+                    &mut location,
+                    source.as_ref(),
+                    &mut sources,
+                );
+            }
+            merged.merge(layer);
+        }
+        let sources = sources
+            .into_iter()
+            .map(|(path, source)| (render(&path), source))
+            .collect();
+        (merged, sources)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deserialise;
+    use std::error::Error;
+
+    #[test]
+    fn sources() -> Result<(), Box<dyn Error>> {
+        let layers = vec![
+            (
+                "a",
+                deserialise!(r#"{ "x": { "y": 1, "z": { "w": 2 } }, "list": [1, 2], "k": 1 }"#)?,
+            ),
+            (
+                "b",
+                deserialise!(r#"{ "x": { "z": 3 }, "list": [3], "k": {} }"#)?,
+            ),
+            (
+                "c",
+                deserialise!(r#"{ "x": { "z": { "v": 4 } }, "k": { "e": { "f": 5 } } }"#)?,
+            ),
+        ];
+        let (merged, sources) = Json::merge_layers(layers);
+        assert_eq!(
+            deserialise!(
+                r#"{ "x": { "y": 1, "z": { "v": 4 } }, "list": [3], "k": { "e": { "f": 5 } } }"#
+            )?,
+            merged
+        );
+        let sources: Vec<(&str, &str)> = sources
+            .iter()
+            .map(|(p, s)| (p.as_str(), s.as_str()))
+            .collect();
+        assert_eq!(
+            vec![("k.e.f", "c"), ("list", "b"), ("x.y", "a"), ("x.z.v", "c")],
+            sources
+        );
+        Ok(())
+    }
+}
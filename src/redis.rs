@@ -0,0 +1,104 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! `redis` support, allowing `json_ez::Json` to be used as command
+//! argument (`SET`, `JSON.SET`...) and as reply type (`GET`, `JSON.GET`...).
+
+use ::redis::{FromRedisValue, ParsingError, RedisWrite, ToRedisArgs, Value};
+use serde_json::from_slice;
+
+use crate::{type_name, Json};
+
+impl ToRedisArgs for Json {
+    /// The document is written as `Json::to_vec` does, following its
+    /// profile if any.
+    ///
+    /// # Panics
+    /// Panic if the profile of the document makes several keys of an
+    /// object have the same name
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        out.write_arg(
+            &self
+                .to_vec()
+                .expect("a Json document is serializable unless its profile makes keys collide"),
+        );
+    }
+}
+
+impl FromRedisValue for Json {
+    /// Parse a string reply holding a JSON object.
+    /// Replies to RedisJSON commands using a JSONPath (`JSON.GET key $`)
+    /// hold a single object wrapped into an array, which is unwrapped.
+    fn from_redis_value(v: Value) -> Result<Self, ParsingError> {
+        let bytes = match v {
+            Value::BulkString(bytes) => bytes,
+            Value::SimpleString(s) | Value::VerbatimString { text: s, .. } => s.into_bytes(),
+            other => {
+                return Err(format!("Expected a JSON string reply, got {:?}", other).into());
+            }
+        };
+        let value: serde_json::Value =
+            from_slice(&bytes).map_err(|e| ParsingError::from(e.to_string()))?;
+        let value = match value {
+            serde_json::Value::Array(mut items) if items.len() == 1 => items.remove(0),
+            value => value,
+        };
+        match value {
+            serde_json::Value::Object(map) => Ok(Json::from_data(map.into_iter().collect())),
+            other => Err(format!("Expected a JSON object, got {}", type_name(&other)).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, KeyCase, Profile};
+
+    #[test]
+    fn redis_args() {
+        let args = inline!("answer" => 42).to_redis_args();
+        assert_eq!(vec![br#"{"answer":42}"#.to_vec()], args);
+
+        let mut json = inline!("answer" => 42);
+        json.set_profile(Profile::new().key_case(KeyCase::ScreamingSnake));
+        assert_eq!(vec![br#"{"ANSWER":42}"#.to_vec()], json.to_redis_args());
+    }
+
+    #[test]
+    fn redis_replies() {
+        let json = Json::from_redis_value(Value::BulkString(br#"{"answer":42}"#.to_vec())).unwrap();
+        assert_eq!(42, json.get::<u8>("answer").unwrap());
+
+        let json =
+            Json::from_redis_value(Value::BulkString(br#"[{"answer":42}]"#.to_vec())).unwrap();
+        assert_eq!(42, json.get::<u8>("answer").unwrap());
+
+        let missing: Option<Json> = Option::from_redis_value(Value::Nil).unwrap();
+        assert!(missing.is_none());
+        assert!(Json::from_redis_value(Value::Int(42)).is_err());
+        assert!(Json::from_redis_value(Value::BulkString(b"[1, 2]".to_vec())).is_err());
+    }
+}
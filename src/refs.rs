@@ -0,0 +1,417 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Resolution of the internal `$ref` references of a document to other
+//! parts of it, identified by JSON Pointers, `$anchor` or `$id`, as in
+//! JSON Schema and OpenAPI documents.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use serde_json::Value;
+
+use crate::error::{self, ErrorCode};
+use crate::path::pointer_child;
+use crate::Json;
+
+/// Custom error type used when a `$ref` cannot be resolved.
+#[derive(Debug)]
+pub struct UnresolvedRef {
+    reference: String,
+    reason: String,
+}
+
+impl UnresolvedRef {
+    /// Create a new `UnresolvedRef` error given the reference and the reason it cannot be resolved
+    pub fn new(reference: &str, reason: &str) -> Self {
+        UnresolvedRef {
+            reference: reference.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// The reference which cannot be resolved.
+    pub fn reference(&self) -> &str {
+        &self.reference
+    }
+
+    /// Stable code of the error, `UNRESOLVED_REF`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::UnresolvedRef
+    }
+
+    /// Represent the error as `Json`, with its code, message, reference and reason.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("reference", self.reference.clone().into()),
+            ("reason", self.reason.clone().into()),
+        ];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for UnresolvedRef {}
+
+impl Display for UnresolvedRef {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&format!(
+            "UnresolvedRef: Cannot resolve {:?}, {}",
+            self.reference, self.reason
+        ))
+    }
+}
+
+/// The reference held by a value, if it's an object with a string `$ref`.
+fn reference(value: &Value) -> Option<&str> {
+    value.get("$ref").and_then(Value::as_str)
+}
+
+/// Resolver of the references of a document, knowing the JSON Pointers of
+/// the values identified by an `$anchor` (as `#name`) or an `$id`.
+#[derive(Debug, Clone)]
+pub(crate) struct Resolver {
+    ids: HashMap<String, String>,
+    /// JSON Pointers of the values with an `$id`, the bases of the
+    /// fragment-only references they hold
+    resources: Vec<String>,
+}
+
+impl Resolver {
+    pub(crate) fn new(json: &Json) -> Self {
+        fn index(value: &Value, pointer: String, resolver: &mut Resolver) {
+            match value {
+                Value::Object(map) => {
+                    if let Some(id) = map.get("$id").and_then(Value::as_str) {
+                        resolver.ids.insert(id.into(), pointer.clone());
+                        resolver.resources.push(pointer.clone());
+                    }
+                    if let Some(anchor) = map.get("$anchor").and_then(Value::as_str) {
+                        resolver.ids.insert(format!("#{}", anchor), pointer.clone());
+                    }
+                    for (k, v) in map {
+                        index(v, pointer_child(&pointer, k), resolver);
+                    }
+                }
+                Value::Array(items) => {
+                    for (i, item) in items.iter().enumerate() {
+                        index(item, pointer_child(&pointer, &i.to_string()), resolver);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut resolver = Resolver {
+            ids: HashMap::new(),
+            resources: Vec::new(),
+        };
+        if let Some(id) = json.json_data.get("$id").and_then(Value::as_str) {
+            resolver.ids.insert(id.into(), String::new());
+        }
+        for (k, v) in &json.json_data {
+            index(v, pointer_child("", k), &mut resolver);
+        }
+        resolver
+    }
+
+    /// The JSON Pointer of the innermost value with an `$id` holding the
+    /// value at the given JSON Pointer, or of the root if there is none.
+    fn base(&self, pointer: &str) -> &str {
+        self.resources
+            .iter()
+            .filter(|resource| {
+                pointer
+                    .strip_prefix(resource.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|resource| resource.len())
+            .map_or("", String::as_str)
+    }
+
+    /// The JSON Pointer of the value a reference targets, fragment-only
+    /// references being resolved against the given base.
+    fn pointer(&self, reference: &str, base: &str) -> Result<String, UnresolvedRef> {
+        if let Some(pointer) = self.ids.get(reference) {
+            return Ok(pointer.clone());
+        }
+        let (id, fragment) = reference.split_at(reference.find('#').unwrap_or(reference.len()));
+        let base = match id {
+            "" => base,
+            id => self
+                .ids
+                .get(id)
+                .ok_or_else(|| UnresolvedRef::new(reference, "no value has this $id"))?,
+        };
+        match fragment.strip_prefix('#').unwrap_or(fragment) {
+            "" => Ok(base.into()),
+            pointer if pointer.starts_with('/') => Ok(format!("{}{}", base, pointer)),
+            _ => Err(UnresolvedRef::new(reference, "no value has this $anchor")),
+        }
+    }
+
+    /// The JSON Pointer and the value of the document a reference held by
+    /// the value at the given JSON Pointer targets, as it is.
+    pub(crate) fn target<'a>(
+        &self,
+        json: &'a Json,
+        reference: &str,
+        location: &str,
+    ) -> Result<(String, Cow<'a, Value>), UnresolvedRef> {
+        let pointer = self.pointer(reference, self.base(location))?;
+        match json.at_pointer(&pointer) {
+            Ok(Some(value)) => Ok((pointer, value)),
+            Ok(None) => Err(UnresolvedRef::new(reference, "no value at this location")),
+            Err(err) => Err(UnresolvedRef::new(reference, &err.to_string())),
+        }
+    }
+
+    /// Copy the value at the given JSON Pointer, replacing the references
+    /// it holds with their targets. `followed` holds the targets of the
+    /// references being replaced.
+    fn dereference(
+        &self,
+        json: &Json,
+        value: &Value,
+        location: &str,
+        followed: &mut Vec<String>,
+    ) -> Result<Value, UnresolvedRef> {
+        if let Some(reference) = reference(value) {
+            let (pointer, target) = self.target(json, reference, location)?;
+            if followed.contains(&pointer) {
+                return Err(UnresolvedRef::new(reference, "references are cyclic"));
+            }
+            followed.push(pointer.clone());
+            let mut resolved = self.dereference(json, &target, &pointer, followed)?;
+            followed.pop();
+            // The members next to the reference override the ones of its target
+            if let (Value::Object(map), Value::Object(resolved)) = (value, &mut resolved) {
+                for (k, v) in map.iter().filter(|(k, _)| *k != "$ref") {
+                    let v = self.dereference(json, v, &pointer_child(location, k), followed)?;
+                    resolved.insert(k.clone(), v);
+                }
+            }
+            return Ok(resolved);
+        }
+        Ok(match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| {
+                        let v = self.dereference(json, v, &pointer_child(location, k), followed)?;
+                        Ok((k.clone(), v))
+                    })
+                    .collect::<Result<_, UnresolvedRef>>()?,
+            ),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        let location = pointer_child(location, &i.to_string());
+                        self.dereference(json, item, &location, followed)
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            scalar => scalar.clone(),
+        })
+    }
+}
+
+impl Json {
+    /// The value targeted by a `$ref` of this document, such as
+    /// `#/components/schemas/Pet`, `#pet` for the value with this `$anchor`
+    /// or `pet.json` for the value with this `$id`, followed by a JSON Pointer
+    /// within it if any. A target which is itself a reference is followed,
+    /// and the references nested in the target are kept, to be resolved lazily.
+    /// As in JSON Schema, the fragment-only references held by a value with an
+    /// `$id` (`#/items`) are resolved against this value rather than the root.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::UnresolvedRef)` if no value is identified by
+    /// the reference or if the references followed are cyclic
+    pub fn resolve_ref(&self, reference: &str) -> Result<Cow<'_, Value>, UnresolvedRef> {
+        let resolver = Resolver::new(self);
+        let mut followed = Vec::new();
+        let mut current = reference.to_string();
+        let mut location = String::new();
+        loop {
+            let (pointer, target) = resolver.target(self, &current, &location)?;
+            if followed.contains(&pointer) {
+                return Err(UnresolvedRef::new(reference, "references are cyclic"));
+            }
+            followed.push(pointer.clone());
+            location = pointer;
+            match self::reference(&target) {
+                Some(next) => current = next.into(),
+                None => return Ok(target),
+            }
+        }
+    }
+
+    /// Copy the document, replacing each object holding a `$ref` with
+    /// the value it targets (see `Json::resolve_ref`), itself dereferenced.
+    /// The members next to a `$ref` override the ones of its target.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, inline};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let spec = deserialise!(r##"{
+    ///         "definitions": {"id": {"type": "integer", "$anchor": "id"}},
+    ///         "properties": {
+    ///             "user_id": {"$ref": "#/definitions/id"},
+    ///             "order_id": {"$ref": "#id", "description": "Order"}
+    ///         }
+    ///     }"##)?;
+    ///     let resolved = spec.dereference()?;
+    ///
+    ///     assert_eq!(
+    ///         deserialise!(r#"{"type": "integer", "$anchor": "id", "description": "Order"}"#)?,
+    ///         resolved.get_path("properties.order_id")?
+    ///     );
+    ///
+    ///     let tree = deserialise!(r##"{"node": {"properties": {"child": {"$ref": "#/node"}}}}"##)?;
+    ///     assert_eq!(
+    ///         "UnresolvedRef: Cannot resolve \"#/node\", references are cyclic",
+    ///         tree.dereference().unwrap_err().to_string()
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::UnresolvedRef)` if a reference cannot
+    /// be resolved or if the document refers to itself recursively, in which
+    /// case it cannot be copied without references, or an
+    /// `Err(json_ez::Error::LimitExceeded)` if the copy would exceed the
+    /// limits of the document
+    pub fn dereference(&self) -> Result<Json, crate::Error> {
+        let resolver = Resolver::new(self);
+        let mut json = self.settings();
+        for (k, v) in &self.json_data {
+            let mut followed = Vec::new();
+            let v = resolver.dereference(self, v, &pointer_child("", k), &mut followed)?;
+            json.store(k, v)?;
+        }
+        Ok(json)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{deserialise, Limits};
+
+    #[test]
+    fn resolve_refs() -> Result<(), Box<dyn std::error::Error>> {
+        let json = deserialise!(
+            r##"{
+                "a": {"$ref": "#/b"},
+                "b": {"$ref": "schema.json#/properties/n"},
+                "c": {"$id": "schema.json", "properties": {"n": {"$ref": "#/items"}}, "items": [1]},
+                "d": {"$ref": "#/d"},
+                "e": {"$ref": "#/missing"}
+            }"##
+        )?;
+        assert_eq!(Value::from(vec![1]), *json.resolve_ref("#/a")?);
+        assert_eq!(Value::from(1), *json.resolve_ref("schema.json#/items/0")?);
+        assert_eq!(
+            Some(&Value::from("schema.json")),
+            json.resolve_ref("schema.json")?.get("$id")
+        );
+        assert_eq!(
+            "UnresolvedRef: Cannot resolve \"#/d\", references are cyclic",
+            json.resolve_ref("#/d").unwrap_err().to_string()
+        );
+        assert_eq!(
+            ErrorCode::UnresolvedRef,
+            json.resolve_ref("#/e").unwrap_err().code()
+        );
+        assert!(json.resolve_ref("#nowhere").is_err());
+        assert!(json.resolve_ref("other.json").is_err());
+        assert!(json.dereference().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_against_ids() -> Result<(), Box<dyn std::error::Error>> {
+        let json = deserialise!(
+            r##"{
+                "items": "root",
+                "pet": {
+                    "$id": "pet.json",
+                    "properties": {"tags": {"$ref": "#/items"}, "self": {"$ref": "#"}},
+                    "items": {"type": "string"}
+                },
+                "owner": {"$ref": "pet.json#/properties/tags"}
+            }"##
+        )?;
+        assert_eq!(Value::from("root"), *json.resolve_ref("#/items")?);
+        assert_eq!(
+            Some(&Value::from("string")),
+            json.resolve_ref("#/owner")?.get("type")
+        );
+        // "#" held by the pet refers to the pet itself
+        assert!(json.dereference().is_err());
+        let json = deserialise!(
+            r##"{
+                "pet": {"$id": "pet.json", "properties": {"tags": {"$ref": "#/items"}}, "items": {"type": "string"}},
+                "owner": {"$ref": "pet.json#/properties/tags"}
+            }"##
+        )?;
+        let resolved = json.dereference()?;
+        assert_eq!(
+            "string",
+            resolved.get::<Value>("pet")?["properties"]["tags"]["type"]
+        );
+        assert_eq!("string", resolved.get::<Value>("owner")?["type"]);
+        Ok(())
+    }
+
+    #[test]
+    fn dereference() -> Result<(), Box<dyn std::error::Error>> {
+        let json = deserialise!(
+            r##"{
+                "pet": {"type": "object", "properties": {"tags": {"type": "array", "items": {"$ref": "#/tag"}}}},
+                "tag": {"type": "string"},
+                "pets": [{"$ref": "#/pet"}, {"$ref": "#/pet"}]
+            }"##
+        )?;
+        let resolved = json.dereference()?;
+        let pet = resolved.get::<Value>("pet")?;
+        assert_eq!(
+            Value::from("string"),
+            pet["properties"]["tags"]["items"]["type"]
+        );
+        assert_eq!(vec![pet.clone(), pet], resolved.get::<Vec<Value>>("pets")?);
+
+        let mut json = json;
+        json.set_limits(Limits::new().max_nodes(20))?;
+        assert!(matches!(
+            json.dereference(),
+            Err(crate::Error::LimitExceeded(_))
+        ));
+        Ok(())
+    }
+}
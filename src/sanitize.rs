@@ -0,0 +1,213 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Pipelines of cleanup steps applied to documents before they are
+//! logged, stored or sent.
+
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::path::{self, parse, Segment};
+use crate::{InvalidPath, Json, Schema};
+
+type CustomStep = dyn Fn(&mut Json) + Send + Sync;
+
+/// A single step of a sanitizer.
+#[derive(Clone)]
+enum Step {
+    Redact(Vec<Vec<Segment>>),
+    PruneNulls,
+    Truncate(usize),
+    DropUnknown(Arc<Schema>),
+    Custom(Arc<CustomStep>),
+}
+
+/// Pipeline of cleanup steps, applied in the order they were added to
+/// the documents given to `Sanitizer::apply`. It can be shared between
+/// threads and reused for any number of documents.
+///
+/// # Example
+/// ```
+/// use json_ez::{inline, Sanitizer};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let sanitizer = Sanitizer::new()
+///         .truncate(8)
+///         .redact(&["**.password", "token"])?
+///         .prune_nulls();
+///
+///     let mut request = inline!(
+///         "user" => inline!("name" => "Arthur", "password" => "42", "phone" => None::<String>),
+///         "token" => "abc",
+///         "comment" => "So long, and thanks for all the fish"
+///     );
+///     sanitizer.apply(&mut request);
+///
+///     assert_eq!(
+///         inline!(
+///             "user" => inline!("name" => "Arthur", "password" => "[REDACTED]"),
+///             "token" => "[REDACTED]",
+///             "comment" => "So long,"
+///         ),
+///         request
+///     );
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Sanitizer {
+    steps: Vec<Step>,
+}
+
+impl Sanitizer {
+    /// Create an empty pipeline, keeping documents as they are.
+    pub fn new() -> Self {
+        Sanitizer::default()
+    }
+
+    /// Replace the values matched by any of the given path patterns
+    /// with `"[REDACTED]"`. Use `**` to reach keys at any depth.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::InvalidPath)` if a pattern cannot be parsed
+    pub fn redact(mut self, patterns: &[&str]) -> Result<Self, InvalidPath> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| parse(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.steps.push(Step::Redact(patterns));
+        Ok(self)
+    }
+
+    /// Remove the object members holding `null`, at any depth.
+    /// Array elements are always kept.
+    pub fn prune_nulls(mut self) -> Self {
+        self.steps.push(Step::PruneNulls);
+        self
+    }
+
+    /// Cut the strings longer than the given number of characters, at any depth.
+    pub fn truncate(mut self, max_chars: usize) -> Self {
+        self.steps.push(Step::Truncate(max_chars));
+        self
+    }
+
+    /// Remove the properties not declared by the given schema, see `Json::conform`.
+    pub fn drop_unknown(mut self, schema: Schema) -> Self {
+        self.steps.push(Step::DropUnknown(Arc::new(schema)));
+        self
+    }
+
+    /// Apply a custom step.
+    pub fn step<F>(mut self, step: F) -> Self
+    where
+        F: Fn(&mut Json) + Send + Sync + 'static,
+    {
+        self.steps.push(Step::Custom(Arc::new(step)));
+        self
+    }
+
+    /// Apply the steps of the pipeline to the given document, in order.
+    pub fn apply(&self, json: &mut Json) {
+        for step in &self.steps {
+            match step {
+                Step::Redact(patterns) => json.watched(|json| {
+                    for (k, v) in json.json_data.iter_mut() {
+                        let mut location = vec![Segment::Key(k.clone())];
+                        path::for_each_match(v, &mut location, patterns, &mut |_, v| {
+                            *v = "[REDACTED]".into()
+                        });
+                    }
+                }),
+                Step::PruneNulls => json.watched(|json| {
+                    json.json_data.retain(|_, v| !v.is_null());
+                    json.json_data.values_mut().for_each(prune_nulls);
+                }),
+                Step::Truncate(max_chars) => json.watched(|json| {
+                    for v in json.json_data.values_mut() {
+                        truncate(v, *max_chars);
+                    }
+                }),
+                Step::DropUnknown(schema) => json.conform(schema),
+                Step::Custom(step) => step(json),
+            }
+        }
+    }
+}
+
+fn prune_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(prune_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(prune_nulls),
+        _ => {}
+    }
+}
+
+fn truncate(value: &mut Value, max_chars: usize) {
+    match value {
+        Value::String(s) => {
+            if let Some((end, _)) = s.char_indices().nth(max_chars) {
+                s.truncate(end);
+            }
+        }
+        Value::Object(map) => map.values_mut().for_each(|v| truncate(v, max_chars)),
+        Value::Array(items) => items.iter_mut().for_each(|v| truncate(v, max_chars)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{deserialise, inline};
+    use std::error::Error;
+
+    #[test]
+    fn pipeline() -> Result<(), Box<dyn Error>> {
+        let schema = Schema::new(deserialise!(
+            r#"{ "properties": { "items": {}, "note": {} } }"#
+        )?);
+        let sanitizer = Sanitizer::new()
+            .drop_unknown(schema)
+            .redact(&["items[*].secret"])?
+            .truncate(2)
+            .step(|json| json.add("sanitized", true));
+        let mut json = deserialise!(
+            r#"{ "items": [{ "secret": 1, "name": "été" }, null], "note": "ok", "debug": 1 }"#
+        )?;
+        sanitizer.clone().apply(&mut json);
+        let expected = deserialise!(
+            r#"{ "items": [{ "secret": "[R", "name": "ét" }, null], "note": "ok", "sanitized": true }"#
+        )?;
+        assert_eq!(expected, json);
+        assert!(Sanitizer::new().redact(&["a..b"]).is_err());
+        let mut json = inline!("a" => None::<u8>);
+        Sanitizer::new().prune_nulls().apply(&mut json);
+        assert_eq!(Json::new(), json);
+        Ok(())
+    }
+}
@@ -0,0 +1,524 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! JSON Schema documents, as consumed by the schema related helpers
+//! of this crate.
+//!
+//! Helpers follow the `properties`, `items` (or `prefixItems`) and
+//! `allOf` keywords to reach nested subschemas, other combinators being
+//! ambiguous.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use serde_json::{Number, Value};
+
+use crate::error::{self, ErrorCode};
+use crate::path::pointer_child;
+use crate::Json;
+
+/// A JSON Schema document.
+///
+/// # Example
+/// ```
+/// use json_ez::{deserialise, Schema};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let schema = Schema::new(deserialise!(r#"{
+///         "type": "object",
+///         "properties": { "title": { "type": "string" } },
+///         "required": ["title"]
+///     }"#)?);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Schema {
+    root: Value,
+}
+
+impl Schema {
+    /// Create a new `Schema` from the given `Json` document.
+    pub fn new(json: Json) -> Self {
+        Schema {
+            root: Value::Object(json.json_data.into_iter().collect()),
+        }
+    }
+
+    /// Borrow the schema as a `serde_json::Value`.
+    pub fn as_value(&self) -> &Value {
+        &self.root
+    }
+}
+
+/// Types allowed by the `type` keyword of a (sub)schema, if any.
+pub(crate) fn types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Subschema of the `i`-th element of an array, from `prefixItems`
+/// (or the array form of `items`) then `items`.
+pub(crate) fn item_schema(schema: &Value, i: usize) -> Option<&Value> {
+    let prefix = schema
+        .get("prefixItems")
+        .or_else(|| schema.get("items").filter(|items| items.is_array()))
+        .and_then(|prefix| prefix.get(i));
+    prefix.or_else(|| schema.get("items").filter(|items| items.is_object()))
+}
+
+/// Whether a value is an instance of the given JSON Schema type.
+pub(crate) fn has_type(value: &Value, t: &str) -> bool {
+    match (t, value) {
+        ("null", Value::Null)
+        | ("boolean", Value::Bool(_))
+        | ("number", Value::Number(_))
+        | ("string", Value::String(_))
+        | ("array", Value::Array(_))
+        | ("object", Value::Object(_)) => true,
+        ("integer", Value::Number(n)) => {
+            n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+/// Convert a value to the given JSON Schema type, if possible.
+pub(crate) fn coerce_value(value: &Value, t: &str) -> Option<Value> {
+    match (t, value) {
+        ("integer", Value::String(s)) => {
+            let s = s.trim();
+            s.parse::<i64>()
+                .map(Value::from)
+                .or_else(|_| s.parse::<u64>().map(Value::from))
+                .ok()
+        }
+        ("number", Value::String(s)) => {
+            let s = s.trim();
+            match s.parse::<i64>() {
+                Ok(i) => Some(i.into()),
+                Err(_) => s.parse().ok().and_then(Number::from_f64).map(Value::Number),
+            }
+        }
+        ("boolean", Value::String(s)) => match s.trim() {
+            "true" | "on" | "1" => Some(Value::Bool(true)),
+            "false" | "off" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("string", Value::Number(n)) => Some(Value::String(n.to_string())),
+        ("string", Value::Bool(b)) => Some(Value::String(b.to_string())),
+        ("null", Value::String(s)) if s.is_empty() || s == "null" => Some(Value::Null),
+        ("array", other) if !other.is_array() => Some(Value::Array(vec![other.clone()])),
+        _ => None,
+    }
+}
+
+/// Coerce `value` and its nested values to the types required by the schema,
+/// recording the locations of the ones which cannot be converted.
+fn coerce(value: &mut Value, schema: &Value, location: &str, failures: &mut Vec<String>) {
+    if let Some(branches) = schema.get("allOf").and_then(Value::as_array) {
+        for branch in branches {
+            coerce(value, branch, location, failures);
+        }
+    }
+    let allowed = types(schema);
+    if !allowed.is_empty() && !allowed.iter().any(|t| has_type(value, t)) {
+        match allowed.iter().find_map(|t| coerce_value(value, t)) {
+            Some(coerced) => *value = coerced,
+            None => failures.push(format!(
+                "{}: expected {} but found {}",
+                location,
+                allowed.join(" or "),
+                value
+            )),
+        }
+    }
+    match value {
+        Value::Object(map) => {
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (name, existing) in map.iter_mut() {
+                    if let Some(subschema) = properties.get(name) {
+                        coerce(
+                            existing,
+                            subschema,
+                            &pointer_child(location, name),
+                            failures,
+                        );
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                if let Some(subschema) = item_schema(schema, i) {
+                    coerce(
+                        item,
+                        subschema,
+                        &pointer_child(location, &i.to_string()),
+                        failures,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Insert the defaults of the absent properties of `value`, recursively.
+fn insert_defaults(value: &mut Value, schema: &Value) {
+    if let Some(branches) = schema.get("allOf").and_then(Value::as_array) {
+        for branch in branches {
+            insert_defaults(value, branch);
+        }
+    }
+    match value {
+        Value::Object(map) => {
+            let properties = match schema.get("properties").and_then(Value::as_object) {
+                Some(properties) => properties,
+                None => return,
+            };
+            for (name, subschema) in properties {
+                match (map.get_mut(name), subschema.get("default")) {
+                    (Some(existing), _) => insert_defaults(existing, subschema),
+                    (None, Some(default)) => {
+                        let mut default = default.clone();
+                        insert_defaults(&mut default, subschema);
+                        map.insert(name.clone(), default);
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                if let Some(subschema) = item_schema(schema, i) {
+                    insert_defaults(item, subschema);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Json {
+    /// Insert the `default` values of the absent properties described in
+    /// the given schema, recursively. Existing values are kept as they are,
+    /// even when `null`.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, inline, Json, Schema};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let schema = Schema::new(deserialise!(r#"{
+    ///         "properties": {
+    ///             "read": { "type": "boolean", "default": false },
+    ///             "title": { "type": "string", "default": "Untitled" }
+    ///         }
+    ///     }"#)?);
+    ///     let mut json = inline!("title" => "Mostly Harmless");
+    ///     json.apply_schema_defaults(&schema);
+    ///
+    ///     assert_eq!(inline!("title" => "Mostly Harmless", "read" => false), json);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn apply_schema_defaults(&mut self, schema: &Schema) {
+        self.watched(|json| {
+            let mut root = Value::Object(json.json_data.drain().collect());
+            insert_defaults(&mut root, schema.as_value());
+            if let Value::Object(map) = root {
+                json.json_data = map.into_iter().collect();
+            }
+        })
+    }
+
+    /// Convert the values whose type doesn't match the one required by the
+    /// given schema, such as form fields sent as strings: numeric strings
+    /// become numbers, `"true"`/`"false"` (or `"on"`/`"off"`, `"1"`/`"0"`)
+    /// booleans, numbers and booleans strings, empty strings `null`, and
+    /// single values are wrapped in arrays. Every value which can be
+    /// converted is, even if others cannot.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, inline, Json, Schema};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let schema = Schema::new(deserialise!(r#"{
+    ///         "properties": {
+    ///             "rating": { "type": "integer" },
+    ///             "read": { "type": "boolean" },
+    ///             "tags": { "type": "array", "items": { "type": "string" } }
+    ///         }
+    ///     }"#)?);
+    ///     let mut json = inline!("rating" => "5", "read" => "on", "tags" => "sci-fi");
+    ///     json.coerce_to_schema(&schema)?;
+    ///
+    ///     assert_eq!(inline!("rating" => 5, "read" => true, "tags" => vec!["sci-fi"]), json);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::CoercionError)` listing the
+    /// locations of the values which cannot be converted
+    pub fn coerce_to_schema(&mut self, schema: &Schema) -> Result<(), CoercionError> {
+        let mut failures = Vec::new();
+        self.watched(|json| {
+            let mut root = Value::Object(json.json_data.drain().collect());
+            coerce(&mut root, schema.as_value(), "", &mut failures);
+            if let Value::Object(map) = root {
+                json.json_data = map.into_iter().collect();
+            }
+        });
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(CoercionError { failures })
+        }
+    }
+}
+
+impl From<Json> for Schema {
+    fn from(json: Json) -> Self {
+        Schema::new(json)
+    }
+}
+
+/// Custom error type used when a schema cannot be interpreted.
+#[derive(Debug)]
+pub struct InvalidSchema {
+    location: String,
+    reason: String,
+}
+
+impl InvalidSchema {
+    /// Create a new `InvalidSchema` error given the location of the faulty
+    /// (sub)schema, as a JSON Pointer fragment, and the reason of the failure
+    pub fn new(location: &str, reason: &str) -> Self {
+        InvalidSchema {
+            location: location.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Stable code of the error, `INVALID_SCHEMA`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::InvalidSchema
+    }
+
+    /// Represent the error as `Json`, with its code, message, location and reason.
+    pub fn to_json(&self) -> Json {
+        let details = vec![
+            ("location", self.location.clone().into()),
+            ("reason", self.reason.clone().into()),
+        ];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for InvalidSchema {}
+
+impl Display for InvalidSchema {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&format!(
+            "InvalidSchema: {} at {}",
+            self.reason, self.location
+        ))
+    }
+}
+
+/// Custom error type used when values cannot be converted
+/// to the types required by a schema.
+#[derive(Debug)]
+pub struct CoercionError {
+    failures: Vec<String>,
+}
+
+impl CoercionError {
+    pub(crate) fn new(failures: Vec<String>) -> Self {
+        CoercionError { failures }
+    }
+
+    /// Describe each value which cannot be converted, located by its JSON Pointer.
+    pub fn failures(&self) -> &[String] {
+        &self.failures
+    }
+
+    /// Stable code of the error, `COERCION_FAILED`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::CoercionFailed
+    }
+
+    /// Represent the error as `Json`, with its code, message and failures.
+    pub fn to_json(&self) -> Json {
+        let details = vec![("failures", self.failures.clone().into())];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for CoercionError {}
+
+impl Display for CoercionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&format!(
+            "CoercionError: Cannot convert {}",
+            self.failures.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deserialise;
+    use serde_json::json;
+
+    #[test]
+    fn nested_defaults() -> Result<(), Box<dyn Error>> {
+        let schema = Schema::new(deserialise!(
+            r#"{
+            "allOf": [{ "properties": { "rating": { "default": 3 } } }],
+            "properties": {
+                "movie": {
+                    "default": {},
+                    "properties": { "release_date": { "default": 2005 } }
+                },
+                "cast": {
+                    "items": { "properties": { "role": { "default": "extra" } } }
+                },
+                "pair": {
+                    "prefixItems": [{ "properties": { "first": { "default": true } } }]
+                },
+                "sequel": { "default": "And Another Thing..." }
+            }
+        }"#
+        )?);
+        let mut json: Json = deserialise!(
+            r#"{
+            "cast": [{ "name": "Martin Freeman", "role": "Arthur" }, { "name": "Stephen Fry" }],
+            "pair": [{}, {}],
+            "sequel": null
+        }"#
+        )?;
+        json.apply_schema_defaults(&schema);
+        assert_eq!(
+            json!({
+                "rating": 3,
+                "movie": { "release_date": 2005 },
+                "cast": [
+                    { "name": "Martin Freeman", "role": "Arthur" },
+                    { "name": "Stephen Fry", "role": "extra" }
+                ],
+                "pair": [{ "first": true }, {}],
+                "sequel": null
+            }),
+            serde_json::to_value(&json)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn coercions() -> Result<(), Box<dyn Error>> {
+        let schema = Schema::new(deserialise!(
+            r#"{
+            "properties": {
+                "rating": { "type": "number" },
+                "count": { "type": "integer" },
+                "ratio": { "type": ["integer", "number"] },
+                "label": { "type": "string" },
+                "missing": { "type": ["string", "null"] },
+                "flags": { "type": "array", "items": { "type": "boolean" } },
+                "pair": { "prefixItems": [{ "type": "integer" }, { "type": "string" }] },
+                "nested": { "allOf": [{ "properties": { "n": { "type": "integer" } } }] }
+            }
+        }"#
+        )?);
+        let mut json: Json = deserialise!(
+            r#"{
+            "rating": " 4.5 ",
+            "count": 12.0,
+            "ratio": "0.25",
+            "label": 42,
+            "missing": "",
+            "flags": ["true", "0", false],
+            "pair": ["1", 2],
+            "nested": { "n": "-3" },
+            "extra": "5"
+        }"#
+        )?;
+        json.coerce_to_schema(&schema)?;
+        assert_eq!(
+            json!({
+                "rating": 4.5,
+                "count": 12.0,
+                "ratio": 0.25,
+                "label": "42",
+                "missing": "",
+                "flags": [true, false, false],
+                "pair": [1, "2"],
+                "nested": { "n": -3 },
+                "extra": "5"
+            }),
+            serde_json::to_value(&json)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn coercion_failures() -> Result<(), Box<dyn Error>> {
+        let schema = Schema::new(deserialise!(
+            r#"{
+            "properties": {
+                "a/b": { "type": "integer" },
+                "cast": { "items": { "properties": { "age": { "type": "integer" } } } },
+                "read": { "type": "boolean" }
+            }
+        }"#
+        )?);
+        let mut json: Json = deserialise!(
+            r#"{
+            "a/b": "five",
+            "cast": [{ "age": "42" }, { "age": 1.5 }],
+            "read": "yes"
+        }"#
+        )?;
+        let err = json.coerce_to_schema(&schema).unwrap_err();
+        assert_eq!(
+            vec![
+                "/a~1b: expected integer but found \"five\"",
+                "/cast/1/age: expected integer but found 1.5",
+                "/read: expected boolean but found \"yes\"",
+            ],
+            err.failures()
+        );
+        assert_eq!(42, json.get::<Value>("cast")?[0]["age"]);
+        Ok(())
+    }
+}
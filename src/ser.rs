@@ -0,0 +1,710 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Serialization of Rust values into `serde_json::Value`, mirroring
+//! `serde_json::to_value` while giving control over non-finite floats
+//! and over the encoding of specific types.
+
+use std::any::type_name;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::{Arc, RwLock};
+
+use serde::ser::{self, Error as _, Serialize};
+use serde_json::{value::Serializer as JsonSerializer, Error as SerdeError, Map, Number, Value};
+
+use crate::{Json, TypeMismatch};
+
+/// Prefix of the names of the structs used internally by `serde_json`
+/// to carry arbitrary precision numbers and raw values.
+const SERDE_JSON_PRIVATE: &str = "$serde_json::private::";
+
+type Result<T> = std::result::Result<T, SerdeError>;
+
+/// Policy applied to non-finite floats (`NaN`, `Infinity` and `-Infinity`),
+/// which have no JSON representation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NonFinite {
+    /// Replace them with `null`, as `serde_json` does
+    #[default]
+    Null,
+    /// Replace them with the `"NaN"`, `"Infinity"` and `"-Infinity"` strings
+    String,
+    /// Fail with an error
+    Error,
+}
+
+impl NonFinite {
+    pub(crate) fn apply(self, name: &str) -> Result<Value> {
+        match self {
+            NonFinite::Null => Ok(Value::Null),
+            NonFinite::String => Ok(Value::String(name.into())),
+            NonFinite::Error => Err(SerdeError::custom(format!(
+                "Cannot represent non-finite number {}",
+                name
+            ))),
+        }
+    }
+}
+
+/// JSON-ish name of a non-finite float.
+pub(crate) fn non_finite_name(f: f64) -> &'static str {
+    if f.is_nan() {
+        "NaN"
+    } else if f > 0.0 {
+        "Infinity"
+    } else {
+        "-Infinity"
+    }
+}
+
+type Encode = dyn Fn(Value) -> Value + Send + Sync + UnwindSafe + RefUnwindSafe;
+
+/// Encoders installed crate-wide by `Encoders::install`.
+static GLOBAL_ENCODERS: RwLock<Option<Arc<Encoders>>> = RwLock::new(None);
+
+/// A registry of custom encodings for specific Rust types, applied to the
+/// values added to a `Json` instance, even when nested in other values.
+///
+/// Each encoder receives the default JSON encoding of a value of its type
+/// and returns the one to use instead. References are encoded like the
+/// type they refer to. Encoders can be installed crate-wide or attached
+/// to a single document with `Json::set_encoders`, the latter taking
+/// precedence.
+///
+/// # Example
+/// ```
+/// use json_ez::{Encoders, Json};
+/// use serde_json::Value;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut json = Json::new();
+///     json.set_encoders(Encoders::new().register::<f64>(|v| match v.as_f64() {
+///         Some(f) => Value::from((f * 100.0).round() / 100.0),
+///         None => v,
+///     }));
+///
+///     json.add("ratings", vec![4.256, 3.5]);
+///     assert_eq!(vec![4.26, 3.5], json.get::<Vec<f64>>("ratings")?);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Encoders {
+    encoders: HashMap<&'static str, Arc<Encode>>,
+}
+
+impl Encoders {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Encoders::default()
+    }
+
+    /// Register the encoder of the values of type `T`,
+    /// replacing the previous one if any. The encoder must be
+    /// unwind safe, as the documents holding it are.
+    pub fn register<T: ?Sized>(
+        mut self,
+        encoder: impl Fn(Value) -> Value + Send + Sync + UnwindSafe + RefUnwindSafe + 'static,
+    ) -> Self {
+        self.encoders.insert(type_name::<T>(), Arc::new(encoder));
+        self
+    }
+
+    /// Install the registry crate-wide, replacing the previous one if any.
+    pub fn install(self) {
+        *GLOBAL_ENCODERS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(self));
+    }
+
+    /// Remove the registry installed crate-wide, if any.
+    pub fn uninstall() {
+        *GLOBAL_ENCODERS.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn installed() -> Option<Arc<Encoders>> {
+        GLOBAL_ENCODERS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn get<T: ?Sized>(&self) -> Option<&Encode> {
+        let mut name = type_name::<T>();
+        while let Some(referred) = name
+            .strip_prefix("&mut ")
+            .or_else(|| name.strip_prefix('&'))
+        {
+            name = referred;
+        }
+        self.encoders.get(name).map(|e| e.as_ref())
+    }
+}
+
+impl Debug for Encoders {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_set().entries(self.encoders.keys()).finish()
+    }
+}
+
+impl Json {
+    /// Create a new `Json` instance by serializing any value into it,
+    /// such as a struct or a map, applying the encoders installed crate-wide.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::Json;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Novel {
+    ///     title: &'static str,
+    ///     read: bool,
+    /// }
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let json = Json::from_serialize(&Novel { title: "Mostly Harmless", read: true })?;
+    ///
+    ///     assert_eq!("Mostly Harmless", &json.get::<String>("title")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::Json)` if the value cannot be
+    /// represented as JSON or an `Err(json_ez::Error::TypeMismatch)` if it is
+    /// not serialized as an object
+    pub fn from_serialize<T: ?Sized + Serialize>(
+        value: &T,
+    ) -> std::result::Result<Json, crate::Error> {
+        match to_value(value, NonFinite::Null, None)? {
+            Value::Object(map) => Ok(Json::from_data(map.into_iter().collect())),
+            other => Err(TypeMismatch::new("object", &other).into()),
+        }
+    }
+}
+
+/// Convert a `T` into a `serde_json::Value`, applying the given encoders
+/// then the ones installed crate-wide.
+pub(crate) fn to_value<T: ?Sized + Serialize>(
+    value: &T,
+    non_finite: NonFinite,
+    encoders: Option<&Encoders>,
+) -> Result<Value> {
+    let installed = Encoders::installed();
+    let layers: Vec<&Encoders> = encoders.into_iter().chain(installed.as_deref()).collect();
+    ValueSerializer {
+        non_finite,
+        encoders: &layers,
+    }
+    .nested(value)
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct ValueSerializer<'a> {
+    non_finite: NonFinite,
+    encoders: &'a [&'a Encoders],
+}
+
+impl ValueSerializer<'_> {
+    /// Serialize a value, then apply the encoder of its type if any.
+    fn nested<T: ?Sized + Serialize>(self, value: &T) -> Result<Value> {
+        let encoded = value.serialize(self)?;
+        match self.encoders.iter().find_map(|e| e.get::<T>()) {
+            Some(encode) => Ok(encode(encoded)),
+            None => Ok(encoded),
+        }
+    }
+
+    fn float(self, f: f64) -> Result<Value> {
+        match Number::from_f64(f) {
+            Some(n) => Ok(Value::Number(n)),
+            None => self.non_finite.apply(non_finite_name(f)),
+        }
+    }
+}
+
+/// Serialize a map key, which must be rendered as a string.
+fn key<T: ?Sized + Serialize>(key: &T) -> Result<String> {
+    match key.serialize(JsonSerializer)? {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(SerdeError::custom("key must be a string")),
+    }
+}
+
+impl<'a> ser::Serializer for ValueSerializer<'a> {
+    type Ok = Value;
+    type Error = SerdeError;
+    type SerializeSeq = SerializeVec<'a>;
+    type SerializeTuple = SerializeVec<'a>;
+    type SerializeTupleStruct = SerializeVec<'a>;
+    type SerializeTupleVariant = SerializeVariant<'a, Vec<Value>>;
+    type SerializeMap = SerializeMap<'a>;
+    type SerializeStruct = SerializeStruct<'a>;
+    type SerializeStructVariant = SerializeVariant<'a, Map<String, Value>>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(v.into())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value> {
+        JsonSerializer.serialize_i128(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        Ok(v.into())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value> {
+        JsonSerializer.serialize_u128(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        if v.is_finite() {
+            JsonSerializer.serialize_f32(v)
+        } else {
+            self.float(v.into())
+        }
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        self.float(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::String(v.into()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::Array(v.iter().map(|b| Value::from(*b)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value> {
+        self.nested(value)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::String(variant.into()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        self.nested(value)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        let mut map = Map::new();
+        map.insert(variant.into(), self.nested(value)?);
+        Ok(Value::Object(map))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec<'a>> {
+        Ok(SerializeVec {
+            ser: self,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeVec<'a>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeVec<'a>> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(SerializeVariant {
+            ser: self,
+            variant,
+            content: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap<'a>> {
+        Ok(SerializeMap {
+            ser: self,
+            map: Map::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<SerializeStruct<'a>> {
+        if name.starts_with(SERDE_JSON_PRIVATE) {
+            return JsonSerializer
+                .serialize_struct(name, len)
+                .map(SerializeStruct::Private);
+        }
+        self.serialize_map(Some(len)).map(SerializeStruct::Map)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(SerializeVariant {
+            ser: self,
+            variant,
+            content: Map::new(),
+        })
+    }
+}
+
+pub(crate) struct SerializeVec<'a> {
+    ser: ValueSerializer<'a>,
+    items: Vec<Value>,
+}
+
+impl ser::SerializeSeq for SerializeVec<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.items.push(self.ser.nested(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Array(self.items))
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Enum variants holding data, serialized as `{ "variant": content }`.
+pub(crate) struct SerializeVariant<'a, C> {
+    ser: ValueSerializer<'a>,
+    variant: &'static str,
+    content: C,
+}
+
+impl<C: Into<Value>> SerializeVariant<'_, C> {
+    fn finish(self) -> Value {
+        let mut map = Map::new();
+        map.insert(self.variant.into(), self.content.into());
+        Value::Object(map)
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<'_, Vec<Value>> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.content.push(self.ser.nested(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<'_, Map<String, Value>> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.content.insert(key.into(), self.ser.nested(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(self.finish())
+    }
+}
+
+pub(crate) struct SerializeMap<'a> {
+    ser: ValueSerializer<'a>,
+    map: Map<String, Value>,
+    next_key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeMap<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, k: &T) -> Result<()> {
+        self.next_key = Some(key(k)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .next_key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        self.map.insert(key, self.ser.nested(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Object(self.map))
+    }
+}
+
+/// Structs are serialized as maps, except for the internal ones of
+/// `serde_json` which are handed over to its own serializer.
+pub(crate) enum SerializeStruct<'a> {
+    Map(SerializeMap<'a>),
+    Private(<JsonSerializer as ser::Serializer>::SerializeStruct),
+}
+
+impl ser::SerializeStruct for SerializeStruct<'_> {
+    type Ok = Value;
+    type Error = SerdeError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        match self {
+            SerializeStruct::Map(map) => ser::SerializeMap::serialize_entry(map, key, value),
+            SerializeStruct::Private(s) => s.serialize_field(key, value),
+        }
+    }
+
+    fn end(self) -> Result<Value> {
+        match self {
+            SerializeStruct::Map(map) => ser::SerializeMap::end(map),
+            SerializeStruct::Private(s) => ser::SerializeStruct::end(s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Serialize;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rectangle(f64, f64),
+        Polygon { sides: u8 },
+    }
+
+    #[derive(Serialize)]
+    struct Drawing {
+        name: &'static str,
+        shapes: Vec<Shape>,
+        ids: BTreeMap<u8, Option<char>>,
+        bytes: &'static [u8],
+    }
+
+    #[test]
+    fn same_output_as_serde_json() {
+        let drawing = Drawing {
+            name: "towel",
+            shapes: vec![
+                Shape::Point,
+                Shape::Circle(1.5),
+                Shape::Rectangle(1.0, 2.0),
+                Shape::Polygon { sides: 6 },
+            ],
+            ids: vec![(1, Some('a')), (2, None)].into_iter().collect(),
+            bytes: &[4, 2],
+        };
+        assert_eq!(
+            serde_json::to_value(&drawing).unwrap(),
+            to_value(&drawing, NonFinite::Null, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn non_finite_policies() {
+        let values = vec![f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1.5];
+        assert_eq!(
+            json!([null, null, null, 1.5]),
+            to_value(&values, NonFinite::Null, None).unwrap()
+        );
+        assert_eq!(
+            json!(["NaN", "Infinity", "-Infinity", 1.5]),
+            to_value(&values, NonFinite::String, None).unwrap()
+        );
+        assert_eq!(
+            "Cannot represent non-finite number NaN",
+            to_value(&values, NonFinite::Error, None)
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(
+            json!("-Infinity"),
+            to_value(&f32::NEG_INFINITY, NonFinite::String, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn from_serialize() {
+        let drawing = Drawing {
+            name: "towel",
+            shapes: vec![Shape::Circle(1.5)],
+            ids: BTreeMap::new(),
+            bytes: &[],
+        };
+        let json = Json::from_serialize(&drawing).unwrap();
+        assert_eq!(
+            json!([{ "Circle": 1.5 }]),
+            json.get::<Value>("shapes").unwrap()
+        );
+        assert_eq!(
+            "TypeMismatch: Expected object but found array",
+            Json::from_serialize(&drawing.shapes)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn nested_encoders() {
+        #[derive(Serialize)]
+        struct Celsius(f64);
+
+        let readings = vec![(Some(Celsius(21.5)), &Celsius(-3.0))];
+        let fahrenheit = Encoders::new().register::<Celsius>(|v| match v.as_f64() {
+            Some(c) => json!(format!("{}F", c * 1.8 + 32.0)),
+            None => v,
+        });
+        assert_eq!(
+            json!([["70.7F", "26.6F"]]),
+            to_value(&readings, NonFinite::Null, Some(&fahrenheit)).unwrap()
+        );
+        let kelvin = Encoders::new().register::<Celsius>(|v| json!(v.as_f64().unwrap() + 273.0));
+        kelvin.install();
+        assert_eq!(
+            json!([["70.7F", "26.6F"]]),
+            to_value(&readings, NonFinite::Null, Some(&fahrenheit)).unwrap()
+        );
+        assert_eq!(
+            json!([[294.5, 270.0]]),
+            to_value(&readings, NonFinite::Null, None).unwrap()
+        );
+        Encoders::uninstall();
+        assert_eq!(
+            json!([[21.5, -3.0]]),
+            to_value(&readings, NonFinite::Null, None).unwrap()
+        );
+    }
+}
@@ -0,0 +1,307 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Structural types of documents, to group heterogeneous payloads by
+//! shape. Equal subtrees of a shape are shared rather than copied.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::fingerprint::fnv1a;
+use crate::path::{render, Segment};
+use crate::Json;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Node {
+    Null,
+    Boolean,
+    Number,
+    String,
+    /// The distinct shapes of the items, sorted by hash
+    Array(Vec<Shape>),
+    /// The shapes of the members, sorted by key
+    Object(Vec<(String, Shape)>),
+}
+
+/// Structural type of a value: the kinds of its values and the keys of
+/// its objects, recursively. Arrays are described by the distinct shapes
+/// of their items. Returned by `Json::shape`.
+#[derive(Debug, Clone)]
+pub struct Shape {
+    node: Arc<Node>,
+    hash: u64,
+}
+
+impl PartialEq for Shape {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.node, &other.node) || (self.hash == other.hash && self.node == other.node)
+    }
+}
+
+impl Eq for Shape {}
+
+impl Hash for Shape {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/// Shapes already built, shared by the subtrees having the same shape.
+#[derive(Default)]
+struct Interner {
+    shapes: HashSet<Shape>,
+}
+
+impl Interner {
+    fn intern(&mut self, node: Node) -> Shape {
+        let mut bytes = Vec::new();
+        match &node {
+            Node::Null => bytes.push(b'z'),
+            Node::Boolean => bytes.push(b'b'),
+            Node::Number => bytes.push(b'n'),
+            Node::String => bytes.push(b's'),
+            Node::Array(items) => {
+                bytes.push(b'a');
+                items
+                    .iter()
+                    .for_each(|s| bytes.extend(s.hash.to_le_bytes()));
+            }
+            Node::Object(members) => {
+                bytes.push(b'o');
+                for (k, s) in members {
+                    bytes.extend((k.len() as u64).to_le_bytes());
+                    bytes.extend(k.as_bytes());
+                    bytes.extend(s.hash.to_le_bytes());
+                }
+            }
+        }
+        let shape = Shape {
+            node: Arc::new(node),
+            hash: fnv1a(&bytes),
+        };
+        match self.shapes.get(&shape) {
+            Some(existing) => existing.clone(),
+            None => {
+                self.shapes.insert(shape.clone());
+                shape
+            }
+        }
+    }
+
+    fn shape(&mut self, value: &Value) -> Shape {
+        let node = match value {
+            Value::Null => Node::Null,
+            Value::Bool(_) => Node::Boolean,
+            Value::Number(_) => Node::Number,
+            Value::String(_) => Node::String,
+            Value::Array(items) => {
+                let mut shapes: Vec<Shape> = Vec::new();
+                for item in items {
+                    let shape = self.shape(item);
+                    if !shapes.contains(&shape) {
+                        shapes.push(shape);
+                    }
+                }
+                shapes.sort_by_key(|s| s.hash);
+                Node::Array(shapes)
+            }
+            Value::Object(map) => self.members(map.iter()),
+        };
+        self.intern(node)
+    }
+
+    fn members<'a>(&mut self, members: impl Iterator<Item = (&'a String, &'a Value)>) -> Node {
+        let mut members: Vec<(String, Shape)> =
+            members.map(|(k, v)| (k.clone(), self.shape(v))).collect();
+        members.sort_by(|a, b| a.0.cmp(&b.0));
+        Node::Object(members)
+    }
+}
+
+/// A difference between two shapes: the path of the value (`*` standing for
+/// the items of arrays), its shape before and its shape after, if any.
+pub type ShapeChange = (String, Option<Shape>, Option<Shape>);
+
+impl Shape {
+    /// Hash of the shape, as 16 hexadecimal digits, stable across processes
+    /// and versions of this crate.
+    pub fn fingerprint(&self) -> String {
+        format!("{:016x}", self.hash)
+    }
+
+    /// The differences between this shape and another one, sorted by path.
+    /// Nested objects are compared member by member, as well as the items
+    /// of arrays having a single shape of items on both sides.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let v1 = inline!("id" => 1, "user" => inline!("name" => "Arthur"));
+    /// let v2 = inline!("id" => "a1", "user" => inline!("name" => "Arthur", "email" => "arthur@example.com"));
+    ///
+    /// let changes: Vec<String> = v1
+    ///     .shape()
+    ///     .diff(&v2.shape())
+    ///     .into_iter()
+    ///     .map(|(path, before, after)| format!("{}: {:?} -> {:?}", path, before.map(|s| s.to_string()), after.map(|s| s.to_string())))
+    ///     .collect();
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "id: Some(\"number\") -> Some(\"string\")",
+    ///         "user.email: None -> Some(\"string\")",
+    ///     ],
+    ///     changes
+    /// );
+    /// ```
+    pub fn diff(&self, other: &Shape) -> Vec<ShapeChange> {
+        let mut changes = BTreeMap::new();
+        diff(Some(self), Some(other), &mut Vec::new(), &mut changes);
+        changes
+            .into_iter()
+            .map(|(path, (a, b))| (path, a, b))
+            .collect()
+    }
+}
+
+type Changes = BTreeMap<String, (Option<Shape>, Option<Shape>)>;
+
+fn diff(a: Option<&Shape>, b: Option<&Shape>, location: &mut Vec<Segment>, changes: &mut Changes) {
+    if a == b {
+        return;
+    }
+    let nodes = (a.map(|s| &*s.node), b.map(|s| &*s.node));
+    match nodes {
+        (Some(Node::Object(x)), Some(Node::Object(y))) => {
+            let keys: BTreeMap<&String, ()> = x.iter().chain(y).map(|(k, _)| (k, ())).collect();
+            let member = |members: &'_ [(String, Shape)], k: &String| {
+                members
+                    .iter()
+                    .find(|(key, _)| key == k)
+                    .map(|(_, s)| s.clone())
+            };
+            for k in keys.keys() {
+                location.push(Segment::Key((*k).clone()));
+                diff(
+                    member(x, k).as_ref(),
+                    member(y, k).as_ref(),
+                    location,
+                    changes,
+                );
+                location.pop();
+            }
+        }
+        (Some(Node::Array(x)), Some(Node::Array(y))) if x.len() == 1 && y.len() == 1 => {
+            location.push(Segment::Wildcard);
+            diff(x.first(), y.first(), location, changes);
+            location.pop();
+        }
+        _ => {
+            changes.insert(render(location), (a.cloned(), b.cloned()));
+        }
+    }
+}
+
+impl Display for Shape {
+    /// Write the shape in a JSON-like notation, such as
+    /// `{"tags": [string], "id": number}`, mixed arrays listing their
+    /// shapes of items separated by `|`.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &*self.node {
+            Node::Null => f.write_str("null"),
+            Node::Boolean => f.write_str("boolean"),
+            Node::Number => f.write_str("number"),
+            Node::String => f.write_str("string"),
+            Node::Array(items) => {
+                let items: Vec<String> = items.iter().map(|s| s.to_string()).collect();
+                write!(f, "[{}]", items.join(" | "))
+            }
+            Node::Object(members) => {
+                let members: Vec<String> = members
+                    .iter()
+                    .map(|(k, s)| format!("{}: {}", Value::from(k.as_str()), s))
+                    .collect();
+                write!(f, "{{{}}}", members.join(", "))
+            }
+        }
+    }
+}
+
+impl Json {
+    /// The structural type of the document, to compare or group documents
+    /// by shape rather than by content.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let click = inline!("type" => "click", "x" => 12, "tags" => vec!["a"]);
+    /// let other_click = inline!("type" => "click", "x" => 40, "tags" => Vec::<String>::new());
+    ///
+    /// assert_eq!(r#"{"tags": [string], "type": string, "x": number}"#, click.shape().to_string());
+    /// assert_ne!(click.shape(), other_click.shape());
+    /// assert_eq!(click.shape(), inline!("type" => "scroll", "x" => 0, "tags" => vec!["b", "c"]).shape());
+    /// ```
+    pub fn shape(&self) -> Shape {
+        let mut interner = Interner::default();
+        let node = interner.members(self.json_data.iter());
+        interner.intern(node)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::deserialise;
+    use std::error::Error;
+
+    #[test]
+    fn shapes() -> Result<(), Box<dyn Error>> {
+        let a =
+            deserialise!(r#"{ "items": [{ "id": 1 }, { "id": 2 }, "x"], "meta": { "page": 1 } }"#)?;
+        let b = deserialise!(r#"{ "meta": { "page": 2 }, "items": ["y", { "id": 3 }] }"#)?;
+        assert_eq!(a.shape(), b.shape());
+        assert_eq!(a.shape().fingerprint(), b.shape().fingerprint());
+        assert_eq!(
+            r#"{"items": [string | {"id": number}], "meta": {"page": number}}"#,
+            a.shape().to_string()
+        );
+
+        let c = deserialise!(r#"{ "items": [{ "id": "1" }], "meta": null }"#)?;
+        let d = deserialise!(r#"{ "items": [{ "id": 1 }], "meta": { "page": 1 } }"#)?;
+        let changes: Vec<String> = c
+            .shape()
+            .diff(&d.shape())
+            .into_iter()
+            .map(|(path, a, b)| format!("{} {} {}", path, a.unwrap(), b.unwrap()))
+            .collect();
+        assert_eq!(
+            vec!["items.*.id string number", r#"meta null {"page": number}"#],
+            changes
+        );
+        Ok(())
+    }
+}
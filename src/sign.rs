@@ -0,0 +1,322 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Detached signatures of documents, computed over their canonical
+//! serialization (`Json::to_canonical_string`), such as webhook signatures.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use ed25519_dalek::{Signer, Verifier};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::error::{self, ErrorCode};
+use crate::Json;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Algorithm of a signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    /// HMAC with SHA-256, with a shared secret
+    HmacSha256,
+    /// Ed25519, with a key pair
+    Ed25519,
+}
+
+/// Key used to sign documents.
+#[derive(Clone)]
+pub enum SigningKey {
+    /// Shared HMAC-SHA256 secret
+    HmacSha256(Vec<u8>),
+    /// Ed25519 private key
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl SigningKey {
+    /// Create an HMAC-SHA256 key from a shared secret.
+    pub fn hmac_sha256(secret: &[u8]) -> Self {
+        SigningKey::HmacSha256(secret.into())
+    }
+
+    /// Create an Ed25519 key from its 32 bytes secret.
+    pub fn ed25519(secret: &[u8; 32]) -> Self {
+        SigningKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(secret))
+    }
+
+    /// The key verifying the signatures made with this one.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        match self {
+            SigningKey::HmacSha256(secret) => VerifyingKey::HmacSha256(secret.clone()),
+            SigningKey::Ed25519(key) => VerifyingKey::Ed25519(key.verifying_key()),
+        }
+    }
+}
+
+/// Key used to verify the signatures of documents.
+#[derive(Clone)]
+pub enum VerifyingKey {
+    /// Shared HMAC-SHA256 secret
+    HmacSha256(Vec<u8>),
+    /// Ed25519 public key
+    Ed25519(ed25519_dalek::VerifyingKey),
+}
+
+impl VerifyingKey {
+    /// Create an HMAC-SHA256 key from a shared secret.
+    pub fn hmac_sha256(secret: &[u8]) -> Self {
+        VerifyingKey::HmacSha256(secret.into())
+    }
+
+    /// Create an Ed25519 key from its 32 bytes public key.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::InvalidSignature)` if the bytes
+    /// are not a valid public key
+    pub fn ed25519(public: &[u8; 32]) -> Result<Self, InvalidSignature> {
+        ed25519_dalek::VerifyingKey::from_bytes(public)
+            .map(VerifyingKey::Ed25519)
+            .map_err(|_| InvalidSignature::new("invalid Ed25519 public key"))
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        match self {
+            VerifyingKey::HmacSha256(_) => SignatureAlgorithm::HmacSha256,
+            VerifyingKey::Ed25519(_) => SignatureAlgorithm::Ed25519,
+        }
+    }
+}
+
+/// Signature of a document, transmitted apart from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetachedSignature {
+    algorithm: SignatureAlgorithm,
+    bytes: Vec<u8>,
+}
+
+impl DetachedSignature {
+    /// Create a signature from its algorithm and bytes,
+    /// such as the ones received along with a webhook.
+    pub fn new(algorithm: SignatureAlgorithm, bytes: &[u8]) -> Self {
+        DetachedSignature {
+            algorithm,
+            bytes: bytes.into(),
+        }
+    }
+
+    /// Create a signature from its lowercase or uppercase hexadecimal encoding.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::InvalidSignature)` if the string is not hexadecimal
+    pub fn from_hex(algorithm: SignatureAlgorithm, hex: &str) -> Result<Self, InvalidSignature> {
+        if !hex.len().is_multiple_of(2) {
+            return Err(InvalidSignature::new("odd number of hexadecimal digits"));
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(|| InvalidSignature::new("invalid hexadecimal digit"))
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        Ok(DetachedSignature::new(algorithm, &bytes))
+    }
+
+    /// Algorithm of the signature.
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        self.algorithm
+    }
+
+    /// Bytes of the signature.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Lowercase hexadecimal encoding of the signature.
+    pub fn to_hex(&self) -> String {
+        self.bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Custom error type used when a signature doesn't match a document.
+#[derive(Debug)]
+pub struct InvalidSignature {
+    reason: String,
+}
+
+impl InvalidSignature {
+    /// Create a new `InvalidSignature` error given the reason of the failure
+    pub fn new(reason: &str) -> Self {
+        InvalidSignature {
+            reason: reason.into(),
+        }
+    }
+
+    /// Stable code of the error, `INVALID_SIGNATURE`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::InvalidSignature
+    }
+
+    /// Represent the error as `Json`, with its code, message and reason.
+    pub fn to_json(&self) -> Json {
+        let details = vec![("reason", self.reason.clone().into())];
+        error::to_json(self.code(), self, details)
+    }
+}
+
+impl Error for InvalidSignature {}
+
+impl Display for InvalidSignature {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "InvalidSignature: {}", self.reason)
+    }
+}
+
+impl Json {
+    /// Sign the canonical serialization of this document.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, SigningKey};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let key = SigningKey::ed25519(&[7; 32]);
+    ///     let event = inline!("type" => "order.paid", "amount" => 4200);
+    ///
+    ///     let signature = event.sign(&key);
+    ///
+    ///     assert!(event.verify(&key.verifying_key(), &signature).is_ok());
+    ///     let tampered = inline!("type" => "order.paid", "amount" => 1);
+    ///     assert!(tampered.verify(&key.verifying_key(), &signature).is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn sign(&self, key: &SigningKey) -> DetachedSignature {
+        let message = self.to_canonical_string();
+        match key {
+            SigningKey::HmacSha256(secret) => {
+                let mut mac = hmac(secret);
+                mac.update(message.as_bytes());
+                let bytes = mac.finalize().into_bytes();
+                DetachedSignature::new(SignatureAlgorithm::HmacSha256, &bytes)
+            }
+            SigningKey::Ed25519(key) => {
+                let signature = key.sign(message.as_bytes());
+                DetachedSignature::new(SignatureAlgorithm::Ed25519, &signature.to_bytes())
+            }
+        }
+    }
+
+    /// Verify a signature of the canonical serialization of this document.
+    /// HMAC signatures are compared in constant time.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::InvalidSignature)` if the signature
+    /// doesn't match the document or the key
+    pub fn verify(
+        &self,
+        key: &VerifyingKey,
+        signature: &DetachedSignature,
+    ) -> Result<(), InvalidSignature> {
+        if key.algorithm() != signature.algorithm {
+            return Err(InvalidSignature::new(
+                "signature algorithm doesn't match the key",
+            ));
+        }
+        let message = self.to_canonical_string();
+        let mismatch = || InvalidSignature::new("signature doesn't match the document");
+        match key {
+            VerifyingKey::HmacSha256(secret) => {
+                let mut mac = hmac(secret);
+                mac.update(message.as_bytes());
+                mac.verify_slice(&signature.bytes).map_err(|_| mismatch())
+            }
+            VerifyingKey::Ed25519(key) => {
+                let signature = ed25519_dalek::Signature::from_slice(&signature.bytes)
+                    .map_err(|_| mismatch())?;
+                key.verify(message.as_bytes(), &signature)
+                    .map_err(|_| mismatch())
+            }
+        }
+    }
+}
+
+fn hmac(secret: &[u8]) -> HmacSha256 {
+    HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{deserialise, inline};
+
+    #[test]
+    fn hmac_signatures() -> Result<(), Box<dyn Error>> {
+        let key = SigningKey::hmac_sha256(b"whsec_secret");
+        let event =
+            deserialise!(r#"{ "id": "evt_1", "data": { "amount": 42.0, "currency": "EUR" } }"#)?;
+        let signature = event.sign(&key);
+        assert_eq!(SignatureAlgorithm::HmacSha256, signature.algorithm());
+        assert_eq!(32, signature.as_bytes().len());
+
+        // Key order and number formatting don't matter
+        let received = deserialise!(r#"{"data":{"currency":"EUR","amount":42},"id":"evt_1"}"#)?;
+        let header =
+            DetachedSignature::from_hex(SignatureAlgorithm::HmacSha256, &signature.to_hex())?;
+        received.verify(&VerifyingKey::hmac_sha256(b"whsec_secret"), &header)?;
+
+        let err = received
+            .verify(&VerifyingKey::hmac_sha256(b"other"), &header)
+            .unwrap_err();
+        assert_eq!(
+            "InvalidSignature: signature doesn't match the document",
+            err.to_string()
+        );
+        assert!(DetachedSignature::from_hex(SignatureAlgorithm::HmacSha256, "0g").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn ed25519_signatures() -> Result<(), Box<dyn Error>> {
+        let key = SigningKey::ed25519(&[1; 32]);
+        let public = match key.verifying_key() {
+            VerifyingKey::Ed25519(public) => public.to_bytes(),
+            _ => unreachable!(),
+        };
+        let json = inline!("title" => "Mostly Harmless");
+        let signature = json.sign(&key);
+        assert_eq!(64, signature.as_bytes().len());
+        json.verify(&VerifyingKey::ed25519(&public)?, &signature)?;
+
+        let err = json
+            .verify(&VerifyingKey::hmac_sha256(b"secret"), &signature)
+            .unwrap_err();
+        assert_eq!(ErrorCode::InvalidSignature, err.code());
+        assert!(inline!("title" => "And Another Thing...")
+            .verify(&key.verifying_key(), &signature)
+            .is_err());
+        Ok(())
+    }
+}
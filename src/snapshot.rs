@@ -0,0 +1,89 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Iteration over the members of a document as they were when the
+//! iteration started, leaving the document free to be changed meanwhile.
+
+use std::vec;
+
+use serde_json::Value;
+
+use crate::Json;
+
+/// Iterator over a copy of the members of a document, sorted by key,
+/// returned by `Json::snapshot_iter`.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    members: vec::IntoIter<(String, Value)>,
+}
+
+impl Iterator for Snapshot {
+    type Item = (String, Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.members.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.members.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Snapshot {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.members.next_back()
+    }
+}
+
+impl ExactSizeIterator for Snapshot {}
+
+impl Json {
+    /// Iterate over the members of the document, sorted by key, as they
+    /// were when this method was called. The document can be changed while
+    /// iterating since the iterator doesn't borrow it. Only the members
+    /// are copied, in a single allocation, not the settings, annotations
+    /// or watchers of the document.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let mut prices = inline!("apple" => 2, "pear" => 3);
+    /// for (fruit, price) in prices.snapshot_iter() {
+    ///     prices.add(&format!("{}_discounted", fruit), price.as_i64().unwrap() - 1);
+    /// }
+    ///
+    /// assert_eq!(4, prices.snapshot_iter().len());
+    /// assert_eq!(2, prices.get::<i64>("pear_discounted").unwrap());
+    /// ```
+    pub fn snapshot_iter(&self) -> Snapshot {
+        let mut members: Vec<(String, Value)> = self
+            .json_data
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        members.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        Snapshot {
+            members: members.into_iter(),
+        }
+    }
+}
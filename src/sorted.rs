@@ -0,0 +1,292 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Iteration over the members of a document in a deterministic order,
+//! for reports and generated configuration files.
+
+use std::cmp::Ordering;
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::{Error as SerdeError, Value};
+
+use crate::Json;
+
+/// Ordering of keys for humans, given to `Json::to_string_sorted_by`:
+/// the keys listed first in their given order, then the other ones in
+/// natural order, comparing the digits of keys as numbers.
+///
+/// # Example
+/// ```
+/// use json_ez::KeyOrder;
+/// use std::cmp::Ordering;
+///
+/// let order = KeyOrder::natural().first(&["id", "name"]);
+///
+/// assert_eq!(Ordering::Less, order.compare("item2", "item10"));
+/// assert_eq!(Ordering::Less, order.compare("name", "item1"));
+/// assert_eq!(Ordering::Less, order.compare("id", "name"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct KeyOrder {
+    first: Vec<String>,
+}
+
+impl KeyOrder {
+    /// Order keys naturally: `item2` before `item10`.
+    pub fn natural() -> Self {
+        KeyOrder::default()
+    }
+
+    /// Order the given keys first, in the given order.
+    pub fn first(mut self, keys: &[&str]) -> Self {
+        self.first = keys.iter().map(|k| k.to_string()).collect();
+        self
+    }
+
+    /// Compare two keys.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let rank = |k: &str| self.first.iter().position(|first| first == k);
+        match (rank(a), rank(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => natural_cmp(a, b),
+        }
+    }
+}
+
+/// Compare strings char by char, runs of ASCII digits being compared
+/// as numbers, then as strings for the ones with leading zeros.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_rest, mut b_rest) = (a, b);
+    loop {
+        let (a_digits, b_digits) = (digits(a_rest), digits(b_rest));
+        if a_digits > 0 && b_digits > 0 {
+            let (a_number, b_number) = (&a_rest[..a_digits], &b_rest[..b_digits]);
+            let (a_trimmed, b_trimmed) = (
+                a_number.trim_start_matches('0'),
+                b_number.trim_start_matches('0'),
+            );
+            let ordering = a_trimmed
+                .len()
+                .cmp(&b_trimmed.len())
+                .then_with(|| a_trimmed.cmp(b_trimmed));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            a_rest = &a_rest[a_digits..];
+            b_rest = &b_rest[b_digits..];
+            continue;
+        }
+        let mut a_chars = a_rest.chars();
+        let mut b_chars = b_rest.chars();
+        match (a_chars.next(), b_chars.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x != y => return x.cmp(&y),
+            _ => {}
+        }
+        a_rest = a_chars.as_str();
+        b_rest = b_chars.as_str();
+    }
+}
+
+fn digits(s: &str) -> usize {
+    s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len())
+}
+
+/// Value serialized with the keys of its objects sorted by a comparator.
+struct SortedBy<'a, F> {
+    value: &'a Value,
+    compare: &'a F,
+}
+
+impl<F: Fn(&str, &str) -> Ordering> Serialize for SortedBy<'_, F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let sorted = |value| SortedBy {
+            value,
+            compare: self.compare,
+        };
+        match self.value {
+            Value::Object(map) => {
+                let mut members: Vec<(&String, &Value)> = map.iter().collect();
+                members.sort_by(|a, b| (self.compare)(a.0, b.0));
+                let mut object = serializer.serialize_map(Some(members.len()))?;
+                for (k, v) in members {
+                    object.serialize_entry(k, &sorted(v))?;
+                }
+                object.end()
+            }
+            Value::Array(items) => {
+                let mut array = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    array.serialize_element(&sorted(item))?;
+                }
+                array.end()
+            }
+            scalar => scalar.serialize(serializer),
+        }
+    }
+}
+
+impl Json {
+    /// Serialize the document with the keys of all its objects, nested ones
+    /// included, sorted by the given comparator, such as the one of a
+    /// `KeyOrder`. Keys comparing equal keep an arbitrary order.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, KeyOrder};
+    ///
+    /// fn main() -> Result<(), serde_json::Error> {
+    ///     let report = inline!("item10" => 3, "item2" => 1, "total" => 4, "id" => 7);
+    ///     let order = KeyOrder::natural().first(&["id"]);
+    ///
+    ///     assert_eq!(
+    ///         r#"{"id":7,"item2":1,"item10":3,"total":4}"#,
+    ///         report.to_string_sorted_by(|a, b| order.compare(a, b))?
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(serde_json::error::Error)` if the document
+    /// cannot be serialized
+    pub fn to_string_sorted_by<F>(&self, compare: F) -> Result<String, SerdeError>
+    where
+        F: Fn(&str, &str) -> Ordering,
+    {
+        let root = Value::Object(
+            self.json_data
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        );
+        serde_json::to_string(&SortedBy {
+            value: &root,
+            compare: &compare,
+        })
+    }
+
+    /// Iterate over the members of the document, sorted by key.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let json = inline!("b" => 2, "c" => 3, "a" => 1);
+    /// let keys: Vec<&str> = json.iter_sorted().map(|(k, _)| k).collect();
+    ///
+    /// assert_eq!(vec!["a", "b", "c"], keys);
+    /// ```
+    pub fn iter_sorted(&self) -> impl Iterator<Item = (&str, &Value)> + '_ {
+        let mut members: Vec<(&str, &Value)> = self
+            .json_data
+            .iter()
+            .map(|(k, v)| (k.as_str(), v))
+            .collect();
+        members.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        members.into_iter()
+    }
+
+    /// Iterate over the members of the document grouped by the part of
+    /// their key before the first occurrence of the separator, sorted by
+    /// prefix then by key. The members are given with the rest of their
+    /// key, and the ones without separator are grouped under an empty prefix.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let config = inline!("db.port" => 5432, "name" => "guide", "db.host" => "localhost");
+    /// let sections: Vec<(&str, Vec<&str>)> = config
+    ///     .iter_grouped_by_prefix(".")
+    ///     .map(|(prefix, members)| (prefix, members.into_iter().map(|(k, _)| k).collect()))
+    ///     .collect();
+    ///
+    /// assert_eq!(vec![("", vec!["name"]), ("db", vec!["host", "port"])], sections);
+    /// ```
+    pub fn iter_grouped_by_prefix<'a>(
+        &'a self,
+        sep: &str,
+    ) -> impl Iterator<Item = (&'a str, Vec<(&'a str, &'a Value)>)> + 'a {
+        let mut groups: Vec<(&str, Vec<(&str, &Value)>)> = Vec::new();
+        let mut members: Vec<(&str, &str, &Value)> = self
+            .iter_sorted()
+            .map(
+                |(k, v)| match k.split_once(sep).filter(|_| !sep.is_empty()) {
+                    Some((prefix, rest)) => (prefix, rest, v),
+                    None => ("", k, v),
+                },
+            )
+            .collect();
+        members.sort_by(|a, b| a.0.cmp(b.0));
+        for (prefix, rest, v) in members {
+            match groups.last_mut() {
+                Some((last, group)) if *last == prefix => group.push((rest, v)),
+                _ => groups.push((prefix, vec![(rest, v)])),
+            }
+        }
+        groups.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn natural_order() {
+        let mut keys = vec!["a10", "a2", "a02", "b", "a", "a2b", "a2a", "10", "9"];
+        keys.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            vec!["9", "10", "a", "a02", "a2", "a2a", "a2b", "a10", "b"],
+            keys
+        );
+    }
+
+    #[test]
+    fn sorted_by() -> Result<(), SerdeError> {
+        let json =
+            inline!("b" => vec![inline!("y" => 1, "x" => 2)], "a" => inline!("d" => 1, "c" => 2));
+        assert_eq!(
+            r#"{"b":[{"y":1,"x":2}],"a":{"d":1,"c":2}}"#,
+            json.to_string_sorted_by(|a, b| b.cmp(a))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn groups() {
+        let json = inline!("a::b::c" => 1, "a::a" => 2, "b" => 3, "::x" => 4);
+        let groups: Vec<(&str, Vec<&str>)> = json
+            .iter_grouped_by_prefix("::")
+            .map(|(prefix, members)| (prefix, members.into_iter().map(|(k, _)| k).collect()))
+            .collect();
+        assert_eq!(vec![("", vec!["x", "b"]), ("a", vec!["a", "b::c"])], groups);
+        assert_eq!(1, json.iter_grouped_by_prefix("").count());
+    }
+}
@@ -0,0 +1,197 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Location in the parsed text of every value of a document, so that
+//! tools can point users to the exact line of a faulty value.
+
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+
+use crate::lenient::{to_json, Parser};
+use crate::path::parse_concrete;
+use crate::{Error, Json};
+
+/// Location of a value in the text it was parsed from, as byte offsets
+/// and as lines and columns (both starting at 1, columns counting
+/// characters). The end is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceSpan {
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl SourceSpan {
+    /// Byte offset of the first character of the value.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Byte offset following the last character of the value.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Line of the first character of the value.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Column of the first character of the value.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Line of the position following the value.
+    pub fn end_line(&self) -> usize {
+        self.end_line
+    }
+
+    /// Column of the position following the value.
+    pub fn end_column(&self) -> usize {
+        self.end_column
+    }
+}
+
+impl Display for SourceSpan {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Converts byte offsets to lines and columns.
+struct Lines<'a> {
+    input: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(input: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Lines { input, starts }
+    }
+
+    fn locate(&self, pos: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|start| *start <= pos);
+        let column = self.input[self.starts[line - 1]..pos].chars().count() + 1;
+        (line, column)
+    }
+}
+
+impl Json {
+    /// Deserialize a `Json` instance from a `String` of JSON text, like
+    /// `deserialise!`, recording the location of every value for
+    /// `Json::span_of`.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::Json;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let input = "{\n  \"server\": {\n    \"port\": \"eighty\"\n  }\n}";
+    ///     let config = Json::from_str_with_spans(input)?;
+    ///
+    ///     let span = config.span_of("server.port")?.unwrap();
+    ///     assert_eq!((3, 13), (span.line(), span.column()));
+    ///     assert_eq!("\"eighty\"", &input[span.start()..span.end()]);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::Parse)` if the input is not valid
+    /// JSON or an `Err(json_ez::Error::TypeMismatch)` if it doesn't hold an object
+    pub fn from_str_with_spans(input: &str) -> Result<Json, Error> {
+        let mut parser = Parser::new(input);
+        parser.spans = Some(Vec::new());
+        let mut json = to_json(parser.parse()?)?;
+        let lines = Lines::new(input);
+        let spans = parser.spans.unwrap_or_default().into_iter().map(|span| {
+            let (line, column) = lines.locate(span.value.start);
+            let (end_line, end_column) = lines.locate(span.value.end);
+            let location = SourceSpan {
+                start: span.value.start,
+                end: span.value.end,
+                line,
+                column,
+                end_line,
+                end_column,
+            };
+            (span.path, location)
+        });
+        json.spans = Some(Arc::new(spans.collect()));
+        Ok(json)
+    }
+
+    /// Location in the parsed text of the value at the given path, the empty
+    /// path being the document itself. Only documents parsed with
+    /// `Json::from_str_with_spans` have locations, which are not updated
+    /// when the document is modified.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if the path
+    /// cannot be parsed or holds wildcards
+    pub fn span_of(&self, path: &str) -> Result<Option<SourceSpan>, Error> {
+        let segments = match path {
+            "" => Vec::new(),
+            path => parse_concrete(path)?,
+        };
+        Ok(self
+            .spans
+            .as_ref()
+            .and_then(|spans| spans.get(&segments))
+            .copied())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn spans() -> Result<(), Box<dyn Error>> {
+        let input = "{\"a\": [1, {\"b\": \"é\"}],\r\n \"c\": null}";
+        let json = Json::from_str_with_spans(input)?;
+        let span = |path: &str| json.span_of(path).unwrap().unwrap();
+
+        assert_eq!((0, input.len()), (span("").start(), span("").end()));
+        assert_eq!("1", &input[span("a[0]").start()..span("a[0]").end()]);
+        let b = span("a[1].b");
+        assert_eq!(
+            (1, 17, 1, 20),
+            (b.line(), b.column(), b.end_line(), b.end_column())
+        );
+        assert_eq!("line 2, column 7", span("c").to_string());
+        assert_eq!(None, json.span_of("a[2]")?);
+        assert_eq!(None, crate::inline!("a" => 1).span_of("a")?);
+        assert!(json.span_of("a[*]").is_err());
+        assert!(Json::from_str_with_spans("{\"a\": 1,}").is_err());
+        Ok(())
+    }
+}
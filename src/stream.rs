@@ -0,0 +1,198 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Serialization of documents in chunks produced on demand, so that
+//! large documents can be sent without being buffered as a whole.
+
+use std::collections::hash_map;
+use std::mem;
+use std::slice;
+
+use serde_json::{map, Value};
+
+use crate::Json;
+
+/// Iterator over the chunks of the serialization of a document,
+/// returned by `Json::to_string_stream`.
+pub struct Chunks<'a> {
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    /// Start of the bytes of the buffer not yet returned
+    start: usize,
+    stack: Vec<Frame<'a>>,
+}
+
+/// A container being serialized, with whether its first item is still to come.
+enum Frame<'a> {
+    Members(hash_map::Iter<'a, String, Value>, bool),
+    Object(map::Iter<'a>, bool),
+    Array(slice::Iter<'a, Value>, bool),
+}
+
+impl<'a> Chunks<'a> {
+    /// Serialize the next item of the innermost container, or close it.
+    fn step(&mut self) {
+        let buffer = &mut self.buffer;
+        let (member, first) = match self.stack.last_mut() {
+            Some(Frame::Members(members, first)) => {
+                (members.next().map(|(k, v)| (Some(k), v)), first)
+            }
+            Some(Frame::Object(members, first)) => {
+                (members.next().map(|(k, v)| (Some(k), v)), first)
+            }
+            Some(Frame::Array(items, first)) => (items.next().map(|v| (None, v)), first),
+            None => return,
+        };
+        let (key, value) = match member {
+            Some(member) => member,
+            None => {
+                let closing = match self.stack.pop() {
+                    Some(Frame::Array(..)) => b']',
+                    _ => b'}',
+                };
+                buffer.push(closing);
+                return;
+            }
+        };
+        if !mem::replace(first, false) {
+            buffer.push(b',');
+        }
+        if let Some(key) = key {
+            write(buffer, key);
+            buffer.push(b':');
+        }
+        match value {
+            Value::Object(members) => {
+                buffer.push(b'{');
+                self.stack.push(Frame::Object(members.iter(), true));
+            }
+            Value::Array(items) => {
+                buffer.push(b'[');
+                self.stack.push(Frame::Array(items.iter(), true));
+            }
+            scalar => write(buffer, scalar),
+        }
+    }
+}
+
+fn write<T: serde::Serialize + ?Sized>(buffer: &mut Vec<u8>, value: &T) {
+    serde_json::to_writer(buffer, value).expect("keys and values are serializable")
+}
+
+impl Iterator for Chunks<'_> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.buffer.len() - self.start < self.chunk_size && !self.stack.is_empty() {
+            // Less than a chunk is left, cheap to move to the start of the buffer
+            self.buffer.drain(..self.start);
+            self.start = 0;
+            while self.buffer.len() < self.chunk_size && !self.stack.is_empty() {
+                self.step();
+            }
+        }
+        let end = self.buffer.len().min(self.start + self.chunk_size);
+        if self.start == end {
+            return None;
+        }
+        if self.start == 0 && end == self.buffer.len() {
+            return Some(mem::take(&mut self.buffer));
+        }
+        let chunk = self.buffer[self.start..end].to_vec();
+        self.start = end;
+        Some(chunk)
+    }
+}
+
+impl Json {
+    /// Serialize this `Json` instance like `Json::to_vec`, as chunks of
+    /// `chunk_size` bytes (the last one may be shorter). Each chunk is
+    /// serialized when requested, so that servers can send large documents
+    /// at the pace of the client. A document with a profile is serialized
+    /// as a whole on the first request though.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// let json = inline!("title" => "Mostly Harmless", "tags" => vec!["sci-fi"]);
+    /// let mut body = Vec::new();
+    /// for chunk in json.to_string_stream(8) {
+    ///     assert!(chunk.len() <= 8);
+    ///     body.extend(chunk);
+    /// }
+    ///
+    /// assert_eq!(json.to_vec().unwrap(), body);
+    /// ```
+    ///
+    /// # Panics
+    /// Panic if `chunk_size` is zero
+    pub fn to_string_stream(&self, chunk_size: usize) -> Chunks<'_> {
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        match &self.profile {
+            Some(profile) => Chunks {
+                chunk_size,
+                buffer: self
+                    .to_string_with(profile)
+                    .map(String::into_bytes)
+                    .expect("keys and values are serializable"),
+                start: 0,
+                stack: Vec::new(),
+            },
+            None => Chunks {
+                chunk_size,
+                buffer: vec![b'{'],
+                start: 0,
+                stack: vec![Frame::Members(self.json_data.iter(), true)],
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::deserialise;
+    use std::error::Error;
+
+    #[test]
+    fn chunks() -> Result<(), Box<dyn Error>> {
+        let json = deserialise!(
+            r#"{ "a": [], "b": {}, "c": [1, { "d": "é\n", "e": [null, true] }], "f": 1.5 }"#
+        )?;
+        let expected = json.to_vec()?;
+        for size in 1..=expected.len() + 1 {
+            let chunks: Vec<Vec<u8>> = json.to_string_stream(size).collect();
+            assert_eq!(expected.len().div_ceil(size), chunks.len());
+            assert_eq!(expected, chunks.concat());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn large_scalars() -> Result<(), Box<dyn Error>> {
+        let json = crate::inline!("a" => "x".repeat(1 << 20), "b" => vec![1, 2]);
+        let chunks: Vec<Vec<u8>> = json.to_string_stream(4096).collect();
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 4096));
+        assert_eq!(json.to_vec()?, chunks.concat());
+        Ok(())
+    }
+}
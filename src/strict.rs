@@ -0,0 +1,158 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Numbers read without converting integers to floats or the other way
+//! round, for the code where such silent conversions are bugs.
+
+use std::any;
+use std::convert::TryFrom;
+
+use serde_json::{Number, Value};
+
+use crate::{Error, Json, NotFound, TypeMismatch};
+
+/// Whether a number is written as an integer, without fraction or exponent.
+fn is_integer(n: &Number) -> bool {
+    !n.to_string().contains(['.', 'e', 'E'])
+}
+
+fn mismatch(expected: &str, found: String) -> Error {
+    TypeMismatch {
+        expected: expected.into(),
+        found,
+    }
+    .into()
+}
+
+impl Json {
+    fn number(&self, k: &str) -> Result<&Number, Error> {
+        match self.json_data.get(self.key(k).as_ref()) {
+            Some(Value::Number(n)) => Ok(n),
+            Some(other) => Err(TypeMismatch::new("number", other).into()),
+            None => Err(NotFound::new(k.into(), self)?.into()),
+        }
+    }
+
+    /// Get the integer stored under the given key, refusing the numbers
+    /// written with a fraction or an exponent, such as `2.0`, unlike
+    /// `Json::get` which would accept them as integers when exact.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::deserialise;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let order = deserialise!(r#"{ "quantity": 3, "stock": 2.0, "total": 70000 }"#)?;
+    ///
+    ///     assert_eq!(3, order.get_int_strict::<u16>("quantity")?);
+    ///     assert_eq!(
+    ///         "TypeMismatch: Expected integer but found float 2.0",
+    ///         order.get_int_strict::<u16>("stock").unwrap_err().to_string()
+    ///     );
+    ///     assert_eq!(
+    ///         "TypeMismatch: Expected u16 but found integer 70000",
+    ///         order.get_int_strict::<u16>("total").unwrap_err().to_string()
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::TypeMismatch)` if its value is not an integer or
+    /// doesn't fit in a `T`
+    pub fn get_int_strict<T: TryFrom<i128>>(&self, k: &str) -> Result<T, Error> {
+        let n = self.number(k)?;
+        if !is_integer(n) {
+            return Err(mismatch("integer", format!("float {}", n)));
+        }
+        n.to_string()
+            .parse::<i128>()
+            .ok()
+            .and_then(|i| T::try_from(i).ok())
+            .ok_or_else(|| mismatch(any::type_name::<T>(), format!("integer {}", n)))
+    }
+
+    /// Get the float stored under the given key, refusing the integers
+    /// which cannot be represented exactly as a `f64`, such as
+    /// `9007199254740993`, unlike `Json::get` which would round them.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::deserialise;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let invoice = deserialise!(r#"{ "rate": 0.2, "units": 3, "id": 9007199254740993 }"#)?;
+    ///
+    ///     assert_eq!(0.2, invoice.get_float_strict("rate")?);
+    ///     assert_eq!(3.0, invoice.get_float_strict("units")?);
+    ///     assert!(invoice.get_float_strict("id").is_err());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::TypeMismatch)` if its value is not a number or an
+    /// integer which cannot be represented exactly
+    pub fn get_float_strict(&self, k: &str) -> Result<f64, Error> {
+        let n = self.number(k)?;
+        let inexact = || mismatch("float", format!("inexact integer {}", n));
+        let f = n.as_f64().ok_or_else(inexact)?;
+        if is_integer(n) {
+            // The integer is exact if it can be written back from the float
+            let i = n.to_string().parse::<i128>().map_err(|_| inexact())?;
+            if !f.is_finite() || f.abs() >= 2f64.powi(127) || f as i128 != i {
+                return Err(inexact());
+            }
+        }
+        Ok(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::deserialise;
+    use std::error::Error;
+
+    #[test]
+    fn strict_numbers() -> Result<(), Box<dyn Error>> {
+        let json = deserialise!(
+            r#"{ "neg": -3, "exp": 1e2, "big": 18446744073709551615, "s": "1", "f": 1.5 }"#
+        )?;
+        assert_eq!(-3, json.get_int_strict::<i8>("neg")?);
+        assert!(json.get_int_strict::<u8>("neg").is_err());
+        assert!(json.get_int_strict::<i64>("exp").is_err());
+        assert_eq!(u64::MAX, json.get_int_strict::<u64>("big")?);
+        assert_eq!(
+            "TypeMismatch: Expected number but found string",
+            json.get_int_strict::<u8>("s").unwrap_err().to_string()
+        );
+        assert_eq!(100.0, json.get_float_strict("exp")?);
+        assert_eq!(-3.0, json.get_float_strict("neg")?);
+        assert!(json.get_float_strict("big").is_err());
+        assert_eq!(1.5, json.get_float_strict("f")?);
+        Ok(())
+    }
+}
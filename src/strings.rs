@@ -0,0 +1,129 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Cleaning of the string values of a document, such as scraped
+//! or user-provided data.
+
+use serde_json::Value;
+
+use crate::{Error, Json};
+
+/// Transformation applied to string values by `Json::transform_strings`.
+/// Some variants only exist with the `regex` feature.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum StringOp {
+    /// Remove leading and trailing whitespace
+    Trim,
+    /// Convert to lowercase
+    Lowercase,
+    /// Convert to uppercase
+    Uppercase,
+    /// Replace all the matches of a regular expression, the replacement
+    /// referring to capture groups like `regex::Regex::replace_all` (`$1`)
+    #[cfg(feature = "regex")]
+    Replace(regex::Regex, String),
+}
+
+impl StringOp {
+    fn apply(&self, s: &str) -> String {
+        match self {
+            StringOp::Trim => s.trim().to_string(),
+            StringOp::Lowercase => s.to_lowercase(),
+            StringOp::Uppercase => s.to_uppercase(),
+            #[cfg(feature = "regex")]
+            StringOp::Replace(regex, replacement) => {
+                regex.replace_all(s, replacement.as_str()).into_owned()
+            }
+        }
+    }
+}
+
+impl Json {
+    /// Apply the given transformation to the strings matched by any of the
+    /// given path patterns, other values being left untouched. Use `**` to
+    /// reach the strings nested at any depth, such as `**.email`.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, StringOp};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut user = deserialise!(r#"{
+    ///         "name": "  Arthur Dent ",
+    ///         "emails": [" Arthur@Example.com", 42],
+    ///         "contact": { "email": "ARTHUR@earth.org " }
+    ///     }"#)?;
+    ///     user.transform_strings(&["name", "**.email", "emails[*]"], StringOp::Trim)?;
+    ///     user.transform_strings(&["**.email", "emails[*]"], StringOp::Lowercase)?;
+    ///
+    ///     assert_eq!(
+    ///         deserialise!(r#"{
+    ///             "name": "Arthur Dent",
+    ///             "emails": ["arthur@example.com", 42],
+    ///             "contact": { "email": "arthur@earth.org" }
+    ///         }"#)?,
+    ///         user
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if a pattern cannot be parsed
+    pub fn transform_strings(&mut self, patterns: &[&str], op: StringOp) -> Result<(), Error> {
+        self.for_each_match(patterns, &mut |_, value| {
+            if let Value::String(s) = value {
+                *s = op.apply(s);
+            }
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn transform() -> Result<(), Box<dyn std::error::Error>> {
+        let mut json = inline!("a" => " Hello ", "b" => vec![" x "]);
+        json.transform_strings(&["**"], StringOp::Uppercase)?;
+        assert_eq!(inline!("a" => " HELLO ", "b" => vec![" X "]), json);
+        json.transform_strings(&["b[*]"], StringOp::Trim)?;
+        assert_eq!(inline!("a" => " HELLO ", "b" => vec!["X"]), json);
+        assert!(json.transform_strings(&["["], StringOp::Trim).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn replace() -> Result<(), Box<dyn std::error::Error>> {
+        let mut json = inline!("phone" => "+33 (0)1 23 45 67 89");
+        let op = StringOp::Replace(regex::Regex::new(r"\(0\)|\s")?, String::new());
+        json.transform_strings(&["phone"], op)?;
+        assert_eq!("+33123456789", json.get::<String>("phone")?);
+        Ok(())
+    }
+}
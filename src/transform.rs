@@ -0,0 +1,200 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Rewriting of documents in a single traversal, the general engine
+//! behind custom redactions, prunings and conversions.
+
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+use crate::limits::LimitExceeded;
+use crate::path::{render, Segment};
+use crate::Json;
+
+/// Nesting depth at which `Json::deep_map` stops when the document has
+/// no depth limit, as `serde_json` does when parsing.
+const MAX_DEPTH: usize = 128;
+
+/// Outcome of the visit of a value by `Json::deep_map`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transform {
+    /// Keep the value, visiting its own values if it's an array or an object
+    Keep,
+    /// Replace the value with the given one, which is not visited
+    Replace(Value),
+    /// Remove the value from its object or array
+    Drop,
+}
+
+struct Mapper<F> {
+    f: F,
+    max_depth: usize,
+    location: Vec<Segment>,
+}
+
+impl<F: FnMut(&str, &Value) -> Transform> Mapper<F> {
+    /// The transformed value at the current location, if it's kept.
+    fn visit(&mut self, value: &Value) -> Result<Option<Value>, LimitExceeded> {
+        match (self.f)(&render(&self.location), value) {
+            Transform::Keep => {}
+            Transform::Replace(value) => return Ok(Some(value)),
+            Transform::Drop => return Ok(None),
+        }
+        // The document itself is at depth 1
+        let depth = self.location.len() + 1;
+        if (value.is_array() || value.is_object()) && depth > self.max_depth {
+            return Err(LimitExceeded::new("depth", self.max_depth, depth));
+        }
+        Ok(Some(match value {
+            Value::Array(items) => {
+                let mut mapped = Vec::with_capacity(items.len());
+                for (i, item) in items.iter().enumerate() {
+                    self.location.push(Segment::Index(i));
+                    mapped.extend(self.visit(item)?);
+                    self.location.pop();
+                }
+                Value::Array(mapped)
+            }
+            Value::Object(map) => {
+                let mut mapped = Map::new();
+                for (k, v) in map {
+                    self.location.push(Segment::Key(k.clone()));
+                    if let Some(v) = self.visit(v)? {
+                        mapped.insert(k.clone(), v);
+                    }
+                    self.location.pop();
+                }
+                Value::Object(mapped)
+            }
+            scalar => scalar.clone(),
+        }))
+    }
+}
+
+impl Json {
+    /// Create a new document, with the settings of this one, by calling the
+    /// given function on each value with its path (such as `cast[0].name`)
+    /// before its own values, in a single traversal. The values replaced or
+    /// dropped are not visited further, and the array indexes given are the
+    /// ones of this document.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Transform};
+    /// use serde_json::Value;
+    ///
+    /// fn main() -> Result<(), json_ez::Error> {
+    ///     let user = inline!(
+    ///         "name" => "Arthur",
+    ///         "password" => "42",
+    ///         "towel" => inline!("color" => None::<String>, "size" => "L")
+    ///     );
+    ///     let cleaned = user.deep_map(|path, value| match (path, value) {
+    ///         ("password", _) => Transform::Replace("***".into()),
+    ///         (_, Value::Null) => Transform::Drop,
+    ///         _ => Transform::Keep,
+    ///     })?;
+    ///
+    ///     assert_eq!(
+    ///         inline!("name" => "Arthur", "password" => "***", "towel" => inline!("size" => "L")),
+    ///         cleaned
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::LimitExceeded)` if a kept value is
+    /// nested deeper than the depth limit of the document, 128 levels by
+    /// default, or if the new document would exceed its limits, and an
+    /// `Err(json_ez::Error::InvalidKey)` if a replacement doesn't follow
+    /// its key policy
+    pub fn deep_map<F: FnMut(&str, &Value) -> Transform>(&self, f: F) -> Result<Json, Error> {
+        let mut mapper = Mapper {
+            f,
+            max_depth: self
+                .limits
+                .as_ref()
+                .and_then(|l| l.depth())
+                .unwrap_or(MAX_DEPTH),
+            location: Vec::new(),
+        };
+        let mut mapped = self.settings();
+        for (k, v) in &self.json_data {
+            mapper.location.push(Segment::Key(k.clone()));
+            if let Some(v) = mapper.visit(v)? {
+                mapped.store(k, v)?;
+            }
+            mapper.location.pop();
+        }
+        Ok(mapped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, Limits};
+
+    #[test]
+    fn paths() -> Result<(), Error> {
+        let json = inline!("cast" => vec![inline!("name" => "Ford"), inline!("name" => "Zaphod")]);
+        let mut visited = Vec::new();
+        let mapped = json.deep_map(|path, _| {
+            visited.push(path.to_string());
+            match path {
+                "cast[0]" => Transform::Drop,
+                _ => Transform::Keep,
+            }
+        })?;
+        visited.sort();
+        assert_eq!(vec!["cast", "cast[0]", "cast[1]", "cast[1].name"], visited);
+        assert_eq!(inline!("cast" => vec![inline!("name" => "Zaphod")]), mapped);
+        Ok(())
+    }
+
+    #[test]
+    fn depth() -> Result<(), Error> {
+        let mut deep = Value::Null;
+        for _ in 0..200 {
+            deep = Value::Array(vec![deep]);
+        }
+        let json = inline!("deep" => deep);
+        match json.deep_map(|_, _| Transform::Keep) {
+            Err(Error::LimitExceeded(err)) => assert_eq!(
+                "LimitExceeded: Document would be nested 129 levels deep, the maximum being 128",
+                err.to_string()
+            ),
+            other => panic!("unexpected result {:?}", other),
+        }
+        let mut shallow = inline!("a" => inline!("b" => 1));
+        shallow.set_limits(Limits::new().max_depth(2))?;
+        assert!(shallow.deep_map(|_, _| Transform::Keep).is_ok());
+        let replaced = shallow.deep_map(|path, _| match path {
+            "a.b" => Transform::Replace(Value::Array(vec![])),
+            _ => Transform::Keep,
+        });
+        assert!(matches!(replaced, Err(Error::LimitExceeded(_))));
+        Ok(())
+    }
+}
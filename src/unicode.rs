@@ -0,0 +1,115 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! `unicode-normalization` support, storing and looking up keys in
+//! Normalization Form C so that keys spelled with combining characters
+//! match their precomposed equivalent.
+
+use std::borrow::Cow;
+
+use serde_json::Value;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+use crate::Json;
+
+impl Json {
+    /// Enable or disable the NFC normalization of keys. Once enabled,
+    /// the keys already in the document, those of the added values and
+    /// the ones looked up are normalized, nested objects included.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::inline;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     // "cafe" followed by a combining acute accent
+    ///     let mut menu = inline!("cafe\u{301}" => 2.5);
+    ///     menu.set_normalize_keys(true);
+    ///
+    ///     assert_eq!(2.5, menu.get::<f64>("caf\u{e9}")?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_normalize_keys(&mut self, enabled: bool) {
+        self.nfc_keys = enabled;
+        if enabled {
+            self.watched(|json| {
+                json.json_data = json
+                    .json_data
+                    .drain()
+                    .map(|(k, v)| (nfc(&k).into_owned(), nfc_value(v)))
+                    .collect();
+            });
+        }
+    }
+}
+
+/// The NFC form of the given key, borrowed if already normalized.
+pub(crate) fn nfc(key: &str) -> Cow<'_, str> {
+    if is_nfc(key) {
+        Cow::Borrowed(key)
+    } else {
+        Cow::Owned(key.nfc().collect())
+    }
+}
+
+/// Normalize the keys of the objects nested in the given value.
+pub(crate) fn nfc_value(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (nfc(&k).into_owned(), nfc_value(v)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(nfc_value).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+    use std::error::Error;
+
+    const COMPOSED: &str = "caf\u{e9}";
+    const DECOMPOSED: &str = "cafe\u{301}";
+
+    #[test]
+    fn normalized_keys() -> Result<(), Box<dyn Error>> {
+        let mut json = inline!(DECOMPOSED => inline!(DECOMPOSED => 1));
+        assert!(json.get::<Json>(COMPOSED).is_err());
+
+        json.set_normalize_keys(true);
+        assert_eq!(1, json.get::<Json>(COMPOSED)?.get::<u8>(COMPOSED)?);
+        assert_eq!(1, json.get::<Json>(DECOMPOSED)?.get::<u8>(COMPOSED)?);
+
+        json.add(DECOMPOSED, 2);
+        json.merge(inline!(COMPOSED => 3, "tea" => vec![inline!(DECOMPOSED => 4)]));
+        assert_eq!(
+            inline!(COMPOSED => 3, "tea" => vec![inline!(COMPOSED => 4)]),
+            json
+        );
+        Ok(())
+    }
+}
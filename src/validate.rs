@@ -0,0 +1,587 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Validation of documents against a JSON Schema, reporting every violation.
+//!
+//! Supported keywords are `type`, `enum`, `const`, `properties`, `required`,
+//! `additionalProperties`, `minProperties`, `maxProperties`, `items`,
+//! `prefixItems`, `minItems`, `maxItems`, `uniqueItems`, `minLength`,
+//! `maxLength`, `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`,
+//! `multipleOf`, `allOf`, `anyOf`, `oneOf` and `not`, as well as `$ref` when
+//! validating against an OpenAPI document. Other keywords are ignored.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::error::{self, ErrorCode};
+use crate::path::pointer_child;
+use crate::refs::Resolver;
+use crate::schema::{has_type, item_schema, types, Schema};
+use crate::{type_name, Json};
+
+/// A single violation of a schema by a document.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    location: String,
+    keyword: String,
+    expected: Value,
+    actual: Value,
+}
+
+impl Violation {
+    pub(crate) fn new(location: &str, keyword: &str, expected: Value, actual: Value) -> Self {
+        Violation {
+            location: location.into(),
+            keyword: keyword.into(),
+            expected,
+            actual,
+        }
+    }
+
+    /// Location of the faulty value, as a JSON Pointer (empty for the whole document).
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Violated keyword of the schema, such as `type` or `required`.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    /// Value expected by the keyword, such as the allowed types for `type`,
+    /// the bound for `minimum` or the missing property for `required`.
+    pub fn expected(&self) -> &Value {
+        &self.expected
+    }
+
+    /// Actual value checked by the keyword, such as the type of the value
+    /// for `type`, its length for `minLength` or the value itself.
+    pub fn actual(&self) -> &Value {
+        &self.actual
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let location = if self.location.is_empty() {
+            "/"
+        } else {
+            &self.location
+        };
+        write!(
+            f,
+            "{} at {}: expected {} but found {}",
+            self.keyword, location, self.expected, self.actual
+        )
+    }
+}
+
+/// Custom error type used when a document doesn't satisfy a schema,
+/// listing every violation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationError {
+    violations: Vec<Violation>,
+}
+
+impl ValidationError {
+    pub(crate) fn new(violations: Vec<Violation>) -> Self {
+        ValidationError { violations }
+    }
+
+    pub(crate) fn into_violations(self) -> Vec<Violation> {
+        self.violations
+    }
+
+    /// Violations of the schema, in document order.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Stable code of the error, `VALIDATION_FAILED`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::ValidationFailed
+    }
+
+    /// Machine-readable representation of the error, suitable for API
+    /// responses, with its code, message and violations:
+    /// `{ "errors": [{ "location", "keyword", "expected", "actual" }] }`.
+    pub fn to_json(&self) -> Json {
+        let errors = serde_json::to_value(&self.violations).expect("violations are serializable");
+        error::to_json(self.code(), self, vec![("errors", errors)])
+    }
+}
+
+impl Error for ValidationError {}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let violations: Vec<String> = self.violations.iter().map(|v| v.to_string()).collect();
+        write!(f, "ValidationError: {}", violations.join(", "))
+    }
+}
+
+/// Equality of JSON values, numbers being compared by value (`1` equals `1.0`).
+fn same(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x == y || x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| same(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(k, v)| y.get(k).is_some_and(|w| same(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+pub(crate) fn check(
+    value: &Value,
+    subschema: &Value,
+    location: &str,
+    violations: &mut Vec<Violation>,
+) {
+    Checker::default().check(value, subschema, location, violations)
+}
+
+/// Checker of values against schemas, following their `$ref` in a document
+/// if any, as they are met.
+#[derive(Default)]
+pub(crate) struct Checker<'a> {
+    refs: Option<(&'a Json, &'a Resolver)>,
+    /// Targets of the references being followed, with the location of the
+    /// value checked against them, to detect cycles
+    followed: Vec<(String, String)>,
+}
+
+impl<'a> Checker<'a> {
+    /// A checker following the `$ref` of the schemas in the given document.
+    pub(crate) fn with_refs(json: &'a Json, resolver: &'a Resolver) -> Self {
+        Checker {
+            refs: Some((json, resolver)),
+            followed: Vec::new(),
+        }
+    }
+
+    /// Check a value against the target of a reference. A reference which
+    /// cannot be resolved, or which is followed again for the same value,
+    /// without any value being consumed, is reported as a violation.
+    fn follow(
+        &mut self,
+        value: &Value,
+        reference: &str,
+        location: &str,
+        violations: &mut Vec<Violation>,
+    ) {
+        let (json, resolver) = match self.refs {
+            Some(refs) => refs,
+            None => return,
+        };
+        // References are resolved against the target of the last one followed
+        let base = self
+            .followed
+            .last()
+            .map_or("", |(pointer, _)| pointer.as_str());
+        let (pointer, target) = match resolver.target(json, reference, base) {
+            Ok(target) => target,
+            Err(_) => {
+                violations.push(Violation::new(
+                    location,
+                    "$ref",
+                    reference.into(),
+                    json!("unresolved reference"),
+                ));
+                return;
+            }
+        };
+        let step = (pointer, location.to_string());
+        if self.followed.contains(&step) {
+            violations.push(Violation::new(
+                location,
+                "$ref",
+                reference.into(),
+                json!("cyclic reference"),
+            ));
+            return;
+        }
+        self.followed.push(step);
+        self.check(value, &target, location, violations);
+        self.followed.pop();
+    }
+
+    pub(crate) fn check(
+        &mut self,
+        value: &Value,
+        subschema: &Value,
+        location: &str,
+        violations: &mut Vec<Violation>,
+    ) {
+        let schema = match subschema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                violations.push(Violation::new(
+                    location,
+                    "false",
+                    json!(false),
+                    value.clone(),
+                ));
+                return;
+            }
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            self.follow(value, reference, location, violations);
+        }
+        let mut violation = |keyword: &str, expected: Value, actual: Value| {
+            violations.push(Violation::new(location, keyword, expected, actual))
+        };
+
+        let allowed = types(subschema);
+        if !allowed.is_empty() && !allowed.iter().any(|t| has_type(value, t)) {
+            let actual = match value {
+                Value::Number(n) if n.is_f64() => "number",
+                other => type_name(other),
+            };
+            violation("type", schema["type"].clone(), actual.into());
+            return;
+        }
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.iter().any(|a| same(a, value)) {
+                violation("enum", allowed.clone().into(), value.clone());
+            }
+        }
+        if let Some(constant) = schema.get("const") {
+            if !same(constant, value) {
+                violation("const", constant.clone(), value.clone());
+            }
+        }
+
+        match value {
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or(f64::NAN);
+                let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+                if let Some(min) = bound("minimum") {
+                    if n < min {
+                        violation("minimum", min.into(), value.clone());
+                    }
+                }
+                if let Some(max) = bound("maximum") {
+                    if n > max {
+                        violation("maximum", max.into(), value.clone());
+                    }
+                }
+                if let Some(min) = bound("exclusiveMinimum") {
+                    if n <= min {
+                        violation("exclusiveMinimum", min.into(), value.clone());
+                    }
+                }
+                if let Some(max) = bound("exclusiveMaximum") {
+                    if n >= max {
+                        violation("exclusiveMaximum", max.into(), value.clone());
+                    }
+                }
+                if let Some(step) = bound("multipleOf").filter(|s| *s > 0.0) {
+                    let quotient = n / step;
+                    if (quotient - quotient.round()).abs() > 1e-9 {
+                        violation("multipleOf", step.into(), value.clone());
+                    }
+                }
+            }
+            Value::String(s) => {
+                let length = s.chars().count() as u64;
+                let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_u64);
+                if let Some(min) = bound("minLength").filter(|min| length < *min) {
+                    violation("minLength", min.into(), length.into());
+                }
+                if let Some(max) = bound("maxLength").filter(|max| length > *max) {
+                    violation("maxLength", max.into(), length.into());
+                }
+            }
+            Value::Array(items) => {
+                let length = items.len() as u64;
+                let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_u64);
+                if let Some(min) = bound("minItems").filter(|min| length < *min) {
+                    violation("minItems", min.into(), length.into());
+                }
+                if let Some(max) = bound("maxItems").filter(|max| length > *max) {
+                    violation("maxItems", max.into(), length.into());
+                }
+                if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+                    let duplicate = items
+                        .iter()
+                        .enumerate()
+                        .find(|(i, a)| items[..*i].iter().any(|b| same(a, b)));
+                    if let Some((_, duplicate)) = duplicate {
+                        violation("uniqueItems", true.into(), duplicate.clone());
+                    }
+                }
+            }
+            Value::Object(map) => {
+                let length = map.len() as u64;
+                let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_u64);
+                if let Some(min) = bound("minProperties").filter(|min| length < *min) {
+                    violation("minProperties", min.into(), length.into());
+                }
+                if let Some(max) = bound("maxProperties").filter(|max| length > *max) {
+                    violation("maxProperties", max.into(), length.into());
+                }
+                let required = schema.get("required").and_then(Value::as_array);
+                for name in required.into_iter().flatten().filter_map(Value::as_str) {
+                    if !map.contains_key(name) {
+                        violation("required", name.into(), Value::Null);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Nested values, reported with their own location
+        match value {
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if let Some(subschema) = item_schema(subschema, i) {
+                        self.check(
+                            item,
+                            subschema,
+                            &pointer_child(location, &i.to_string()),
+                            violations,
+                        );
+                    }
+                }
+            }
+            Value::Object(map) => {
+                let properties = schema.get("properties").and_then(Value::as_object);
+                let additional = schema.get("additionalProperties");
+                for (name, item) in map {
+                    let item_location = pointer_child(location, name);
+                    match (properties.and_then(|p| p.get(name)), additional) {
+                        (Some(subschema), _) | (None, Some(subschema)) => {
+                            self.check(item, subschema, &item_location, violations)
+                        }
+                        (None, None) => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Combinators
+        if let Some(branches) = schema.get("allOf").and_then(Value::as_array) {
+            for branch in branches {
+                self.check(value, branch, location, violations);
+            }
+        }
+        let mut matching = |keyword: &str| {
+            schema
+                .get(keyword)
+                .and_then(Value::as_array)
+                .map(|branches| {
+                    branches
+                        .iter()
+                        .filter(|branch| {
+                            let mut nested = Vec::new();
+                            self.check(value, branch, location, &mut nested);
+                            nested.is_empty()
+                        })
+                        .count()
+                })
+        };
+        if matching("anyOf") == Some(0) {
+            violations.push(Violation::new(
+                location,
+                "anyOf",
+                json!("at least 1 matching schema"),
+                json!(0),
+            ));
+        }
+        if let Some(count) = matching("oneOf").filter(|count| *count != 1) {
+            violations.push(Violation::new(location, "oneOf", json!(1), json!(count)));
+        }
+        if let Some(not) = schema.get("not") {
+            let mut nested = Vec::new();
+            self.check(value, not, location, &mut nested);
+            if nested.is_empty() {
+                violations.push(Violation::new(
+                    location,
+                    "not",
+                    json!("no match"),
+                    value.clone(),
+                ));
+            }
+        }
+    }
+}
+
+impl Json {
+    /// Validate the document against the given schema, reporting every
+    /// violation rather than the first one.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{deserialise, inline, Schema};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let schema = Schema::new(deserialise!(r#"{
+    ///         "properties": {
+    ///             "title": { "type": "string", "minLength": 1 },
+    ///             "rating": { "type": "integer", "maximum": 5 }
+    ///         },
+    ///         "required": ["title"]
+    ///     }"#)?);
+    ///
+    ///     let err = inline!("rating" => 6).validate(&schema).unwrap_err();
+    ///     let violations: Vec<String> = err.violations().iter().map(|v| v.to_string()).collect();
+    ///     assert_eq!(
+    ///         vec![
+    ///             "required at /: expected \"title\" but found null",
+    ///             "maximum at /rating: expected 5.0 but found 6",
+    ///         ],
+    ///         violations
+    ///     );
+    ///     assert!(inline!("title" => "Mostly Harmless").validate(&schema).is_ok());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::ValidationError)` listing every violation
+    pub fn validate(&self, schema: &Schema) -> Result<(), ValidationError> {
+        let root = Value::Object(
+            self.json_data
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        );
+        let mut violations = Vec::new();
+        check(&root, schema.as_value(), "", &mut violations);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { violations })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deserialise;
+
+    fn violations(schema: &str, document: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let schema = Schema::new(deserialise!(schema)?);
+        let json: Json = deserialise!(document)?;
+        Ok(match json.validate(&schema) {
+            Ok(()) => Vec::new(),
+            Err(e) => e.violations().iter().map(|v| v.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn all_violations() -> Result<(), Box<dyn Error>> {
+        let schema = r#"{
+            "properties": {
+                "title": { "type": "string", "maxLength": 4 },
+                "tags": { "type": "array", "items": { "enum": ["novel", "sci-fi"] }, "uniqueItems": true },
+                "pair": { "prefixItems": [{ "const": 1 }], "maxItems": 1 },
+                "rating": { "type": "number", "exclusiveMinimum": 0, "multipleOf": 0.5 },
+                "movie": { "additionalProperties": false, "minProperties": 2 },
+                "a/b": false
+            },
+            "required": ["title", "author"]
+        }"#;
+        let document = r#"{
+            "title": "Mostly Harmless",
+            "tags": ["novel", "poem", "novel"],
+            "pair": [1.0, 2],
+            "rating": 0.75,
+            "movie": { "year": 2005 },
+            "a/b": null
+        }"#;
+        let mut expected = vec![
+            "required at /: expected \"author\" but found null",
+            "false at /a~1b: expected false but found null",
+            "minProperties at /movie: expected 2 but found 1",
+            "false at /movie/year: expected false but found 2005",
+            "maxItems at /pair: expected 1 but found 2",
+            "multipleOf at /rating: expected 0.5 but found 0.75",
+            "uniqueItems at /tags: expected true but found \"novel\"",
+            "enum at /tags/1: expected [\"novel\",\"sci-fi\"] but found \"poem\"",
+            "maxLength at /title: expected 4 but found 15",
+        ];
+        let mut actual = violations(schema, document)?;
+        expected.sort_unstable();
+        actual.sort();
+        assert_eq!(expected, actual);
+        assert!(violations(schema, r#"{ "title": "H2G2", "author": "DNA" }"#)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn combinators() -> Result<(), Box<dyn Error>> {
+        let schema = r#"{
+            "properties": {
+                "any": { "anyOf": [{ "type": "string" }, { "type": "integer" }] },
+                "one": { "oneOf": [{ "minimum": 0 }, { "maximum": 10 }] },
+                "not": { "not": { "type": "null" } },
+                "all": { "allOf": [{ "minimum": 0 }, { "maximum": 10 }] }
+            }
+        }"#;
+        assert_eq!(
+            vec!["allOf at /all: expected ".to_string(),][..0].to_vec(),
+            violations(schema, r#"{ "any": 1, "one": -1, "not": 0, "all": 5 }"#)?
+        );
+        assert_eq!(
+            vec![
+                "maximum at /all: expected 10.0 but found 11",
+                "anyOf at /any: expected \"at least 1 matching schema\" but found 0",
+                "not at /not: expected \"no match\" but found null",
+                "oneOf at /one: expected 1 but found 2",
+            ],
+            violations(
+                schema,
+                r#"{ "any": 1.5, "one": 5, "not": null, "all": 11 }"#
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn machine_readable() -> Result<(), Box<dyn Error>> {
+        let schema = Schema::new(deserialise!(
+            r#"{ "properties": { "n": { "type": "integer" } } }"#
+        )?);
+        let err = crate::inline!("n" => "5").validate(&schema).unwrap_err();
+        assert_eq!(
+            json!([{ "location": "/n", "keyword": "type", "expected": "integer", "actual": "string" }]),
+            err.to_json().get::<Value>("errors")?
+        );
+        assert_eq!(
+            "ValidationError: type at /n: expected \"integer\" but found \"string\"",
+            err.to_string()
+        );
+        Ok(())
+    }
+}
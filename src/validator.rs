@@ -0,0 +1,289 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Validation of several members at once, extracting their values or
+//! reporting every problem in a single error, as API responses should.
+
+use std::any;
+use std::ops::RangeBounds;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::path::pointer_child;
+use crate::validate::{ValidationError, Violation};
+use crate::{type_name, Error, Format, Json};
+
+/// Builder checking members of a document one after the other, returned by
+/// `Json::validator`. The problems are accumulated instead of stopping at
+/// the first one.
+#[derive(Debug)]
+pub struct Validator<'a, V = ()> {
+    json: &'a Json,
+    values: V,
+    violations: Vec<Violation>,
+}
+
+impl Json {
+    /// Start validating several members at once. Each `require` method
+    /// of the returned `Validator` checks a member, and `finish` returns the
+    /// extracted values in order or a single error listing every violation.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Format};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let signup = inline!("name" => "Arthur Dent", "age" => 42, "email" => "arthur@example.com");
+    ///
+    ///     let (name, age, email) = signup
+    ///         .validator()
+    ///         .require::<String>("name")
+    ///         .require_range("age", 0..150)
+    ///         .require_format("email", Format::Email)
+    ///         .finish()?;
+    ///     assert_eq!(("Arthur Dent", 42, "arthur@example.com"), (name.as_str(), age, email.as_str()));
+    ///
+    ///     let err = inline!("age" => 420)
+    ///         .validator()
+    ///         .require::<String>("name")
+    ///         .require_range("age", 0..150)
+    ///         .finish()
+    ///         .unwrap_err();
+    ///     assert_eq!(
+    ///         "ValidationError: required at /: expected \"name\" but found null, \
+    ///          exclusiveMaximum at /age: expected 150 but found 420",
+    ///         err.to_string()
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn validator(&self) -> Validator<'_> {
+        Validator {
+            json: self,
+            values: (),
+            violations: Vec::new(),
+        }
+    }
+}
+
+impl<'a, V> Validator<'a, V> {
+    fn check<T>(mut self, k: &str, result: Result<T, Error>) -> Validator<'a, (V, Option<T>)> {
+        let value = match result {
+            Ok(value) => Some(value),
+            Err(Error::Validation(err)) => {
+                self.violations.extend(err.into_violations());
+                None
+            }
+            Err(Error::NotFound(_)) => {
+                let violation = Violation::new("", "required", k.into(), Value::Null);
+                self.violations.push(violation);
+                None
+            }
+            Err(_) => {
+                let actual = self.json.get::<Value>(k).unwrap_or(Value::Null);
+                let violation = Violation::new(
+                    &pointer_child("", k),
+                    "type",
+                    short_type_name::<T>().into(),
+                    type_name(&actual).into(),
+                );
+                self.violations.push(violation);
+                None
+            }
+        };
+        Validator {
+            json: self.json,
+            values: (self.values, value),
+            violations: self.violations,
+        }
+    }
+
+    /// Require the given key, with a value which can be deserialized as
+    /// a `T`, like `Json::get`.
+    pub fn require<T: DeserializeOwned>(self, k: &str) -> Validator<'a, (V, Option<T>)> {
+        let result = self.json.get(k);
+        self.check(k, result)
+    }
+
+    /// Require the given key, with one of the allowed strings,
+    /// see `Json::expect_one_of`.
+    pub fn require_one_of(self, k: &str, allowed: &[&str]) -> Validator<'a, (V, Option<String>)> {
+        let result = self.json.expect_one_of(k, allowed).map(String::from);
+        self.check(k, result)
+    }
+
+    /// Require the given key, with a number within the given range,
+    /// see `Json::expect_range`.
+    pub fn require_range<T, R>(self, k: &str, range: R) -> Validator<'a, (V, Option<T>)>
+    where
+        T: DeserializeOwned + PartialOrd + Serialize,
+        R: RangeBounds<T>,
+    {
+        let result = self.json.expect_range(k, range);
+        self.check(k, result)
+    }
+
+    /// Require the given key, with a string, array or object whose length
+    /// is within the given range, see `Json::expect_len`, and a value which
+    /// can be deserialized as a `T`.
+    pub fn require_len<T, R>(self, k: &str, range: R) -> Validator<'a, (V, Option<T>)>
+    where
+        T: DeserializeOwned,
+        R: RangeBounds<usize>,
+    {
+        let result = self
+            .json
+            .expect_len(k, range)
+            .and_then(|_| self.json.get(k));
+        self.check(k, result)
+    }
+
+    /// Require the given key, with a string of the given format,
+    /// see `Json::expect_format`.
+    pub fn require_format(self, k: &str, format: Format) -> Validator<'a, (V, Option<String>)> {
+        let result = self.json.expect_format(k, format).map(String::from);
+        self.check(k, result)
+    }
+}
+
+impl<V: Extracted> Validator<'_, V> {
+    /// Return the values extracted by the checks, as a tuple in the order
+    /// of the checks.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::ValidationError)` listing the violations
+    /// of every failed check, missing keys being `required` violations
+    pub fn finish(self) -> Result<V::Output, ValidationError> {
+        match (self.violations.is_empty(), self.values.extracted()) {
+            (true, Some(values)) => Ok(values),
+            _ => Err(ValidationError::new(self.violations)),
+        }
+    }
+}
+
+/// Values accumulated by a `Validator`, nested as `(((), Option<A>), Option<B>)`,
+/// flattened as `(A, B)` when all the checks succeeded. It is implemented
+/// for up to 12 checks.
+pub trait Extracted {
+    /// Flattened tuple of the values.
+    type Output;
+
+    /// The flattened values, if all the checks succeeded.
+    fn extracted(self) -> Option<Self::Output>;
+}
+
+impl Extracted for () {
+    type Output = ();
+
+    fn extracted(self) -> Option<()> {
+        Some(())
+    }
+}
+
+macro_rules! extracted {
+    (@type $nested:ty ;) => { $nested };
+    (@type $nested:ty ; $t:ident $(, $rest:ident)*) => {
+        extracted!(@type ($nested, Option<$t>) ; $($rest),*)
+    };
+    (@pat $nested:pat,) => { $nested };
+    (@pat $nested:pat, $t:ident $(, $rest:ident)*) => {
+        extracted!(@pat ($nested, Some($t)), $($rest),*)
+    };
+    ($($t:ident),+) => {
+        impl<$($t),+> Extracted for extracted!(@type () ; $($t),+) {
+            type Output = ($($t,)+);
+
+            #[allow(non_snake_case)]
+            fn extracted(self) -> Option<Self::Output> {
+                match self {
+                    extracted!(@pat (), $($t),+) => Some(($($t,)+)),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+extracted!(A);
+extracted!(A, B);
+extracted!(A, B, C);
+extracted!(A, B, C, D);
+extracted!(A, B, C, D, E);
+extracted!(A, B, C, D, E, F);
+extracted!(A, B, C, D, E, F, G);
+extracted!(A, B, C, D, E, F, G, H);
+extracted!(A, B, C, D, E, F, G, H, I);
+extracted!(A, B, C, D, E, F, G, H, I, J);
+extracted!(A, B, C, D, E, F, G, H, I, J, K);
+extracted!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+/// Name of a type without the paths of its modules, such as `Vec<String>`.
+fn short_type_name<T>() -> String {
+    let mut name = String::new();
+    let mut word = String::new();
+    for c in any::type_name::<T>().chars() {
+        match c {
+            ':' => word.clear(),
+            c if c.is_alphanumeric() || c == '_' => word.push(c),
+            c => {
+                name.push_str(&word);
+                name.push(c);
+                word.clear();
+            }
+        }
+    }
+    name.push_str(&word);
+    name
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+
+    #[test]
+    fn all_problems() {
+        let json = inline!("tags" => vec![1, 2], "status" => "lost", "name" => "");
+        let err = json
+            .validator()
+            .require::<Vec<String>>("tags")
+            .require_one_of("status", &["active", "archived"])
+            .require_len::<String, _>("name", 1..=64)
+            .require::<u8>("age")
+            .finish()
+            .unwrap_err();
+        let violations: Vec<String> = err.violations().iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            vec![
+                "type at /tags: expected \"Vec<String>\" but found \"array\"",
+                "enum at /status: expected [\"active\",\"archived\"] but found \"lost\"",
+                "minLength at /name: expected 1 but found 0",
+                "required at /: expected \"age\" but found null",
+            ],
+            violations
+        );
+        assert_eq!(Ok(()), Json::new().validator().finish());
+    }
+}
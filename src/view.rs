@@ -0,0 +1,165 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Read-only views of the objects nested in a document, to look up
+//! deep values without cloning the levels in between.
+
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::{Error, Json, NotFound, TypeMismatch};
+
+/// Borrowed, read-only view of a `Json` instance or of an object nested in it.
+#[derive(Debug, Clone, Copy)]
+pub struct JsonView<'a> {
+    members: Members<'a>,
+    json: &'a Json,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Members<'a> {
+    Root(&'a HashMap<String, Value>),
+    Object(&'a Map<String, Value>),
+}
+
+impl<'a> JsonView<'a> {
+    fn value(&self, k: &str) -> Result<&'a Value, Error> {
+        let key = self.json.key(k);
+        let value = match self.members {
+            Members::Root(members) => members.get(key.as_ref()),
+            Members::Object(members) => members.get(key.as_ref()),
+        };
+        match (value, self.members) {
+            (Some(value), _) => Ok(value),
+            (None, Members::Root(_)) => Err(NotFound::new(k.into(), self.json)?.into()),
+            (None, Members::Object(members)) => Err(NotFound::in_object(k.into(), members)?.into()),
+        }
+    }
+
+    /// View of the object stored under the given key.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::TypeMismatch)` if its value is not an object
+    pub fn view(&self, k: &str) -> Result<JsonView<'a>, Error> {
+        match self.value(k)? {
+            Value::Object(members) => Ok(JsonView {
+                members: Members::Object(members),
+                json: self.json,
+            }),
+            other => Err(TypeMismatch::new("object", other).into()),
+        }
+    }
+
+    /// Get the value stored under the given key, like `Json::get`.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::Json)` if its value cannot be deserialized as a `T`
+    pub fn get<T: DeserializeOwned>(&self, k: &str) -> Result<T, Error> {
+        Ok(T::deserialize(self.value(k)?)?)
+    }
+
+    /// Copy the viewed object into an owned `Json` instance.
+    pub fn to_json(&self) -> Json {
+        match self.members {
+            Members::Root(members) => Json::from_data(members.clone()),
+            Members::Object(members) => Json::from_data(
+                members
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> From<&'a Json> for JsonView<'a> {
+    fn from(json: &'a Json) -> Self {
+        JsonView {
+            members: Members::Root(&json.json_data),
+            json,
+        }
+    }
+}
+
+impl Json {
+    /// Borrow the object stored under the given key as a `JsonView`,
+    /// which looks up nested values without cloning anything.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let mut json = Json::new();
+    ///     json.add("movie", inline!("director" => inline!("name" => "Garth Jennings")));
+    ///
+    ///     let director = json.view("movie")?.view("director")?;
+    ///     assert_eq!("Garth Jennings", &director.get::<String>("name")?);
+    ///     assert_eq!(inline!("name" => "Garth Jennings"), director.to_json());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::NotFound)` if the key doesn't exist or an
+    /// `Err(json_ez::Error::TypeMismatch)` if its value is not an object
+    pub fn view(&self, k: &str) -> Result<JsonView<'_>, Error> {
+        JsonView::from(self).view(k)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{inline, ErrorCode};
+    use std::error::Error;
+
+    #[test]
+    fn views() -> Result<(), Box<dyn Error>> {
+        let mut json = inline!("title" => "Hitchhiker's Guide");
+        json.add(
+            "cast",
+            inline!("lead" => inline!("name" => "Martin Freeman")),
+        );
+
+        let root = JsonView::from(&json);
+        assert_eq!(json, root.to_json());
+        let lead = root.view("cast")?.view("lead")?;
+        assert_eq!("Martin Freeman", lead.get::<String>("name")?);
+
+        let err = json.view("title").unwrap_err();
+        assert_eq!(ErrorCode::TypeMismatch, err.code());
+        let err = json.view("cast")?.get::<String>("led").unwrap_err();
+        assert_eq!(ErrorCode::NotFound, err.code());
+        assert_eq!(
+            "NotFound: Cannot found key led in {\"lead\":{\"name\":\"Martin Freeman\"}}; \
+             did you mean \"lead\"?",
+            err.to_string()
+        );
+        Ok(())
+    }
+}
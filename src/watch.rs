@@ -0,0 +1,188 @@
+// The MIT License
+//
+// Copyright (c) 2019 Alexandre BOUTHINON
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+// THE SOFTWARE.
+
+//! Notifications of the changes of the values at given paths, whichever
+//! method changed them.
+
+use std::mem;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::path::{parse_concrete, Segment};
+use crate::{Error, Json};
+
+type Callback = dyn Fn(Option<&Value>, Option<&Value>) + Send + Sync + UnwindSafe + RefUnwindSafe;
+
+/// A callback watching the value at a path.
+#[derive(Clone)]
+pub(crate) struct Watcher {
+    path: Vec<Segment>,
+    callback: Arc<Callback>,
+}
+
+impl Json {
+    /// Call the given callback with the previous and the new value (`None`
+    /// if missing) each time the value at the given path is changed by
+    /// any method of the document. Changes made in a transaction are only
+    /// notified once it succeeds. Copies of the document keep its watchers.
+    /// The callback must be unwind safe, as the document is.
+    ///
+    /// # Example
+    /// ```
+    /// use json_ez::{inline, Json};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let readings = Arc::new(Mutex::new(Vec::new()));
+    ///     let mut metrics = Json::new();
+    ///
+    ///     let seen = Arc::clone(&readings);
+    ///     metrics.watch("system.cpu", move |_, cpu| {
+    ///         seen.lock().unwrap().push(cpu.cloned());
+    ///     })?;
+    ///     metrics.add("system", inline!("cpu" => 0.42, "memory" => 0.8));
+    ///     metrics.merge(inline!("system" => inline!("memory" => 0.7)));
+    ///     metrics.merge(inline!("system" => inline!("cpu" => 0.5)));
+    ///
+    ///     assert_eq!(vec![Some(0.42.into()), Some(0.5.into())], *readings.lock().unwrap());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if the path
+    /// cannot be parsed or holds wildcards
+    pub fn watch<F>(&mut self, path: &str, callback: F) -> Result<(), Error>
+    where
+        F: Fn(Option<&Value>, Option<&Value>) + Send + Sync + UnwindSafe + RefUnwindSafe + 'static,
+    {
+        self.watchers.push(Watcher {
+            path: parse_concrete(path)?,
+            callback: Arc::new(callback),
+        });
+        Ok(())
+    }
+
+    /// Remove the callbacks watching the given path.
+    ///
+    /// # Errors
+    /// Return an `Err(json_ez::Error::InvalidPath)` if the path
+    /// cannot be parsed or holds wildcards
+    pub fn unwatch(&mut self, path: &str) -> Result<(), Error> {
+        let path = parse_concrete(path)?;
+        self.watchers.retain(|watcher| watcher.path != path);
+        Ok(())
+    }
+
+    /// Apply the given change to the data, then notify the watchers of the
+    /// paths whose value changed. The fingerprint and the usage are computed
+    /// again lazily.
+    pub(crate) fn watched<R>(&mut self, change: impl FnOnce(&mut Json) -> R) -> R {
+        self.fingerprint.take();
+        self.tally.take();
+        if let Some(fragments) = &mut self.fragments {
+            fragments.clear();
+        }
+        if self.watchers.is_empty() {
+            return change(self);
+        }
+        let before = self.watched_values();
+        let result = change(self);
+        self.notify(before);
+        result
+    }
+
+    /// The values at the paths of the watchers, to be given to `Json::notify`.
+    pub(crate) fn watched_values(&self) -> Vec<Option<Value>> {
+        self.watchers
+            .iter()
+            .map(|watcher| self.value_at(&watcher.path).cloned())
+            .collect()
+    }
+
+    /// Notify the watchers of the paths whose value changed since the given
+    /// values were taken by `Json::watched_values`.
+    pub(crate) fn notify(&self, before: Vec<Option<Value>>) {
+        for (watcher, before) in self.watchers.iter().zip(before) {
+            let after = self.value_at(&watcher.path);
+            if before.as_ref() != after {
+                (watcher.callback)(before.as_ref(), after);
+            }
+        }
+    }
+
+    /// Replace the data of the document by the one of the given copy, notifying the watchers.
+    pub(crate) fn commit(&mut self, mut copy: Json) {
+        self.watched(move |json| {
+            copy.watchers = mem::take(&mut json.watchers);
+            *json = copy;
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::inline;
+    use std::sync::Mutex;
+
+    #[test]
+    fn watchers() {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let mut json = inline!("a" => 1);
+        let seen = Arc::clone(&changes);
+        json.watch("items[1].name", move |before, after| {
+            let change = (before.cloned(), after.cloned());
+            seen.lock().unwrap().push(change);
+        })
+        .unwrap();
+
+        json.add(
+            "items",
+            vec![inline!("name" => "x"), inline!("name" => "y")],
+        );
+        json.add("b", 2);
+        let _ = json.transaction(|tx| {
+            tx.add("items", Vec::<Json>::new());
+            tx.get::<bool>("c")
+        });
+        json.transaction(|tx| {
+            tx.add("items", Vec::<Json>::new());
+            tx.get::<u8>("a")
+        })
+        .unwrap();
+        json.unwatch("items[1].name").unwrap();
+        json.add(
+            "items",
+            vec![inline!("name" => "x"), inline!("name" => "y")],
+        );
+
+        assert_eq!(
+            vec![(None, Some("y".into())), (Some("y".into()), None)],
+            *changes.lock().unwrap()
+        );
+        assert!(json.watch("items[*]", |_, _| ()).is_err());
+    }
+}